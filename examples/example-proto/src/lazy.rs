@@ -17,6 +17,7 @@ impl<T> std::ops::DerefMut for GrpcClient<T> {
     }
 }
 
+#[allow(async_fn_in_trait)]
 pub trait LazyStateInit: Sized + Clone {
     type Error;
     type Context;
@@ -1,6 +1,5 @@
 use crate::context::AppContext;
 use example_proto::client::{GrpcCtx, LazyTestUserGrpcClient};
-use std::env;
 use std::sync::Arc;
 
 mod context;
@@ -2,4 +2,5 @@
 
 pub mod prelude;
 
+#[allow(clippy::module_inception)]
 pub mod order;
@@ -1,2 +1 @@
 
-pub use super::order::Entity as Order;
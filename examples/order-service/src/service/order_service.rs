@@ -12,7 +12,7 @@ pub async fn add_order_then_add_user(app_ctx: Arc<AppContext>) -> anyhow::Result
     tracing::info!("start transaction session is : {:?}", session);
 
     let db = app_ctx.db_conn.clone();
-    let a = db
+    db
         .clone()
         .transaction::<_, (), DbErr>(|txn| {
             Box::pin(async move {
@@ -2,7 +2,7 @@ use crate::context::AppContext;
 use crate::service;
 use crate::web::handler::handle_result;
 use axum::Router;
-use axum::extract::{Path, State};
+use axum::extract::State;
 use axum::response::IntoResponse;
 use axum::routing::get;
 use std::sync::Arc;
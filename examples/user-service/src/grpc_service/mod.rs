@@ -6,15 +6,12 @@ use example_proto::rseata_proto::proto::{
     AddUserRequest, AddUserResponse, GetUserByIdRequest, GetUserByIdResponse, User,
 };
 use rseata::RSEATA_CLIENT_SESSION;
-use rseata::core::GrpcServerInterceptor;
 use rseata::core::grpc_layer::SeataMiddlewareLayer;
 use sea_orm::prelude::async_trait::async_trait;
 use sea_orm::sqlx::types::uuid;
-use sea_orm::{DbErr, EntityTrait, TransactionTrait};
-use std::pin::Pin;
+use sea_orm::EntityTrait;
 use std::sync::Arc;
 use tonic::transport::Server;
-use uuid::uuid;
 
 pub(crate) async fn start(ctx: Arc<AppContext>) -> anyhow::Result<()> {
     let addr = std::env::var("GRPC_BIND")
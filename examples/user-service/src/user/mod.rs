@@ -1,2 +1,3 @@
 pub mod prelude;
+#[allow(clippy::module_inception)]
 pub mod user;
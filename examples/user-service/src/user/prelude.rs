@@ -1 +0,0 @@
-pub use super::user::Entity as User;
@@ -0,0 +1,181 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rseata_core::branch::{BranchStatus, BranchType};
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
+use rseata_core::session::session_manager::SessionManager;
+use rseata_core::store::LogOperation;
+use rseata_core::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+use rseata_core::types::GlobalStatus;
+use std::sync::Arc;
+use std::thread::available_parallelism;
+
+// 每个并发任务执行的全局事务数
+const TRANSACTIONS_PER_WORKER: u64 = 200;
+const BRANCHES_PER_TRANSACTION: u64 = 3;
+
+// 开启全局事务、注册分支、上报分支状态、提交并移除
+async fn run_transaction(store: &MemeryTransactionStoreManager, seq: u64) {
+    let mut gs = DefaultGlobalSession::new(
+        "bench".to_string(),
+        "bench_group".to_string(),
+        "bench_tx".to_string(),
+        60_000,
+        false,
+    );
+    store
+        .write_session(LogOperation::GlobalAdd, &gs)
+        .await
+        .unwrap();
+
+    for i in 0..BRANCHES_PER_TRANSACTION {
+        let mut bs = DefaultBranchSession::new(BranchType::AT);
+        bs.xid = gs.xid.clone();
+        bs.transaction_id = gs.transaction_id;
+        bs.branch_id = (seq * BRANCHES_PER_TRANSACTION + i).into();
+        bs.status = BranchStatus::Registered;
+        gs.add_branch(bs);
+        store
+            .write_session(LogOperation::BranchAdd, &gs)
+            .await
+            .unwrap();
+    }
+
+    for i in 0..gs.branch_sessions.len() {
+        gs.branch_sessions[i].status = BranchStatus::PhaseOneDone;
+        store
+            .write_session(LogOperation::BranchUpdate, &gs)
+            .await
+            .unwrap();
+    }
+
+    gs.status = GlobalStatus::Committed;
+    store
+        .write_session(LogOperation::GlobalUpdate, &gs)
+        .await
+        .unwrap();
+    store
+        .write_session(LogOperation::GlobalRemove, &gs)
+        .await
+        .unwrap();
+}
+
+// 与 run_transaction 相同的流程，经由会话管理器读改写
+async fn run_managed_transaction(manager: &DefaultSessionManager, seq: u64) {
+    let gs = DefaultGlobalSession::new(
+        "bench".to_string(),
+        "bench_group".to_string(),
+        "bench_tx".to_string(),
+        60_000,
+        false,
+    );
+    manager.add_global_session(&gs).await.unwrap();
+
+    let mut branches = Vec::new();
+    for i in 0..BRANCHES_PER_TRANSACTION {
+        let mut bs = DefaultBranchSession::new(BranchType::AT);
+        bs.xid = gs.xid.clone();
+        bs.transaction_id = gs.transaction_id;
+        bs.branch_id = (seq * BRANCHES_PER_TRANSACTION + i).into();
+        bs.status = BranchStatus::Registered;
+        manager.add_branch_session(&gs, &bs).await.unwrap();
+        branches.push(bs);
+    }
+
+    for bs in &branches {
+        manager
+            .update_branch_session_status(&gs, bs, BranchStatus::PhaseOneDone)
+            .await
+            .unwrap();
+    }
+
+    manager
+        .update_global_session_status(&gs, GlobalStatus::Committing)
+        .await
+        .unwrap();
+    manager
+        .update_global_session_status(&gs, GlobalStatus::Committed)
+        .await
+        .unwrap();
+    manager.end_global_session(&gs).await.unwrap();
+}
+
+async fn run(shards: usize, workers: u64) {
+    let store = Arc::new(MemeryTransactionStoreManager::with_shards(shards));
+    let tasks = (0..workers)
+        .map(|worker| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                for n in 0..TRANSACTIONS_PER_WORKER {
+                    run_transaction(&store, worker * TRANSACTIONS_PER_WORKER + n).await;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+async fn run_managed(manager: Arc<DefaultSessionManager>, workers: u64) {
+    let tasks = (0..workers)
+        .map(|worker| {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                for n in 0..TRANSACTIONS_PER_WORKER {
+                    run_managed_transaction(&manager, worker * TRANSACTIONS_PER_WORKER + n).await;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+// 单个任务与每个 CPU 一个任务，线程数与任务数相同，任务数体现在 bench id 中
+fn worker_counts() -> Vec<u64> {
+    let parallelism = available_parallelism().map_or(4, |n| n.get()) as u64;
+    let mut workers = vec![1, parallelism];
+    workers.dedup();
+    workers
+}
+
+fn memory_session_store(c: &mut Criterion) {
+    for workers in worker_counts() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(workers as usize)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let mut group = c.benchmark_group("memory_session_store");
+        group.throughput(Throughput::Elements(workers * TRANSACTIONS_PER_WORKER));
+        for shards in [1, 64] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("shards_{shards}"), format!("workers_{workers}")),
+                &shards,
+                |b, &shards| b.iter(|| runtime.block_on(run(shards, workers))),
+            );
+        }
+        group.finish();
+
+        let mut group = c.benchmark_group("session_manager");
+        group.throughput(Throughput::Elements(workers * TRANSACTIONS_PER_WORKER));
+        group.bench_function(
+            BenchmarkId::new("memory", format!("workers_{workers}")),
+            |b| {
+                let manager = Arc::new(DefaultSessionManager::new(
+                    "bench".to_string(),
+                    Box::new(MemeryTransactionStoreManager::new()),
+                ));
+                b.iter(|| runtime.block_on(run_managed(manager.clone(), workers)))
+            },
+        );
+        group.finish();
+    }
+}
+
+criterion_group!(benches, memory_session_store);
+criterion_main!(benches);
@@ -1,10 +1,19 @@
 use crate::branch::{BranchId, BranchStatus, BranchType};
 use crate::types::{ResourceId, Xid};
 use tonic::async_trait;
-use crate::branch::branch_transaction::BranchTransaction;
 
 #[async_trait]
 pub trait BranchManagerInbound {
+    /// 返回 PhaseOneDone 表示分支可以提交，其他状态时 TC 回滚全局事务
+    async fn branch_prepare(
+        &self,
+        branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus>;
+
     async fn branch_commit(
         &self,
         branch_type: BranchType,
@@ -1,10 +1,21 @@
-use crate::branch::branch_manager_inbound::BranchManagerInbound;
 use crate::branch::{BranchId, BranchStatus, BranchType};
 use crate::types::{ClientId, ResourceId, Xid};
 use async_trait::async_trait;
 
 #[async_trait]
 pub trait BranchTransaction: Send + Sync + 'static {
+    /// TC 在提交任何分支前要求分支就绪，默认一阶段结束时已就绪
+    async fn branch_prepare(
+        &self,
+        _branch_type: BranchType,
+        _xid: Xid,
+        _branch_id: BranchId,
+        _resource_id: ResourceId,
+        _application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        Ok(BranchStatus::PhaseOneDone)
+    }
+
     async fn branch_commit(
         &self,
         branch_type: BranchType,
@@ -26,6 +37,7 @@ pub trait BranchTransaction: Send + Sync + 'static {
 
 #[async_trait]
 pub trait BranchTransactionRegistry {
+    #[allow(clippy::too_many_arguments)]
     async fn branch_transaction_registry(
         &self,
         branch_type: BranchType,
@@ -5,6 +5,15 @@ pub mod branch_manager_inbound;
 pub mod branch_manager_outbound;
 pub mod branch_transaction;
 
+/// TC 在全局提交时下发给唯一 XA 分支的 application_data，RM 据此执行 `XA COMMIT ... ONE PHASE`
+pub const XA_ONE_PHASE: &str = "xa_one_phase";
+
+/// RM 注册分支时的 application_data，表示分支已 `XA END` 但尚未 `XA PREPARE`
+pub const XA_IDLE: &str = "xa_idle";
+
+/// 由 TM 自行驱动二阶段的分支使用的资源前缀，TC 不向这类分支下发指令，结果通过 GlobalReport 上报
+pub const TM_DRIVEN_RESOURCE_PREFIX: &str = "tm-driven:";
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct BranchId(pub u64);
 impl From<u64> for BranchId {
@@ -13,9 +22,9 @@ impl From<u64> for BranchId {
     }
 }
 
-impl Into<u64> for BranchId {
-    fn into(self) -> u64 {
-        self.0
+impl From<BranchId> for u64 {
+    fn from(val: BranchId) -> Self {
+        val.0
     }
 }
 
@@ -25,7 +34,7 @@ impl Display for BranchId {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum BranchType {
     AT = 1,
     TCC = 2,
@@ -45,9 +54,9 @@ impl From<i32> for BranchType {
     }
 }
 
-impl Into<i32> for BranchType {
-    fn into(self) -> i32 {
-        self as i32
+impl From<BranchType> for i32 {
+    fn from(val: BranchType) -> Self {
+        val as i32
     }
 }
 
@@ -85,8 +94,98 @@ impl From<i32> for BranchStatus {
         }
     }
 }
-impl Into<i32> for BranchStatus {
-    fn into(self) -> i32 {
-        self as i32
+impl From<BranchStatus> for i32 {
+    fn from(val: BranchStatus) -> Self {
+        val as i32
+    }
+}
+
+impl BranchStatus {
+    /// 分支状态迁移表，相同状态视为幂等更新
+    pub fn can_transition_to(&self, to: Self) -> bool {
+        if *self == to {
+            return true;
+        }
+        let phase_two_rollback = matches!(
+            to,
+            Self::PhaseTwoRollbacked
+                | Self::PhaseTwoRollbackFailedRetryable
+                | Self::PhaseTwoRollbackFailedUnretryable
+                | Self::PhaseTwoTimeout
+        );
+        let phase_two_commit = matches!(
+            to,
+            Self::PhaseTwoCommitted
+                | Self::PhaseTwoCommitFailedRetryable
+                | Self::PhaseTwoCommitFailedUnretryable
+                | Self::PhaseTwoTimeout
+        );
+        match self {
+            Self::Unknown => true,
+            // 一阶段未上报完成的分支只能回滚
+            Self::Registered => {
+                matches!(
+                    to,
+                    Self::PhaseOneDone | Self::PhaseOneFailed | Self::PhaseOneTimeout
+                ) || phase_two_rollback
+            }
+            Self::PhaseOneDone => phase_two_commit || phase_two_rollback,
+            Self::PhaseOneFailed | Self::PhaseOneTimeout => phase_two_rollback,
+            Self::PhaseTwoCommitFailedRetryable => phase_two_commit,
+            Self::PhaseTwoRollbackFailedRetryable => phase_two_rollback,
+            Self::PhaseTwoTimeout => phase_two_commit || phase_two_rollback,
+            Self::PhaseTwoCommitted
+            | Self::PhaseTwoCommitFailedUnretryable
+            | Self::PhaseTwoRollbacked
+            | Self::PhaseTwoRollbackFailedUnretryable => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BranchStatus::{self, *};
+
+    #[test]
+    fn branch_status_transition_table() {
+        let commit: &[BranchStatus] = &[
+            PhaseTwoCommitted,
+            PhaseTwoCommitFailedRetryable,
+            PhaseTwoCommitFailedUnretryable,
+            PhaseTwoTimeout,
+        ];
+        let rollback: &[BranchStatus] = &[
+            PhaseTwoRollbacked,
+            PhaseTwoRollbackFailedRetryable,
+            PhaseTwoRollbackFailedUnretryable,
+            PhaseTwoTimeout,
+        ];
+        let all: Vec<_> = (1..=12).map(BranchStatus::from).collect();
+        let phase_one = [PhaseOneDone, PhaseOneFailed, PhaseOneTimeout];
+        let table: Vec<(BranchStatus, Vec<BranchStatus>)> = vec![
+            (Unknown, all.clone()),
+            (Registered, [&phase_one[..], rollback].concat()),
+            (PhaseOneDone, [commit, rollback].concat()),
+            (PhaseOneFailed, rollback.to_vec()),
+            (PhaseOneTimeout, rollback.to_vec()),
+            (PhaseTwoCommitFailedRetryable, commit.to_vec()),
+            (PhaseTwoRollbackFailedRetryable, rollback.to_vec()),
+            (PhaseTwoTimeout, [commit, rollback].concat()),
+            // 二阶段终态之后只接受相同状态的重复上报
+            (PhaseTwoCommitted, vec![]),
+            (PhaseTwoCommitFailedUnretryable, vec![]),
+            (PhaseTwoRollbacked, vec![]),
+            (PhaseTwoRollbackFailedUnretryable, vec![]),
+        ];
+        assert_eq!(table.len(), all.len());
+        for (from, allowed) in &table {
+            for &to in &all {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    *from == to || allowed.contains(&to),
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
     }
 }
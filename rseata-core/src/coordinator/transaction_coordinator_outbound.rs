@@ -9,6 +9,16 @@ use async_trait::async_trait;
 pub trait TransactionCoordinatorOutbound {
     type GlobalSession: GlobalSession + Send + Sync;
     type BranchSession: BranchSession + Send + Sync;
+
+    /// 多分支提交前确认分支已就绪，未就绪的分支在此完成准备；默认一阶段结束即已就绪
+    async fn branch_prepare(
+        &self,
+        _global_session: &Self::GlobalSession,
+        _branch_session: &Self::BranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        Ok(BranchStatus::PhaseOneDone)
+    }
+
     async fn branch_commit(
         &self,
         global_session: &Self::GlobalSession,
@@ -1,3 +1,5 @@
+use crate::branch::{BranchId, BranchStatus};
+use crate::types::{ClientId, GlobalStatus, ResourceId, Xid};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +8,27 @@ pub enum TransactionError {
     Unknown,
     #[error("context: missing '{info}'")]
     ErrorInfo { info: String },
+    #[error("lock conflict: {info}")]
+    LockConflict { info: String },
+    #[error("illegal global status transition of {xid}: {from:?} -> {to:?}")]
+    IllegalGlobalTransition {
+        xid: Xid,
+        from: GlobalStatus,
+        to: GlobalStatus,
+    },
+    #[error("illegal branch status transition of {branch_id}: {from:?} -> {to:?}")]
+    IllegalBranchTransition {
+        branch_id: BranchId,
+        from: BranchStatus,
+        to: BranchStatus,
+    },
+    #[error("phase two of branch {branch_id} not attempted")]
+    NotAttempted { branch_id: BranchId },
+    #[error("resource {resource_id} unavailable on client {client_id}")]
+    ResourceUnavailable {
+        resource_id: ResourceId,
+        client_id: ClientId,
+    },
 }
 
 impl TransactionError {
@@ -9,6 +9,12 @@ use std::sync::Arc;
 pub struct DefaultEventHandlerChain {
     handlers: Vec<Arc<dyn EventHandler<Event = TransactionEvent>>>,
 }
+impl Default for DefaultEventHandlerChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DefaultEventHandlerChain {
     pub fn new() -> Self {
         Self {
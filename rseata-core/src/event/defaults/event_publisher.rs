@@ -9,16 +9,12 @@ use tokio::sync::mpsc;
 #[derive(Clone)]
 pub struct DefaultEventPublisher {
     event_tx: mpsc::UnboundedSender<TransactionEvent>,
-    handler_chain: Arc<DefaultEventHandlerChain>,
 }
 
 impl DefaultEventPublisher {
     pub fn new(handler_chain: Arc<DefaultEventHandlerChain>) -> Self {
         let (event_tx, mut event_rx) = mpsc::unbounded_channel();
-        let publisher = Self {
-            event_tx,
-            handler_chain: handler_chain.clone(),
-        };
+        let publisher = Self { event_tx };
 
         // 启动事件处理循环
         tokio::spawn(async move {
@@ -7,9 +7,3 @@ pub enum EventError {
     #[error("context: missing '{info}'")]
     ErrorInfo { info: String },
 }
-
-impl EventError {
-    pub(crate) fn new(e: String) -> Self {
-        EventError::ErrorInfo { info: e }
-    }
-}
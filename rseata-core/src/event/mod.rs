@@ -1,4 +1,5 @@
 pub mod defaults;
+#[allow(clippy::module_inception)]
 pub mod event;
 pub mod event_error;
 pub mod event_handler;
@@ -33,37 +33,35 @@ impl<T> std::ops::DerefMut for GrpcClient<T> {
 pub struct RseataInterceptor;
 impl Interceptor for RseataInterceptor {
     fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
-        if let Some(started) = RSEATA_CLIENT_SESSION
+        if let Ok(started) = RSEATA_CLIENT_SESSION
             .try_with(|v| v.is_global_tx_started())
-            .ok()
+            && started
         {
-            if started {
-                let xid1 = RSEATA_CLIENT_SESSION.try_with(|s| s.get_xid()).ok();
-                if let Some(xid2) = xid1 {
-                    if let Some(xid3) = xid2 {
-                        if let Ok(metadata_key) = MetadataKey::from_bytes(RSEATA_XID_KEY.as_bytes())
-                        {
-                            if let Ok(metadata_value) = MetadataValue::from_str(&xid3.to_string()) {
-                                request.metadata_mut().insert(metadata_key, metadata_value);
-                            } else {
-                                return Err(Status::new(
-                                    Code::Internal,
-                                    "RSEATA_XID is invalid",
-                                ));
-                            }
+            let xid1 = RSEATA_CLIENT_SESSION.try_with(|s| s.get_xid()).ok();
+            if let Some(xid2) = xid1 {
+                if let Some(xid3) = xid2 {
+                    if let Ok(metadata_key) = MetadataKey::from_bytes(RSEATA_XID_KEY.as_bytes())
+                    {
+                        if let Ok(metadata_value) = MetadataValue::from_str(&xid3.to_string()) {
+                            request.metadata_mut().insert(metadata_key, metadata_value);
                         } else {
                             return Err(Status::new(
                                 Code::Internal,
-                                "RSEATA_XID_KEY is invalid",
+                                "RSEATA_XID is invalid",
                             ));
                         }
+                    } else {
+                        return Err(Status::new(
+                            Code::Internal,
+                            "RSEATA_XID_KEY is invalid",
+                        ));
                     }
-                } else {
-                    return Err(Status::new(
-                        Code::Internal,
-                        "RSEATA_XID_KEY is invalid",
-                    ));
                 }
+            } else {
+                return Err(Status::new(
+                    Code::Internal,
+                    "RSEATA_XID_KEY is invalid",
+                ));
             }
         }
         Ok(request)
@@ -14,14 +14,15 @@ pub mod lock;
 pub mod resource;
 pub mod session;
 pub mod store;
+pub mod tcc;
 pub mod transaction;
 pub mod types;
 mod config;
 
-pub static EMPTY_STR: &'static str = "";
-pub static RSEATA_VERSION: &'static str = "0.0.1";
+pub static EMPTY_STR: &str = "";
+pub static RSEATA_VERSION: &str = "0.0.1";
 
-pub static RSEATA_XID_KEY: &'static str = "RSEATA_XID";
+pub static RSEATA_XID_KEY: &str = "RSEATA_XID";
 
 task_local! {
    pub static RSEATA_CLIENT_SESSION: Arc<ClientSession>;
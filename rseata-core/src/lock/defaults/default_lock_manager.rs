@@ -1,5 +1,6 @@
 use crate::branch::BranchId;
 use crate::error::TransactionError;
+use crate::lock::LockStatus;
 use crate::lock::lock_manager::LockManager;
 use crate::lock::locker::Locker;
 use crate::lock::row_lock::RowLockData;
@@ -10,18 +11,18 @@ use async_trait::async_trait;
 use std::sync::Arc;
 
 /// 默认锁管理器实现
-pub struct DefaultLockManager<L: Locker> {
+pub struct DefaultLockManager<L: Locker + ?Sized> {
     locker: Arc<L>,
 }
 
-impl<L: Locker> DefaultLockManager<L> {
+impl<L: Locker + ?Sized> DefaultLockManager<L> {
     pub fn new(locker: Arc<L>) -> Self {
         Self { locker }
     }
 }
 
 #[async_trait]
-impl<L: Locker> LockManager for DefaultLockManager<L> {
+impl<L: Locker + ?Sized> LockManager for DefaultLockManager<L> {
     type L = L;
     type RowLock = L::RowLock;
 
@@ -101,49 +102,16 @@ impl<L: Locker> LockManager for DefaultLockManager<L> {
         }
     }
 
-    async fn update_lock_status(&self) -> Result<(), TransactionError> {
-        // 实现锁状态更新逻辑
-        Ok(())
-    }
-}
-
-impl<L: Locker> DefaultLockManager<L> {
-    async fn parse_lock_key_test(
+    async fn update_lock_status(
         &self,
-        lock_key: &str,
-        resource_id: &ResourceId,
         xid: &Xid,
-        transaction_id: u64,
-        branch_id: Option<BranchId>,
-    ) -> Result<Vec<L::RowLock>, TransactionError> {
-        let mut row_locks = Vec::new();
-
-        // 解析锁键格式: table1:pk1,pk2;table2:pk3,pk4
-        for table_group in lock_key.split(';') {
-            if let Some((table_name, pks_str)) = table_group.split_once(':') {
-                for pk in pks_str.split(',') {
-                    if !pk.trim().is_empty() {
-                        let row_lock = L::RowLock::from(RowLockData {
-                            xid: xid.clone(),
-                            transaction_id,
-                            branch_id,
-                            resource_id: resource_id.clone(),
-                            table_name: table_name.to_string(),
-                            pk: pk.trim().to_string(),
-                            row_key: None,
-                            feature: None,
-                        });
-                        // 这里需要将 DefaultRowLock 转换为 L::RowLock
-                        // 由于类型系统限制，这里简化处理
-                        row_locks.push(row_lock);
-                    }
-                }
-            }
-        }
-
-        Ok(row_locks)
+        lock_status: LockStatus,
+    ) -> Result<(), TransactionError> {
+        self.locker.update_lock_status(xid, lock_status).await
     }
+}
 
+impl<L: Locker + ?Sized> DefaultLockManager<L> {
     async fn parse_lock_key(
         &self,
         lock_key: &str,
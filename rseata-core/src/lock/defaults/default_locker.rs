@@ -6,16 +6,17 @@ use crate::lock::locker::Locker;
 use crate::lock::row_lock::RowLock;
 use crate::types::{ResourceId, Xid};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-#[derive(Debug, Clone)]
-struct LockEntry {
-    xid: Xid,
-    transaction_id: u64,
-    branch_id: Option<BranchId>,
-    status: LockStatus,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockEntry {
+    pub xid: Xid,
+    pub transaction_id: u64,
+    pub branch_id: Option<BranchId>,
+    pub status: LockStatus,
 }
 
 #[derive(Debug)]
@@ -34,6 +35,25 @@ impl MemoryLocker {
         }
     }
 
+    /// 导出全部行锁，用于生成快照
+    pub async fn export(&self) -> Vec<(String, LockEntry)> {
+        self.locks
+            .read()
+            .await
+            .iter()
+            .map(|(row_key, entry)| (row_key.clone(), entry.clone()))
+            .collect()
+    }
+
+    /// 从快照导入行锁
+    pub async fn import(&self, entries: Vec<(String, LockEntry)>) {
+        for (row_key, entry) in entries {
+            self.add_to_index(&entry.xid, entry.branch_id, row_key.clone())
+                .await;
+            self.locks.write().await.insert(row_key, entry);
+        }
+    }
+
     fn build_row_key(resource_id: &ResourceId, table_name: &str, pk: &str) -> String {
         format!("{}:{}:{}", resource_id, table_name, pk)
     }
@@ -178,11 +198,11 @@ impl Locker for MemoryLocker {
 
         let mut locks = self.locks.write().await;
         for row_key in branch_keys {
-            if let Some(lock_entry) = locks.get(&row_key) {
-                if &lock_entry.xid == xid {
-                    locks.remove(&row_key);
-                    self.remove_from_index(xid, Some(branch_id), &row_key).await;
-                }
+            if let Some(lock_entry) = locks.get(&row_key)
+                && &lock_entry.xid == xid
+            {
+                locks.remove(&row_key);
+                self.remove_from_index(xid, Some(branch_id), &row_key).await;
             }
         }
 
@@ -233,10 +253,10 @@ impl Locker for MemoryLocker {
             let row_key =
                 Self::build_row_key(row_lock.resource_id(), row_lock.table_name(), row_lock.pk());
 
-            if let Some(existing_lock) = locks.get(&row_key) {
-                if &existing_lock.xid != xid {
-                    return Ok(false);
-                }
+            if let Some(existing_lock) = locks.get(&row_key)
+                && &existing_lock.xid != xid
+            {
+                return Ok(false);
             }
         }
 
@@ -1,8 +1,9 @@
 use crate::branch::BranchId;
 use crate::lock::row_lock::{RowLock, RowLockData};
 use crate::types::{ResourceId, Xid};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefaultRowLock {
     pub xid: Xid,
     pub transaction_id: u64,
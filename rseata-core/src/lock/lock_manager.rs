@@ -1,4 +1,5 @@
 use crate::error::TransactionError;
+use crate::lock::LockStatus;
 use crate::lock::locker::Locker;
 use crate::lock::row_lock::RowLock;
 use crate::session::branch_session::BranchSession;
@@ -8,7 +9,7 @@ use async_trait::async_trait;
 
 #[async_trait]
 pub trait LockManager: Send + Sync {
-    type L: Locker<RowLock = Self::RowLock>;
+    type L: Locker<RowLock = Self::RowLock> + ?Sized;
     type RowLock: RowLock + Send + Sync;
     async fn acquire_lock(
         &self,
@@ -40,5 +41,9 @@ pub trait LockManager: Send + Sync {
         &self,
         branch_session: &dyn BranchSession,
     ) -> Result<Vec<Self::RowLock>, TransactionError>;
-    async fn update_lock_status(&self) -> Result<(), TransactionError>;
+    async fn update_lock_status(
+        &self,
+        xid: &Xid,
+        lock_status: LockStatus,
+    ) -> Result<(), TransactionError>;
 }
@@ -1,7 +1,7 @@
 pub mod defaults;
 pub mod lock_manager;
 pub mod lockable;
-mod locker;
+pub mod locker;
 pub mod row_lock;
 
 use serde::{Deserialize, Serialize};
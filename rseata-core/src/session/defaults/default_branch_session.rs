@@ -1,4 +1,4 @@
-use crate::branch::{BranchId, BranchStatus, BranchType};
+use crate::branch::{BranchId, BranchStatus, BranchType, TM_DRIVEN_RESOURCE_PREFIX};
 use crate::error::TransactionError;
 use crate::lock::LockStatus;
 use crate::lock::lockable::Lockable;
@@ -24,6 +24,23 @@ pub struct DefaultBranchSession {
     pub application_data: Option<String>,
     pub lock_status: LockStatus,
     pub lock_holder: HashMap<String, Vec<String>>,
+    // RM 不在线时暂存的二阶段指令，等待 RM 重连后重新下发
+    #[serde(default)]
+    pub queued_instruction: Option<QueuedInstruction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BranchInstructionKind {
+    Commit,
+    Rollback,
+}
+
+/// 未能送达 RM 的二阶段指令，随会话一起持久化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedInstruction {
+    pub kind: BranchInstructionKind,
+    // 首次入队时间，超过宽限期后可由同一资源的其他 RM 实例接管
+    pub queued_at_millis: u64,
 }
 
 impl DefaultBranchSession {
@@ -41,6 +58,7 @@ impl DefaultBranchSession {
             application_data: None,
             lock_status: LockStatus::Locked,
             lock_holder: HashMap::new(),
+            queued_instruction: None,
         }
     }
 }
@@ -71,11 +89,11 @@ impl BranchSession for DefaultBranchSession {
     }
 
     fn branch_type(&self) -> BranchType {
-        self.branch_type.clone()
+        self.branch_type
     }
 
     fn status(&self) -> BranchStatus {
-        self.status.clone()
+        self.status
     }
 
     fn client_id(&self) -> ClientId {
@@ -87,7 +105,7 @@ impl BranchSession for DefaultBranchSession {
     }
 
     fn lock_status(&self) -> LockStatus {
-        self.lock_status.clone()
+        self.lock_status
     }
 }
 
@@ -122,8 +140,16 @@ impl DefaultBranchSession {
             Ok(true)
         }
     }
+    /// 二阶段由 TM 驱动，没有可以接收指令的 RM
+    pub fn is_tm_driven(&self) -> bool {
+        self.resource_id
+            .as_ref()
+            .is_some_and(|r| r.0.starts_with(TM_DRIVEN_RESOURCE_PREFIX))
+    }
+
+    // AT 二阶段提交只需删除 undo log，可以异步进行
     pub(crate) fn can_be_committed_async(&self) -> bool {
-        true
+        self.branch_type == BranchType::AT
     }
 }
 
@@ -131,7 +157,7 @@ impl SessionStorable for DefaultBranchSession {
     fn encode(&self) -> Result<Vec<u8>, TransactionError> {
         // Implementation for encoding branch session to bytes
         // Similar to Java version's encode method
-        Ok(serde_json::to_vec(self).map_err(|e| TransactionError::new(e.to_string()))?)
+        serde_json::to_vec(self).map_err(|e| TransactionError::new(e.to_string()))
     }
 
     fn decode(data: &[u8]) -> Result<Self, TransactionError> {
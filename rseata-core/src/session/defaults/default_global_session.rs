@@ -6,10 +6,8 @@ use crate::session::global_session::GlobalSession;
 use crate::session::session_storable::SessionStorable;
 use crate::types::{GlobalStatus, Xid};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
-use std::sync::Arc;
+use std::collections::VecDeque;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,8 +24,6 @@ pub struct DefaultGlobalSession {
     pub lazy_load_branch: bool,
     pub active: bool,
     pub branch_sessions: VecDeque<DefaultBranchSession>,
-    #[serde(skip)]
-    pub lifecycle_listeners: Arc<RwLock<HashSet<String>>>,
 }
 
 impl DefaultGlobalSession {
@@ -57,7 +53,6 @@ impl DefaultGlobalSession {
             lazy_load_branch,
             active: true,
             branch_sessions: VecDeque::new(),
-            lifecycle_listeners: Arc::new(RwLock::new(HashSet::new())),
         }
     }
 
@@ -105,8 +100,31 @@ impl DefaultGlobalSession {
             .any(|branch| branch.branch_type == BranchType::AT)
     }
 
+    /// 全部分支都是 Saga 分支，此时由发起方驱动二阶段并上报结果
     pub fn is_saga(&self) -> bool {
-        false
+        !self.branch_sessions.is_empty()
+            && self
+                .branch_sessions
+                .iter()
+                .all(|branch| branch.branch_type == BranchType::SAGA)
+    }
+
+    /// 不含分支会话的轻量副本，用于不需要分支的查询
+    pub fn without_branches(&self) -> Self {
+        Self {
+            xid: self.xid.clone(),
+            transaction_id: self.transaction_id,
+            status: self.status,
+            application_id: self.application_id.clone(),
+            transaction_service_group: self.transaction_service_group.clone(),
+            transaction_name: self.transaction_name.clone(),
+            timeout_millis: self.timeout_millis,
+            begin_time_millis: self.begin_time_millis,
+            application_data: self.application_data.clone(),
+            lazy_load_branch: true,
+            active: self.active,
+            branch_sessions: VecDeque::new(),
+        }
     }
 
     pub fn is_timeout(&self) -> bool {
@@ -114,7 +132,7 @@ impl DefaultGlobalSession {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64;
-        (current_time - self.begin_time_millis) > self.begin_time_millis * 1000
+        current_time.saturating_sub(self.begin_time_millis) > self.timeout_millis
     }
 
     pub async fn get_branch(&self, branch_id: BranchId) -> Option<DefaultBranchSession> {
@@ -128,42 +146,17 @@ impl DefaultGlobalSession {
         self.branch_sessions.clone()
     }
 
-    // pub async fn reverse_sorted_branches(&self) -> Vec<DefaultBranchSession> {
-    //     let mut branches = self.sorted_branches().await;
-    //     branches.reverse();
-    //     branches
-    // }
+    pub async fn reverse_sorted_branches(&self) -> Vec<DefaultBranchSession> {
+        self.branch_sessions.iter().rev().cloned().collect()
+    }
 
     pub async fn has_branch(&self) -> bool {
         !self.branch_sessions.is_empty()
     }
 
-    pub async fn clean(&self) -> Result<(), TransactionError> {
-        // Release global session lock
-        // lock_manager.release_global_session_lock(self).await
-        Ok(())
-    }
-
-    pub async fn close_and_clean(&mut self) -> Result<(), TransactionError> {
-        // self.close().await?;
-        if self.has_at_branch().await {
-            self.clean().await?;
-        }
-        Ok(())
-    }
-
-    pub async fn add_session_lifecycle_listener(&self, listener_id: String) {
-        self.lifecycle_listeners.write().await.insert(listener_id);
-    }
-
-    pub async fn remove_session_lifecycle_listener(&self, listener_id: &str) {
-        self.lifecycle_listeners.write().await.remove(listener_id);
-    }
-
-    pub async fn async_commit(&self) -> Result<(), TransactionError> {
-        // self.change_global_status(GlobalStatus::AsyncCommitting)
-        //     .await
-        Ok(())
+    /// 关闭会话，不再接受新的分支注册
+    pub fn close(&mut self) {
+        self.active = false;
     }
 }
 
@@ -179,7 +172,7 @@ impl GlobalSession for DefaultGlobalSession {
     }
 
     fn status(&self) -> GlobalStatus {
-        self.status.clone()
+        self.status
     }
 
     fn application_id(&self) -> &str {
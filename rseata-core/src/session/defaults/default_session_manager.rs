@@ -1,21 +1,121 @@
 use crate::branch::BranchStatus;
 use crate::error::TransactionError;
-use crate::session::defaults::default_branch_session::DefaultBranchSession;
+use crate::event::defaults::event_publisher::DefaultEventPublisher;
+use crate::event::event::TransactionEvent;
+use crate::event::event_publisher::EventPublisher;
+use crate::event::event_type::TransactionEventType;
+use crate::session::defaults::default_branch_session::{DefaultBranchSession, QueuedInstruction};
 use crate::session::defaults::default_global_session::DefaultGlobalSession;
+use crate::session::defaults::finished_sessions::FinishedSessions;
 use crate::session::global_session::GlobalSession;
 use crate::session::session_condition::SessionCondition;
+use crate::session::session_lifecycle_listener::{ListenerDelivery, SessionLifecycleListener};
 use crate::session::session_manager::SessionManager;
 use crate::store::LogOperation;
 use crate::store::transaction_store_manager::TransactionStoreManager;
 use crate::types::{GlobalStatus, Xid};
 use async_trait::async_trait;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc};
+use uuid::Uuid;
+
+// 写锁分段数，不同全局事务的写入互不阻塞
+const WRITE_LOCK_STRIPES: usize = 64;
+// 默认保留最近结束的全局事务数
+const DEFAULT_FINISHED_SESSION_CAPACITY: usize = 100_000;
 
-#[derive(Debug)]
 pub struct DefaultSessionManager {
     name: String,
     transaction_store_manager:
         Box<dyn TransactionStoreManager<GlobalSession = DefaultGlobalSession>>,
     rollback_failed_unlock_enable: bool,
+    // 按 xid 分段串行化读改写，保证并发的提交、回滚、超时只有一个状态迁移生效
+    write_locks: Box<[Mutex<()>]>,
+    event_publisher: Option<Arc<DefaultEventPublisher>>,
+    lifecycle_listeners: std::sync::RwLock<Vec<RegisteredListener>>,
+    // 已移出存储的全局事务的最终状态
+    finished_sessions: std::sync::Mutex<FinishedSessions>,
+}
+
+pub type DefaultSessionLifecycleListener = dyn SessionLifecycleListener<
+        GlobalSession = DefaultGlobalSession,
+        BranchSession = DefaultBranchSession,
+    >;
+
+#[derive(Clone)]
+struct RegisteredListener {
+    listener: Arc<DefaultSessionLifecycleListener>,
+    // Async 监听器的投递队列，由该监听器独占的任务按写入顺序消费
+    queue: Option<mpsc::UnboundedSender<LifecycleEvent>>,
+}
+
+// 会话写入成功后通知监听器的事件，持有会话快照以便异步投递
+#[derive(Clone)]
+enum LifecycleEvent {
+    Begin(DefaultGlobalSession),
+    StatusChange(DefaultGlobalSession, GlobalStatus),
+    BranchStatusChange(DefaultGlobalSession, DefaultBranchSession, BranchStatus),
+    AddBranch(DefaultGlobalSession, DefaultBranchSession),
+    RemoveBranch(DefaultGlobalSession, DefaultBranchSession),
+    Close(DefaultGlobalSession),
+    SuccessEnd(DefaultGlobalSession),
+    FailEnd(DefaultGlobalSession),
+}
+
+impl LifecycleEvent {
+    fn xid(&self) -> &Xid {
+        match self {
+            Self::Begin(gs)
+            | Self::StatusChange(gs, _)
+            | Self::BranchStatusChange(gs, _, _)
+            | Self::AddBranch(gs, _)
+            | Self::RemoveBranch(gs, _)
+            | Self::Close(gs)
+            | Self::SuccessEnd(gs)
+            | Self::FailEnd(gs) => &gs.xid,
+        }
+    }
+
+    // 监听器失败只记录日志，不影响已经落盘的会话
+    async fn dispatch_logged(&self, listener: &DefaultSessionLifecycleListener) {
+        if let Err(e) = self.dispatch(listener).await {
+            log::error!("Session lifecycle listener failed: {}, {:?}", self.xid(), e);
+        }
+    }
+
+    async fn dispatch(
+        &self,
+        listener: &DefaultSessionLifecycleListener,
+    ) -> Result<(), TransactionError> {
+        match self {
+            Self::Begin(gs) => listener.on_begin(gs).await,
+            Self::StatusChange(gs, status) => listener.on_status_change(gs, *status).await,
+            Self::BranchStatusChange(gs, bs, status) => {
+                listener.on_branch_status_change(gs, bs, *status).await
+            }
+            Self::AddBranch(gs, bs) => listener.on_add_branch(gs, bs).await,
+            Self::RemoveBranch(gs, bs) => listener.on_remove_branch(gs, bs).await,
+            Self::Close(gs) => listener.on_close(gs).await,
+            Self::SuccessEnd(gs) => listener.on_success_end(gs).await,
+            Self::FailEnd(gs) => listener.on_fail_end(gs).await,
+        }
+    }
+}
+
+impl Debug for DefaultSessionManager {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DefaultSessionManager")
+            .field("name", &self.name)
+            .field("transaction_store_manager", &self.transaction_store_manager)
+            .field(
+                "rollback_failed_unlock_enable",
+                &self.rollback_failed_unlock_enable,
+            )
+            .finish()
+    }
 }
 
 impl DefaultSessionManager {
@@ -29,6 +129,110 @@ impl DefaultSessionManager {
             name,
             transaction_store_manager,
             rollback_failed_unlock_enable: true, // Should be from config
+            write_locks: (0..WRITE_LOCK_STRIPES).map(|_| Mutex::new(())).collect(),
+            event_publisher: None,
+            lifecycle_listeners: Default::default(),
+            finished_sessions: std::sync::Mutex::new(FinishedSessions::new(
+                DEFAULT_FINISHED_SESSION_CAPACITY,
+            )),
+        }
+    }
+
+    /// 保留最近结束的 capacity 个全局事务的最终状态，0 表示不保留
+    pub fn with_finished_session_capacity(mut self, capacity: usize) -> Self {
+        self.finished_sessions = std::sync::Mutex::new(FinishedSessions::new(capacity));
+        self
+    }
+
+    /// 已结束并移出存储的全局事务的最终状态，从未存在或已被淘汰时返回 None
+    pub fn finished_status(&self, xid: &Xid) -> Option<GlobalStatus> {
+        self.finished_sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(xid)
+    }
+
+    fn write_lock(&self, xid: &Xid) -> &Mutex<()> {
+        let mut hasher = DefaultHasher::new();
+        xid.hash(&mut hasher);
+        &self.write_locks[hasher.finish() as usize % self.write_locks.len()]
+    }
+
+    /// Async 监听器需在 tokio 运行时中注册
+    pub fn add_session_lifecycle_listener(&self, listener: Arc<DefaultSessionLifecycleListener>) {
+        let queue = match listener.delivery() {
+            ListenerDelivery::Sync => None,
+            ListenerDelivery::Async => {
+                let (tx, mut rx) = mpsc::unbounded_channel::<LifecycleEvent>();
+                let listener = listener.clone();
+                // 移除监听器后发送端被丢弃，处理完剩余事件后任务退出
+                tokio::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        event.dispatch_logged(listener.as_ref()).await;
+                    }
+                });
+                Some(tx)
+            }
+        };
+        self.lifecycle_listeners
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(RegisteredListener { listener, queue });
+    }
+
+    pub fn remove_session_lifecycle_listener(
+        &self,
+        listener: &Arc<DefaultSessionLifecycleListener>,
+    ) {
+        self.lifecycle_listeners
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|registered| !Arc::ptr_eq(&registered.listener, listener));
+    }
+
+    // 调用方持有该 xid 的写锁，各监听器收到的同一全局事务的事件与写入顺序一致
+    async fn notify(&self, event: LifecycleEvent) {
+        let listeners = self
+            .lifecycle_listeners
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        for registered in listeners {
+            match &registered.queue {
+                None => event.dispatch_logged(registered.listener.as_ref()).await,
+                Some(queue) => {
+                    if queue.send(event.clone()).is_err() {
+                        log::error!("Session lifecycle listener stopped: {}", event.xid());
+                    }
+                }
+            }
+        }
+    }
+
+    /// 每次被接受的全局状态迁移都会发布 GlobalStatusChange 事件
+    pub fn with_event_publisher(mut self, event_publisher: Arc<DefaultEventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+
+    async fn publish_status_change(
+        &self,
+        session: &DefaultGlobalSession,
+        from: GlobalStatus,
+        to: GlobalStatus,
+    ) {
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher
+                .publish(TransactionEvent {
+                    event_id: Uuid::new_v4().to_string(),
+                    timestamp: Default::default(),
+                    event_type: TransactionEventType::GlobalStatusChange { from, to },
+                    xid: session.xid.clone(),
+                    application_id: session.application_id.clone(),
+                    transaction_name: session.transaction_name.clone(),
+                    metadata: Default::default(),
+                })
+                .await;
         }
     }
 
@@ -41,10 +245,228 @@ impl DefaultSessionManager {
             .write_session(log_operation, session)
             .await
             .map_err(|e| TransactionError::ErrorInfo {
-                info: String::from(e.to_string()),
+                info: e.to_string(),
+            })?;
+        Ok(())
+    }
+
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> Result<(), TransactionError> {
+        self.transaction_store_manager
+            .write_branch_session(log_operation, branch_session)
+            .await
+            .map_err(|e| TransactionError::ErrorInfo {
+                info: e.to_string(),
+            })
+    }
+
+    // 分支操作只读取全局会话本身，监听器收到的会话不含分支
+    async fn find_global_session_header(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.transaction_store_manager
+            .read_global_session(xid, false)
+            .await
+    }
+}
+
+impl DefaultSessionManager {
+    /// 仅当存储中的状态仍为 expected 时才迁移，用于提交、回滚、超时之间的抢占
+    pub async fn compare_and_set_global_status(
+        &self,
+        session: &DefaultGlobalSession,
+        expected: GlobalStatus,
+        status: GlobalStatus,
+    ) -> Result<GlobalStatus, TransactionError> {
+        self.transition_global_status(session, Some(expected), status)
+            .await
+    }
+
+    async fn transition_global_status(
+        &self,
+        session: &DefaultGlobalSession,
+        expected: Option<GlobalStatus>,
+        status: GlobalStatus,
+    ) -> Result<GlobalStatus, TransactionError> {
+        let guard = self.write_lock(session.xid()).lock().await;
+        // 以存储中的最新会话为准，避免覆盖期间上报的分支状态
+        // 已结束并移除的会话不能被旧快照重新写回
+        let mut gs = self
+            .find_global_session(session.xid())
+            .await
+            .ok_or_else(|| TransactionError::ErrorInfo {
+                info: format!("no such global session {}", session.xid()),
+            })?;
+        let from = gs.status;
+        // 不带期望状态的更新把重复写入同一状态视为幂等；CAS 必须真正从 expected 迁出，
+        // 否则两个抢占者会同时认为自己成功
+        if expected.is_none() && from == status {
+            return Ok(status);
+        }
+        if expected.is_some_and(|expected| expected != from) || !from.can_transition_to(status) {
+            return Err(TransactionError::IllegalGlobalTransition {
+                xid: gs.xid.clone(),
+                from,
+                to: status,
+            });
+        }
+        gs.status = status;
+        // 共享存储的其他 TC 实例可能已修改状态，以存储中的比较结果为准
+        let swapped = self
+            .transaction_store_manager
+            .compare_and_set_global_session(&gs, from)
+            .await
+            .map_err(|e| TransactionError::ErrorInfo {
+                info: e.to_string(),
             })?;
+        if !swapped {
+            return Err(TransactionError::IllegalGlobalTransition {
+                xid: gs.xid.clone(),
+                from,
+                to: status,
+            });
+        }
+        self.notify(LifecycleEvent::StatusChange(gs.clone(), status))
+            .await;
+        drop(guard);
+        self.publish_status_change(&gs, from, status).await;
+        Ok(status)
+    }
+
+    /// 结束已到达终态的全局会话：成功时移除分支与全局会话，失败时关闭后保留在存储中等待人工处理
+    pub async fn end_global_session(
+        &self,
+        session: &DefaultGlobalSession,
+    ) -> Result<(), TransactionError> {
+        let _guard = self.write_lock(session.xid()).lock().await;
+        let Some(mut gs) = self.find_global_session(session.xid()).await else {
+            return Ok(());
+        };
+        if !gs.status.is_end() {
+            return Err(TransactionError::ErrorInfo {
+                info: format!("global session {} is not ended: {:?}", gs.xid, gs.status),
+            });
+        }
+
+        let success = GlobalStatus::is_two_phase_success(gs.status);
+        gs.close();
+        if success {
+            self.archive_global_session(&mut gs).await?;
+        } else {
+            self.write_session(LogOperation::GlobalUpdate, &gs).await?;
+        }
+
+        self.notify(LifecycleEvent::Close(gs.clone())).await;
+        if success {
+            self.notify(LifecycleEvent::SuccessEnd(gs)).await;
+        } else {
+            self.notify(LifecycleEvent::FailEnd(gs)).await;
+        }
+        Ok(())
+    }
+
+    /// 移除超过保留期限仍未处理的失败会话，只保留其最终状态
+    pub async fn purge_global_session(
+        &self,
+        session: &DefaultGlobalSession,
+    ) -> Result<(), TransactionError> {
+        let _guard = self.write_lock(session.xid()).lock().await;
+        let Some(mut gs) = self
+            .find_global_session_with_branches(session.xid(), true)
+            .await
+        else {
+            return Ok(());
+        };
+        if !gs.status.is_end() {
+            return Err(TransactionError::ErrorInfo {
+                info: format!("global session {} is not ended: {:?}", gs.xid, gs.status),
+            });
+        }
+        self.archive_global_session(&mut gs).await
+    }
+
+    // 删除分支与全局会话，并记录最终状态供之后的查询使用；调用方持有该 xid 的写锁
+    async fn archive_global_session(
+        &self,
+        gs: &mut DefaultGlobalSession,
+    ) -> Result<(), TransactionError> {
+        for branch_session in gs.branch_sessions.clone() {
+            self.remove_branch(gs, &branch_session).await?;
+        }
+        gs.branch_sessions.clear();
+        self.remove_global_session(gs).await?;
+        self.finished_sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(gs.xid.clone(), gs.status);
         Ok(())
     }
+
+    // 调用方持有该 xid 的写锁
+    async fn remove_branch(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+    ) -> Result<(), TransactionError> {
+        let Some(gs) = self.find_global_session_header(global_session.xid()).await else {
+            return Ok(());
+        };
+        let Some(bs) = self
+            .transaction_store_manager
+            .read_branch_session(global_session.xid(), branch_session.branch_id)
+            .await
+        else {
+            return Ok(());
+        };
+        self.write_branch_session(LogOperation::BranchRemove, &bs)
+            .await?;
+        self.notify(LifecycleEvent::RemoveBranch(gs, bs)).await;
+        Ok(())
+    }
+
+    /// 记录或清除分支上未送达的二阶段指令，不改变分支状态
+    pub async fn update_branch_queued_instruction(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+        queued_instruction: Option<QueuedInstruction>,
+    ) -> Result<(), TransactionError> {
+        let _guard = self.write_lock(global_session.xid()).lock().await;
+        let Some(mut bs) = self
+            .transaction_store_manager
+            .read_branch_session(global_session.xid(), branch_session.branch_id)
+            .await
+        else {
+            return Ok(());
+        };
+        if bs.queued_instruction == queued_instruction {
+            return Ok(());
+        }
+        bs.queued_instruction = queued_instruction;
+        self.write_branch_session(LogOperation::BranchUpdate, &bs)
+            .await
+    }
+
+    /// 已超时的 Begin 状态全局会话，按超时截止时间排序
+    pub async fn find_timeout_sessions(&self) -> Vec<DefaultGlobalSession> {
+        self.transaction_store_manager
+            .read_sort_by_timeout_begin_sessions(true)
+            .await
+            .into_iter()
+            .take_while(|session| session.is_timeout())
+            .collect()
+    }
+
+    /// 指定状态的全局会话，供二阶段重试等后台任务使用
+    pub async fn find_sessions_by_status(
+        &self,
+        statuses: &[GlobalStatus],
+    ) -> Vec<DefaultGlobalSession> {
+        self.transaction_store_manager
+            .read_session_by_global_status(statuses, true)
+            .await
+    }
 }
 
 #[async_trait]
@@ -56,8 +478,10 @@ impl SessionManager for DefaultSessionManager {
         &self,
         session: &Self::GlobalSession,
     ) -> Result<(), TransactionError> {
-        self.write_session(LogOperation::GlobalUpdate, session)
-            .await
+        let _guard = self.write_lock(session.xid()).lock().await;
+        self.write_session(LogOperation::GlobalAdd, session).await?;
+        self.notify(LifecycleEvent::Begin(session.clone())).await;
+        Ok(())
     }
 
     async fn find_global_session(&self, xid: &Xid) -> Option<Self::GlobalSession> {
@@ -83,17 +507,7 @@ impl SessionManager for DefaultSessionManager {
         session: &Self::GlobalSession,
         status: GlobalStatus,
     ) -> Result<GlobalStatus, TransactionError> {
-        if status == GlobalStatus::Rollbacking || status == GlobalStatus::TimeoutRollbacking {
-            // Update lock status for all branches
-            // for branch in session.branch_sessions() {
-            //     // branch.set_lock_status(LockStatus::Rollbacking);
-            // }
-        }
-
-        // session.set_status(status);
-        self.write_session(LogOperation::GlobalUpdate, session)
-            .await
-            .map(|_| status)
+        self.transition_global_status(session, None, status).await
     }
 
     async fn remove_global_session(
@@ -109,14 +523,18 @@ impl SessionManager for DefaultSessionManager {
         global_session: &Self::GlobalSession,
         branch_session: &Self::BranchSession,
     ) -> Result<(), TransactionError> {
-        let mut gs = self
-            .find_global_session(global_session.xid())
+        let _guard = self.write_lock(global_session.xid()).lock().await;
+        let gs = self
+            .find_global_session_header(global_session.xid())
             .await
             .ok_or_else(|| TransactionError::ErrorInfo {
                 info: String::from("global_session"),
             })?;
-        gs.add_branch(branch_session.clone());
-        self.write_session(LogOperation::BranchAdd, &gs).await
+        self.write_branch_session(LogOperation::BranchAdd, branch_session)
+            .await?;
+        self.notify(LifecycleEvent::AddBranch(gs, branch_session.clone()))
+            .await;
+        Ok(())
     }
 
     async fn update_branch_session_status(
@@ -125,20 +543,34 @@ impl SessionManager for DefaultSessionManager {
         branch_session: &Self::BranchSession,
         status: BranchStatus,
     ) -> Result<(), TransactionError> {
-        let mut gs = self
-            .find_global_session(global_session.xid())
+        let _guard = self.write_lock(global_session.xid()).lock().await;
+        let gs = self
+            .find_global_session_header(global_session.xid())
             .await
             .ok_or_else(|| TransactionError::ErrorInfo {
                 info: String::from("global_session"),
             })?;
-
-        gs.branch_sessions.iter_mut().for_each(|e| {
-            if e.branch_id == branch_session.branch_id {
-                e.status = status;
-            }
-        });
-
-        self.write_session(LogOperation::BranchUpdate, &gs).await
+        // 分支已被移除时没有可更新的状态
+        let Some(mut bs) = self
+            .transaction_store_manager
+            .read_branch_session(global_session.xid(), branch_session.branch_id)
+            .await
+        else {
+            return Ok(());
+        };
+        if !bs.status.can_transition_to(status) {
+            return Err(TransactionError::IllegalBranchTransition {
+                branch_id: bs.branch_id,
+                from: bs.status,
+                to: status,
+            });
+        }
+        bs.status = status;
+        self.write_branch_session(LogOperation::BranchUpdate, &bs)
+            .await?;
+        self.notify(LifecycleEvent::BranchStatusChange(gs, bs, status))
+            .await;
+        Ok(())
     }
 
     async fn remove_branch_session(
@@ -146,19 +578,301 @@ impl SessionManager for DefaultSessionManager {
         global_session: &Self::GlobalSession,
         branch_session: &Self::BranchSession,
     ) -> Result<(), TransactionError> {
-        self.write_session(LogOperation::BranchRemove, global_session)
-            .await
+        let _guard = self.write_lock(global_session.xid()).lock().await;
+        self.remove_branch(global_session, branch_session).await
     }
 
     async fn all_sessions(&self) -> Vec<Self::GlobalSession> {
-        vec![]
+        self.find_global_sessions(&SessionCondition::new()).await
     }
 
     async fn find_global_sessions(&self, condition: &SessionCondition) -> Vec<Self::GlobalSession> {
-        vec![]
+        self.transaction_store_manager
+            .read_session_by_session_condition(condition)
+            .await
     }
 
     async fn destroy(&self) {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch::{BranchId, BranchType};
+    use crate::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+
+    // 记录整会话读写的存储，分支操作不应触发这些调用
+    #[derive(Debug, Default)]
+    struct BranchOnlyStore {
+        inner: MemeryTransactionStoreManager,
+        full_session_calls: Arc<std::sync::Mutex<Vec<&'static str>>>,
+    }
+
+    impl BranchOnlyStore {
+        fn record(&self, call: &'static str) {
+            self.full_session_calls.lock().unwrap().push(call);
+        }
+    }
+
+    #[async_trait]
+    impl TransactionStoreManager for BranchOnlyStore {
+        type GlobalSession = DefaultGlobalSession;
+
+        async fn write_session(
+            &self,
+            log_operation: LogOperation,
+            session: &DefaultGlobalSession,
+        ) -> anyhow::Result<()> {
+            if matches!(
+                log_operation,
+                LogOperation::BranchAdd | LogOperation::BranchUpdate | LogOperation::BranchRemove
+            ) {
+                self.record("write_session");
+            }
+            self.inner.write_session(log_operation, session).await
+        }
+
+        async fn write_branch_session(
+            &self,
+            log_operation: LogOperation,
+            branch_session: &DefaultBranchSession,
+        ) -> anyhow::Result<()> {
+            self.inner
+                .write_branch_session(log_operation, branch_session)
+                .await
+        }
+
+        async fn read_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+            self.inner.read_session(xid).await
+        }
+
+        async fn read_branch_session(
+            &self,
+            xid: &Xid,
+            branch_id: BranchId,
+        ) -> Option<DefaultBranchSession> {
+            self.inner.read_branch_session(xid, branch_id).await
+        }
+
+        async fn read_session_with_branches(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+            self.record("read_session_with_branches");
+            self.inner.read_session_with_branches(xid).await
+        }
+
+        async fn read_global_session(
+            &self,
+            xid: &Xid,
+            with_branch_sessions: bool,
+        ) -> Option<DefaultGlobalSession> {
+            if with_branch_sessions {
+                self.record("read_global_session");
+            }
+            self.inner
+                .read_global_session(xid, with_branch_sessions)
+                .await
+        }
+
+        async fn read_sort_by_timeout_begin_sessions(
+            &self,
+            with_branch_sessions: bool,
+        ) -> Vec<DefaultGlobalSession> {
+            self.inner
+                .read_sort_by_timeout_begin_sessions(with_branch_sessions)
+                .await
+        }
+
+        async fn read_session_by_global_status(
+            &self,
+            statuses: &[GlobalStatus],
+            with_branch_sessions: bool,
+        ) -> Vec<DefaultGlobalSession> {
+            self.inner
+                .read_session_by_global_status(statuses, with_branch_sessions)
+                .await
+        }
+
+        async fn read_session_by_session_condition(
+            &self,
+            condition: &SessionCondition,
+        ) -> Vec<DefaultGlobalSession> {
+            self.inner
+                .read_session_by_session_condition(condition)
+                .await
+        }
+    }
+
+    fn session_manager() -> DefaultSessionManager {
+        DefaultSessionManager::new(
+            "test".to_string(),
+            Box::new(MemeryTransactionStoreManager::new()),
+        )
+    }
+
+    #[derive(Default)]
+    struct RecordingListener {
+        events: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    impl RecordingListener {
+        fn record(&self, event: &'static str) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[async_trait]
+    impl SessionLifecycleListener for RecordingListener {
+        type GlobalSession = DefaultGlobalSession;
+        type BranchSession = DefaultBranchSession;
+
+        fn delivery(&self) -> ListenerDelivery {
+            ListenerDelivery::Async
+        }
+
+        // 先到的事件处理得更慢，逐事件派发任务时顺序会被打乱
+        async fn on_begin(&self, _: &DefaultGlobalSession) -> Result<(), TransactionError> {
+            tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+            self.record("begin");
+            Ok(())
+        }
+
+        async fn on_status_change(
+            &self,
+            _: &DefaultGlobalSession,
+            _: GlobalStatus,
+        ) -> Result<(), TransactionError> {
+            self.record("status_change");
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn async_listener_receives_events_in_order() {
+        let manager = session_manager();
+        let listener = Arc::new(RecordingListener::default());
+        manager.add_session_lifecycle_listener(listener.clone());
+
+        let gs = DefaultGlobalSession::new(
+            "app".to_string(),
+            "group".to_string(),
+            "tx".to_string(),
+            60_000,
+            false,
+        );
+        manager.add_global_session(&gs).await.unwrap();
+        manager
+            .update_global_session_status(&gs, GlobalStatus::Rollbacking)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(
+            *listener.events.lock().unwrap(),
+            vec!["begin", "status_change"]
+        );
+    }
+
+    #[tokio::test]
+    async fn branch_operations_only_touch_the_branch() {
+        let store = BranchOnlyStore::default();
+        let full_session_calls = store.full_session_calls.clone();
+        let manager = DefaultSessionManager::new("test".to_string(), Box::new(store));
+        let gs = DefaultGlobalSession::new(
+            "app".to_string(),
+            "group".to_string(),
+            "tx".to_string(),
+            60_000,
+            false,
+        );
+        manager.add_global_session(&gs).await.unwrap();
+
+        let branches: Vec<DefaultBranchSession> = (1..=3u64)
+            .map(|id| {
+                let mut bs = DefaultBranchSession::new(BranchType::AT);
+                bs.xid = gs.xid.clone();
+                bs.branch_id = id.into();
+                bs.status = BranchStatus::Registered;
+                bs
+            })
+            .collect();
+        for bs in &branches {
+            manager.add_branch_session(&gs, bs).await.unwrap();
+        }
+        manager
+            .update_branch_session_status(&gs, &branches[0], BranchStatus::PhaseOneDone)
+            .await
+            .unwrap();
+        manager
+            .update_branch_queued_instruction(&gs, &branches[1], None)
+            .await
+            .unwrap();
+        manager
+            .remove_branch_session(&gs, &branches[2])
+            .await
+            .unwrap();
+        // 已移除的分支再次更新是无操作
+        manager
+            .update_branch_session_status(&gs, &branches[2], BranchStatus::PhaseOneDone)
+            .await
+            .unwrap();
+
+        assert!(full_session_calls.lock().unwrap().is_empty());
+        let stored = manager
+            .find_global_session_with_branches(gs.xid(), true)
+            .await
+            .unwrap();
+        let statuses: Vec<_> = stored
+            .branch_sessions
+            .iter()
+            .map(|bs| (u64::from(bs.branch_id), bs.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (1, BranchStatus::PhaseOneDone),
+                (2, BranchStatus::Registered)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn compare_and_set_requires_expected_status() {
+        let manager = session_manager();
+        let gs = DefaultGlobalSession::new(
+            "app".to_string(),
+            "group".to_string(),
+            "tx".to_string(),
+            60_000,
+            false,
+        );
+        manager.add_global_session(&gs).await.unwrap();
+
+        let committing = manager
+            .compare_and_set_global_status(&gs, GlobalStatus::Begin, GlobalStatus::Committing)
+            .await;
+        assert_eq!(committing.unwrap(), GlobalStatus::Committing);
+
+        // 第二个抢占者即使目标状态与当前状态相同也必须失败
+        let again = manager
+            .compare_and_set_global_status(&gs, GlobalStatus::Begin, GlobalStatus::Committing)
+            .await;
+        assert!(matches!(
+            again,
+            Err(TransactionError::IllegalGlobalTransition {
+                from: GlobalStatus::Committing,
+                ..
+            })
+        ));
+        let same = manager
+            .compare_and_set_global_status(&gs, GlobalStatus::Committing, GlobalStatus::Committing)
+            .await;
+        assert!(same.is_err());
+
+        // 不带期望状态的更新仍是幂等的
+        let update = manager
+            .update_global_session_status(&gs, GlobalStatus::Committing)
+            .await;
+        assert_eq!(update.unwrap(), GlobalStatus::Committing);
+    }
+}
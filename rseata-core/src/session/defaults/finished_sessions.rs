@@ -0,0 +1,65 @@
+use crate::types::{GlobalStatus, Xid};
+use std::collections::{HashMap, VecDeque};
+
+/// 已结束并移出存储的全局事务的最终状态
+///
+/// 容量有限，超出时按结束顺序淘汰最早的记录；用于区分“已结束”和“从未存在”的 xid
+#[derive(Debug)]
+pub struct FinishedSessions {
+    capacity: usize,
+    statuses: HashMap<Xid, GlobalStatus>,
+    order: VecDeque<Xid>,
+}
+
+impl FinishedSessions {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            statuses: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, xid: Xid, status: GlobalStatus) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.statuses.insert(xid.clone(), status).is_some() {
+            return;
+        }
+        self.order.push_back(xid);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statuses.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn get(&self, xid: &Xid) -> Option<GlobalStatus> {
+        self.statuses.get(xid).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_beyond_capacity() {
+        let mut finished = FinishedSessions::new(2);
+        finished.record(Xid::from("a"), GlobalStatus::Committed);
+        finished.record(Xid::from("b"), GlobalStatus::Rollbacked);
+        finished.record(Xid::from("a"), GlobalStatus::Committed);
+        finished.record(Xid::from("c"), GlobalStatus::RollbackFailed);
+
+        assert_eq!(finished.get(&Xid::from("a")), None);
+        assert_eq!(
+            finished.get(&Xid::from("b")),
+            Some(GlobalStatus::Rollbacked)
+        );
+        assert_eq!(
+            finished.get(&Xid::from("c")),
+            Some(GlobalStatus::RollbackFailed)
+        );
+    }
+}
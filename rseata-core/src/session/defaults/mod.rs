@@ -1,3 +1,4 @@
 pub mod default_branch_session;
 pub mod default_global_session;
 pub mod default_session_manager;
+pub mod finished_sessions;
@@ -4,7 +4,7 @@ pub mod global_session;
 pub mod session_condition;
 pub mod session_helper;
 pub mod session_life_cycle;
-mod session_lifecycle_listener;
+pub mod session_lifecycle_listener;
 pub mod session_manager;
 pub mod session_storable;
 
@@ -19,9 +19,10 @@ pub struct ClientSession {
     pub transaction_name: String,
     xid: RwLock<Option<Xid>>,
     is_global_tx_started: AtomicBool,
-    rm: RwLock<Vec<String>>,
     branch_id: AtomicU64,
     branch_undo_logs: tokio::sync::RwLock<Vec<String>>,
+    // 本地事务执行过可能写入的语句，与是否生成了 undo 镜像无关
+    branch_has_writes: AtomicBool,
     branch_luck_keys: tokio::sync::RwLock<Option<String>>,
 }
 
@@ -30,10 +31,10 @@ impl ClientSession {
         Self {
             transaction_name,
             xid: RwLock::new(None),
-            rm: RwLock::new(Vec::new()),
             is_global_tx_started: AtomicBool::new(false),
             branch_id: AtomicU64::new(0),
             branch_undo_logs: tokio::sync::RwLock::new(Vec::new()),
+            branch_has_writes: AtomicBool::new(false),
             branch_luck_keys: tokio::sync::RwLock::new(None),
         }
     }
@@ -71,6 +72,7 @@ impl ClientSession {
                 let mut luck = self.branch_undo_logs.write().await;
                 luck.clear();
                 self.branch_id.store(0, Ordering::Release);
+                self.branch_has_writes.store(false, Ordering::Release);
             }
 
             {
@@ -91,4 +93,21 @@ impl ClientSession {
     pub async fn get_branch_luck_keys(&self) -> Option<String> {
         self.branch_luck_keys.read().await.clone()
     }
+
+    pub async fn add_branch_undo_log(&self, undo_log: String) {
+        self.branch_undo_logs.write().await.push(undo_log);
+    }
+
+    pub async fn has_branch_undo_logs(&self) -> bool {
+        !self.branch_undo_logs.read().await.is_empty()
+    }
+
+    pub fn mark_branch_writes(&self) {
+        self.branch_has_writes.store(true, Ordering::Release);
+    }
+
+    /// 本地事务没有执行过任何可能写入的语句时视为只读，不需要注册分支
+    pub fn has_branch_writes(&self) -> bool {
+        self.branch_has_writes.load(Ordering::Acquire)
+    }
 }
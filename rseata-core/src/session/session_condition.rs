@@ -1,4 +1,23 @@
+use crate::session::defaults::default_global_session::DefaultGlobalSession;
 use crate::types::{GlobalStatus, Xid};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// 条件查询结果的排序字段，排序值相同时按 xid 排序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionSortBy {
+    #[default]
+    BeginTime,
+    // 超时截止时间，即开始时间 + 超时时长
+    Deadline,
+}
+
+/// 分页游标，记录上一页最后一个会话的排序值和 xid，下一页从它之后开始
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCursor {
+    pub sort_key: u64,
+    pub xid: Xid,
+}
 
 #[derive(Debug, Clone)]
 pub struct SessionCondition {
@@ -8,6 +27,17 @@ pub struct SessionCondition {
     pub statuses: Vec<GlobalStatus>,
     pub over_time_alive_mills: Option<u64>,
     pub lazy_load_branch: bool,
+    pub sort_by: SessionSortBy,
+    pub descending: bool,
+    pub cursor: Option<SessionCursor>,
+    // None 表示不分页
+    pub limit: Option<usize>,
+}
+
+impl Default for SessionCondition {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SessionCondition {
@@ -19,6 +49,10 @@ impl SessionCondition {
             statuses: Vec::new(),
             over_time_alive_mills: None,
             lazy_load_branch: false,
+            sort_by: SessionSortBy::default(),
+            descending: false,
+            cursor: None,
+            limit: None,
         }
     }
 
@@ -50,4 +84,102 @@ impl SessionCondition {
             ..Self::new()
         }
     }
+
+    pub fn sorted_by(mut self, sort_by: SessionSortBy, descending: bool) -> Self {
+        self.sort_by = sort_by;
+        self.descending = descending;
+        self
+    }
+
+    /// 取游标之后的 limit 个会话，第一页游标为 None
+    pub fn page(mut self, cursor: Option<SessionCursor>, limit: usize) -> Self {
+        self.cursor = cursor;
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn without_branches(mut self) -> Self {
+        self.lazy_load_branch = true;
+        self
+    }
+
+    /// status 与 statuses 的并集，为空表示不按状态过滤
+    pub fn all_statuses(&self) -> Vec<GlobalStatus> {
+        let mut statuses = self.statuses.clone();
+        if let Some(status) = self.status
+            && !statuses.contains(&status)
+        {
+            statuses.push(status);
+        }
+        statuses
+    }
+
+    /// 会话是否满足过滤条件且位于游标之后
+    pub fn matches(&self, session: &DefaultGlobalSession, now_millis: u64) -> bool {
+        let statuses = self.all_statuses();
+        self.xid.as_ref().is_none_or(|xid| *xid == session.xid)
+            && self
+                .transaction_id
+                .is_none_or(|id| id == session.transaction_id)
+            && (statuses.is_empty() || statuses.contains(&session.status))
+            && self
+                .over_time_alive_mills
+                .is_none_or(|over| session.begin_time_millis < now_millis.saturating_sub(over))
+            && self.after_cursor(session)
+    }
+
+    pub fn after_cursor(&self, session: &DefaultGlobalSession) -> bool {
+        let Some(cursor) = &self.cursor else {
+            return true;
+        };
+        let ordering = self
+            .sort_key(session)
+            .cmp(&cursor.sort_key)
+            .then_with(|| session.xid.0.cmp(&cursor.xid.0));
+        match self.descending {
+            true => ordering.is_lt(),
+            false => ordering.is_gt(),
+        }
+    }
+
+    pub fn sort_key(&self, session: &DefaultGlobalSession) -> u64 {
+        match self.sort_by {
+            SessionSortBy::BeginTime => session.begin_time_millis,
+            SessionSortBy::Deadline => session
+                .begin_time_millis
+                .saturating_add(session.timeout_millis),
+        }
+    }
+
+    pub fn compare(&self, a: &DefaultGlobalSession, b: &DefaultGlobalSession) -> Ordering {
+        let ordering = self
+            .sort_key(a)
+            .cmp(&self.sort_key(b))
+            .then_with(|| a.xid.0.cmp(&b.xid.0));
+        match self.descending {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    }
+
+    /// 查询下一页的游标
+    pub fn cursor_of(&self, session: &DefaultGlobalSession) -> SessionCursor {
+        SessionCursor {
+            sort_key: self.sort_key(session),
+            xid: session.xid.clone(),
+        }
+    }
+
+    /// 对已过滤的会话排序并截取一页，只需部分排序出前 limit 个
+    pub fn paginate<S: Borrow<DefaultGlobalSession>>(&self, mut sessions: Vec<S>) -> Vec<S> {
+        let compare = |a: &S, b: &S| self.compare(a.borrow(), b.borrow());
+        if let Some(limit) = self.limit
+            && limit < sessions.len()
+        {
+            sessions.select_nth_unstable_by(limit, compare);
+            sessions.truncate(limit);
+        }
+        sessions.sort_by(compare);
+        sessions
+    }
 }
@@ -5,39 +5,76 @@ use crate::session::global_session::GlobalSession;
 use crate::types::GlobalStatus;
 use async_trait::async_trait;
 
+/// 监听器的通知方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerDelivery {
+    /// 在会话写入后、释放该全局事务的写锁前同步调用，会拖慢事务流程；回调中不能再写同一全局事务
+    Sync,
+    /// 投递到该监听器独占的队列中按顺序执行，不阻塞事务流程
+    Async,
+}
+
+/// 会话生命周期监听器，所有回调默认为空实现，只需覆盖关心的事件
 #[async_trait]
 pub trait SessionLifecycleListener: Send + Sync {
     type GlobalSession: GlobalSession + Send + Sync;
     type BranchSession: BranchSession + Send + Sync;
-    async fn on_begin(&self, global_session: &Self::GlobalSession) -> Result<(), TransactionError>;
+
+    fn delivery(&self) -> ListenerDelivery {
+        ListenerDelivery::Sync
+    }
+
+    async fn on_begin(
+        &self,
+        _global_session: &Self::GlobalSession,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
     async fn on_status_change(
         &self,
-        global_session: &Self::GlobalSession,
-        status: GlobalStatus,
-    ) -> Result<(), TransactionError>;
+        _global_session: &Self::GlobalSession,
+        _status: GlobalStatus,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
     async fn on_branch_status_change(
         &self,
-        global_session: &Self::GlobalSession,
-        branch_session: &Self::BranchSession,
-        status: BranchStatus,
-    ) -> Result<(), TransactionError>;
+        _global_session: &Self::GlobalSession,
+        _branch_session: &Self::BranchSession,
+        _status: BranchStatus,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
     async fn on_add_branch(
         &self,
-        global_session: &Self::GlobalSession,
-        branch_session: &Self::BranchSession,
-    ) -> Result<(), TransactionError>;
+        _global_session: &Self::GlobalSession,
+        _branch_session: &Self::BranchSession,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
     async fn on_remove_branch(
         &self,
-        global_session: &Self::GlobalSession,
-        branch_session: &Self::BranchSession,
-    ) -> Result<(), TransactionError>;
-    async fn on_close(&self, global_session: &Self::GlobalSession) -> Result<(), TransactionError>;
+        _global_session: &Self::GlobalSession,
+        _branch_session: &Self::BranchSession,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
+    async fn on_close(
+        &self,
+        _global_session: &Self::GlobalSession,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
     async fn on_success_end(
         &self,
-        global_session: &Self::GlobalSession,
-    ) -> Result<(), TransactionError>;
+        _global_session: &Self::GlobalSession,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
     async fn on_fail_end(
         &self,
-        global_session: &Self::GlobalSession,
-    ) -> Result<(), TransactionError>;
+        _global_session: &Self::GlobalSession,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
 }
@@ -0,0 +1,856 @@
+use crate::branch::BranchId;
+use crate::event::defaults::event_publisher::DefaultEventPublisher;
+use crate::event::event::TransactionEvent;
+use crate::event::event_publisher::EventPublisher;
+use crate::event::event_type::TransactionEventType;
+use crate::session::defaults::default_branch_session::DefaultBranchSession;
+use crate::session::defaults::default_global_session::DefaultGlobalSession;
+use crate::session::session_condition::SessionCondition;
+use crate::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+use crate::store::transaction_store_manager::TransactionStoreManager;
+use crate::store::{LogOperation, StoreConfig};
+use crate::types::{GlobalStatus, Xid};
+use anyhow::{Context, anyhow, bail};
+use async_trait::async_trait;
+use crc::{CRC_32_ISO_HDLC, Crc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use tokio::sync::{RwLock, oneshot};
+use uuid::Uuid;
+
+const WAL_PREFIX: &str = "wal-";
+const WAL_SUFFIX: &str = ".log";
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const SNAPSHOT_SUFFIX: &str = ".snap";
+// 记录头：4 字节长度 + 4 字节 CRC32，均为小端
+const RECORD_HEADER_SIZE: usize = 8;
+const CHECKSUM: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// 基于预写日志的会话存储
+///
+/// 每次写入先交给 WAL 写线程追加并落盘，再更新内存索引；写线程把同时到达的记录合并为一次落盘。
+/// 追加一定数量的记录后生成快照并删除快照之前的段，启动时由快照和剩余段重建会话
+#[derive(Debug)]
+pub struct FileTransactionStoreManager {
+    memory: MemeryTransactionStoreManager,
+    dir: PathBuf,
+    wal: WalHandle,
+    // 写入在追加 WAL 和更新内存期间持有读锁，快照滚动段并读取内存视图时持有写锁
+    snapshot_gate: RwLock<()>,
+    snapshotting: AtomicBool,
+    snapshot_interval: u64,
+}
+
+#[derive(Debug)]
+enum WalCommand {
+    // 追加一条记录，落盘后返回上次快照以来的记录数
+    Append {
+        record: Vec<u8>,
+        done: oneshot::Sender<anyhow::Result<u64>>,
+    },
+    // 滚动到新段并重新计数，返回新段序号
+    Roll {
+        done: oneshot::Sender<anyhow::Result<u64>>,
+    },
+}
+
+/// WAL 写线程的句柄，文件读写和落盘都不在异步运行时的线程上执行
+#[derive(Debug)]
+struct WalHandle {
+    commands: mpsc::Sender<WalCommand>,
+}
+
+impl WalHandle {
+    fn spawn(writer: WalWriter) -> anyhow::Result<Self> {
+        let (commands, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("rseata-wal-writer"))
+            .spawn(move || writer.run(receiver))?;
+        Ok(Self { commands })
+    }
+
+    async fn append(&self, record: Vec<u8>) -> anyhow::Result<u64> {
+        self.request(|done| WalCommand::Append { record, done })
+            .await
+    }
+
+    async fn roll(&self) -> anyhow::Result<u64> {
+        self.request(|done| WalCommand::Roll { done }).await
+    }
+
+    async fn request(
+        &self,
+        command: impl FnOnce(oneshot::Sender<anyhow::Result<u64>>) -> WalCommand,
+    ) -> anyhow::Result<u64> {
+        let (done, result) = oneshot::channel();
+        self.commands
+            .send(command(done))
+            .map_err(|_| anyhow!("wal writer stopped"))?;
+        result.await.map_err(|_| anyhow!("wal writer stopped"))?
+    }
+}
+
+#[derive(Debug)]
+struct WalWriter {
+    dir: PathBuf,
+    file: File,
+    segment: u64,
+    // 当前段中已写入的字节数，写入失败时截断回该长度
+    size: u64,
+    max_segment_size: u64,
+    records_since_snapshot: u64,
+    // 落盘失败后文件内容不可信，之后的写入全部拒绝
+    failure: Option<String>,
+}
+
+impl WalWriter {
+    fn create(dir: &Path, segment: u64, max_segment_size: u64) -> anyhow::Result<Self> {
+        let file = open_segment(dir, segment)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            segment,
+            size,
+            max_segment_size,
+            records_since_snapshot: 0,
+            failure: None,
+        })
+    }
+
+    // 每次取出队列中全部命令，连续的追加只落盘一次
+    fn run(mut self, commands: mpsc::Receiver<WalCommand>) {
+        while let Ok(command) = commands.recv() {
+            let mut written = Vec::new();
+            for command in std::iter::once(command).chain(commands.try_iter()) {
+                match command {
+                    WalCommand::Append { record, done } => {
+                        if self.size > 0 && self.size + record.len() as u64 > self.max_segment_size
+                        {
+                            self.sync(&mut written);
+                            if let Err(e) = self.roll() {
+                                let _ = done.send(Err(e));
+                                continue;
+                            }
+                        }
+                        match self.write(&record) {
+                            Ok(()) => written.push(done),
+                            Err(e) => {
+                                let _ = done.send(Err(e));
+                            }
+                        }
+                    }
+                    WalCommand::Roll { done } => {
+                        self.sync(&mut written);
+                        let rolled = self.roll().map(|()| {
+                            self.records_since_snapshot = 0;
+                            self.segment
+                        });
+                        let _ = done.send(rolled);
+                    }
+                }
+            }
+            self.sync(&mut written);
+        }
+    }
+
+    fn write(&mut self, record: &[u8]) -> anyhow::Result<()> {
+        if let Some(failure) = &self.failure {
+            bail!("wal unavailable after failed sync: {failure}");
+        }
+        if let Err(e) = self.file.write_all(record) {
+            // 去掉写了一半的记录，避免后续记录接在残缺记录之后
+            if let Err(truncate) = self.file.set_len(self.size) {
+                self.failure = Some(truncate.to_string());
+            }
+            return Err(e).context("append wal record");
+        }
+        self.size += record.len() as u64;
+        Ok(())
+    }
+
+    // 落盘已写入的记录并通知等待者
+    fn sync(&mut self, written: &mut Vec<oneshot::Sender<anyhow::Result<u64>>>) {
+        if written.is_empty() {
+            return;
+        }
+        match self.file.sync_data() {
+            Ok(()) => {
+                self.records_since_snapshot += written.len() as u64;
+                for done in written.drain(..) {
+                    let _ = done.send(Ok(self.records_since_snapshot));
+                }
+            }
+            Err(e) => {
+                log::error!("Sync wal segment {} failed: {}", self.segment, e);
+                self.failure = Some(e.to_string());
+                for done in written.drain(..) {
+                    let _ = done.send(Err(anyhow!("sync wal failed: {e}")));
+                }
+            }
+        }
+    }
+
+    fn roll(&mut self) -> anyhow::Result<()> {
+        if let Some(failure) = &self.failure {
+            bail!("wal unavailable after failed sync: {failure}");
+        }
+        let file = open_segment(&self.dir, self.segment + 1)?;
+        self.size = file.metadata()?.len();
+        self.file = file;
+        self.segment += 1;
+        Ok(())
+    }
+}
+
+// WAL 记录：整个会话的操作编码为 [操作, 会话]，单个分支的操作编码为对象
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WalRecord {
+    Session(LogOperation, DefaultGlobalSession),
+    Branch {
+        operation: LogOperation,
+        branch: DefaultBranchSession,
+    },
+}
+
+/// 从快照和 WAL 读出的恢复数据
+struct Recovered {
+    sessions: Vec<DefaultGlobalSession>,
+    records: Vec<WalRecord>,
+    writer: WalWriter,
+}
+
+impl FileTransactionStoreManager {
+    /// 打开 file_store_dir 下的存储，并从快照和 WAL 恢复会话
+    pub async fn open(
+        config: &StoreConfig,
+        event_publisher: Option<Arc<DefaultEventPublisher>>,
+    ) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(&config.file_store_dir);
+        let max_segment_size = config.file_max_segment_size;
+        let Recovered {
+            sessions,
+            records,
+            writer,
+        } = {
+            let dir = dir.clone();
+            tokio::task::spawn_blocking(move || recover(&dir, max_segment_size)).await??
+        };
+
+        let memory = MemeryTransactionStoreManager::new();
+        publish(
+            &event_publisher,
+            TransactionEventType::RecoveryStarted {
+                recovered_sessions: sessions.len(),
+            },
+        )
+        .await;
+        for session in &sessions {
+            memory
+                .write_session(LogOperation::GlobalAdd, session)
+                .await?;
+        }
+        let replayed = records.len();
+        for record in &records {
+            match record {
+                WalRecord::Session(log_operation, session) => {
+                    memory.write_session(*log_operation, session).await?
+                }
+                WalRecord::Branch { operation, branch } => {
+                    memory.write_branch_session(*operation, branch).await?
+                }
+            }
+        }
+
+        let store = Self {
+            memory,
+            dir,
+            wal: WalHandle::spawn(writer)?,
+            snapshot_gate: RwLock::new(()),
+            snapshotting: AtomicBool::new(false),
+            snapshot_interval: config.file_snapshot_interval.max(1),
+        };
+        // 重放过 WAL 则立即压缩，缩短下次启动的恢复时间
+        if replayed > 0 {
+            store.snapshot().await?;
+        }
+
+        let total_sessions = store.memory.session_count().await;
+        log::info!(
+            "Session store recovered from {}: {} sessions, {} wal records replayed",
+            store.dir.display(),
+            total_sessions,
+            replayed
+        );
+        publish(
+            &event_publisher,
+            TransactionEventType::RecoveryCompleted { total_sessions },
+        )
+        .await;
+        Ok(store)
+    }
+
+    // 滚动到新段后写入快照，快照覆盖新段之前的全部记录
+    async fn snapshot(&self) -> anyhow::Result<()> {
+        // 只在滚动和读取内存视图期间阻塞写入，序列化和写文件在阻塞线程池中完成
+        let (segment, sessions) = {
+            let _gate = self.snapshot_gate.write().await;
+            let segment = self.wal.roll().await?;
+            (segment, self.memory.all_sessions().await)
+        };
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || write_snapshot(&dir, segment, &sessions)).await?
+    }
+
+    // 先追加 WAL 再更新内存视图，同一 xid 的写入由会话管理器串行执行，两者的顺序一致
+    async fn append<F>(&self, record: Vec<u8>, apply: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = anyhow::Result<()>>,
+    {
+        let records_since_snapshot = {
+            let _gate = self.snapshot_gate.read().await;
+            let records_since_snapshot = self.wal.append(encode_record(&record)).await?;
+            apply.await?;
+            records_since_snapshot
+        };
+        self.snapshot_if_due(records_since_snapshot).await;
+        Ok(())
+    }
+
+    // 同一时刻只生成一个快照，失败时等待下一个周期重试
+    async fn snapshot_if_due(&self, records_since_snapshot: u64) {
+        if records_since_snapshot < self.snapshot_interval
+            || self.snapshotting.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        if let Err(e) = self.snapshot().await {
+            log::error!(
+                "Snapshot session store {} failed: {:?}",
+                self.dir.display(),
+                e
+            );
+        }
+        self.snapshotting.store(false, Ordering::Release);
+    }
+}
+
+#[async_trait]
+impl TransactionStoreManager for FileTransactionStoreManager {
+    type GlobalSession = DefaultGlobalSession;
+
+    async fn write_session(
+        &self,
+        log_operation: LogOperation,
+        session: &Self::GlobalSession,
+    ) -> anyhow::Result<()> {
+        let record = serde_json::to_vec(&(log_operation, session))?;
+        self.append(record, self.memory.write_session(log_operation, session))
+            .await
+    }
+
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        // 会话不存在的分支记录无法重放，不能写入 WAL
+        if self
+            .memory
+            .read_global_session(&branch_session.xid, false)
+            .await
+            .is_none()
+        {
+            bail!("no such global session {}", branch_session.xid);
+        }
+        let record = serde_json::to_vec(&WalRecord::Branch {
+            operation: log_operation,
+            branch: branch_session.clone(),
+        })?;
+        self.append(
+            record,
+            self.memory
+                .write_branch_session(log_operation, branch_session),
+        )
+        .await
+    }
+
+    async fn read_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.memory.read_session(xid).await
+    }
+
+    async fn read_branch_session(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Option<DefaultBranchSession> {
+        self.memory.read_branch_session(xid, branch_id).await
+    }
+
+    async fn read_session_with_branches(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.memory.read_session_with_branches(xid).await
+    }
+
+    async fn read_global_session(
+        &self,
+        xid: &Xid,
+        with_branch_sessions: bool,
+    ) -> Option<DefaultGlobalSession> {
+        self.memory
+            .read_global_session(xid, with_branch_sessions)
+            .await
+    }
+
+    async fn read_sort_by_timeout_begin_sessions(
+        &self,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.memory
+            .read_sort_by_timeout_begin_sessions(with_branch_sessions)
+            .await
+    }
+
+    async fn read_session_by_global_status(
+        &self,
+        statuses: &[GlobalStatus],
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.memory
+            .read_session_by_global_status(statuses, with_branch_sessions)
+            .await
+    }
+
+    async fn read_session_by_session_condition(
+        &self,
+        condition: &SessionCondition,
+    ) -> Vec<DefaultGlobalSession> {
+        self.memory
+            .read_session_by_session_condition(condition)
+            .await
+    }
+}
+
+async fn publish(
+    event_publisher: &Option<Arc<DefaultEventPublisher>>,
+    event_type: TransactionEventType,
+) {
+    if let Some(event_publisher) = event_publisher {
+        event_publisher
+            .publish(TransactionEvent {
+                event_id: Uuid::new_v4().to_string(),
+                timestamp: Default::default(),
+                event_type,
+                xid: Xid(String::new()),
+                application_id: "".to_string(),
+                transaction_name: "".to_string(),
+                metadata: Default::default(),
+            })
+            .await;
+    }
+}
+
+fn encode_record(payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&CHECKSUM.checksum(payload).to_le_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+/// 解析连续的记录，遇到长度不足或校验失败即停止，返回有效记录及其总长度
+fn decode_records(bytes: &[u8]) -> (Vec<&[u8]>, usize) {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while bytes.len() - offset >= RECORD_HEADER_SIZE {
+        let header = &bytes[offset..offset + RECORD_HEADER_SIZE];
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let checksum = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let start = offset + RECORD_HEADER_SIZE;
+        let Some(payload) = bytes.get(start..start + len) else {
+            break;
+        };
+        if CHECKSUM.checksum(payload) != checksum {
+            break;
+        }
+        records.push(payload);
+        offset = start + len;
+    }
+    (records, offset)
+}
+
+// 快照之后的段按顺序重放，最后一个段尾部的残缺记录视为崩溃时未写完
+fn recover(dir: &Path, max_segment_size: u64) -> anyhow::Result<Recovered> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("create session store dir {}", dir.display()))?;
+    let (snapshot_segment, sessions) = load_snapshot(dir)?;
+
+    let segments: Vec<(u64, PathBuf)> = list_files(dir, WAL_PREFIX, WAL_SUFFIX)?
+        .into_iter()
+        .filter(|(segment, _)| *segment >= snapshot_segment)
+        .collect();
+    let mut records = Vec::new();
+    for (index, (_, path)) in segments.iter().enumerate() {
+        let bytes = fs::read(path)?;
+        let (payloads, valid_len) = decode_records(&bytes);
+        if valid_len < bytes.len() {
+            if index + 1 != segments.len() {
+                bail!("corrupted wal segment {}", path.display());
+            }
+            log::warn!(
+                "Truncate torn wal tail {}: {} -> {} bytes",
+                path.display(),
+                bytes.len(),
+                valid_len
+            );
+            OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .set_len(valid_len as u64)?;
+        }
+        for payload in payloads {
+            let record = serde_json::from_slice(payload)
+                .with_context(|| format!("decode wal record in {}", path.display()))?;
+            records.push(record);
+        }
+    }
+
+    let next_segment = segments
+        .last()
+        .map_or(snapshot_segment, |(segment, _)| segment + 1)
+        .max(snapshot_segment);
+    Ok(Recovered {
+        sessions,
+        records,
+        writer: WalWriter::create(dir, next_segment, max_segment_size)?,
+    })
+}
+
+// 快照通过临时文件改名写入，落盘后删除快照之前的段和旧快照
+fn write_snapshot(
+    dir: &Path,
+    segment: u64,
+    sessions: &[DefaultGlobalSession],
+) -> anyhow::Result<()> {
+    let record = encode_record(&serde_json::to_vec(sessions)?);
+    let path = segment_path(dir, SNAPSHOT_PREFIX, segment, SNAPSHOT_SUFFIX);
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&record)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, &path)?;
+    if let Ok(dir) = File::open(dir) {
+        dir.sync_all().ok();
+    }
+
+    for (old, old_path) in list_files(dir, WAL_PREFIX, WAL_SUFFIX)? {
+        if old < segment {
+            fs::remove_file(old_path)?;
+        }
+    }
+    for (old, old_path) in list_files(dir, SNAPSHOT_PREFIX, SNAPSHOT_SUFFIX)? {
+        if old < segment {
+            fs::remove_file(old_path)?;
+        }
+    }
+    Ok(())
+}
+
+// 最新快照不可读说明存储已损坏
+fn load_snapshot(dir: &Path) -> anyhow::Result<(u64, Vec<DefaultGlobalSession>)> {
+    let Some((segment, path)) = list_files(dir, SNAPSHOT_PREFIX, SNAPSHOT_SUFFIX)?.pop() else {
+        return Ok((0, Vec::new()));
+    };
+    let bytes = fs::read(&path)?;
+    let Some(payload) = decode_records(&bytes).0.first().copied() else {
+        bail!("corrupted snapshot {}", path.display());
+    };
+    let sessions = serde_json::from_slice(payload)
+        .with_context(|| format!("decode snapshot {}", path.display()))?;
+    Ok((segment, sessions))
+}
+
+/// 按序号升序列出 {prefix}{序号}{suffix} 文件
+fn list_files(dir: &Path, prefix: &str, suffix: &str) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let segment = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| {
+                name.strip_prefix(prefix)?
+                    .strip_suffix(suffix)?
+                    .parse()
+                    .ok()
+            });
+        if let Some(segment) = segment {
+            files.push((segment, path));
+        }
+    }
+    files.sort_by_key(|(segment, _)| *segment);
+    Ok(files)
+}
+
+fn open_segment(dir: &Path, segment: u64) -> anyhow::Result<File> {
+    Ok(OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(segment_path(dir, WAL_PREFIX, segment, WAL_SUFFIX))?)
+}
+
+fn segment_path(dir: &Path, prefix: &str, segment: u64, suffix: &str) -> PathBuf {
+    dir.join(format!("{prefix}{segment:020}{suffix}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch::{BranchStatus, BranchType};
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("rseata-file-store-{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn config(&self, file_max_segment_size: u64, file_snapshot_interval: u64) -> StoreConfig {
+            StoreConfig {
+                file_store_dir: self.0.to_string_lossy().into_owned(),
+                file_max_segment_size,
+                file_snapshot_interval,
+                ..StoreConfig::default()
+            }
+        }
+
+        fn wal_segments(&self) -> Vec<(u64, PathBuf)> {
+            list_files(&self.0, WAL_PREFIX, WAL_SUFFIX).unwrap()
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    async fn add_sessions(store: &FileTransactionStoreManager, count: usize) -> Vec<Xid> {
+        let mut xids = Vec::new();
+        for _ in 0..count {
+            let session = DefaultGlobalSession::new(
+                "app".to_string(),
+                "group".to_string(),
+                "tx".to_string(),
+                60_000,
+                false,
+            );
+            store
+                .write_session(LogOperation::GlobalAdd, &session)
+                .await
+                .unwrap();
+            xids.push(session.xid);
+        }
+        xids
+    }
+
+    #[tokio::test]
+    async fn torn_wal_tail_is_truncated_on_recovery() {
+        let dir = TestDir::new();
+        let config = dir.config(64 * 1024 * 1024, 10_000);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        let xids = add_sessions(&store, 3).await;
+        drop(store);
+
+        // 模拟崩溃时最后一条记录只写了一半
+        let (_, last) = dir.wal_segments().pop().unwrap();
+        let valid_len = fs::metadata(&last).unwrap().len();
+        let torn = encode_record(b"{\"torn\":");
+        OpenOptions::new()
+            .append(true)
+            .open(&last)
+            .unwrap()
+            .write_all(&torn[..torn.len() - 3])
+            .unwrap();
+
+        let recovered = recover(&dir.0, config.file_max_segment_size).unwrap();
+        assert_eq!(recovered.records.len(), 3);
+        assert_eq!(fs::metadata(&last).unwrap().len(), valid_len);
+
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        for xid in &xids {
+            assert!(store.read_session(xid).await.is_some());
+        }
+
+        // 截断后的存储可以继续写入并再次恢复
+        let more = add_sessions(&store, 1).await;
+        drop(store);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        assert_eq!(store.memory.session_count().await, 4);
+        assert!(store.read_session(&more[0]).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch_in_tail_drops_only_the_bad_record() {
+        let dir = TestDir::new();
+        let config = dir.config(64 * 1024 * 1024, 10_000);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        let xids = add_sessions(&store, 3).await;
+        drop(store);
+
+        // 最后一条记录的内容损坏，校验失败
+        let (_, last) = dir.wal_segments().pop().unwrap();
+        let mut bytes = fs::read(&last).unwrap();
+        let end = bytes.len() - 1;
+        bytes[end] ^= 0xff;
+        fs::write(&last, bytes).unwrap();
+
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        assert!(store.read_session(&xids[0]).await.is_some());
+        assert!(store.read_session(&xids[1]).await.is_some());
+        assert!(store.read_session(&xids[2]).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn corrupted_segment_before_the_tail_fails_recovery() {
+        let dir = TestDir::new();
+        // 每条记录单独一个段
+        let config = dir.config(1, 10_000);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        add_sessions(&store, 3).await;
+        drop(store);
+
+        let segments = dir.wal_segments();
+        assert!(segments.len() >= 3);
+        let (_, first) = &segments[0];
+        let mut bytes = fs::read(first).unwrap();
+        bytes[RECORD_HEADER_SIZE] ^= 0xff;
+        fs::write(first, bytes).unwrap();
+
+        let err = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("corrupted wal segment"));
+    }
+
+    #[tokio::test]
+    async fn corrupted_snapshot_fails_recovery() {
+        let dir = TestDir::new();
+        let config = dir.config(64 * 1024 * 1024, 2);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        add_sessions(&store, 2).await;
+        drop(store);
+
+        let (_, snapshot) = list_files(&dir.0, SNAPSHOT_PREFIX, SNAPSHOT_SUFFIX)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut bytes = fs::read(&snapshot).unwrap();
+        bytes[RECORD_HEADER_SIZE] ^= 0xff;
+        fs::write(&snapshot, bytes).unwrap();
+
+        let err = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("corrupted snapshot"));
+    }
+
+    #[tokio::test]
+    async fn branch_records_are_replayed_on_recovery() {
+        let dir = TestDir::new();
+        let config = dir.config(64 * 1024 * 1024, 10_000);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        let xid = add_sessions(&store, 1).await.remove(0);
+        let mut branches = Vec::new();
+        for id in 1..=2u64 {
+            let mut branch = DefaultBranchSession::new(BranchType::AT);
+            branch.xid = xid.clone();
+            branch.branch_id = id.into();
+            branch.status = BranchStatus::Registered;
+            store
+                .write_branch_session(LogOperation::BranchAdd, &branch)
+                .await
+                .unwrap();
+            branches.push(branch);
+        }
+        branches[0].status = BranchStatus::PhaseOneDone;
+        store
+            .write_branch_session(LogOperation::BranchUpdate, &branches[0])
+            .await
+            .unwrap();
+        store
+            .write_branch_session(LogOperation::BranchRemove, &branches[1])
+            .await
+            .unwrap();
+
+        // 会话不存在时不写入日志
+        let mut orphan = branches[1].clone();
+        orphan.xid = Xid::from(Uuid::new_v4().to_string());
+        assert!(
+            store
+                .write_branch_session(LogOperation::BranchAdd, &orphan)
+                .await
+                .is_err()
+        );
+        drop(store);
+
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        let session = store.read_session_with_branches(&xid).await.unwrap();
+        assert_eq!(session.branch_sessions.len(), 1);
+        assert_eq!(
+            store
+                .read_branch_session(&xid, 1.into())
+                .await
+                .map(|branch| branch.status),
+            Some(BranchStatus::PhaseOneDone)
+        );
+        assert!(store.read_branch_session(&xid, 2.into()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn snapshot_compacts_segments_and_keeps_sessions() {
+        let dir = TestDir::new();
+        let config = dir.config(64 * 1024 * 1024, 4);
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        let xids = add_sessions(&store, 10).await;
+        // 快照之前的段已删除，只剩最近一次快照之后的段
+        assert!(dir.wal_segments().len() <= 2);
+        drop(store);
+
+        let store = FileTransactionStoreManager::open(&config, None)
+            .await
+            .unwrap();
+        for xid in &xids {
+            assert!(store.read_session(xid).await.is_some());
+        }
+    }
+}
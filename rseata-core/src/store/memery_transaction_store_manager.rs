@@ -1,134 +1,223 @@
+use crate::branch::BranchId;
+use crate::session::defaults::default_branch_session::DefaultBranchSession;
 use crate::session::defaults::default_global_session::DefaultGlobalSession;
 use crate::session::session_condition::SessionCondition;
-use crate::session::session_storable::SessionStorable;
 use crate::store::LogOperation;
 use crate::store::transaction_store_manager::TransactionStoreManager;
 use crate::types::{GlobalStatus, Xid};
+use anyhow::bail;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use std::collections::{BTreeMap, HashMap};
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-#[derive(Debug, Clone)]
-struct MemorySessionStore {
-    // 按 XID 索引的全局会话
-    global_sessions: HashMap<Xid, DefaultGlobalSession>,
-    // 按开始时间排序的会话索引，用于超时查询
-    timeout_index: BTreeMap<DateTime<Utc>, Xid>,
-    // 按状态索引的会话
-    status_index: HashMap<GlobalStatus, Vec<Xid>>,
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 默认分片数，按 xid 哈希分散写锁竞争
+const DEFAULT_SHARD_COUNT: usize = 64;
+
+#[derive(Debug)]
+struct StoredSession {
+    // 不含分支的全局会话
+    global: DefaultGlobalSession,
+    // 分支按 branch_id 索引，branch_order 保留注册顺序
+    branches: HashMap<BranchId, DefaultBranchSession>,
+    branch_order: Vec<BranchId>,
 }
 
-impl MemorySessionStore {
-    fn new() -> Self {
-        Self {
-            global_sessions: HashMap::new(),
-            timeout_index: BTreeMap::new(),
-            status_index: HashMap::new(),
-        }
+impl StoredSession {
+    fn new(session: &DefaultGlobalSession) -> Self {
+        let mut stored = Self {
+            global: header(session),
+            branches: HashMap::with_capacity(session.branch_sessions.len()),
+            branch_order: Vec::with_capacity(session.branch_sessions.len()),
+        };
+        stored.sync_branches(session, false);
+        stored
     }
 
-    fn add_global_session(&mut self, session: DefaultGlobalSession) {
-        let xid = session.xid.clone();
-        let begin_time = DateTime::from_timestamp_millis(session.begin_time_millis as i64)
-            .unwrap_or_else(Utc::now);
-        let status = session.status;
-
-        // 插入主存储
-        self.global_sessions.insert(xid.clone(), session);
+    // 按 branch_id 原地覆盖分支，新分支追加到注册顺序末尾
+    fn put_branch(&mut self, branch: &DefaultBranchSession) {
+        match self.branches.get_mut(&branch.branch_id) {
+            Some(stored) => stored.clone_from(branch),
+            None => {
+                self.branches.insert(branch.branch_id, branch.clone());
+                self.branch_order.push(branch.branch_id);
+            }
+        }
+    }
 
-        // 更新超时索引
-        self.timeout_index.insert(begin_time, xid.clone());
+    fn remove_branch(&mut self, branch_id: BranchId) {
+        if self.branches.remove(&branch_id).is_some() {
+            self.branch_order.retain(|id| *id != branch_id);
+        }
+    }
 
-        // 更新状态索引
-        self.status_index
-            .entry(status)
-            .or_insert_with(Vec::new)
-            .push(xid);
+    // remove 为 true 时删除会话中已不存在的分支
+    fn sync_branches(&mut self, session: &DefaultGlobalSession, remove: bool) {
+        for branch in &session.branch_sessions {
+            self.put_branch(branch);
+        }
+        if remove && self.branches.len() > session.branch_sessions.len() {
+            let retained: HashSet<BranchId> = session
+                .branch_sessions
+                .iter()
+                .map(|branch| branch.branch_id)
+                .collect();
+            self.branches.retain(|id, _| retained.contains(id));
+            self.branch_order.retain(|id| retained.contains(id));
+        }
     }
 
-    fn update_global_session_status(&mut self, xid: &Xid, new_status: GlobalStatus) -> bool {
-        if let Some(session) = self.global_sessions.get_mut(xid) {
-            let old_status = session.status;
-
-            // 如果状态改变，更新索引
-            if old_status != new_status {
-                // 从旧状态索引中移除
-                if let Some(ids) = self.status_index.get_mut(&old_status) {
-                    ids.retain(|id| id != xid);
-                    if ids.is_empty() {
-                        self.status_index.remove(&old_status);
-                    }
-                }
-
-                // 添加到新状态索引
-                self.status_index
-                    .entry(new_status)
-                    .or_insert_with(Vec::new)
-                    .push(xid.clone());
-
-                session.status = new_status;
-                return true;
-            }
+    fn view(&self, with_branch_sessions: bool) -> DefaultGlobalSession {
+        if !with_branch_sessions {
+            return self.global.without_branches();
         }
-        false
+        let mut session = self.global.clone();
+        session.branch_sessions = self
+            .branch_order
+            .iter()
+            .filter_map(|id| self.branches.get(id))
+            .cloned()
+            .collect();
+        session
     }
+}
 
-    fn remove_global_session(&mut self, xid: &Xid) -> Option<DefaultGlobalSession> {
-        if let Some(session) = self.global_sessions.remove(xid) {
-            let begin_time = DateTime::from_timestamp_millis(session.begin_time_millis as i64)
-                .unwrap_or_else(Utc::now);
+#[derive(Debug, Default)]
+struct Shard {
+    sessions: HashMap<Xid, StoredSession>,
+    // 按 (超时截止时间, xid) 排序，同一时刻开始的会话互不覆盖
+    timeout_index: BTreeSet<(u64, Xid)>,
+    status_index: HashMap<GlobalStatus, HashSet<Xid>>,
+}
 
-            // 从超时索引中移除
-            self.timeout_index.retain(|_, id| id != xid);
+impl Shard {
+    fn insert(&mut self, session: &DefaultGlobalSession) {
+        self.remove(&session.xid);
+        self.index(&session.xid, deadline(session), session.status);
+        self.sessions
+            .insert(session.xid.clone(), StoredSession::new(session));
+    }
 
-            // 从状态索引中移除
-            if let Some(ids) = self.status_index.get_mut(&session.status) {
-                ids.retain(|id| id != xid);
-                if ids.is_empty() {
-                    self.status_index.remove(&session.status);
-                }
-            }
+    // 只更新全局会话本身，分支通过 Branch* 操作维护
+    fn update(&mut self, session: &DefaultGlobalSession) {
+        let Some(stored) = self.sessions.get_mut(&session.xid) else {
+            self.insert(session);
+            return;
+        };
+        let old_deadline = deadline(&stored.global);
+        let old_status = stored.global.status;
+        stored.global = header(session);
+
+        let new_deadline = deadline(session);
+        if old_deadline != new_deadline || old_status != session.status {
+            self.unindex(&session.xid, old_deadline, old_status);
+            self.index(&session.xid, new_deadline, session.status);
+        }
+    }
 
-            Some(session)
-        } else {
-            None
+    fn sync_branches(&mut self, session: &DefaultGlobalSession, remove: bool) {
+        match self.sessions.get_mut(&session.xid) {
+            Some(stored) => stored.sync_branches(session, remove),
+            None => self.insert(session),
         }
     }
 
-    fn get_global_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
-        self.global_sessions.get(xid).cloned()
+    fn write_branch(
+        &mut self,
+        log_operation: LogOperation,
+        branch: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        let Some(stored) = self.sessions.get_mut(&branch.xid) else {
+            bail!("no such global session {}", branch.xid);
+        };
+        match log_operation {
+            LogOperation::BranchAdd | LogOperation::BranchUpdate => stored.put_branch(branch),
+            LogOperation::BranchRemove => stored.remove_branch(branch.branch_id),
+            operation => bail!("{operation:?} is not a branch operation"),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, xid: &Xid) -> Option<StoredSession> {
+        let stored = self.sessions.remove(xid)?;
+        self.unindex(xid, deadline(&stored.global), stored.global.status);
+        Some(stored)
     }
 
-    fn get_sessions_by_status(&self, status: &GlobalStatus) -> Vec<DefaultGlobalSession> {
+    fn index(&mut self, xid: &Xid, deadline: u64, status: GlobalStatus) {
+        self.timeout_index.insert((deadline, xid.clone()));
         self.status_index
-            .get(status)
-            .map(|ids| {
-                ids.iter()
-                    .filter_map(|id| self.global_sessions.get(id).cloned())
-                    .collect()
-            })
-            .unwrap_or_default()
+            .entry(status)
+            .or_default()
+            .insert(xid.clone());
+    }
+
+    fn unindex(&mut self, xid: &Xid, deadline: u64, status: GlobalStatus) {
+        self.timeout_index.remove(&(deadline, xid.clone()));
+        if let Some(xids) = self.status_index.get_mut(&status) {
+            xids.remove(xid);
+            if xids.is_empty() {
+                self.status_index.remove(&status);
+            }
+        }
+    }
+
+    fn sessions_by_status<'a>(
+        &'a self,
+        statuses: &'a [GlobalStatus],
+    ) -> impl Iterator<Item = &'a StoredSession> {
+        statuses
+            .iter()
+            .filter_map(|status| self.status_index.get(status))
+            .flatten()
+            .filter_map(|xid| self.sessions.get(xid))
     }
 
-    fn get_sessions_sorted_by_timeout(&self) -> Vec<DefaultGlobalSession> {
-        self.timeout_index
-            .values()
-            .filter_map(|xid| self.global_sessions.get(xid).cloned())
+    // 在本分片内完成过滤和分页，只复制可能进入结果页的会话
+    fn sessions_by_condition(
+        &self,
+        condition: &SessionCondition,
+        statuses: &[GlobalStatus],
+        now: u64,
+    ) -> Vec<DefaultGlobalSession> {
+        let candidates: Box<dyn Iterator<Item = &StoredSession>> = if let Some(xid) = &condition.xid
+        {
+            Box::new(self.sessions.get(xid).into_iter())
+        } else if !statuses.is_empty() {
+            Box::new(self.sessions_by_status(statuses))
+        } else {
+            Box::new(self.sessions.values())
+        };
+        let matched = candidates
+            .filter(|stored| condition.matches(&stored.global, now))
+            .map(Matched)
+            .collect();
+        condition
+            .paginate(matched)
+            .into_iter()
+            .map(|matched| matched.0.view(!condition.lazy_load_branch))
             .collect()
     }
+}
 
-    fn get_sessions_by_condition(&self, condition: &SessionCondition) -> Vec<DefaultGlobalSession> {
-        // 这里需要根据 SessionCondition 的具体结构来实现过滤逻辑
-        // 暂时返回所有会话，实际实现中需要根据条件过滤
-        self.global_sessions.values().cloned().collect()
+// 按全局会话参与排序，避免为分页复制分支
+struct Matched<'a>(&'a StoredSession);
+
+impl std::borrow::Borrow<DefaultGlobalSession> for Matched<'_> {
+    fn borrow(&self) -> &DefaultGlobalSession {
+        &self.0.global
     }
 }
 
+/// 分片的内存会话存储
+///
+/// 会话按 xid 哈希分布到各分片，每个分片有独立的锁和超时、状态索引；
+/// 分支按 branch_id 保存，分支操作只改动涉及的分支，不再整体重新编码
 #[derive(Debug)]
 pub struct MemeryTransactionStoreManager {
-    store: Arc<RwLock<MemorySessionStore>>,
+    shards: Box<[RwLock<Shard>]>,
 }
 
 impl Default for MemeryTransactionStoreManager {
@@ -139,37 +228,44 @@ impl Default for MemeryTransactionStoreManager {
 
 impl MemeryTransactionStoreManager {
     pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARD_COUNT)
+    }
+
+    pub fn with_shards(shard_count: usize) -> Self {
         Self {
-            store: Arc::new(RwLock::new(MemorySessionStore::new())),
+            shards: (0..shard_count.max(1))
+                .map(|_| RwLock::new(Shard::default()))
+                .collect(),
         }
     }
 
-    async fn apply_log_operation(
-        &self,
-        log_operation: LogOperation,
-        session: &dyn SessionStorable,
-    ) -> anyhow::Result<()> {
-        let mut store = self.store.write().await;
+    /// 存储中的全部会话，用于生成快照
+    pub async fn all_sessions(&self) -> Vec<DefaultGlobalSession> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                let shard = shard.read().unwrap();
+                shard
+                    .sessions
+                    .values()
+                    .map(|stored| stored.view(true))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 
-        match log_operation {
-            LogOperation::GlobalAdd | LogOperation::GlobalUpdate => {
-                if let Ok(global_session) = DefaultGlobalSession::decode(&session.encode()?) {
-                    store.add_global_session(global_session);
-                }
-            }
-            LogOperation::GlobalRemove => {
-                if let Ok(global_session) = DefaultGlobalSession::decode(&session.encode()?) {
-                    store.remove_global_session(&global_session.xid);
-                }
-            }
-            LogOperation::BranchAdd | LogOperation::BranchUpdate | LogOperation::BranchRemove => {
-                if let Ok(global_session) = DefaultGlobalSession::decode(&session.encode()?) {
-                    store.add_global_session(global_session);
-                }
-            }
-        }
+    pub async fn session_count(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().sessions.len())
+            .sum()
+    }
 
-        Ok(())
+    // 临界区内没有 await，使用同步锁减少调度开销
+    fn shard(&self, xid: &Xid) -> &RwLock<Shard> {
+        let mut hasher = DefaultHasher::new();
+        xid.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
     }
 }
 
@@ -182,17 +278,53 @@ impl TransactionStoreManager for MemeryTransactionStoreManager {
         log_operation: LogOperation,
         session: &Self::GlobalSession,
     ) -> anyhow::Result<()> {
-        self.apply_log_operation(log_operation, session).await
+        let mut shard = self.shard(&session.xid).write().unwrap();
+        match log_operation {
+            LogOperation::GlobalAdd => shard.insert(session),
+            LogOperation::GlobalUpdate => shard.update(session),
+            LogOperation::GlobalRemove => {
+                shard.remove(&session.xid);
+            }
+            LogOperation::BranchAdd | LogOperation::BranchUpdate => {
+                shard.sync_branches(session, false)
+            }
+            LogOperation::BranchRemove => shard.sync_branches(session, true),
+        }
+        Ok(())
+    }
+
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        self.shard(&branch_session.xid)
+            .write()
+            .unwrap()
+            .write_branch(log_operation, branch_session)
     }
 
     async fn read_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
-        let store = self.store.read().await;
-        store.get_global_session(xid)
+        self.read_global_session(xid, true).await
+    }
+
+    async fn read_branch_session(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Option<DefaultBranchSession> {
+        self.shard(xid)
+            .read()
+            .unwrap()
+            .sessions
+            .get(xid)?
+            .branches
+            .get(&branch_id)
+            .cloned()
     }
 
     async fn read_session_with_branches(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
-        // 在内存存储中，会话总是包含分支信息
-        self.read_session(xid).await
+        self.read_global_session(xid, true).await
     }
 
     async fn read_global_session(
@@ -200,65 +332,88 @@ impl TransactionStoreManager for MemeryTransactionStoreManager {
         xid: &Xid,
         with_branch_sessions: bool,
     ) -> Option<DefaultGlobalSession> {
-        // 内存存储中分支会话信息总是可用的
-        let session = self.read_session(xid).await;
-        if with_branch_sessions {
-            session
-        } else {
-            // 如果不要求分支会话，返回一个不包含分支会话的副本
-            session.map(|mut s| {
-                // 这里需要根据 DefaultGlobalSession 的实际结构来清空分支会话
-                // 暂时返回原会话
-                s
-            })
-        }
+        self.shard(xid)
+            .read()
+            .unwrap()
+            .sessions
+            .get(xid)
+            .map(|stored| stored.view(with_branch_sessions))
     }
 
     async fn read_sort_by_timeout_begin_sessions(
         &self,
         with_branch_sessions: bool,
     ) -> Vec<DefaultGlobalSession> {
-        let store = self.store.read().await;
-        let mut sessions = store.get_sessions_sorted_by_timeout();
-
-        if !with_branch_sessions {
-            // 如果不要求分支会话，清空所有会话的分支信息
-            for session in &mut sessions {
-                // 这里需要根据 DefaultGlobalSession 的实际结构来清空分支会话
-                // 暂时保持原样
-            }
+        let mut sessions = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.read().unwrap();
+            sessions.extend(
+                shard
+                    .timeout_index
+                    .iter()
+                    .filter_map(|(deadline, xid)| Some((*deadline, shard.sessions.get(xid)?)))
+                    .filter(|(_, stored)| stored.global.status == GlobalStatus::Begin)
+                    .map(|(deadline, stored)| (deadline, stored.view(with_branch_sessions))),
+            );
         }
-
-        sessions
+        sessions.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.xid.cmp(&y.xid)));
+        sessions.into_iter().map(|(_, session)| session).collect()
     }
 
     async fn read_session_by_global_status(
         &self,
-        statuses: &Vec<GlobalStatus>,
+        statuses: &[GlobalStatus],
         with_branch_sessions: bool,
     ) -> Vec<DefaultGlobalSession> {
-        let store = self.store.read().await;
-        let mut result = Vec::new();
-
-        for status in statuses {
-            let mut sessions = store.get_sessions_by_status(status);
-            if !with_branch_sessions {
-                // 清空分支会话信息
-                for session in &mut sessions {
-                    // 这里需要根据 DefaultGlobalSession 的实际结构来清空分支会话
-                }
-            }
-            result.extend(sessions);
+        let mut sessions = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.read().unwrap();
+            sessions.extend(
+                shard
+                    .sessions_by_status(statuses)
+                    .map(|stored| stored.view(with_branch_sessions)),
+            );
         }
-
-        result
+        sessions
     }
 
     async fn read_session_by_session_condition(
         &self,
         condition: &SessionCondition,
     ) -> Vec<DefaultGlobalSession> {
-        let store = self.store.read().await;
-        store.get_sessions_by_condition(condition)
+        let statuses = condition.all_statuses();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let shards: Box<dyn Iterator<Item = &RwLock<Shard>>> = match &condition.xid {
+            Some(xid) => Box::new(std::iter::once(self.shard(xid))),
+            None => Box::new(self.shards.iter()),
+        };
+        // 各分片先各自取一页，合并后再取一次
+        let sessions = shards
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .sessions_by_condition(condition, &statuses, now)
+            })
+            .collect();
+        condition.paginate(sessions)
+    }
+}
+
+// 超时截止时间 = 开始时间 + 超时时长
+fn deadline(session: &DefaultGlobalSession) -> u64 {
+    session
+        .begin_time_millis
+        .saturating_add(session.timeout_millis)
+}
+
+// 保存的全局会话不含分支，但保留原有的 lazy_load_branch
+fn header(session: &DefaultGlobalSession) -> DefaultGlobalSession {
+    DefaultGlobalSession {
+        lazy_load_branch: session.lazy_load_branch,
+        ..session.without_branches()
     }
 }
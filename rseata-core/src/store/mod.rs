@@ -1,3 +1,4 @@
+pub mod file_transaction_store_manager;
 pub mod memery_transaction_store_manager;
 pub mod transaction_store_manager;
 
@@ -12,7 +13,22 @@ pub enum StoreMode {
     Memory,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl std::str::FromStr for StoreMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "file" => Ok(StoreMode::File),
+            "db" => Ok(StoreMode::Db),
+            "redis" => Ok(StoreMode::Redis),
+            "raft" => Ok(StoreMode::Raft),
+            "memory" => Ok(StoreMode::Memory),
+            other => Err(format!("unknown store mode: {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum LogOperation {
     GlobalAdd,
     GlobalUpdate,
@@ -25,9 +41,15 @@ pub enum LogOperation {
 #[derive(Debug, Clone)]
 pub struct StoreConfig {
     pub session_mode: StoreMode,
+    // 行锁存储方式，目前支持 memory、db、redis 和 raft
+    pub lock_mode: StoreMode,
     pub max_global_session_size: usize,
     pub max_branch_session_size: usize,
     pub file_store_dir: String,
+    // 单个 WAL 段文件的最大字节数，超过后滚动到新段
+    pub file_max_segment_size: u64,
+    // 每追加多少条 WAL 记录生成一次快照并清理旧段
+    pub file_snapshot_interval: u64,
     pub redis_host: String,
     pub redis_port: u16,
     pub db_url: String,
@@ -37,9 +59,12 @@ impl Default for StoreConfig {
     fn default() -> Self {
         Self {
             session_mode: StoreMode::File,
+            lock_mode: StoreMode::Memory,
             max_global_session_size: 64 * 1024, // 64KB
             max_branch_session_size: 32 * 1024, // 32KB
             file_store_dir: "session_data".to_string(),
+            file_max_segment_size: 64 * 1024 * 1024, // 64MB
+            file_snapshot_interval: 10_000,
             redis_host: "localhost".to_string(),
             redis_port: 6379,
             db_url: "".to_string(),
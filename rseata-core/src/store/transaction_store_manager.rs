@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 
+use crate::branch::BranchId;
 use crate::session::global_session::GlobalSession;
 use crate::session::session_condition::SessionCondition;
 use crate::store::LogOperation;
@@ -17,8 +18,35 @@ pub trait TransactionStoreManager: Send + Sync + Debug {
         session: &Self::GlobalSession,
     ) -> anyhow::Result<()>;
 
+    /// 仅当存储中的全局状态仍为 expected 时写入 session，返回 false 表示竞争失败
+    ///
+    /// 默认实现直接写入，适用于只由本实例的会话管理器访问的存储
+    async fn compare_and_set_global_session(
+        &self,
+        session: &Self::GlobalSession,
+        _expected: GlobalStatus,
+    ) -> anyhow::Result<bool> {
+        self.write_session(LogOperation::GlobalUpdate, session)
+            .await?;
+        Ok(true)
+    }
+
+    /// 按 BranchAdd、BranchUpdate 或 BranchRemove 写入单个分支，不读写会话的其他分支
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &<Self::GlobalSession as GlobalSession>::BranchSession,
+    ) -> anyhow::Result<()>;
+
     async fn read_session(&self, xid: &Xid) -> Option<Self::GlobalSession>;
 
+    /// 读取单个分支，会话或分支不存在时返回 None
+    async fn read_branch_session(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Option<<Self::GlobalSession as GlobalSession>::BranchSession>;
+
     async fn read_session_with_branches(&self, xid: &Xid) -> Option<Self::GlobalSession>;
 
     /// Read global session by xid
@@ -35,7 +63,7 @@ pub trait TransactionStoreManager: Send + Sync + Debug {
 
     async fn read_session_by_global_status(
         &self,
-        statuses: &Vec<GlobalStatus>,
+        statuses: &[GlobalStatus],
         with_branch_sessions: bool,
     ) -> Vec<Self::GlobalSession>;
 
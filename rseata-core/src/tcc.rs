@@ -0,0 +1,37 @@
+use crate::branch::BranchId;
+use crate::types::Xid;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// TCC 动作：一阶段 try 预留资源，二阶段 confirm / cancel
+///
+/// confirm 与 cancel 可能被 TC 重复下发，实现需要保证幂等（可借助 `TccFence`）
+#[async_trait]
+pub trait TccAction: Send + Sync + 'static {
+    type Context: Serialize + DeserializeOwned + Send + Sync + 'static;
+
+    /// 动作名，RM 重启后据此找回动作
+    fn name(&self) -> &'static str;
+
+    async fn r#try(&self, branch: &TccBranch, context: &Self::Context) -> anyhow::Result<()>;
+
+    async fn confirm(&self, branch: &TccBranch, context: Self::Context) -> anyhow::Result<()>;
+
+    async fn cancel(&self, branch: &TccBranch, context: Self::Context) -> anyhow::Result<()>;
+}
+
+/// 当前执行的 TCC 分支
+#[derive(Debug, Clone)]
+pub struct TccBranch {
+    pub xid: Xid,
+    pub branch_id: BranchId,
+    pub action_name: String,
+}
+
+/// 保存在分支 application_data 中的数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TccApplicationData {
+    pub action_name: String,
+    pub context: serde_json::Value,
+}
@@ -14,5 +14,12 @@ pub trait TransactionManager {
     async fn commit(&self, xid: Xid) -> Result<GlobalStatus>;
     async fn rollback(&self, xid: Xid) -> Result<GlobalStatus>;
     async fn get_status(&self, xid: Xid) -> Result<GlobalStatus>;
-    async fn global_report(&self, xid: &Xid, global_status: GlobalStatus) -> Result<GlobalStatus>;
+    /// 上报由发起方自行驱动的全局事务（Saga）的结果，只有开启该事务的应用可以上报
+    async fn global_report(
+        &self,
+        xid: &Xid,
+        application_id: String,
+        transaction_service_group: String,
+        global_status: GlobalStatus,
+    ) -> Result<GlobalStatus>;
 }
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Xid(pub String);
 impl From<&str> for Xid {
     fn from(value: &str) -> Self {
@@ -174,6 +174,82 @@ impl GlobalStatus {
     pub fn is_two_phase_heuristic(status: Self) -> bool {
         matches!(status, Self::Finished)
     }
+
+    /// 全局事务的终态，不会再发生二阶段动作
+    pub fn is_end(&self) -> bool {
+        matches!(
+            self,
+            Self::Committed
+                | Self::CommitFailed
+                | Self::CommitRetryTimeout
+                | Self::Rollbacked
+                | Self::RollbackFailed
+                | Self::RollbackRetryTimeout
+                | Self::TimeoutRollbacked
+                | Self::TimeoutRollbackFailed
+                | Self::Finished
+        )
+    }
+
+    /// 全局状态迁移表，相同状态不算迁移
+    pub fn can_transition_to(&self, to: Self) -> bool {
+        if *self == to {
+            return false;
+        }
+        match self {
+            Self::UnKnown => to == Self::Begin,
+            Self::Begin => matches!(
+                to,
+                Self::Committing
+                    | Self::AsyncCommitting
+                    | Self::Rollbacking
+                    | Self::TimeoutRollbacking
+            ),
+            Self::Committing => matches!(
+                to,
+                Self::Committed | Self::CommitRetrying | Self::CommitFailed
+            ),
+            Self::AsyncCommitting => matches!(to, Self::Committed | Self::CommitFailed),
+            Self::CommitRetrying => matches!(
+                to,
+                Self::Committed
+                    | Self::CommitFailed
+                    | Self::CommitRetryTimeout
+                    | Self::StopCommitOrCommitRetry
+            ),
+            Self::StopCommitOrCommitRetry => matches!(to, Self::CommitRetrying),
+            Self::Rollbacking => matches!(
+                to,
+                Self::Rollbacked | Self::RollbackRetrying | Self::RollbackFailed
+            ),
+            Self::RollbackRetrying => matches!(
+                to,
+                Self::Rollbacked
+                    | Self::RollbackFailed
+                    | Self::RollbackRetryTimeout
+                    | Self::StopRollbackOrRollbackRetry
+            ),
+            Self::StopRollbackOrRollbackRetry => {
+                matches!(to, Self::RollbackRetrying | Self::TimeoutRollbackRetrying)
+            }
+            Self::TimeoutRollbacking => matches!(
+                to,
+                Self::TimeoutRollbacked
+                    | Self::TimeoutRollbackRetrying
+                    | Self::TimeoutRollbackFailed
+            ),
+            Self::TimeoutRollbackRetrying => matches!(
+                to,
+                Self::TimeoutRollbacked
+                    | Self::TimeoutRollbackFailed
+                    | Self::RollbackRetryTimeout
+                    | Self::StopRollbackOrRollbackRetry
+            ),
+            // 终态只允许进入删除流程
+            _ if self.is_end() => to == Self::Deleting,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -184,9 +260,9 @@ impl From<u64> for ClientId {
     }
 }
 
-impl Into<u64> for ClientId {
-    fn into(self) -> u64 {
-        self.0
+impl From<ClientId> for u64 {
+    fn from(val: ClientId) -> Self {
+        val.0
     }
 }
 
@@ -195,3 +271,92 @@ impl Display for ClientId {
         self.0.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GlobalStatus::{self, *};
+
+    fn all() -> Vec<GlobalStatus> {
+        (0..=20)
+            .map(|code| GlobalStatus::from_code(code).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn global_status_transition_table() {
+        let terminal: &[GlobalStatus] = &[Deleting];
+        let table: &[(GlobalStatus, &[GlobalStatus])] = &[
+            (UnKnown, &[Begin]),
+            (
+                Begin,
+                &[Committing, AsyncCommitting, Rollbacking, TimeoutRollbacking],
+            ),
+            (Committing, &[Committed, CommitRetrying, CommitFailed]),
+            // 异步提交一直重试，不会进入 CommitRetrying 或重试超时
+            (AsyncCommitting, &[Committed, CommitFailed]),
+            (
+                CommitRetrying,
+                &[
+                    Committed,
+                    CommitFailed,
+                    CommitRetryTimeout,
+                    StopCommitOrCommitRetry,
+                ],
+            ),
+            (StopCommitOrCommitRetry, &[CommitRetrying]),
+            (Rollbacking, &[Rollbacked, RollbackRetrying, RollbackFailed]),
+            (
+                RollbackRetrying,
+                &[
+                    Rollbacked,
+                    RollbackFailed,
+                    RollbackRetryTimeout,
+                    StopRollbackOrRollbackRetry,
+                ],
+            ),
+            (
+                StopRollbackOrRollbackRetry,
+                &[RollbackRetrying, TimeoutRollbackRetrying],
+            ),
+            (
+                TimeoutRollbacking,
+                &[
+                    TimeoutRollbacked,
+                    TimeoutRollbackRetrying,
+                    TimeoutRollbackFailed,
+                ],
+            ),
+            (
+                TimeoutRollbackRetrying,
+                &[
+                    TimeoutRollbacked,
+                    TimeoutRollbackFailed,
+                    RollbackRetryTimeout,
+                    StopRollbackOrRollbackRetry,
+                ],
+            ),
+            // 终态只能进入删除流程
+            (Committed, terminal),
+            (CommitFailed, terminal),
+            (CommitRetryTimeout, terminal),
+            (Rollbacked, terminal),
+            (RollbackFailed, terminal),
+            (RollbackRetryTimeout, terminal),
+            (TimeoutRollbacked, terminal),
+            (TimeoutRollbackFailed, terminal),
+            (Finished, terminal),
+            (Deleting, &[]),
+        ];
+        assert_eq!(table.len(), all().len());
+        for (from, allowed) in table {
+            assert_eq!(from.is_end(), *allowed == terminal, "{from:?}");
+            for to in all() {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&to),
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
+    }
+}
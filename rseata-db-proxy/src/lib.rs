@@ -1,4 +1,3 @@
-use ::sea_orm::DbErr;
 
 pub mod table_primary_select;
 pub mod sea_orm;
@@ -46,7 +46,7 @@ impl TransactionTrait for ATConnectionProxy {
                                     "".to_string(),
                                     "".to_string(),
                                     session.transaction_name.clone(),
-                                    100,
+                                    RSEATA_TM.timeout_millis,
                                 )
                                 .await
                                 .map_err(|e| DbErr::Conn(RuntimeErr::Internal(e.to_string())))?;
@@ -58,7 +58,6 @@ impl TransactionTrait for ATConnectionProxy {
                             }
                         }
                         session.init_branch().await;
-
                     }
                 }
                 Ok(ATTransactionProxy::new(self.clone(), t))
@@ -3,9 +3,12 @@ mod impl_stream_trait;
 mod impl_transaction_trait;
 mod impl_branch_transaction;
 
+use rseata_core::branch::BranchType;
+use rseata_rm::RSEATA_RM;
 use sea_orm::error::*;
 use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct ATConnectionProxy {
@@ -15,10 +18,31 @@ pub struct ATConnectionProxy {
 impl ATConnectionProxy {
     pub async fn connect_mysql(url: &str) -> Result<Self, DbErr> {
         let t = sea_orm::Database::connect(url).await?;
-        Ok(Self {
+        let proxy = Self {
             url: url.to_string(),
             sea_conn: t,
-        })
+        };
+        // AT 分支不在 RM 中逐个登记，二阶段按分支携带的数据源标识交给对应的连接代理
+        RSEATA_RM
+            .register_resource_handler(
+                BranchType::AT,
+                proxy.resource_key(),
+                Arc::new(proxy.clone()),
+            )
+            .await;
+        Ok(proxy)
+    }
+
+    /// 数据源标识：去掉账号密码和连接参数的 url
+    pub fn resource_key(&self) -> String {
+        let url = get_url(&self.url);
+        match url.split_once("://") {
+            Some((scheme, rest)) => match rest.split_once('@') {
+                Some((_, address)) => format!("{}://{}", scheme, address),
+                None => url,
+            },
+            None => url,
+        }
     }
 }
 impl Deref for ATConnectionProxy {
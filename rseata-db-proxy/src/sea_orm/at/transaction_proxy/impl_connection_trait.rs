@@ -13,22 +13,27 @@ impl ConnectionTrait for ATTransactionProxy {
     async fn execute_raw(&self, stmt: Statement) -> Result<ExecResult, DbErr> {
         let session = RSEATA_CLIENT_SESSION.try_get().ok();
         println!("Transaction------execute_raw------------{:?}", session);
+        self.mark_writes(&stmt.sql);
         self.process_execute(&stmt).await.ok();
 
         self.at_connection_proxy.execute_raw(stmt).await
     }
     async fn execute_unprepared(&self, sql: &str) -> Result<ExecResult, DbErr> {
         println!("Transaction------execute_unprepared----------------------");
+        self.mark_writes(sql);
         self.at_connection_proxy.execute_unprepared(sql).await
     }
 
     async fn query_one_raw(&self, stmt: Statement) -> Result<Option<QueryResult>, DbErr> {
         println!("Transaction------query_one_raw----------------------");
+        // 如 INSERT ... RETURNING 也会经查询接口执行
+        self.mark_writes(&stmt.sql);
         self.at_connection_proxy.query_one_raw(stmt).await
     }
 
     async fn query_all_raw(&self, stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
         println!("Transaction------query_all_raw----------------------");
+        self.mark_writes(&stmt.sql);
         self.at_connection_proxy.query_all_raw(stmt).await
     }
 }
@@ -44,9 +49,28 @@ pub fn get_sql_pars_detect(db_backend: &DbBackend) -> Box<dyn Dialect + Send + '
     }
 }
 
-pub struct SqlLog {
-    sql: String,
-    params: Vec<String>,
-    result: Result<QueryResult, DbErr>,
-    types: i32, //
+/// 除查询外的语句都视为写入，无法解析时同样按写入处理
+pub(crate) fn is_write(db_backend: &DbBackend, sql: &str) -> bool {
+    let detect = get_sql_pars_detect(db_backend);
+    sqlparser::parser::Parser::parse_sql(detect.as_ref(), sql).map_or(true, |statements| {
+        statements
+            .iter()
+            .any(|statement| !matches!(statement, sqlparser::ast::Statement::Query(_)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_queries_are_read_only() {
+        let mysql = DbBackend::MySql;
+        assert!(!is_write(&mysql, "SELECT * FROM t WHERE id = ?"));
+        assert!(is_write(&mysql, "INSERT INTO t (id) VALUES (?)"));
+        assert!(is_write(&mysql, "DELETE FROM t WHERE id = 1"));
+        assert!(is_write(&mysql, "TRUNCATE TABLE t"));
+        assert!(is_write(&mysql, "SELECT 1; UPDATE t SET a = 1"));
+        assert!(is_write(&mysql, "NOT VALID SQL"));
+    }
 }
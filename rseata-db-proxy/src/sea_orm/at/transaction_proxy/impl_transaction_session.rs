@@ -4,6 +4,10 @@ use sea_orm::{DbErr, TransactionSession};
 #[async_trait::async_trait]
 impl TransactionSession for ATTransactionProxy {
     async fn commit(self) -> Result<(), DbErr> {
+        // 只读的本地事务不注册分支，也不上报一阶段结果
+        if self.is_read_only().await {
+            return self.sea_transaction.commit().await;
+        }
         self.branch_register().await?;
         self.prepare_undo_log().await?;
         let lucked = self.check_luck().await?;
@@ -15,6 +19,9 @@ impl TransactionSession for ATTransactionProxy {
     }
 
     async fn rollback(self) -> Result<(), DbErr> {
+        if self.is_read_only().await {
+            return self.sea_transaction.rollback().await;
+        }
         // rollback 之前准备 undo log
         self.branch_register().await?;
         let r = self.sea_transaction.rollback().await;
@@ -4,7 +4,7 @@ mod impl_transaction_session;
 mod impl_transaction_trait;
 
 use crate::sea_orm::at::connection_proxy::ATConnectionProxy;
-use crate::sea_orm::at::transaction_proxy::impl_connection_trait::get_sql_pars_detect;
+use crate::sea_orm::at::transaction_proxy::impl_connection_trait::{get_sql_pars_detect, is_write};
 use rseata_core::RSEATA_CLIENT_SESSION;
 use rseata_core::branch::BranchType;
 use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
@@ -13,7 +13,6 @@ use rseata_rm::RSEATA_RM;
 use sea_orm::sqlx::{Column, Row, TypeInfo};
 use sea_orm::{ConnectionTrait, DbErr, Statement};
 use std::collections::HashMap;
-use rseata_core::branch::branch_transaction::BranchTransactionRegistry;
 
 pub struct ATTransactionProxy {
     at_connection_proxy: ATConnectionProxy,
@@ -58,15 +57,16 @@ impl ATTransactionProxy {
             let xid_guard = session.get_xid();
             if let Some(xid) = xid_guard {
                 let lock_keys = session.get_branch_luck_keys().await.unwrap_or_default();
+                // AT 的二阶段由按分支类型注册的处理器完成，RM 不保存单个分支，TC 跳过二阶段时也不会残留
                 let branch_id = RSEATA_RM
-                    .branch_transaction_registry(
+                    .branch_register(
                         RSEATA_RM.resource_info.get_branch_type().await,
                         RSEATA_RM.resource_info.get_resource_id().await,
                         RSEATA_RM.resource_info.get_client_id().await,
                         xid,
-                        "application_data".into(),
+                        // 二阶段按数据源标识路由到注册该分支的连接代理
+                        self.at_connection_proxy.resource_key(),
                         lock_keys,
-                        Box::new(self.at_connection_proxy.clone()),
                     )
                     .await
                     .map_err(|e| DbErr::Custom(e.to_string()))?;
@@ -80,25 +80,24 @@ impl ATTransactionProxy {
     pub async fn global_commit(local_commit_result: Result<(), DbErr>) -> Result<(), DbErr> {
         let session = RSEATA_CLIENT_SESSION.try_get().ok();
         println!("TransactionSession------commit----------------------");
-        if let Some(session) = session {
-            if session.is_global_tx_started() {
-                if let Some(xid) = session.get_xid() {
-                    let branch_status = match local_commit_result {
-                        Ok(_) => rseata_core::branch::BranchStatus::PhaseOneDone,
-                        Err(_) => rseata_core::branch::BranchStatus::PhaseOneFailed,
-                    };
-                    RSEATA_RM
-                        .branch_report(
-                            BranchType::AT,
-                            xid,
-                            session.get_branch_id(),
-                            branch_status,
-                            String::from(""),
-                        )
-                        .await
-                        .map_err(|e| DbErr::Custom(e.to_string()))?;
-                }
-            }
+        if let Some(session) = session
+            && session.is_global_tx_started()
+            && let Some(xid) = session.get_xid()
+        {
+            let branch_status = match local_commit_result {
+                Ok(_) => rseata_core::branch::BranchStatus::PhaseOneDone,
+                Err(_) => rseata_core::branch::BranchStatus::PhaseOneFailed,
+            };
+            RSEATA_RM
+                .branch_report(
+                    BranchType::AT,
+                    xid,
+                    session.get_branch_id(),
+                    branch_status,
+                    String::from(""),
+                )
+                .await
+                .map_err(|e| DbErr::Custom(e.to_string()))?;
         }
         local_commit_result
     }
@@ -109,22 +108,21 @@ impl ATTransactionProxy {
             "TransactionSession------rollback----------------------{:?}",
             session
         );
-        if let Some(session) = session {
-            if session.is_global_tx_started() {
-                if let Some(xid) = session.get_xid() {
-                    let branch_status = rseata_core::branch::BranchStatus::PhaseOneFailed;
-                    RSEATA_RM
-                        .branch_report(
-                            BranchType::AT,
-                            xid,
-                            session.get_branch_id(),
-                            branch_status,
-                            String::from(""),
-                        )
-                        .await
-                        .map_err(|e| DbErr::Custom(e.to_string()))?;
-                }
-            }
+        if let Some(session) = session
+            && session.is_global_tx_started()
+            && let Some(xid) = session.get_xid()
+        {
+            let branch_status = rseata_core::branch::BranchStatus::PhaseOneFailed;
+            RSEATA_RM
+                .branch_report(
+                    BranchType::AT,
+                    xid,
+                    session.get_branch_id(),
+                    branch_status,
+                    String::from(""),
+                )
+                .await
+                .map_err(|e| DbErr::Custom(e.to_string()))?;
         }
         Ok(())
     }
@@ -155,7 +153,7 @@ impl ATTransactionProxy {
 impl ATTransactionProxy {
     async fn query_as_json(&self, sql: &str) -> Result<serde_json::Value, DbErr> {
         // 执行查询
-        let mut stmt = Statement::from_string(
+        let stmt = Statement::from_string(
             ConnectionTrait::get_database_backend(&self.at_connection_proxy),
             sql.to_owned(),
         );
@@ -261,108 +259,108 @@ impl ATTransactionProxy {
 
                         // todo : 把 before_image_select_sql 中的参数 替换为 statement.values 中对应的参数值
 
-                        if !where_clause.trim().is_empty() {
-                            if let Some(mut values) = statement.values.clone() {
-                                values.0.reverse();
-
-                                for value in values.0.iter() {
-                                    if before_image_select_sql.contains("?") {
-                                        let index = before_image_select_sql.find("?").unwrap();
-                                        before_image_select_sql.replace_range(
-                                            index..index + 1,
-                                            value.to_string().as_str(),
-                                        );
-                                    }
+                        if !where_clause.trim().is_empty()
+                            && let Some(mut values) = statement.values.clone()
+                        {
+                            values.0.reverse();
+
+                            for value in values.0.iter() {
+                                if before_image_select_sql.contains("?") {
+                                    let index = before_image_select_sql.find("?").unwrap();
+                                    before_image_select_sql.replace_range(
+                                        index..index + 1,
+                                        value.to_string().as_str(),
+                                    );
                                 }
+                            }
 
+                            println!(
+                                "before_image_select_sql ---EEENNND-- {:?}",
+                                before_image_select_sql
+                            );
+
+                            for (param_flag, value) in assignments.iter().zip(values.iter()) {
                                 println!(
-                                    "before_image_select_sql ---EEENNND-- {:?}",
-                                    before_image_select_sql
+                                    "params--------------{:?}----{:?}",
+                                    param_flag,
+                                    value.to_string()
                                 );
+                            }
 
-                                for (param_flag, value) in assignments.iter().zip(values.iter()) {
-                                    println!(
-                                        "params--------------{:?}----{:?}",
-                                        param_flag,
-                                        value.to_string()
-                                    );
-                                }
+                            // todo : 把 before_image_select_sql 中的参数 替换为 statement.values 中对应的参数值
+                            let select_before = Statement::from_sql_and_values(
+                                ConnectionTrait::get_database_backend(
+                                    &self.at_connection_proxy,
+                                ),
+                                before_image_select_sql.clone(),
+                                values.clone(), // 这里直接使用原始参数是不对的
+                            );
+
+                            let before_values =
+                                self.at_connection_proxy.query_all_raw(select_before).await;
+                            println!("r--select_before------------{:?}", before_values);
 
-                                // todo : 把 before_image_select_sql 中的参数 替换为 statement.values 中对应的参数值
-                                let select_before = Statement::from_sql_and_values(
+                            if let Ok(old_values) = &before_values {
+                                let key_sql = format!(
+                                    "SHOW KEYS FROM {} WHERE Key_name = 'PRIMARY'",
+                                    table_name
+                                );
+                                let key_select = Statement::from_string(
                                     ConnectionTrait::get_database_backend(
                                         &self.at_connection_proxy,
                                     ),
-                                    before_image_select_sql.clone(),
-                                    values.clone(), // 这里直接使用原始参数是不对的
+                                    key_sql,
                                 );
-
-                                let before_values =
-                                    self.at_connection_proxy.query_all_raw(select_before).await;
-                                println!("r--select_before------------{:?}", before_values);
-
-                                if let Ok(old_values) = &before_values {
-                                    let key_sql = format!(
-                                        "SHOW KEYS FROM {} WHERE Key_name = 'PRIMARY'",
-                                        table_name
-                                    );
-                                    let key_select = Statement::from_string(
-                                        ConnectionTrait::get_database_backend(
-                                            &self.at_connection_proxy,
-                                        ),
-                                        key_sql,
-                                    );
-                                    let key_select =
-                                        self.at_connection_proxy.query_all_raw(key_select).await;
-                                    if let Ok(key_select) = key_select {
-                                        let keys: Vec<_> = key_select
+                                let key_select =
+                                    self.at_connection_proxy.query_all_raw(key_select).await;
+                                if let Ok(key_select) = key_select {
+                                    let keys: Vec<_> = key_select
+                                        .iter()
+                                        .filter_map(|key| {
+                                            key.try_get::<String>("", "Column_name").ok()
+                                        })
+                                        .collect();
+
+                                    let mut key_values = HashMap::new();
+                                    keys.iter().for_each(|key| {
+                                        let values = old_values
                                             .iter()
-                                            .filter_map(|key| {
-                                                key.try_get::<String>("", "Column_name").ok()
+                                            .filter_map(|old_value| {
+                                                let r =
+                                                    old_value.try_get_by::<i64, &str>(key).ok();
+                                                println!("----try_get_by--{}-{:?}", key, r);
+                                                r.map(|r| r.to_string())
                                             })
-                                            .collect();
-
-                                        let mut key_values = HashMap::new();
-                                        keys.iter().for_each(|key| {
-                                            let values = old_values
-                                                .iter()
-                                                .filter_map(|old_value| {
-                                                    let r =
-                                                        old_value.try_get_by::<i64, &str>(key).ok();
-                                                    println!("----try_get_by--{}-{:?}", key, r);
-                                                    r.map(|r| r.to_string())
-                                                })
-                                                .collect::<Vec<_>>();
-                                            key_values.insert(key.to_string(), values);
-                                        });
-
-                                        old_values.iter().for_each(|old_one| {
-                                            old_one
-                                                .try_as_mysql_row()
-                                                .unwrap()
-                                                .columns()
-                                                .iter()
-                                                .for_each(|column| {
-                                                    let r = column.type_info();
-                                                    println!(
-                                                        "----type_info--------------{:?}",
-                                                        r.name()
-                                                    );
-                                                })
-                                        });
-
-                                        let key_str = key_values
+                                            .collect::<Vec<_>>();
+                                        key_values.insert(key.to_string(), values);
+                                    });
+
+                                    old_values.iter().for_each(|old_one| {
+                                        old_one
+                                            .try_as_mysql_row()
+                                            .unwrap()
+                                            .columns()
                                             .iter()
-                                            .map(|(key, values)| {
-                                                format!("{}:{}", key, values.join("_"))
+                                            .for_each(|column| {
+                                                let r = column.type_info();
+                                                println!(
+                                                    "----type_info--------------{:?}",
+                                                    r.name()
+                                                );
                                             })
-                                            .collect::<Vec<String>>()
-                                            .join(",");
-
-                                        let session = RSEATA_CLIENT_SESSION.try_get().ok();
-                                        if let Some(session) = session {
-                                            session.set_branch_luck_keys(key_str).await;
-                                        }
+                                    });
+
+                                    let key_str = key_values
+                                        .iter()
+                                        .map(|(key, values)| {
+                                            format!("{}:{}", key, values.join("_"))
+                                        })
+                                        .collect::<Vec<String>>()
+                                        .join(",");
+
+                                    let session = RSEATA_CLIENT_SESSION.try_get().ok();
+                                    if let Some(session) = session {
+                                        session.set_branch_luck_keys(key_str).await;
                                     }
                                 }
                             }
@@ -374,6 +372,7 @@ impl ATTransactionProxy {
                             Ok(data) => {
                                 let old = serde_json::to_string(&data).unwrap_or_default();
                                 println!("before old-------{}", old);
+                                Self::record_undo_log(old).await;
 
                                 // 生成回滚sql
                                 // fn generate_update_rollback(table: &str, data: &Value) -> String {
@@ -412,385 +411,39 @@ impl ATTransactionProxy {
                         }
 
                         println!("before is-------{:?}", before);
-                    } else if let sqlparser::ast::Statement::Insert(i) = parsed_statement {
-                        // Insert没有 before_image
-                    } else if let sqlparser::ast::Statement::Delete(i) = parsed_statement {
-                        // 记录 Delete 的 before_image
                     }
 
                     println!("{:#?}", statement);
                 }
             }
-            Err(e) => eprintln!("Parse error: {}", e),
-        }
-
-        Ok(())
-    }
-
-    async fn process_luck_keys(&self, update: &sqlparser::ast::Statement) -> Result<(), DbErr> {
-        if let sqlparser::ast::Statement::Update {
-            table,
-            assignments,
-            from,
-            selection,
-            returning,
-            or,
-            ..
-        } = update
-        {
-            let table_name = table.relation.to_string();
-            let where_clause = selection
-                .as_ref()
-                .map(|e| e.to_string())
-                .unwrap_or_default();
-
-            for assignment in assignments {
-                println!("assignments-------------{:?}", assignment);
-            }
-            let vec_str: Vec<String> = assignments.iter().map(|it| it.target.to_string()).collect();
-
-            println!("assignments-----CLO--------{:?}", vec_str);
-
-            // 获取before_image
-            let select_sql = format!("SELECT * FROM {} WHERE {}", table_name, where_clause);
-            println!("before select_sql-------{}", select_sql);
-            // let before_result =  self.0.execute_unprepared(select_sql.as_ref()).await?;
-            let before = self.query_as_json(&select_sql).await;
-            match &before {
-                Ok(data) => {
-                    let old = serde_json::to_string(&data).unwrap_or_default();
-                    println!("before old-------{}", old);
-
-                    // 生成回滚sql
-                    // fn generate_update_rollback(table: &str, data: &Value) -> String {
-                    //     let mut sql = format!("UPDATE {} SET ", table);
-                    //     if let Some(first_row) = data.as_array().and_then(|a| a.first()) {
-                    //         for (key, value) in first_row.as_object().unwrap() {
-                    //             sql.push_str(&format!("{} = {}, ", key, value));
-                    //         }
-                    //         sql.truncate(sql.len() - 2);
-                    //         sql.push_str(" WHERE ..."); // 根据主键生成条件
-                    //     }
-                    //     sql
-                    // }
-                    //
-
-                    let mut sql = format!("UPDATE {} SET ", table);
-                    if let Some(first_row) = data.as_array().and_then(|a| a.first()) {
-                        for (key, value) in first_row.as_object().unwrap() {
-                            if vec_str.contains(key) {
-                                sql.push_str(&format!("{} = {}, ", key, value));
-                            }
-                        }
-                        sql.truncate(sql.len() - 2);
-                        sql.push_str(format!(" WHERE {}", where_clause).as_str()); // 根据主键生成条件
-                    }
-                    println!("---back sql-------{}", sql);
-                    let back = self
-                        .at_connection_proxy
-                        .execute_unprepared(sql.as_str())
-                        .await;
-                    println!("---back sql--back-----{:?}", back);
-                }
-                Err(e) => {
-                    eprintln!("{}", e);
-                }
+            Err(e) => {
+                eprintln!("Parse error: {}", e);
             }
-
-            println!("before is-------{:?}", before);
-
-            // let r =  self.0.query_all(Statement::from_string(self.0.get_database_backend(), select_sql.as_str())).await?;
-            //
-            //   println!("before is-------{:?}",r);
-
-            // let before = self.query_as_json(&select_sql).await?;
-
-            // // 执行更新
-            //  let _ = self.0.execute_unprepared(select_sql).await?;
-            //
-            // // 获取after_image
-            // let after = self.query_as_json(&select_sql).await?;
-            //
-            // // 生成回滚SQL
-            // let rollback = generate_update_rollback(&table_name, &before);
         }
 
         Ok(())
     }
 
-    async fn process_execute_qw(&self, statement: &Statement) -> Result<(), DbErr> {
-        println!("Processing execute: {:?}", statement);
-        let detect = get_sql_pars_detect(&ConnectionTrait::get_database_backend(
-            &self.at_connection_proxy,
-        ));
-        let parsed = sqlparser::parser::Parser::parse_sql(detect.as_ref(), statement.sql.as_str());
-
-        match &parsed {
-            Ok(parsed_statements) => {
-                for parsed_statement in parsed_statements {
-                    match parsed_statement {
-                        sqlparser::ast::Statement::Update {
-                            table,
-                            assignments,
-                            from,
-                            selection,
-                            returning: _,
-                            or: _,
-                            ..
-                        } => {
-                            let table_name = table.relation.to_string();
-                            let where_clause = selection
-                                .as_ref()
-                                .map(|e| e.to_string())
-                                .unwrap_or_default();
-
-                            for assignment in assignments {
-                                println!("assignments-------------{:?}", assignment);
-                            }
-                            let vec_str: Vec<String> =
-                                assignments.iter().map(|it| it.target.to_string()).collect();
-
-                            println!("assignments-----CLO--------{:?}", vec_str);
-
-                            // 获取before_image
-                            let before_image_select_sql = {
-                                if where_clause.trim().is_empty() {
-                                    format!("SELECT * FROM {} ", table_name)
-                                } else {
-                                    format!("SELECT * FROM {} WHERE {}", table_name, where_clause)
-                                }
-                            };
-                            println!(
-                                "before_image_select_sql is ------ {}",
-                                before_image_select_sql
-                            );
-
-                            if !where_clause.trim().is_empty() {
-                                if let Some(values) = &statement.values {
-                                    // 构建参数映射，将原SQL中的参数值按顺序映射到before_image_select_sql中
-                                    let mut new_values: Vec<sea_orm::query::Values> = Vec::new();
-
-                                    // 解析before_image_select_sql，统计参数占位符数量
-                                    let before_detect = get_sql_pars_detect(
-                                        &ConnectionTrait::get_database_backend(
-                                            &self.at_connection_proxy,
-                                        ),
-                                    );
-                                    let before_parsed = sqlparser::parser::Parser::parse_sql(
-                                        before_detect.as_ref(),
-                                        &before_image_select_sql,
-                                    );
-
-                                    if let Ok(before_statements) = before_parsed {
-                                        if let Some(before_statement) = before_statements.first() {
-                                            // 获取before_image_sql中的参数数量
-                                            let before_param_count =
-                                                self.count_params_in_statement(before_statement);
-
-                                            // 根据before_image_sql中参数的位置，从原始values中提取对应参数
-                                            // 这里需要更精确的参数映射逻辑
-                                            for _ in 0..before_param_count {
-                                                // 按顺序从原始values中取值，这里简化处理
-                                                // 实际应用中需要根据参数位置精确映射
-                                                new_values.extend_from_slice(&[values.clone()]);
-                                                break; // 为了防止重复添加，暂时跳出
-                                            }
-
-                                            // 重新解析以准确计算参数数量
-                                            let param_count =
-                                                self.count_placeholders(&before_image_select_sql);
-                                            if param_count > 0 && !values.0.is_empty() {
-                                                // 如果before_image_sql有参数，则按需复制原始值
-                                                for _ in 0..param_count {
-                                                    new_values.extend_from_slice(&[values.clone()]);
-                                                    break; // 避免无限复制
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    // 为简化，我们直接使用原始values，但需要确保参数数量匹配
-                                    // 这里先用原始参数值
-                                    let select_before = Statement::from_sql_and_values(
-                                        ConnectionTrait::get_database_backend(
-                                            &self.at_connection_proxy,
-                                        ),
-                                        before_image_select_sql.clone(),
-                                        values.clone(),
-                                    );
-
-                                    let r =
-                                        self.at_connection_proxy.query_all_raw(select_before).await;
-
-                                    println!("r--select_before------------{:?}", r);
-
-                                    // 处理查询结果，生成undo log
-                                    if let Ok(query_results) = r {
-                                        if !query_results.is_empty() {
-                                            let before =
-                                                self.query_as_json(&before_image_select_sql).await;
-                                            match &before {
-                                                Ok(data) => {
-                                                    let old = serde_json::to_string(&data)
-                                                        .unwrap_or_default();
-                                                    println!("before old-------{}", old);
-
-                                                    // 生成回滚sql
-                                                    let mut rollback_sql =
-                                                        format!("UPDATE {} SET ", table);
-                                                    if let Some(first_row) =
-                                                        data.as_array().and_then(|a| a.first())
-                                                    {
-                                                        for (key, value) in
-                                                            first_row.as_object().unwrap()
-                                                        {
-                                                            if vec_str.contains(key) {
-                                                                rollback_sql.push_str(&format!(
-                                                                    "{} = {}, ",
-                                                                    key, value
-                                                                ));
-                                                            }
-                                                        }
-                                                        rollback_sql
-                                                            .truncate(rollback_sql.len() - 2);
-                                                        rollback_sql.push_str(
-                                                            format!(" WHERE {}", where_clause)
-                                                                .as_str(),
-                                                        );
-                                                    }
-                                                    println!(
-                                                        "---rollback sql-------{}",
-                                                        rollback_sql
-                                                    );
-
-                                                    // 这里应该将回滚SQL和相关信息保存到undo log中
-                                                    // 暂时只打印，实际实现需要存储到事务上下文中
-                                                }
-                                                Err(e) => {
-                                                    eprintln!(
-                                                        "Error processing before image: {}",
-                                                        e
-                                                    );
-                                                }
-                                            }
-                                        } else {
-                                            // 如果查询没有返回结果，说明WHERE条件不匹配任何记录
-                                            println!("No records found for before image query");
-                                        }
-                                    }
-                                } else {
-                                    // 如果没有参数值，直接执行查询（可能不适用于参数化查询）
-                                    let select_before = Statement::from_string(
-                                        ConnectionTrait::get_database_backend(
-                                            &self.at_connection_proxy,
-                                        ),
-                                        before_image_select_sql.clone(),
-                                    );
-
-                                    let r =
-                                        self.at_connection_proxy.query_all_raw(select_before).await;
-                                    println!("r--select_before------------{:?}", r);
-                                }
-                            } else {
-                                // 如果没有WHERE子句，查询整个表
-                                let select_before = Statement::from_string(
-                                    ConnectionTrait::get_database_backend(
-                                        &self.at_connection_proxy,
-                                    ),
-                                    before_image_select_sql.clone(),
-                                );
-
-                                let r = self.at_connection_proxy.query_all_raw(select_before).await;
-                                println!("r--select_before------------{:?}", r);
-                            }
-                        }
-                        sqlparser::ast::Statement::Insert(i) => {
-                            // Insert没有 before_image
-                            println!("Processing INSERT statement");
-                        }
-                        sqlparser::ast::Statement::Delete(d) => {
-                            // 记录 Delete 的 before_image
-                            println!("Processing DELETE statement");
-
-                            let table_name = d.from.to_string();
-                            let where_clause = d
-                                .selection
-                                .as_ref()
-                                .map(|e| e.to_string())
-                                .unwrap_or_default();
-
-                            let before_image_select_sql = {
-                                if where_clause.trim().is_empty() {
-                                    format!("SELECT * FROM {} ", table_name)
-                                } else {
-                                    format!("SELECT * FROM {} WHERE {}", table_name, where_clause)
-                                }
-                            };
-
-                            if let Some(values) = &statement.values {
-                                let select_before = Statement::from_sql_and_values(
-                                    ConnectionTrait::get_database_backend(
-                                        &self.at_connection_proxy,
-                                    ),
-                                    before_image_select_sql.clone(),
-                                    values.clone(),
-                                );
-
-                                let r = self.at_connection_proxy.query_all_raw(select_before).await;
-                                println!("r--select_before_delete------------{:?}", r);
-                            } else {
-                                let select_before = Statement::from_string(
-                                    ConnectionTrait::get_database_backend(
-                                        &self.at_connection_proxy,
-                                    ),
-                                    before_image_select_sql.clone(),
-                                );
-
-                                let r = self.at_connection_proxy.query_all_raw(select_before).await;
-                                println!("r--select_before_delete------------{:?}", r);
-                            }
-                        }
-                        _ => {
-                            // 其他SQL语句，暂时不处理
-                            println!("Processing other statement type: {:?}", parsed_statement);
-                        }
-                    }
-
-                    println!("{:#?}", statement);
-                }
-            }
-            Err(e) => eprintln!("Parse error: {}", e),
+    async fn record_undo_log(undo_log: String) {
+        if let Ok(session) = RSEATA_CLIENT_SESSION.try_get() {
+            session.add_branch_undo_log(undo_log).await;
         }
-
-        Ok(())
     }
 
-    // 辅助方法：计算SQL语句中的参数占位符数量
-    fn count_placeholders(&self, sql: &str) -> usize {
-        sql.chars().filter(|&c| c == '?').count()
-    }
-
-    // 辅助方法：计算语句中的参数数量
-    fn count_params_in_statement(&self, stmt: &sqlparser::ast::Statement) -> usize {
-        // 这是一个简化的实现，实际需要根据具体语句类型分析
-        // 通常SQL中的参数占位符是?号
-        match stmt {
-            sqlparser::ast::Statement::Query(query) => {
-                // 递归分析查询中的参数
-                self.count_params_in_query(query)
-            }
-            sqlparser::ast::Statement::Update { .. }
-            | sqlparser::ast::Statement::Insert { .. }
-            | sqlparser::ast::Statement::Delete { .. } => {
-                // 简单计算SQL字符串中的问号数量
-                format!("{:?}", stmt).chars().filter(|&c| c == '?').count()
-            }
-            _ => 0,
+    // 除查询外的语句都可能写入，无法解析的语句同样按写入处理
+    fn mark_writes(&self, sql: &str) {
+        if let Ok(session) = RSEATA_CLIENT_SESSION.try_get()
+            && is_write(&ConnectionTrait::get_database_backend(self), sql)
+        {
+            session.mark_branch_writes();
         }
     }
 
-    fn count_params_in_query(&self, query: &sqlparser::ast::Query) -> usize {
-        // 简化实现，计算查询中的参数数量
-        format!("{:?}", query).chars().filter(|&c| c == '?').count()
+    /// 全局事务中没有执行过写入语句的本地事务，不注册分支
+    pub(crate) async fn is_read_only(&self) -> bool {
+        match RSEATA_CLIENT_SESSION.try_get() {
+            Ok(session) => session.is_global_tx_started() && !session.has_branch_writes(),
+            Err(_) => false,
+        }
     }
 }
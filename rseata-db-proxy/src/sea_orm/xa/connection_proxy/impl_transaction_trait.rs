@@ -4,7 +4,6 @@ use sea_orm::{
     AccessMode, DbErr, IsolationLevel, TransactionError,
     TransactionSession, TransactionTrait,
 };
-use std::env;
 use std::fmt::{Debug, Display};
 use std::pin::Pin;
 #[async_trait::async_trait]
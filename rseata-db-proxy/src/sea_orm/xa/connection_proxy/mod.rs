@@ -13,14 +13,21 @@ pub struct XAId(pub String);
 pub struct XAConnectionProxy {
     pub url: String,
     pub sea_connection: sea_orm::DatabaseConnection,
+    /// 一阶段只执行 XA END 不 PREPARE，由 TC 在提交时决定是否 ONE PHASE 提交
+    pub one_phase_enabled: bool,
 }
 impl XAConnectionProxy {
     pub async fn connect_mysql(url: &str) -> Result<Self, DbErr> {
         let t = sea_orm::Database::connect(url).await?;
 
+        let one_phase_enabled = std::env::var("RSEATA_XA_ONE_PHASE_ENABLED")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
         Ok(Self {
             url: url.to_string(),
             sea_connection: t,
+            one_phase_enabled,
         })
     }
 }
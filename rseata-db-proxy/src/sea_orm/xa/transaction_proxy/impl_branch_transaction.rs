@@ -1,17 +1,41 @@
-use crate::sea_orm::xa::transaction_proxy::{TransactionType, XATransactionProxy};
+use crate::sea_orm::xa::transaction_proxy::{TransactionType, XATransaction, XATransactionProxy};
 use async_trait::async_trait;
 use rseata_core::branch::branch_transaction::BranchTransaction;
-use rseata_core::branch::{BranchId, BranchStatus, BranchType};
+use rseata_core::branch::{BranchId, BranchStatus, BranchType, XA_ONE_PHASE};
 use rseata_core::types::{ResourceId, Xid};
 
 #[async_trait]
 impl BranchTransaction for XATransactionProxy {
+    // TC 在提交任何分支前要求 IDLE 分支 PREPARE，失败时全局事务改为回滚
+    async fn branch_prepare(
+        &self,
+        _branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        _resource_id: ResourceId,
+        _application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        let TransactionType::XA(xa_transaction) = &self.transaction_type else {
+            return Ok(BranchStatus::PhaseOneDone);
+        };
+        if xa_transaction.is_prepared() {
+            return Ok(BranchStatus::PhaseOneDone);
+        }
+        match xa_transaction.xa_prepare().await {
+            Ok(_) => Ok(BranchStatus::PhaseOneDone),
+            Err(e) => {
+                tracing::warn!("XA branch prepare failed :{xid},{branch_id}, {e}");
+                Ok(BranchStatus::PhaseOneFailed)
+            }
+        }
+    }
+
     async fn branch_commit(
         &self,
-        branch_type: BranchType,
+        _branch_type: BranchType,
         xid: Xid,
         branch_id: BranchId,
-        resource_id: ResourceId,
+        _resource_id: ResourceId,
         application_data: String,
     ) -> anyhow::Result<BranchStatus> {
         tracing::info!("XA branch_commit ing :{xid},{branch_id}",);
@@ -24,7 +48,21 @@ impl BranchTransaction for XATransactionProxy {
                 }
             }
             TransactionType::XA(xa_transaction) => {
-                xa_transaction.xa_commit().await?;
+                let commit_result = if xa_transaction.is_prepared() {
+                    xa_transaction.xa_commit().await
+                } else if application_data == XA_ONE_PHASE {
+                    xa_transaction.xa_commit_one_phase().await
+                } else {
+                    // 未 PREPARE 的分支只能由 TC 确认为唯一分支后一阶段提交
+                    tracing::error!("XA branch_commit on unprepared branch :{xid},{branch_id}");
+                    return Ok(BranchStatus::PhaseTwoCommitFailedUnretryable);
+                };
+                if let Err(e) = commit_result
+                    && !XATransaction::is_xa_not_found(&e)
+                {
+                    tracing::warn!("XA branch_commit failed :{xid},{branch_id}, {e}");
+                    return Ok(BranchStatus::PhaseTwoCommitFailedRetryable);
+                }
             }
         }
         Ok(BranchStatus::PhaseTwoCommitted)
@@ -32,11 +70,11 @@ impl BranchTransaction for XATransactionProxy {
 
     async fn branch_rollback(
         &self,
-        branch_type: BranchType,
+        _branch_type: BranchType,
         xid: Xid,
         branch_id: BranchId,
-        resource_id: ResourceId,
-        application_data: String,
+        _resource_id: ResourceId,
+        _application_data: String,
     ) -> anyhow::Result<BranchStatus> {
         match &self.transaction_type {
             TransactionType::Local(local) => {
@@ -46,9 +84,14 @@ impl BranchTransaction for XATransactionProxy {
                 }
             }
             TransactionType::XA(xa_transaction) => {
-                xa_transaction.xa_rollback().await?;
+                if let Err(e) = xa_transaction.xa_rollback().await
+                    && !XATransaction::is_xa_not_found(&e)
+                {
+                    tracing::warn!("XA branch_rollback failed :{xid},{branch_id}, {e}");
+                    return Ok(BranchStatus::PhaseTwoRollbackFailedRetryable);
+                }
             }
         }
-        Ok(BranchStatus::PhaseTwoCommitted)
+        Ok(BranchStatus::PhaseTwoRollbacked)
     }
 }
@@ -1,6 +1,5 @@
 use crate::sea_orm::xa::transaction_proxy::{TransactionType, XATransactionProxy};
 use sea_orm::{sqlx, ConnectionTrait, DbBackend, DbErr, ExecResult, QueryResult, Statement, Values};
-use sea_orm::sqlx::Executor;
 use sea_query_sqlx::SqlxValues;
 
 #[async_trait::async_trait]
@@ -1,6 +1,3 @@
-use crate::sea_orm::xa::transaction_proxy::{TransactionType, XATransactionProxy};
-use sea_orm::{DbBackend, DbErr, QueryStream, Statement, StreamTrait, TransactionStream};
-use std::pin::Pin;
 
 // impl StreamTrait for XATransactionProxy {
 //     type Stream<'a> = TransactionStream<'a>;
@@ -1,5 +1,5 @@
 use crate::sea_orm::xa::transaction_proxy::{TransactionType, XATransactionProxy};
-use sea_orm::{DbErr, ExecResult, TransactionSession};
+use sea_orm::{DbErr, TransactionSession};
 
 #[async_trait::async_trait]
 impl TransactionSession for XATransactionProxy {
@@ -14,7 +14,8 @@ impl TransactionSession for XATransactionProxy {
                 }
             }
             TransactionType::XA(ref xa_transaction) => {
-                self.branch_register().await?;
+                self.branch_register(self.xa_connection_proxy.one_phase_enabled)
+                    .await?;
                 let end_result = xa_transaction.xa_end().await;
                 let lucked = self.check_lock().await?;
                 if !lucked {
@@ -22,6 +23,10 @@ impl TransactionSession for XATransactionProxy {
                     return self.rollback().await;
                 }
                 match end_result {
+                    // 不做 PREPARE，分支以 IDLE 注册；TC 确认为唯一分支时 ONE PHASE 提交，否则提交前先要求 PREPARE
+                    Ok(_) if self.xa_connection_proxy.one_phase_enabled => {
+                        XATransactionProxy::report_local_commit(Ok(())).await
+                    }
                     Ok(_) => {
                         let prepare_result = xa_transaction.xa_prepare().await;
                         match prepare_result {
@@ -46,7 +51,7 @@ impl TransactionSession for XATransactionProxy {
                 }
             }
             TransactionType::XA(xa_transaction) => {
-                self.branch_register().await?;
+                self.branch_register(false).await?;
                 let end_result = xa_transaction.xa_rollback().await;
                 let _ = XATransactionProxy::report_local_rollback().await?;
                 end_result.map(|_| ())
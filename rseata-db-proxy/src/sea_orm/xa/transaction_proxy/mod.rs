@@ -5,13 +5,13 @@ mod impl_transaction_session;
 mod impl_transaction_trait;
 
 use crate::sea_orm::xa::connection_proxy::{XAConnectionProxy, XAId};
+use rseata_core::RSEATA_CLIENT_SESSION;
+use rseata_core::branch::{BranchType, XA_IDLE};
 use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
 use rseata_core::branch::branch_transaction::BranchTransactionRegistry;
-use rseata_core::branch::BranchType;
 use rseata_core::resource::Resource;
 use rseata_core::transaction::transaction_manager::TransactionManager;
 use rseata_core::types::Xid;
-use rseata_core::RSEATA_CLIENT_SESSION;
 use rseata_rm::RSEATA_RM;
 use rseata_tm::RSEATA_TM;
 use sea_orm::sqlx::types::uuid;
@@ -20,6 +20,7 @@ use sea_orm::{
     AccessMode, DatabaseTransaction, DbErr, IsolationLevel, RuntimeErr, TransactionTrait,
 };
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
 
 #[derive(Clone)]
@@ -27,6 +28,7 @@ pub struct XATransaction {
     pub xa_id: XAId,
     pub xid: Xid,
     pub connection: Arc<Mutex<MySqlConnection>>,
+    pub prepared: Arc<AtomicBool>,
 }
 
 impl XATransaction {
@@ -43,7 +45,7 @@ impl XATransaction {
         r
     }
 
-    async fn xa_start(conn: &mut MySqlConnection) -> Result<(XAId), DbErr> {
+    async fn xa_start(conn: &mut MySqlConnection) -> Result<XAId , DbErr> {
         let xa_id = uuid::Uuid::new_v4().to_string();
         let sql = format!("XA START '{}'", xa_id);
         conn.execute(sql.as_str())
@@ -58,17 +60,32 @@ impl XATransaction {
     }
     pub async fn xa_prepare(&self) -> Result<(), DbErr> {
         let sql = format!("XA PREPARE '{}'", self.xa_id.0);
-        self.execute_sql(&sql).await
+        self.execute_sql(&sql).await?;
+        self.prepared.store(true, Ordering::SeqCst);
+        Ok(())
     }
     pub async fn xa_commit(&self) -> Result<(), DbErr> {
         let sql = format!("XA COMMIT '{}'", self.xa_id.0);
         self.execute_sql(&sql).await
     }
+    pub async fn xa_commit_one_phase(&self) -> Result<(), DbErr> {
+        let sql = format!("XA COMMIT '{}' ONE PHASE", self.xa_id.0);
+        self.execute_sql(&sql).await
+    }
+
+    pub fn is_prepared(&self) -> bool {
+        self.prepared.load(Ordering::SeqCst)
+    }
 
     pub async fn xa_rollback(&self) -> Result<(), DbErr> {
         let sql = format!("XA ROLLBACK '{}'", self.xa_id.0);
         self.execute_sql(&sql).await
     }
+
+    /// XAER_NOTA：xa_id 在数据库中已不存在，说明二阶段已经完成过
+    pub(crate) fn is_xa_not_found(err: &DbErr) -> bool {
+        err.to_string().contains("XAER_NOTA")
+    }
 }
 
 #[derive(Clone)]
@@ -110,7 +127,7 @@ impl XATransactionProxy {
                         RSEATA_TM.application_id.to_string(),
                         RSEATA_TM.transaction_service_group.to_string(),
                         session.transaction_name.clone(),
-                        100,
+                        RSEATA_TM.timeout_millis,
                     )
                     .await
                     .map_err(|e| DbErr::Conn(RuntimeErr::Internal(e.to_string())))?;
@@ -130,6 +147,7 @@ impl XATransactionProxy {
                     xa_id,
                     xid: xid_init.ok_or(DbErr::Custom("XID initialization failed".to_string()))?,
                     connection: Arc::new(Mutex::new(conn)),
+                    prepared: Arc::new(AtomicBool::new(false)),
                 }),
                 xa_connection_proxy: xa_connection_proxy.clone(),
             })
@@ -153,7 +171,8 @@ impl std::fmt::Debug for XATransactionProxy {
 }
 
 impl XATransactionProxy {
-    pub async fn branch_register(&self) -> Result<(), DbErr> {
+    /// 注册 XA 分支，idle 为 true 表示分支 END 后不做 PREPARE，由 TC 在二阶段决定
+    pub async fn branch_register(&self, idle: bool) -> Result<(), DbErr> {
         let session = RSEATA_CLIENT_SESSION.try_get().ok();
         if let Some(session) = &session {
             let xid_guard = session.get_xid();
@@ -161,11 +180,11 @@ impl XATransactionProxy {
                 let lock_keys = session.get_branch_luck_keys().await.unwrap_or_default();
                 let branch_id = RSEATA_RM
                     .branch_transaction_registry(
-                        RSEATA_RM.resource_info.get_branch_type().await,
+                        BranchType::XA,
                         RSEATA_RM.resource_info.get_resource_id().await,
                         RSEATA_RM.resource_info.get_client_id().await,
                         xid,
-                        "application_data".into(),
+                        if idle { XA_IDLE } else { "application_data" }.into(),
                         lock_keys,
                         Box::new(self.clone()),
                     )
@@ -179,25 +198,24 @@ impl XATransactionProxy {
 
     pub async fn report_local_commit(local_commit_result: Result<(), DbErr>) -> Result<(), DbErr> {
         let session = RSEATA_CLIENT_SESSION.try_get().ok();
-        if let Some(session) = session {
-            if session.is_global_tx_started() {
-                if let Some(xid) = session.get_xid() {
-                    let branch_status = match local_commit_result {
-                        Ok(_) => rseata_core::branch::BranchStatus::PhaseOneDone,
-                        Err(_) => rseata_core::branch::BranchStatus::PhaseOneFailed,
-                    };
-                    RSEATA_RM
-                        .branch_report(
-                            BranchType::AT,
-                            xid,
-                            session.get_branch_id(),
-                            branch_status,
-                            String::from(""),
-                        )
-                        .await
-                        .map_err(|e| DbErr::Custom(e.to_string()))?;
-                }
-            }
+        if let Some(session) = session
+            && session.is_global_tx_started()
+            && let Some(xid) = session.get_xid()
+        {
+            let branch_status = match local_commit_result {
+                Ok(_) => rseata_core::branch::BranchStatus::PhaseOneDone,
+                Err(_) => rseata_core::branch::BranchStatus::PhaseOneFailed,
+            };
+            RSEATA_RM
+                .branch_report(
+                    BranchType::XA,
+                    xid,
+                    session.get_branch_id(),
+                    branch_status,
+                    String::from(""),
+                )
+                .await
+                .map_err(|e| DbErr::Custom(e.to_string()))?;
         }
         local_commit_result.map(|_| ())
     }
@@ -205,22 +223,21 @@ impl XATransactionProxy {
     pub async fn report_local_rollback() -> Result<(), DbErr> {
         let session = RSEATA_CLIENT_SESSION.try_get().ok();
 
-        if let Some(session) = session {
-            if session.is_global_tx_started() {
-                if let Some(xid) = session.get_xid() {
-                    let branch_status = rseata_core::branch::BranchStatus::PhaseOneFailed;
-                    RSEATA_RM
-                        .branch_report(
-                            BranchType::AT,
-                            xid,
-                            session.get_branch_id(),
-                            branch_status,
-                            String::from(""),
-                        )
-                        .await
-                        .map_err(|e| DbErr::Custom(e.to_string()))?;
-                }
-            }
+        if let Some(session) = session
+            && session.is_global_tx_started()
+            && let Some(xid) = session.get_xid()
+        {
+            let branch_status = rseata_core::branch::BranchStatus::PhaseOneFailed;
+            RSEATA_RM
+                .branch_report(
+                    BranchType::XA,
+                    xid,
+                    session.get_branch_id(),
+                    branch_status,
+                    String::from(""),
+                )
+                .await
+                .map_err(|e| DbErr::Custom(e.to_string()))?;
         }
         Ok(())
     }
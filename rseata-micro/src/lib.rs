@@ -1,6 +1,9 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, spanned::Spanned, Error, ItemFn, LitStr};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    parse_macro_input, spanned::Spanned, AttributeArgs, Error, FnArg, ItemFn, Lit, LitStr, Meta,
+    MetaNameValue, NestedMeta, Pat, PatType, Path,
+};
 
 #[proc_macro_attribute]
 pub fn global_transaction(
@@ -73,4 +76,186 @@ pub fn global_transaction(
     };
     input_fn.block = syn::parse2(new_block).unwrap();
     TokenStream::from(quote! { #input_fn })
-}
\ No newline at end of file
+}
+/// TCC 动作注解：函数体作为 try，参数序列化为动作上下文，confirm / cancel 指向同参数的函数
+///
+/// 动作在 `rseata::init()` 时自动注册
+///
+/// ```ignore
+/// #[tcc_action(confirm = "deduct_confirm", cancel = "deduct_cancel")]
+/// pub async fn deduct(user_id: i64, amount: i64) -> anyhow::Result<()> { Ok(()) }
+///
+/// pub async fn deduct_confirm(branch: &TccBranch, user_id: i64, amount: i64) -> anyhow::Result<()> { Ok(()) }
+/// pub async fn deduct_cancel(branch: &TccBranch, user_id: i64, amount: i64) -> anyhow::Result<()> { Ok(()) }
+/// ```
+#[proc_macro_attribute]
+pub fn tcc_action(attr: TokenStream, func: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as AttributeArgs);
+    let input_fn = parse_macro_input!(func as ItemFn);
+
+    let mut confirm = None;
+    let mut cancel = None;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(value),
+                ..
+            })) if path.is_ident("confirm") || path.is_ident("cancel") => {
+                let target = match value.parse::<Path>() {
+                    Ok(target) => target,
+                    Err(_) => {
+                        return Error::new(value.span(), "expected a function path")
+                            .to_compile_error()
+                            .into();
+                    }
+                };
+                if path.is_ident("confirm") {
+                    confirm = Some(target);
+                } else {
+                    cancel = Some(target);
+                }
+            }
+            other => {
+                return Error::new(
+                    other.span(),
+                    "expected `confirm = \"...\"` or `cancel = \"...\"`",
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+    }
+    let (Some(confirm), Some(cancel)) = (confirm, cancel) else {
+        return Error::new(
+            input_fn.sig.span(),
+            "tcc_action requires both `confirm` and `cancel`",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let sig = &input_fn.sig;
+    if sig.asyncness.is_none() {
+        return Error::new(sig.span(), "tcc_action can only be applied to async functions")
+            .to_compile_error()
+            .into();
+    }
+    if !sig.generics.params.is_empty() {
+        return Error::new(sig.generics.span(), "tcc_action does not support generic functions")
+            .to_compile_error()
+            .into();
+    }
+
+    let mut arg_idents = Vec::new();
+    let mut arg_types = Vec::new();
+    for input in &sig.inputs {
+        match input {
+            FnArg::Typed(PatType { pat, ty, .. }) => match &**pat {
+                Pat::Ident(pat_ident) => {
+                    arg_idents.push(pat_ident.ident.clone());
+                    arg_types.push(ty.clone());
+                }
+                other => {
+                    return Error::new(other.span(), "tcc_action arguments must be plain identifiers")
+                        .to_compile_error()
+                        .into();
+                }
+            },
+            FnArg::Receiver(receiver) => {
+                return Error::new(receiver.span(), "tcc_action does not support methods")
+                    .to_compile_error()
+                    .into();
+            }
+        }
+    }
+
+    let fn_span = sig.span();
+    let vis = &input_fn.vis;
+    let attrs = &input_fn.attrs;
+    let name = &sig.ident;
+    let inputs = &sig.inputs;
+    let output = &sig.output;
+    let block = &input_fn.block;
+    let context_ident = format_ident!("{}_tcc_context", name);
+    let action_ident = format_ident!("{}_tcc_action", name);
+    let try_ident = format_ident!("__{}_tcc_try", name);
+
+    let confirm_call = quote_spanned! {fn_span=>
+        #confirm(branch, #(context.#arg_idents),*).await
+    };
+    let cancel_call = quote_spanned! {fn_span=>
+        #cancel(branch, #(context.#arg_idents),*).await
+    };
+    let try_call = quote_spanned! {fn_span=>
+        rseata::tcc::tcc_try_with(
+            std::sync::Arc::new(#action_ident),
+            &context,
+            |_branch| #try_ident(#(#arg_idents),*),
+        )
+        .await
+    };
+
+    TokenStream::from(quote! {
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, rseata::tcc::__private::serde::Serialize, rseata::tcc::__private::serde::Deserialize)]
+        #[serde(crate = "rseata::tcc::__private::serde")]
+        #vis struct #context_ident {
+            #(pub #arg_idents: #arg_types,)*
+        }
+
+        #[allow(non_camel_case_types)]
+        #vis struct #action_ident;
+
+        #[rseata::tcc::__private::async_trait::async_trait]
+        impl rseata::tcc::TccAction for #action_ident {
+            type Context = #context_ident;
+
+            fn name(&self) -> &'static str {
+                concat!(module_path!(), "::", stringify!(#name))
+            }
+
+            async fn r#try(
+                &self,
+                _branch: &rseata::tcc::TccBranch,
+                context: &Self::Context,
+            ) -> rseata::tcc::__private::anyhow::Result<()> {
+                let context = context.clone();
+                #try_ident(#(context.#arg_idents),*).await.map(|_| ())
+            }
+
+            async fn confirm(
+                &self,
+                branch: &rseata::tcc::TccBranch,
+                context: Self::Context,
+            ) -> rseata::tcc::__private::anyhow::Result<()> {
+                #confirm_call
+            }
+
+            async fn cancel(
+                &self,
+                branch: &rseata::tcc::TccBranch,
+                context: Self::Context,
+            ) -> rseata::tcc::__private::anyhow::Result<()> {
+                #cancel_call
+            }
+        }
+
+        // 启动时注册动作，RM 重启后先于 try 到达的二阶段指令也能找到动作
+        rseata::tcc::__private::inventory::submit! {
+            rseata::tcc::TccActionRegistration::new(|| rseata::tcc::tcc_branch_transaction(#action_ident))
+        }
+
+        #[allow(unused_mut)]
+        async fn #try_ident(#inputs) #output #block
+
+        #(#attrs)*
+        #[allow(unused_mut)]
+        #vis async fn #name(#inputs) #output {
+            let context = #context_ident {
+                #(#arg_idents: #arg_idents.clone(),)*
+            };
+            #try_call
+        }
+    })
+}
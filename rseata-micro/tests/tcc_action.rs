@@ -0,0 +1,10 @@
+#[test]
+fn tcc_action_ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/tcc_action_pass.rs");
+    t.compile_fail("tests/ui/tcc_action_missing_*.rs");
+    t.compile_fail("tests/ui/tcc_action_not_async.rs");
+    t.compile_fail("tests/ui/tcc_action_generic.rs");
+    t.compile_fail("tests/ui/tcc_action_self.rs");
+    t.compile_fail("tests/ui/tcc_action_pattern_arg.rs");
+}
@@ -0,0 +1,9 @@
+use rseata::tcc_action;
+
+#[tcc_action(confirm = "deduct_confirm", cancel = "deduct_cancel")]
+pub async fn deduct<T: Clone>(value: T) -> anyhow::Result<()> {
+    let _ = value;
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use rseata::tcc::TccBranch;
+use rseata::tcc_action;
+
+#[tcc_action(confirm = "deduct_confirm")]
+pub async fn deduct(user_id: i64) -> anyhow::Result<()> {
+    let _ = user_id;
+    Ok(())
+}
+
+pub async fn deduct_confirm(_branch: &TccBranch, _user_id: i64) -> anyhow::Result<()> {
+    Ok(())
+}
+
+fn main() {}
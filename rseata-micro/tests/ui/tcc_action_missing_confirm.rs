@@ -0,0 +1,14 @@
+use rseata::tcc::TccBranch;
+use rseata::tcc_action;
+
+#[tcc_action(cancel = "deduct_cancel")]
+pub async fn deduct(user_id: i64) -> anyhow::Result<()> {
+    let _ = user_id;
+    Ok(())
+}
+
+pub async fn deduct_cancel(_branch: &TccBranch, _user_id: i64) -> anyhow::Result<()> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rseata::tcc_action;
+
+#[tcc_action(confirm = "deduct_confirm", cancel = "deduct_cancel")]
+pub fn deduct(user_id: i64) -> anyhow::Result<()> {
+    let _ = user_id;
+    Ok(())
+}
+
+fn main() {}
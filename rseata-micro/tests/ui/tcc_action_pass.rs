@@ -0,0 +1,17 @@
+use rseata::tcc::TccBranch;
+use rseata::tcc_action;
+
+#[tcc_action(confirm = "deduct_confirm", cancel = "deduct_cancel")]
+pub async fn deduct(user_id: i64, amount: i64) -> anyhow::Result<i64> {
+    Ok(user_id + amount)
+}
+
+pub async fn deduct_confirm(_branch: &TccBranch, _user_id: i64, _amount: i64) -> anyhow::Result<()> {
+    Ok(())
+}
+
+pub async fn deduct_cancel(_branch: &TccBranch, _user_id: i64, _amount: i64) -> anyhow::Result<()> {
+    Ok(())
+}
+
+fn main() {}
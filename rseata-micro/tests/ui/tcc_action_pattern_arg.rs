@@ -0,0 +1,9 @@
+use rseata::tcc_action;
+
+#[tcc_action(confirm = "deduct_confirm", cancel = "deduct_cancel")]
+pub async fn deduct((user_id, amount): (i64, i64)) -> anyhow::Result<()> {
+    let _ = (user_id, amount);
+    Ok(())
+}
+
+fn main() {}
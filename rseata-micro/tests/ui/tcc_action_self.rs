@@ -0,0 +1,13 @@
+use rseata::tcc_action;
+
+pub struct Account;
+
+impl Account {
+    #[tcc_action(confirm = "deduct_confirm", cancel = "deduct_cancel")]
+    pub async fn deduct(&self, user_id: i64) -> anyhow::Result<()> {
+        let _ = user_id;
+        Ok(())
+    }
+}
+
+fn main() {}
@@ -6,6 +6,8 @@ pub mod rseata_proto {
     }
 }
 const BASE_RESPONSE_SUCCESS: u32 = 0;
+// 分支注册时行锁被其他全局事务持有
+pub const RESULT_CODE_LOCK_CONFLICT: u32 = 409;
 impl BaseResponse {
     pub fn is_success(&self) -> bool {
         self.result_code == BASE_RESPONSE_SUCCESS
@@ -13,7 +15,10 @@ impl BaseResponse {
     pub fn is_failed(&self) -> bool {
         !self.is_success()
     }
-    pub fn some(self: Self) -> Option<Self> {
+    pub fn is_lock_conflict(&self) -> bool {
+        self.result_code == RESULT_CODE_LOCK_CONFLICT
+    }
+    pub fn some(self) -> Option<Self> {
         Some(self)
     }
 }
@@ -36,4 +41,7 @@ impl BaseResponse {
     pub fn failed_with_code_msg(result_code: u32, message: &str) -> Self {
         Self::new(result_code, Some(message))
     }
+    pub fn lock_conflict(message: &str) -> Self {
+        Self::failed_with_code_msg(RESULT_CODE_LOCK_CONFLICT, message)
+    }
 }
@@ -1,6 +0,0 @@
-use rseata_core::branch::BranchType;
-use rseata_core::types::ResourceId;
-
-pub struct RMConfig {
-    pub resource_id: ResourceId,
-}
\ No newline at end of file
@@ -2,8 +2,8 @@ use crate::resource::{DefaultResourceManager, ResourceInfo};
 use lazy_static::lazy_static;
 use rseata_core::branch::BranchType;
 
-mod config;
 pub mod resource;
+pub mod tcc;
 
 lazy_static! {
     pub static ref RSEATA_RM: DefaultResourceManager =
@@ -13,5 +13,6 @@ lazy_static! {
 pub async fn init() {
     tracing::info!("RSEATA_RM init....");
     RSEATA_RM.init().await;
+    tcc::register_submitted_tcc_actions(&RSEATA_RM).await;
     tracing::info!("RSEATA_RM init end");
 }
@@ -7,6 +7,31 @@ use rseata_core::types::{ResourceId, Xid};
 
 #[async_trait]
 impl BranchManagerInbound for DefaultResourceManager {
+    async fn branch_prepare(
+        &self,
+        branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        let branch_transaction = self.branch_transactions.write().await.remove(&branch_id);
+        let Some(branch_transaction) = branch_transaction else {
+            // 分支已不在本地（如 RM 重启），无法保证能够提交，交给 TC 回滚
+            tracing::error!("Branch prepare failed: BranchId {} not exist", branch_id);
+            return Ok(BranchStatus::PhaseOneFailed);
+        };
+        let status = branch_transaction
+            .branch_prepare(branch_type, xid, branch_id, resource_id, application_data)
+            .await;
+        // 准备后仍要等待二阶段指令，分支留在本地
+        self.branch_transactions
+            .write()
+            .await
+            .insert(branch_id, branch_transaction);
+        status
+    }
+
     async fn branch_commit(
         &self,
         branch_type: BranchType,
@@ -23,7 +48,25 @@ impl BranchManagerInbound for DefaultResourceManager {
         };
 
        let branch_status = if let Some(branch_transaction) = branch_transaction.take() {
-           branch_transaction
+           let status = branch_transaction
+                .branch_commit(
+                    branch_type,
+                    xid.clone(),
+                    branch_id,
+                    resource_id,
+                    application_data.clone(),
+                )
+                .await?;
+           // 可重试的失败需要保留分支，等待 TC 重新下发
+           if status == BranchStatus::PhaseTwoCommitFailedRetryable {
+               self.branch_transactions.write().await.insert(branch_id, branch_transaction);
+           }
+           status
+        } else if let Some(handler) = self
+            .find_branch_handler(branch_type, &application_data)
+            .await
+        {
+            handler
                 .branch_commit(
                     branch_type,
                     xid.clone(),
@@ -33,8 +76,9 @@ impl BranchManagerInbound for DefaultResourceManager {
                 )
                 .await?
         }else {
+           // 本地既没有分支也没有处理器（如资源尚未注册），不能认定已提交，交给 TC 稍后重试
            tracing::error!("Branch commit failed: BranchId {} not exist", branch_id);
-            BranchStatus::PhaseTwoCommitted
+            BranchStatus::PhaseTwoCommitFailedRetryable
         };
 
         let _ = self
@@ -63,7 +107,24 @@ impl BranchManagerInbound for DefaultResourceManager {
             t.remove(&branch_id)
         };
         let branch_status = if let Some(branch_transactions) = branch_transactions {
-            branch_transactions
+            let status = branch_transactions
+                .branch_rollback(
+                    branch_type,
+                    xid.clone(),
+                    branch_id,
+                    resource_id,
+                    application_data.clone(),
+                )
+                .await?;
+            if status == BranchStatus::PhaseTwoRollbackFailedRetryable {
+                self.branch_transactions.write().await.insert(branch_id, branch_transactions);
+            }
+            status
+        } else if let Some(handler) = self
+            .find_branch_handler(branch_type, &application_data)
+            .await
+        {
+            handler
                 .branch_rollback(
                     branch_type,
                     xid.clone(),
@@ -73,8 +134,9 @@ impl BranchManagerInbound for DefaultResourceManager {
                 )
                 .await?
         }else {
+            // 同提交，未回滚的分支不能报告为已回滚
             tracing::error!("Branch_rollback failed: BranchId {} not exist", branch_id);
-            BranchStatus::PhaseTwoRollbacked
+            BranchStatus::PhaseTwoRollbackFailedRetryable
         };
 
         let _ = self
@@ -89,3 +151,117 @@ impl BranchManagerInbound for DefaultResourceManager {
         Ok(branch_status)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::ResourceInfo;
+    use rseata_core::branch::branch_transaction::BranchTransaction;
+    use rseata_core::types::ClientId;
+
+    // 只有一阶段 END 的 XA 分支，准备后才能提交
+    struct IdleBranch;
+
+    #[async_trait]
+    impl BranchTransaction for IdleBranch {
+        async fn branch_prepare(
+            &self,
+            _: BranchType,
+            _: Xid,
+            _: BranchId,
+            _: ResourceId,
+            _: String,
+        ) -> anyhow::Result<BranchStatus> {
+            Ok(BranchStatus::PhaseOneDone)
+        }
+
+        async fn branch_commit(
+            &self,
+            _: BranchType,
+            _: Xid,
+            _: BranchId,
+            _: ResourceId,
+            _: String,
+        ) -> anyhow::Result<BranchStatus> {
+            Ok(BranchStatus::PhaseTwoCommitted)
+        }
+
+        async fn branch_rollback(
+            &self,
+            _: BranchType,
+            _: Xid,
+            _: BranchId,
+            _: ResourceId,
+            _: String,
+        ) -> anyhow::Result<BranchStatus> {
+            Ok(BranchStatus::PhaseTwoRollbacked)
+        }
+    }
+
+    #[tokio::test]
+    async fn prepared_branch_stays_for_phase_two() {
+        let rm = DefaultResourceManager::new(ResourceInfo {
+            resource_group_id: "group".to_string(),
+            resource_id: ResourceId::from("rm"),
+            branch_type: BranchType::XA,
+            client_id: ClientId::from(1),
+        });
+        rm.branch_transactions
+            .write()
+            .await
+            .insert(BranchId::from(1), Box::new(IdleBranch));
+
+        let prepare = |branch_id: u64| {
+            rm.branch_prepare(
+                BranchType::XA,
+                Xid::from("xid"),
+                BranchId::from(branch_id),
+                ResourceId::from("rm"),
+                String::new(),
+            )
+        };
+        assert_eq!(prepare(1).await.unwrap(), BranchStatus::PhaseOneDone);
+        assert!(
+            rm.branch_transactions
+                .read()
+                .await
+                .contains_key(&BranchId::from(1))
+        );
+        // 本地没有的分支无法保证能提交
+        assert_eq!(prepare(2).await.unwrap(), BranchStatus::PhaseOneFailed);
+    }
+
+    #[tokio::test]
+    async fn unknown_branch_is_left_for_retry() {
+        let rm = DefaultResourceManager::new(ResourceInfo {
+            resource_group_id: "group".to_string(),
+            resource_id: ResourceId::from("rm"),
+            branch_type: BranchType::AT,
+            client_id: ClientId::from(1),
+        });
+
+        let status = rm
+            .branch_commit(
+                BranchType::AT,
+                Xid::from("xid"),
+                BranchId::from(1),
+                ResourceId::from("rm"),
+                String::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(status, BranchStatus::PhaseTwoCommitFailedRetryable);
+
+        let status = rm
+            .branch_rollback(
+                BranchType::TCC,
+                Xid::from("xid"),
+                BranchId::from(2),
+                ResourceId::from("rm"),
+                "order_action".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(status, BranchStatus::PhaseTwoRollbackFailedRetryable);
+    }
+}
@@ -30,8 +30,16 @@ impl BranchManagerOutbound for DefaultResourceManager {
             .await?
             .rm
             .branch_register(request)
-            .await?;
-        Ok(response.into_inner().branch_id.into())
+            .await?
+            .into_inner();
+        if let Some(base) = response.base.as_ref().filter(|base| base.is_failed()) {
+            anyhow::bail!(
+                "branch register failed, code {}: {}",
+                base.result_code,
+                base.message.clone().unwrap_or_default()
+            );
+        }
+        Ok(response.branch_id.into())
     }
 
     async fn branch_report(
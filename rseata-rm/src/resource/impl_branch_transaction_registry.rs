@@ -30,7 +30,7 @@ impl BranchTransactionRegistry for DefaultResourceManager {
         self.branch_transactions
             .write()
             .await
-            .insert(branch_id.clone(), branch_transaction);
+            .insert(branch_id, branch_transaction);
         Ok(branch_id)
     }
 }
@@ -1,13 +1,16 @@
+use crate::resource::{DefaultResourceManager, ResourceInfo};
 use async_trait::async_trait;
-use tokio::sync::mpsc;
-use tonic::codegen::tokio_stream::StreamExt;
-use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
+use rseata_core::branch::BranchStatus;
 use rseata_core::branch::branch_manager_inbound::BranchManagerInbound;
 use rseata_core::resource::Resource;
 use rseata_core::resource::resource_registry::ResourceRegistry;
 use rseata_proto::rseata_proto::proto::resource_instruction::Instruction;
-use rseata_proto::rseata_proto::proto::ResourceProto;
-use crate::resource::{DefaultResourceManager, ResourceInfo};
+use rseata_proto::rseata_proto::proto::{
+    InstructionResult, ResourceProto, ResourceRequest, resource_request,
+};
+use tokio::sync::mpsc;
+use tonic::codegen::tokio_stream::StreamExt;
+use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
 
 #[async_trait]
 impl ResourceRegistry for DefaultResourceManager {
@@ -18,7 +21,7 @@ impl ResourceRegistry for DefaultResourceManager {
         // 注册流到远程
         {
             let (request_tx, request_rx) = mpsc::channel(100);
-            let (response_tx, response_rx) = mpsc::channel(100);
+            let (_response_tx, response_rx) = mpsc::channel(100);
 
             let request_stream = ReceiverStream::new(request_rx);
 
@@ -35,40 +38,36 @@ impl ResourceRegistry for DefaultResourceManager {
                 .into_inner();
 
             let self_cloned = self.clone();
+            let result_tx = request_tx.clone();
             // 处理响应流的后台任务
             tokio::spawn(async move {
                 let mut response_stream = response_stream;
                 while let Some(response) = response_stream.next().await {
                     match response {
                         Ok(branch_response) => {
+                            let instruction_id = branch_response.instruction_id;
                             if let Some(instruction) = branch_response.instruction {
-                                tracing::info!("------------Received instruction: {:?}", instruction);
-                                match instruction {
-                                    Instruction::Commit(commit) => {
-                                        self_cloned
-                                            .branch_commit(
-                                                commit.branch_type.into(),
-                                                commit.xid.into(),
-                                                commit.branch_id.into(),
-                                                commit.resource_id.into(),
-                                                commit.application_data,
-                                            )
-                                            .await
-                                            .ok();
-                                    }
-                                    Instruction::Rollback(rollback) => {
-                                        self_cloned
-                                            .branch_rollback(
-                                                rollback.branch_type.into(),
-                                                rollback.xid.into(),
-                                                rollback.branch_id.into(),
-                                                rollback.resource_id.into(),
-                                                rollback.application_data,
-                                            )
-                                            .await
-                                            .ok();
-                                    }
-                                }
+                                tracing::info!(
+                                    "------------Received instruction {instruction_id}: {:?}",
+                                    instruction
+                                );
+                                let rm = self_cloned.clone();
+                                let result_tx = result_tx.clone();
+                                // 每条指令独立执行，执行结果按 instruction_id 回传 TC
+                                tokio::spawn(async move {
+                                    let result = rm.execute_instruction(instruction).await;
+                                    let _ = result_tx
+                                        .send(ResourceRequest {
+                                            request: Some(resource_request::Request::Result(
+                                                InstructionResult {
+                                                    instruction_id,
+                                                    status: result.0.into(),
+                                                    message: result.1,
+                                                },
+                                            )),
+                                        })
+                                        .await;
+                                });
                             }
                         }
                         Err(e) => {
@@ -82,11 +81,13 @@ impl ResourceRegistry for DefaultResourceManager {
             // Resource Registry
             {
                 let _ = request_tx
-                    .send(ResourceProto {
-                        resource_group_id: resource_clone.resource_group_id,
-                        resource_id: resource_clone.resource_id.0,
-                        client_id: resource_clone.client_id.into(),
-                        branch_type: resource_clone.branch_type.into(),
+                    .send(ResourceRequest {
+                        request: Some(resource_request::Request::Resource(ResourceProto {
+                            resource_group_id: resource_clone.resource_group_id,
+                            resource_id: resource_clone.resource_id.0,
+                            client_id: resource_clone.client_id.into(),
+                            branch_type: resource_clone.branch_type.into(),
+                        })),
                     })
                     .await;
             }
@@ -101,5 +102,64 @@ impl ResourceRegistry for DefaultResourceManager {
         resources.insert(resource.get_resource_id().await, Box::new(resource.clone()));
     }
 
-    async fn unregister_resource(&mut self, resource: &Self::Resource) {}
-}
\ No newline at end of file
+    async fn unregister_resource(&self, resource: &Self::Resource) {
+        self.resources
+            .write()
+            .await
+            .remove(&resource.get_resource_id().await);
+    }
+}
+
+impl DefaultResourceManager {
+    /// 执行 TC 下发的指令，提交、回滚失败时返回可重试状态，准备失败时返回一阶段失败
+    async fn execute_instruction(
+        &self,
+        instruction: Instruction,
+    ) -> (BranchStatus, Option<String>) {
+        match instruction {
+            Instruction::Prepare(prepare) => self
+                .branch_prepare(
+                    prepare.branch_type.into(),
+                    prepare.xid.into(),
+                    prepare.branch_id.into(),
+                    prepare.resource_id.into(),
+                    prepare.application_data,
+                )
+                .await
+                .map(|status| (status, None))
+                .unwrap_or_else(|e| (BranchStatus::PhaseOneFailed, Some(e.to_string()))),
+            Instruction::Commit(commit) => self
+                .branch_commit(
+                    commit.branch_type.into(),
+                    commit.xid.into(),
+                    commit.branch_id.into(),
+                    commit.resource_id.into(),
+                    commit.application_data,
+                )
+                .await
+                .map(|status| (status, None))
+                .unwrap_or_else(|e| {
+                    (
+                        BranchStatus::PhaseTwoCommitFailedRetryable,
+                        Some(e.to_string()),
+                    )
+                }),
+            Instruction::Rollback(rollback) => self
+                .branch_rollback(
+                    rollback.branch_type.into(),
+                    rollback.xid.into(),
+                    rollback.branch_id.into(),
+                    rollback.resource_id.into(),
+                    rollback.application_data,
+                )
+                .await
+                .map(|status| (status, None))
+                .unwrap_or_else(|e| {
+                    (
+                        BranchStatus::PhaseTwoRollbackFailedRetryable,
+                        Some(e.to_string()),
+                    )
+                }),
+        }
+    }
+}
@@ -20,10 +20,9 @@ use rseata_core::resource::resource_registry::ResourceRegistry;
 use rseata_core::resource::Resource;
 use rseata_proto::rseata_proto::proto::{
     ResourceInstruction,
-    ResourceProto,
+    ResourceRequest,
 };
 use tokio::sync::mpsc::{Receiver, Sender};
-use tonic::codegen::tokio_stream::StreamExt;
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -34,17 +33,30 @@ pub struct ResourceInfo {
     client_id: ClientId,
 }
 impl ResourceInfo {
+    /// 每个 RM 进程使用随机的 client_id
+    pub fn new(
+        resource_group_id: String,
+        resource_id: ResourceId,
+        branch_type: BranchType,
+    ) -> Self {
+        Self {
+            resource_group_id,
+            resource_id,
+            branch_type,
+            client_id: ClientId::from(Uuid::new_v4().as_u128() as u64),
+        }
+    }
+
     pub fn new_with_env(branch_type: BranchType) -> Self {
         let resource_group_id = env::var("RSEATA_RM_RESOURCE_GROUP_ID")
             .unwrap_or("RSEATA_RM_RESOURCE_GROUP_ID".to_owned());
         let resource_id =
             env::var("RSEATA_RM_RESOURCE_ID").expect("env RSEATA_RM_RESOURCE_ID not set");
-        Self {
+        Self::new(
             resource_group_id,
-            resource_id: ResourceId::from(resource_id),
+            ResourceId::from(resource_id),
             branch_type,
-            client_id: ClientId::from(Uuid::new_v4().as_u128() as u64),
-        }
+        )
     }
 }
 #[async_trait]
@@ -58,7 +70,7 @@ impl Resource for ResourceInfo {
     }
 
     async fn get_branch_type(&self) -> BranchType {
-        self.branch_type.clone()
+        self.branch_type
     }
 
     async fn get_client_id(&self) -> ClientId {
@@ -72,13 +84,26 @@ fn get_tc_grpc_server_addr() -> String {
     format!("tcp://{}:{}", ip, prot)
 }
 
+type ResourceChannel =
+    Arc<RwLock<Option<(Sender<ResourceRequest>, Receiver<ResourceInstruction>)>>>;
+
+type BranchTypeHandlers = Arc<RwLock<HashMap<BranchType, Arc<dyn BranchTransaction>>>>;
+type ResourceHandlers = Arc<RwLock<HashMap<(BranchType, String), Arc<dyn BranchTransaction>>>>;
+
+pub type BranchTransactions =
+    Arc<RwLock<HashMap<BranchId, Box<dyn BranchTransaction + Send + Sync + 'static>>>>;
+
 #[derive(Clone)]
 pub struct DefaultResourceManager {
     rm_client: LazyRMGrpcClient,
     resources: Arc<RwLock<HashMap<ResourceId, Box<ResourceInfo>>>>,
-    channel: Arc<RwLock<Option<(Sender<ResourceProto>, Receiver<ResourceInstruction>)>>>,
+    channel: ResourceChannel,
     pub resource_info: ResourceInfo,
-    pub branch_transactions: Arc<RwLock<HashMap<BranchId, Box<dyn BranchTransaction + Send + Sync + 'static>>>>,
+    pub branch_transactions: BranchTransactions,
+    /// 本地找不到分支时（如 RM 重启）按分支类型兜底处理二阶段
+    branch_type_handlers: BranchTypeHandlers,
+    /// 同一分支类型有多个本地资源时，按分支 application_data 中的资源标识处理二阶段
+    resource_handlers: ResourceHandlers,
 }
 impl DefaultResourceManager {
     pub fn new(resource_info: ResourceInfo) -> Self {
@@ -90,8 +115,52 @@ impl DefaultResourceManager {
             channel: Arc::new(RwLock::new(Default::default())),
             resource_info,
             branch_transactions: Arc::new(Default::default()),
+            branch_type_handlers: Arc::new(Default::default()),
+            resource_handlers: Arc::new(Default::default()),
         }
     }
+    pub async fn register_branch_type_handler(
+        &self,
+        branch_type: BranchType,
+        handler: Arc<dyn BranchTransaction>,
+    ) {
+        self.branch_type_handlers
+            .write()
+            .await
+            .insert(branch_type, handler);
+    }
+
+    /// 注册分支时以 resource_key 作为 application_data，二阶段指令即交给该处理器
+    pub async fn register_resource_handler(
+        &self,
+        branch_type: BranchType,
+        resource_key: String,
+        handler: Arc<dyn BranchTransaction>,
+    ) {
+        self.resource_handlers
+            .write()
+            .await
+            .insert((branch_type, resource_key), handler);
+    }
+
+    // 先按资源标识查找，找不到时使用分支类型的处理器
+    pub(crate) async fn find_branch_handler(
+        &self,
+        branch_type: BranchType,
+        application_data: &str,
+    ) -> Option<Arc<dyn BranchTransaction>> {
+        let handler = self
+            .resource_handlers
+            .read()
+            .await
+            .get(&(branch_type, application_data.to_string()))
+            .cloned();
+        match handler {
+            Some(handler) => Some(handler),
+            None => self.branch_type_handlers.read().await.get(&branch_type).cloned(),
+        }
+    }
+
     pub async fn init(&self) {
         self.register_resource(&self.resource_info).await;
     }
@@ -104,7 +173,7 @@ impl ResourceManager for DefaultResourceManager {
         todo!()
     }
 
-    async fn find_resource(&self, resource_id: &ResourceId) -> Option<Self::Resource> {
+    async fn find_resource(&self, _resource_id: &ResourceId) -> Option<Self::Resource> {
         todo!()
     }
 }
@@ -117,7 +186,105 @@ impl HandleBranchType for DefaultResourceManager {
 
 #[async_trait]
 impl GlobalStatusQuery for DefaultResourceManager {
-    async fn get_global_status(&self, xid: Xid) -> anyhow::Result<GlobalStatus> {
+    async fn get_global_status(&self, _xid: Xid) -> anyhow::Result<GlobalStatus> {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseata_core::branch::BranchStatus;
+
+    struct Handler(BranchStatus);
+
+    #[async_trait]
+    impl BranchTransaction for Handler {
+        async fn branch_commit(
+            &self,
+            _: BranchType,
+            _: Xid,
+            _: BranchId,
+            _: ResourceId,
+            _: String,
+        ) -> anyhow::Result<BranchStatus> {
+            Ok(self.0)
+        }
+
+        async fn branch_rollback(
+            &self,
+            _: BranchType,
+            _: Xid,
+            _: BranchId,
+            _: ResourceId,
+            _: String,
+        ) -> anyhow::Result<BranchStatus> {
+            Ok(self.0)
+        }
+    }
+
+    async fn route(
+        rm: &DefaultResourceManager,
+        branch_type: BranchType,
+        data: &str,
+    ) -> BranchStatus {
+        rm.find_branch_handler(branch_type, data)
+            .await
+            .unwrap()
+            .branch_commit(
+                branch_type,
+                Xid::from("xid"),
+                BranchId::from(1),
+                ResourceId::from("r"),
+                data.to_string(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn handlers_are_routed_per_resource() {
+        let rm = DefaultResourceManager::new(ResourceInfo {
+            resource_group_id: "group".to_string(),
+            resource_id: ResourceId::from("rm"),
+            branch_type: BranchType::AT,
+            client_id: ClientId::from(1),
+        });
+        rm.register_resource_handler(
+            BranchType::AT,
+            "mysql://order".to_string(),
+            Arc::new(Handler(BranchStatus::PhaseTwoCommitted)),
+        )
+        .await;
+        rm.register_resource_handler(
+            BranchType::AT,
+            "mysql://stock".to_string(),
+            Arc::new(Handler(BranchStatus::PhaseTwoRollbacked)),
+        )
+        .await;
+        rm.register_branch_type_handler(
+            BranchType::TCC,
+            Arc::new(Handler(BranchStatus::PhaseTwoCommitFailedRetryable)),
+        )
+        .await;
+
+        // 后注册的数据源不会接管先注册的数据源的分支
+        assert_eq!(
+            route(&rm, BranchType::AT, "mysql://order").await,
+            BranchStatus::PhaseTwoCommitted
+        );
+        assert_eq!(
+            route(&rm, BranchType::AT, "mysql://stock").await,
+            BranchStatus::PhaseTwoRollbacked
+        );
+        assert_eq!(
+            route(&rm, BranchType::TCC, "order_action").await,
+            BranchStatus::PhaseTwoCommitFailedRetryable
+        );
+        assert!(
+            rm.find_branch_handler(BranchType::AT, "mysql://unknown")
+                .await
+                .is_none()
+        );
+    }
+}
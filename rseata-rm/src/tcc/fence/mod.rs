@@ -0,0 +1,402 @@
+mod tcc_fence_log;
+
+use crate::tcc::TccBranch;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    DatabaseTransaction, EntityTrait, QueryFilter, QuerySelect, Schema, TransactionTrait,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum TccFenceStatus {
+    Tried = 1,
+    Committed = 2,
+    Rollbacked = 3,
+    /// cancel 先于 try 到达（空回滚），之后的 try 将被拒绝（防悬挂）
+    Suspended = 4,
+}
+
+impl TryFrom<i32> for TccFenceStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Tried),
+            2 => Ok(Self::Committed),
+            3 => Ok(Self::Rollbacked),
+            4 => Ok(Self::Suspended),
+            _ => Err(anyhow::anyhow!("unknown tcc fence status: {value}")),
+        }
+    }
+}
+
+/// 在同一个本地事务内执行的业务逻辑
+pub type FenceCallback<'c, T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send + 'c>>;
+
+/// TCC 防护表：幂等、空回滚、防悬挂
+///
+/// 防护记录与业务写入在同一个本地事务中提交
+#[derive(Clone)]
+pub struct TccFence {
+    conn: DatabaseConnection,
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+impl TccFence {
+    /// 创建防护表（不存在时）
+    pub async fn new(conn: DatabaseConnection) -> anyhow::Result<Self> {
+        let schema = Schema::new(conn.get_database_backend());
+        let mut stmt = schema.create_table_from_entity(tcc_fence_log::Entity);
+        stmt.if_not_exists();
+        conn.execute(&stmt).await?;
+        Ok(Self { conn })
+    }
+
+    pub async fn run_try<F, T>(&self, branch: &TccBranch, f: F) -> anyhow::Result<T>
+    where
+        F: for<'c> FnOnce(&'c DatabaseTransaction) -> FenceCallback<'c, T> + Send,
+        T: Send,
+    {
+        let txn = self.conn.begin().await?;
+        let now = now_millis();
+        // 已存在记录（包括空回滚留下的 Suspended）时主键冲突，拒绝 try
+        tcc_fence_log::ActiveModel {
+            xid: ActiveValue::set(branch.xid.to_string()),
+            branch_id: ActiveValue::set(branch.branch_id.0 as i64),
+            action_name: ActiveValue::set(branch.action_name.clone()),
+            status: ActiveValue::set(TccFenceStatus::Tried as i32),
+            gmt_create: ActiveValue::set(now),
+            gmt_modified: ActiveValue::set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "tcc try rejected, fence exists {},{}: {e}",
+                branch.xid,
+                branch.branch_id
+            )
+        })?;
+
+        let result = f(&txn).await?;
+        txn.commit().await?;
+        Ok(result)
+    }
+
+    pub async fn run_commit<F>(&self, branch: &TccBranch, f: F) -> anyhow::Result<()>
+    where
+        F: for<'c> FnOnce(&'c DatabaseTransaction) -> FenceCallback<'c, ()> + Send,
+    {
+        let txn = self.conn.begin().await?;
+        let fence = Self::find_for_update(&txn, branch).await?.ok_or_else(|| {
+            anyhow::anyhow!("tcc fence not found {},{}", branch.xid, branch.branch_id)
+        })?;
+        match TccFenceStatus::try_from(fence.status)? {
+            // 重复的 confirm
+            TccFenceStatus::Committed => return Ok(()),
+            TccFenceStatus::Rollbacked | TccFenceStatus::Suspended => {
+                return Err(anyhow::anyhow!(
+                    "tcc branch already rollbacked {},{}",
+                    branch.xid,
+                    branch.branch_id
+                ));
+            }
+            TccFenceStatus::Tried => {}
+        }
+        Self::update_status(&txn, fence, TccFenceStatus::Committed).await?;
+        f(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn run_rollback<F>(&self, branch: &TccBranch, f: F) -> anyhow::Result<()>
+    where
+        F: for<'c> FnOnce(&'c DatabaseTransaction) -> FenceCallback<'c, ()> + Send,
+    {
+        let txn = self.conn.begin().await?;
+        let Some(fence) = Self::find_for_update(&txn, branch).await? else {
+            // 空回滚：try 尚未执行，记录 Suspended 防止之后的 try 悬挂
+            let now = now_millis();
+            tcc_fence_log::ActiveModel {
+                xid: ActiveValue::set(branch.xid.to_string()),
+                branch_id: ActiveValue::set(branch.branch_id.0 as i64),
+                action_name: ActiveValue::set(branch.action_name.clone()),
+                status: ActiveValue::set(TccFenceStatus::Suspended as i32),
+                gmt_create: ActiveValue::set(now),
+                gmt_modified: ActiveValue::set(now),
+            }
+            .insert(&txn)
+            .await?;
+            txn.commit().await?;
+            return Ok(());
+        };
+        match TccFenceStatus::try_from(fence.status)? {
+            // 重复的 cancel
+            TccFenceStatus::Rollbacked | TccFenceStatus::Suspended => return Ok(()),
+            TccFenceStatus::Committed => {
+                return Err(anyhow::anyhow!(
+                    "tcc branch already committed {},{}",
+                    branch.xid,
+                    branch.branch_id
+                ));
+            }
+            TccFenceStatus::Tried => {}
+        }
+        Self::update_status(&txn, fence, TccFenceStatus::Rollbacked).await?;
+        f(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// 删除早于 `keep` 的已完成记录
+    pub async fn clean(&self, keep: Duration) -> anyhow::Result<u64> {
+        let before = now_millis() - keep.as_millis() as i64;
+        let result = tcc_fence_log::Entity::delete_many()
+            .filter(tcc_fence_log::Column::GmtModified.lt(before))
+            .filter(tcc_fence_log::Column::Status.is_in([
+                TccFenceStatus::Committed as i32,
+                TccFenceStatus::Rollbacked as i32,
+                TccFenceStatus::Suspended as i32,
+            ]))
+            .exec(&self.conn)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 启动定时清理任务
+    pub fn spawn_clean_task(&self, interval: Duration, keep: Duration) -> JoinHandle<()> {
+        let fence = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match fence.clean(keep).await {
+                    Ok(n) if n > 0 => tracing::info!("tcc fence cleaned {n} records"),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("tcc fence clean failed: {e}"),
+                }
+            }
+        })
+    }
+
+    async fn find_for_update(
+        txn: &DatabaseTransaction,
+        branch: &TccBranch,
+    ) -> anyhow::Result<Option<tcc_fence_log::Model>> {
+        Ok(
+            tcc_fence_log::Entity::find_by_id((branch.xid.to_string(), branch.branch_id.0 as i64))
+                .lock_exclusive()
+                .one(txn)
+                .await?,
+        )
+    }
+
+    async fn update_status(
+        txn: &DatabaseTransaction,
+        fence: tcc_fence_log::Model,
+        status: TccFenceStatus,
+    ) -> anyhow::Result<()> {
+        let mut active: tcc_fence_log::ActiveModel = fence.into();
+        active.status = ActiveValue::set(status as i32);
+        active.gmt_modified = ActiveValue::set(now_millis());
+        active.update(txn).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseata_core::branch::BranchId;
+    use sea_orm::Database;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct SqliteFence {
+        fence: TccFence,
+        path: PathBuf,
+    }
+
+    impl SqliteFence {
+        async fn new() -> Self {
+            let path =
+                std::env::temp_dir().join(format!("rseata-tcc-fence-{}.db", uuid::Uuid::new_v4()));
+            let conn = Database::connect(format!("sqlite://{}?mode=rwc", path.display()))
+                .await
+                .unwrap();
+            Self {
+                fence: TccFence::new(conn).await.unwrap(),
+                path,
+            }
+        }
+
+        async fn status(&self, branch: &TccBranch) -> Option<TccFenceStatus> {
+            tcc_fence_log::Entity::find_by_id((branch.xid.to_string(), branch.branch_id.0 as i64))
+                .one(&self.fence.conn)
+                .await
+                .unwrap()
+                .map(|fence| TccFenceStatus::try_from(fence.status).unwrap())
+        }
+    }
+
+    impl Drop for SqliteFence {
+        fn drop(&mut self) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+
+    fn branch(branch_id: u64) -> TccBranch {
+        TccBranch {
+            xid: "xid".into(),
+            branch_id: BranchId(branch_id),
+            action_name: "order".to_string(),
+        }
+    }
+
+    // 记录业务逻辑的执行次数
+    fn counted(
+        calls: &Arc<AtomicUsize>,
+    ) -> impl for<'c> FnOnce(&'c DatabaseTransaction) -> FenceCallback<'c, ()> + Send + use<> {
+        let calls = calls.clone();
+        move |_| {
+            Box::pin(async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn confirm_and_cancel_are_idempotent() {
+        let db = SqliteFence::new().await;
+        let fence = &db.fence;
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let committed = branch(1);
+        fence.run_try(&committed, counted(&calls)).await.unwrap();
+        assert_eq!(db.status(&committed).await, Some(TccFenceStatus::Tried));
+        for _ in 0..2 {
+            fence.run_commit(&committed, counted(&calls)).await.unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(db.status(&committed).await, Some(TccFenceStatus::Committed));
+        assert!(
+            fence
+                .run_rollback(&committed, counted(&calls))
+                .await
+                .is_err()
+        );
+
+        let rollbacked = branch(2);
+        fence.run_try(&rollbacked, counted(&calls)).await.unwrap();
+        for _ in 0..2 {
+            fence
+                .run_rollback(&rollbacked, counted(&calls))
+                .await
+                .unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(
+            db.status(&rollbacked).await,
+            Some(TccFenceStatus::Rollbacked)
+        );
+        assert!(
+            fence
+                .run_commit(&rollbacked, counted(&calls))
+                .await
+                .is_err()
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn failed_try_leaves_no_fence() {
+        let db = SqliteFence::new().await;
+        let branch = branch(1);
+        let failed = db
+            .fence
+            .run_try::<_, ()>(&branch, |_| Box::pin(async { anyhow::bail!("no stock") }))
+            .await;
+        assert!(failed.is_err());
+        // 防护记录随业务一起回滚，之后的 cancel 按空回滚处理
+        assert_eq!(db.status(&branch).await, None);
+    }
+
+    #[tokio::test]
+    async fn empty_rollback_suspends_later_try() {
+        let db = SqliteFence::new().await;
+        let fence = &db.fence;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let branch = branch(1);
+
+        fence.run_rollback(&branch, counted(&calls)).await.unwrap();
+        assert_eq!(db.status(&branch).await, Some(TccFenceStatus::Suspended));
+        // 空回滚不执行 cancel 业务逻辑，重复的 cancel 直接成功
+        fence.run_rollback(&branch, counted(&calls)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        assert!(fence.run_try(&branch, counted(&calls)).await.is_err());
+        assert!(fence.run_commit(&branch, counted(&calls)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(db.status(&branch).await, Some(TccFenceStatus::Suspended));
+    }
+
+    #[tokio::test]
+    async fn clean_removes_only_old_finished_fences() {
+        let db = SqliteFence::new().await;
+        let fence = &db.fence;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (tried, committed, rollbacked, suspended) =
+            (branch(1), branch(2), branch(3), branch(4));
+        for branch in [&tried, &committed, &rollbacked] {
+            fence.run_try(branch, counted(&calls)).await.unwrap();
+        }
+        fence.run_commit(&committed, counted(&calls)).await.unwrap();
+        fence
+            .run_rollback(&rollbacked, counted(&calls))
+            .await
+            .unwrap();
+        fence
+            .run_rollback(&suspended, counted(&calls))
+            .await
+            .unwrap();
+
+        assert_eq!(fence.clean(Duration::from_secs(3600)).await.unwrap(), 0);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(fence.clean(Duration::ZERO).await.unwrap(), 3);
+        // 仍在等待二阶段的记录保留
+        assert_eq!(db.status(&tried).await, Some(TccFenceStatus::Tried));
+        for branch in [&committed, &rollbacked, &suspended] {
+            assert_eq!(db.status(branch).await, None);
+        }
+    }
+
+    #[test]
+    fn fence_status_round_trip() {
+        for status in [
+            TccFenceStatus::Tried,
+            TccFenceStatus::Committed,
+            TccFenceStatus::Rollbacked,
+            TccFenceStatus::Suspended,
+        ] {
+            assert_eq!(TccFenceStatus::try_from(status as i32).unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn unknown_fence_status_is_rejected() {
+        assert!(TccFenceStatus::try_from(0).is_err());
+        assert!(TccFenceStatus::try_from(5).is_err());
+    }
+}
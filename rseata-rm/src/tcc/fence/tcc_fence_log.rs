@@ -0,0 +1,24 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "tcc_fence_log")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "String(StringLen::N(128))"
+    )]
+    pub xid: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub branch_id: i64,
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub action_name: String,
+    pub status: i32,
+    pub gmt_create: i64,
+    pub gmt_modified: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,99 @@
+use crate::tcc::{
+    TccAction, TccApplicationData, TccBranch, TccBranchTransaction, TccDispatcher, find_tcc_action,
+};
+use async_trait::async_trait;
+use rseata_core::branch::branch_transaction::BranchTransaction;
+use rseata_core::branch::{BranchId, BranchStatus, BranchType};
+use rseata_core::types::{ResourceId, Xid};
+
+impl<A: TccAction> TccBranchTransaction<A> {
+    fn decode(
+        xid: Xid,
+        branch_id: BranchId,
+        application_data: &str,
+    ) -> anyhow::Result<(TccBranch, A::Context)> {
+        let data: TccApplicationData = serde_json::from_str(application_data)?;
+        let branch = TccBranch {
+            xid,
+            branch_id,
+            action_name: data.action_name,
+        };
+        Ok((branch, serde_json::from_value(data.context)?))
+    }
+}
+
+#[async_trait]
+impl<A: TccAction> BranchTransaction for TccBranchTransaction<A> {
+    async fn branch_commit(
+        &self,
+        _branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        _resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        tracing::info!("TCC branch_commit ing :{xid},{branch_id}");
+        let (branch, context) = Self::decode(xid.clone(), branch_id, &application_data)?;
+        match self.action.confirm(&branch, context).await {
+            Ok(_) => Ok(BranchStatus::PhaseTwoCommitted),
+            Err(e) => {
+                tracing::warn!("TCC confirm failed :{xid},{branch_id}, {e}");
+                Ok(BranchStatus::PhaseTwoCommitFailedRetryable)
+            }
+        }
+    }
+
+    async fn branch_rollback(
+        &self,
+        _branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        _resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        tracing::info!("TCC branch_rollback ing :{xid},{branch_id}");
+        let (branch, context) = Self::decode(xid.clone(), branch_id, &application_data)?;
+        match self.action.cancel(&branch, context).await {
+            Ok(_) => Ok(BranchStatus::PhaseTwoRollbacked),
+            Err(e) => {
+                tracing::warn!("TCC cancel failed :{xid},{branch_id}, {e}");
+                Ok(BranchStatus::PhaseTwoRollbackFailedRetryable)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl BranchTransaction for TccDispatcher {
+    async fn branch_commit(
+        &self,
+        branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        let action = find_tcc_action(&application_data)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("tcc action not registered: {application_data}"))?;
+        action
+            .branch_commit(branch_type, xid, branch_id, resource_id, application_data)
+            .await
+    }
+
+    async fn branch_rollback(
+        &self,
+        branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        let action = find_tcc_action(&application_data)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("tcc action not registered: {application_data}"))?;
+        action
+            .branch_rollback(branch_type, xid, branch_id, resource_id, application_data)
+            .await
+    }
+}
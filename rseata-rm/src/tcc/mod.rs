@@ -0,0 +1,257 @@
+#[cfg(feature = "sea_orm")]
+pub mod fence;
+mod impl_branch_transaction;
+
+use crate::RSEATA_RM;
+use crate::resource::DefaultResourceManager;
+use lazy_static::lazy_static;
+use rseata_core::RSEATA_CLIENT_SESSION;
+use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
+use rseata_core::branch::branch_transaction::{BranchTransaction, BranchTransactionRegistry};
+use rseata_core::branch::{BranchStatus, BranchType};
+use rseata_core::resource::Resource;
+pub use rseata_core::tcc::{TccAction, TccApplicationData, TccBranch};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub struct TccBranchTransaction<A: TccAction> {
+    action: Arc<A>,
+}
+
+impl<A: TccAction> Clone for TccBranchTransaction<A> {
+    fn clone(&self) -> Self {
+        Self {
+            action: self.action.clone(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref TCC_ACTIONS: RwLock<HashMap<String, Arc<dyn BranchTransaction>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// 按动作名查找已注册的 TCC 动作，用于分支不在本地缓存时（如 RM 重启）处理二阶段
+pub async fn find_tcc_action(application_data: &str) -> Option<Arc<dyn BranchTransaction>> {
+    let data: TccApplicationData = serde_json::from_str(application_data).ok()?;
+    TCC_ACTIONS.read().await.get(&data.action_name).cloned()
+}
+
+/// 按 application_data 中的动作名分发二阶段
+struct TccDispatcher;
+
+async fn register_tcc_dispatcher(rm: &DefaultResourceManager) {
+    rm.register_branch_type_handler(BranchType::TCC, Arc::new(TccDispatcher))
+        .await;
+}
+
+/// 注册 TCC 动作，二阶段指令可以在 try 之前的进程中到达时需要提前注册
+pub async fn register_tcc_action<A: TccAction>(action: A) {
+    let (name, transaction) = tcc_branch_transaction(action);
+    TCC_ACTIONS
+        .write()
+        .await
+        .insert(name.to_string(), transaction);
+    register_tcc_dispatcher(&RSEATA_RM).await;
+}
+
+/// `#[tcc_action]` 生成的启动注册项，`init` 时统一注册，重启后的二阶段指令无需等待 try 执行
+pub struct TccActionRegistration {
+    register: fn() -> (&'static str, Arc<dyn BranchTransaction>),
+}
+
+impl TccActionRegistration {
+    pub const fn new(register: fn() -> (&'static str, Arc<dyn BranchTransaction>)) -> Self {
+        Self { register }
+    }
+}
+
+inventory::collect!(TccActionRegistration);
+
+/// 将 TCC 动作包装为分支事务，用于 `TccActionRegistration`
+pub fn tcc_branch_transaction<A: TccAction>(
+    action: A,
+) -> (&'static str, Arc<dyn BranchTransaction>) {
+    (
+        action.name(),
+        Arc::new(TccBranchTransaction {
+            action: Arc::new(action),
+        }),
+    )
+}
+
+/// 注册所有通过 `TccActionRegistration` 提交的 TCC 动作，二阶段指令由 rm 分发给这些动作
+pub async fn register_submitted_tcc_actions(rm: &DefaultResourceManager) {
+    let mut registered = false;
+    for registration in inventory::iter::<TccActionRegistration> {
+        let (name, transaction) = (registration.register)();
+        TCC_ACTIONS
+            .write()
+            .await
+            .insert(name.to_string(), transaction);
+        registered = true;
+    }
+    if registered {
+        register_tcc_dispatcher(rm).await;
+    }
+}
+
+/// 在已开启的全局事务中执行 TCC 一阶段：注册分支、执行 try 并上报结果
+pub async fn tcc_try<A: TccAction>(action: A, context: A::Context) -> anyhow::Result<()> {
+    let action = Arc::new(action);
+    let try_action = action.clone();
+    let context = &context;
+    tcc_try_with(action, context, |branch| async move {
+        try_action.r#try(&branch, context).await
+    })
+    .await
+}
+
+/// 与 `tcc_try` 相同，但一阶段执行 `f`，用于 try 需要返回值的场景（如 `#[tcc_action]`）
+pub async fn tcc_try_with<A, F, Fut, T>(
+    action: Arc<A>,
+    context: &A::Context,
+    f: F,
+) -> anyhow::Result<T>
+where
+    A: TccAction,
+    F: FnOnce(TccBranch) -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    // 全局事务由 TM 开启，RM 只在已有的全局事务中注册分支
+    let xid = RSEATA_CLIENT_SESSION
+        .try_get()
+        .ok()
+        .filter(|session| session.is_global_tx_started())
+        .and_then(|session| session.get_xid())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "tcc action {} must run in global transaction",
+                action.name()
+            )
+        })?;
+
+    let application_data = serde_json::to_string(&TccApplicationData {
+        action_name: action.name().to_string(),
+        context: serde_json::to_value(context)?,
+    })?;
+
+    let branch_transaction = TccBranchTransaction { action };
+    TCC_ACTIONS
+        .write()
+        .await
+        .entry(branch_transaction.action.name().to_string())
+        .or_insert_with(|| Arc::new(branch_transaction.clone()));
+    register_tcc_dispatcher(&RSEATA_RM).await;
+
+    let branch_id = RSEATA_RM
+        .branch_transaction_registry(
+            BranchType::TCC,
+            RSEATA_RM.resource_info.get_resource_id().await,
+            RSEATA_RM.resource_info.get_client_id().await,
+            xid.clone(),
+            application_data,
+            String::new(),
+            Box::new(branch_transaction.clone()),
+        )
+        .await?;
+
+    let branch = TccBranch {
+        xid: xid.clone(),
+        branch_id,
+        action_name: branch_transaction.action.name().to_string(),
+    };
+    let try_result = f(branch).await;
+    let branch_status = match try_result {
+        Ok(_) => BranchStatus::PhaseOneDone,
+        Err(_) => BranchStatus::PhaseOneFailed,
+    };
+    RSEATA_RM
+        .branch_report(
+            BranchType::TCC,
+            xid,
+            branch_id,
+            branch_status,
+            String::new(),
+        )
+        .await?;
+    try_result
+}
+
+/// `#[tcc_action]` 生成代码使用
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use async_trait;
+    pub use inventory;
+    pub use serde;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::ResourceInfo;
+    use rseata_core::branch::BranchId;
+    use rseata_core::branch::branch_manager_inbound::BranchManagerInbound;
+    use rseata_core::tcc::TccBranch;
+    use rseata_core::types::{ResourceId, Xid};
+
+    struct NoopAction;
+
+    #[async_trait::async_trait]
+    impl TccAction for NoopAction {
+        type Context = ();
+
+        fn name(&self) -> &'static str {
+            "tests::noop"
+        }
+
+        async fn r#try(&self, _: &TccBranch, _: &()) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn confirm(&self, _: &TccBranch, _: ()) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn cancel(&self, _: &TccBranch, _: ()) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    inventory::submit! {
+        TccActionRegistration::new(|| tcc_branch_transaction(NoopAction))
+    }
+
+    #[tokio::test]
+    async fn submitted_actions_are_registered_at_startup() {
+        let rm = DefaultResourceManager::new(ResourceInfo::new(
+            "group".to_string(),
+            ResourceId::from("tests"),
+            BranchType::TCC,
+        ));
+        let application_data = serde_json::to_string(&TccApplicationData {
+            action_name: "tests::noop".to_string(),
+            context: serde_json::Value::Null,
+        })
+        .unwrap();
+        assert!(find_tcc_action(&application_data).await.is_none());
+
+        register_submitted_tcc_actions(&rm).await;
+        assert!(find_tcc_action(&application_data).await.is_some());
+        // 本地没有该分支时，由 TCC 分发器交给已注册的动作处理
+        let status = rm
+            .branch_rollback(
+                BranchType::TCC,
+                Xid::from("xid"),
+                BranchId::from(1),
+                ResourceId::from("tests"),
+                application_data,
+            )
+            .await
+            .unwrap();
+        assert_eq!(status, BranchStatus::PhaseTwoRollbacked);
+    }
+}
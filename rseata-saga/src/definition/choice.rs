@@ -0,0 +1,66 @@
+use crate::definition::ChoiceBranch;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+const OPERATORS: [&str; 6] = ["==", "!=", ">=", "<=", ">", "<"];
+
+impl ChoiceBranch {
+    pub fn matches(&self, context: &Map<String, Value>) -> bool {
+        let expression = self.expression.trim();
+        for operator in OPERATORS {
+            if let Some((key, literal)) = expression.split_once(operator) {
+                let left = lookup(context, key.trim());
+                let right = parse_literal(literal.trim());
+                return compare(left, operator, &right);
+            }
+        }
+        is_truthy(lookup(context, expression))
+    }
+}
+
+pub(crate) fn lookup<'a>(context: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix("$.").unwrap_or(path);
+    let mut parts = path.split('.');
+    let mut value = context.get(parts.next()?)?;
+    for part in parts {
+        value = value.get(part)?;
+    }
+    Some(value)
+}
+
+fn parse_literal(literal: &str) -> Value {
+    serde_json::from_str(literal).unwrap_or_else(|_| Value::String(literal.to_string()))
+}
+
+fn compare(left: Option<&Value>, operator: &str, right: &Value) -> bool {
+    let Some(left) = left else {
+        return operator == "!=";
+    };
+    let ordering = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l
+            .as_f64()
+            .zip(r.as_f64())
+            .and_then(|(l, r)| l.partial_cmp(&r)),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => (left == right).then_some(Ordering::Equal),
+    };
+    match operator {
+        "==" => ordering == Some(Ordering::Equal),
+        "!=" => ordering != Some(Ordering::Equal),
+        ">" => ordering == Some(Ordering::Greater),
+        "<" => ordering == Some(Ordering::Less),
+        ">=" => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        "<=" => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        _ => false,
+    }
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(_) => true,
+    }
+}
@@ -0,0 +1,151 @@
+pub(crate) mod choice;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 状态机定义，可由 JSON 或 YAML 加载
+///
+/// ```yaml
+/// name: create_order
+/// start_state: ReduceInventory
+/// states:
+///   ReduceInventory:
+///     type: ServiceTask
+///     service: inventory.reduce
+///     compensate_state: CompensateReduceInventory
+///     next: ChoiceAmount
+///   ChoiceAmount:
+///     type: Choice
+///     choices:
+///       - expression: "amount > 0"
+///         next: ReduceBalance
+///     default: Succeed
+///   ...
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMachineDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+    pub start_state: String,
+    /// 执行失败且未触发补偿时的恢复方式
+    #[serde(default)]
+    pub recover_strategy: RecoverStrategy,
+    #[serde(default = "default_timeout_millis")]
+    pub timeout_millis: u64,
+    pub states: HashMap<String, State>,
+}
+
+fn default_timeout_millis() -> u64 {
+    60 * 60 * 1000
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecoverStrategy {
+    /// 反向补偿已执行的状态
+    #[default]
+    Compensate,
+    /// 由 TC 重试，从失败的状态继续向前执行
+    Forward,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum State {
+    ServiceTask {
+        service: String,
+        /// 服务入参，`$.` 开头的字符串从上下文取值；为空时传入整个上下文
+        #[serde(default)]
+        input: Option<Value>,
+        #[serde(default)]
+        next: Option<String>,
+        #[serde(default)]
+        compensate_state: Option<String>,
+        /// 服务失败时跳转，一般指向 CompensationTrigger
+        #[serde(default)]
+        catch: Option<String>,
+    },
+    Choice {
+        choices: Vec<ChoiceBranch>,
+        #[serde(default)]
+        default: Option<String>,
+    },
+    CompensationTrigger {
+        #[serde(default)]
+        next: Option<String>,
+    },
+    SubStateMachine {
+        state_machine_name: String,
+        #[serde(default)]
+        next: Option<String>,
+    },
+    Succeed,
+    Fail {
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceBranch {
+    /// `key`、`key == value`、`key != value`、`key > value` 等，key 支持 `a.b` 路径
+    pub expression: String,
+    pub next: String,
+}
+
+impl StateMachineDefinition {
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let definition: Self = serde_json::from_str(json)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    pub fn from_yaml(yaml: &str) -> anyhow::Result<Self> {
+        let definition: Self = serde_yaml::from_str(yaml)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// 检查状态之间的引用，子状态机在执行时才解析
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let check = |from: &str, to: &Option<String>| -> anyhow::Result<()> {
+            match to {
+                Some(to) if !self.states.contains_key(to) => Err(anyhow::anyhow!(
+                    "state machine {}: state {} refers to unknown state {}",
+                    self.name,
+                    from,
+                    to
+                )),
+                _ => Ok(()),
+            }
+        };
+
+        check("start_state", &Some(self.start_state.clone()))?;
+        for (name, state) in &self.states {
+            match state {
+                State::ServiceTask {
+                    next,
+                    compensate_state,
+                    catch,
+                    ..
+                } => {
+                    check(name, next)?;
+                    check(name, compensate_state)?;
+                    check(name, catch)?;
+                }
+                State::Choice { choices, default } => {
+                    for choice in choices {
+                        check(name, &Some(choice.next.clone()))?;
+                    }
+                    check(name, default)?;
+                }
+                State::CompensationTrigger { next } | State::SubStateMachine { next, .. } => {
+                    check(name, next)?;
+                }
+                State::Succeed | State::Fail { .. } => {}
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+use crate::definition::choice::lookup;
+use crate::definition::{State, StateMachineDefinition};
+use crate::engine::StateMachineEngine;
+use crate::store::{ExecutedState, SagaStatus, StateLog, StateLogStatus, StateMachineInstance};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) enum Outcome {
+    Succeed,
+    Fail(String),
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// 解析 input 中 `$.` 开头的取值表达式
+fn resolve_input(input: &Value, context: &Map<String, Value>) -> Value {
+    match input {
+        Value::String(s) if s.starts_with("$.") => {
+            lookup(context, s).cloned().unwrap_or(Value::Null)
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), resolve_input(v, context)))
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| resolve_input(v, context)).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+impl StateMachineEngine {
+    async fn invoke_service(
+        &self,
+        service: &str,
+        input: &Option<Value>,
+        context: &Map<String, Value>,
+    ) -> anyhow::Result<Value> {
+        let handler = self
+            .handlers
+            .read()
+            .await
+            .get(service)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("service handler not found: {service}"))?;
+        let input = match input {
+            Some(input) => resolve_input(input, context),
+            None => Value::Object(context.clone()),
+        };
+        handler.invoke(input).await
+    }
+
+    async fn log(
+        &self,
+        instance: &StateMachineInstance,
+        machine: &str,
+        state: &str,
+        status: StateLogStatus,
+        output: Option<Value>,
+        error: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.store
+            .append_log(StateLog {
+                instance_id: instance.id.clone(),
+                machine: machine.to_string(),
+                state: state.to_string(),
+                status,
+                output,
+                error,
+                timestamp_millis: now_millis(),
+            })
+            .await
+    }
+
+    /// 执行状态机，子状态机递归执行并共享同一个实例
+    pub(crate) fn execute<'a>(
+        &'a self,
+        instance: &'a mut StateMachineInstance,
+        definition: Arc<StateMachineDefinition>,
+        start: String,
+        top_level: bool,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<Outcome>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut current = Some(start);
+            while let Some(name) = current {
+                let state = definition.states.get(&name).ok_or_else(|| {
+                    anyhow::anyhow!("state {} not found in {}", name, definition.name)
+                })?;
+                if top_level {
+                    instance.resume_state = Some(name.clone());
+                    self.store.save_instance(instance).await?;
+                }
+
+                current = match state {
+                    State::ServiceTask {
+                        service,
+                        input,
+                        next,
+                        catch,
+                        ..
+                    } => match self.invoke_service(service, input, &instance.context).await {
+                        Ok(output) => {
+                            if let Value::Object(output) = &output {
+                                instance.context.extend(output.clone());
+                            }
+                            instance.executed.push(ExecutedState {
+                                machine: definition.name.clone(),
+                                state: name.clone(),
+                                compensated: false,
+                            });
+                            self.log(
+                                instance,
+                                &definition.name,
+                                &name,
+                                StateLogStatus::Succeed,
+                                Some(output),
+                                None,
+                            )
+                            .await?;
+                            self.store.save_instance(instance).await?;
+                            next.clone()
+                        }
+                        Err(e) => {
+                            tracing::warn!("saga state {}.{} failed: {}", definition.name, name, e);
+                            self.log(
+                                instance,
+                                &definition.name,
+                                &name,
+                                StateLogStatus::Failed,
+                                None,
+                                Some(e.to_string()),
+                            )
+                            .await?;
+                            match catch {
+                                Some(catch) => Some(catch.clone()),
+                                None => return Ok(Outcome::Fail(e.to_string())),
+                            }
+                        }
+                    },
+                    State::Choice { choices, default } => {
+                        let next = choices
+                            .iter()
+                            .find(|c| c.matches(&instance.context))
+                            .map(|c| c.next.clone())
+                            .or_else(|| default.clone());
+                        match next {
+                            Some(next) => Some(next),
+                            None => {
+                                return Ok(Outcome::Fail(format!(
+                                    "no choice matched in state {name}"
+                                )));
+                            }
+                        }
+                    }
+                    State::CompensationTrigger { next } => {
+                        self.run_compensation(instance).await?;
+                        // 补偿失败时停在这里，等待 TC 重试补偿
+                        if instance.status == SagaStatus::CompensateFailed {
+                            return Ok(Outcome::Fail(instance.error.clone().unwrap_or_default()));
+                        }
+                        match next {
+                            Some(next) => Some(next.clone()),
+                            None => {
+                                return Ok(Outcome::Fail(String::from("compensation triggered")));
+                            }
+                        }
+                    }
+                    State::SubStateMachine {
+                        state_machine_name,
+                        next,
+                    } => {
+                        let sub = self.definition(state_machine_name).await?;
+                        let sub_start = sub.start_state.clone();
+                        match self.execute(instance, sub, sub_start, false).await? {
+                            Outcome::Succeed => next.clone(),
+                            fail => return Ok(fail),
+                        }
+                    }
+                    State::Succeed => return Ok(Outcome::Succeed),
+                    State::Fail { message } => {
+                        return Ok(Outcome::Fail(
+                            message
+                                .clone()
+                                .unwrap_or_else(|| format!("failed at state {name}")),
+                        ));
+                    }
+                };
+            }
+            Ok(Outcome::Succeed)
+        })
+    }
+
+    /// 逆序补偿已执行的状态，失败时停止并保留剩余状态等待重试
+    pub(crate) async fn run_compensation(
+        &self,
+        instance: &mut StateMachineInstance,
+    ) -> anyhow::Result<()> {
+        for index in (0..instance.executed.len()).rev() {
+            if instance.executed[index].compensated {
+                continue;
+            }
+            let executed = instance.executed[index].clone();
+            let definition = self.definition(&executed.machine).await?;
+            let compensate_state = match definition.states.get(&executed.state) {
+                Some(State::ServiceTask {
+                    compensate_state: Some(compensate_state),
+                    ..
+                }) => compensate_state.clone(),
+                _ => {
+                    instance.executed[index].compensated = true;
+                    continue;
+                }
+            };
+            let Some(State::ServiceTask { service, input, .. }) =
+                definition.states.get(&compensate_state)
+            else {
+                return Err(anyhow::anyhow!(
+                    "compensate state {compensate_state} must be a ServiceTask"
+                ));
+            };
+
+            match self.invoke_service(service, input, &instance.context).await {
+                Ok(output) => {
+                    instance.executed[index].compensated = true;
+                    self.log(
+                        instance,
+                        &executed.machine,
+                        &compensate_state,
+                        StateLogStatus::Compensated,
+                        Some(output),
+                        None,
+                    )
+                    .await?;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "saga compensate {}.{} failed: {}",
+                        executed.machine,
+                        compensate_state,
+                        e
+                    );
+                    instance.status = SagaStatus::CompensateFailed;
+                    instance.error = Some(e.to_string());
+                    self.log(
+                        instance,
+                        &executed.machine,
+                        &compensate_state,
+                        StateLogStatus::CompensateFailed,
+                        None,
+                        Some(e.to_string()),
+                    )
+                    .await?;
+                    return self.store.save_instance(instance).await;
+                }
+            }
+        }
+        instance.status = SagaStatus::Compensated;
+        self.store.save_instance(instance).await
+    }
+}
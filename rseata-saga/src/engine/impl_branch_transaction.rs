@@ -0,0 +1,43 @@
+use crate::engine::{SagaApplicationData, StateMachineEngine};
+use crate::store::SagaStatus;
+use async_trait::async_trait;
+use rseata_core::branch::branch_transaction::BranchTransaction;
+use rseata_core::branch::{BranchId, BranchStatus, BranchType};
+use rseata_core::types::{ResourceId, Xid};
+
+#[async_trait]
+impl BranchTransaction for StateMachineEngine {
+    async fn branch_commit(
+        &self,
+        _branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        _resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        tracing::info!("SAGA branch_commit ing :{xid},{branch_id}");
+        let data: SagaApplicationData = serde_json::from_str(&application_data)?;
+        let instance = self.forward(&data.instance_id).await?;
+        match instance.status {
+            SagaStatus::Succeed => Ok(BranchStatus::PhaseTwoCommitted),
+            _ => Ok(BranchStatus::PhaseTwoCommitFailedRetryable),
+        }
+    }
+
+    async fn branch_rollback(
+        &self,
+        _branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        _resource_id: ResourceId,
+        application_data: String,
+    ) -> anyhow::Result<BranchStatus> {
+        tracing::info!("SAGA branch_rollback ing :{xid},{branch_id}");
+        let data: SagaApplicationData = serde_json::from_str(&application_data)?;
+        let instance = self.compensate(&data.instance_id).await?;
+        match instance.status {
+            SagaStatus::Compensated => Ok(BranchStatus::PhaseTwoRollbacked),
+            _ => Ok(BranchStatus::PhaseTwoRollbackFailedRetryable),
+        }
+    }
+}
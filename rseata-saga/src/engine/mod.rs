@@ -0,0 +1,478 @@
+mod execute;
+mod impl_branch_transaction;
+
+use crate::definition::{RecoverStrategy, StateMachineDefinition};
+use crate::handler::ServiceHandler;
+use crate::store::{SagaStatus, StateLogStore, StateMachineInstance};
+use rseata_core::RSEATA_CLIENT_SESSION;
+use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
+use rseata_core::branch::branch_transaction::BranchTransactionRegistry;
+use rseata_core::branch::{BranchStatus, BranchType};
+use rseata_core::resource::Resource;
+use rseata_core::transaction::transaction_manager::TransactionManager;
+use rseata_core::types::GlobalStatus;
+use rseata_rm::RSEATA_RM;
+use rseata_tm::RSEATA_TM;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// 保存在 SAGA 分支 application_data 中的数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaApplicationData {
+    pub instance_id: String,
+}
+
+/// 状态机引擎：每个状态机实例注册为一个 SAGA 分支
+///
+/// TC 回滚时通过分支回滚触发补偿，重试提交时触发向前恢复
+#[derive(Clone)]
+pub struct StateMachineEngine {
+    definitions: Arc<RwLock<HashMap<String, Arc<StateMachineDefinition>>>>,
+    handlers: Arc<RwLock<HashMap<String, Arc<dyn ServiceHandler>>>>,
+    store: Arc<dyn StateLogStore>,
+}
+
+impl StateMachineEngine {
+    pub fn new(store: Arc<dyn StateLogStore>) -> Self {
+        Self {
+            definitions: Arc::new(Default::default()),
+            handlers: Arc::new(Default::default()),
+            store,
+        }
+    }
+
+    pub async fn register_definition(
+        &self,
+        definition: StateMachineDefinition,
+    ) -> anyhow::Result<()> {
+        definition.validate()?;
+        self.definitions
+            .write()
+            .await
+            .insert(definition.name.clone(), Arc::new(definition));
+        // RM 重启后本地没有分支缓存，由引擎按实例 ID 处理二阶段
+        RSEATA_RM
+            .register_branch_type_handler(BranchType::SAGA, Arc::new(self.clone()))
+            .await;
+        Ok(())
+    }
+
+    pub async fn load_json(&self, json: &str) -> anyhow::Result<()> {
+        self.register_definition(StateMachineDefinition::from_json(json)?)
+            .await
+    }
+
+    pub async fn load_yaml(&self, yaml: &str) -> anyhow::Result<()> {
+        self.register_definition(StateMachineDefinition::from_yaml(yaml)?)
+            .await
+    }
+
+    pub async fn register_handler<H: ServiceHandler>(&self, service: &str, handler: H) {
+        self.handlers
+            .write()
+            .await
+            .insert(service.to_string(), Arc::new(handler));
+    }
+
+    pub(crate) async fn definition(
+        &self,
+        name: &str,
+    ) -> anyhow::Result<Arc<StateMachineDefinition>> {
+        self.definitions
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("state machine not found: {name}"))
+    }
+
+    pub async fn find_instance(
+        &self,
+        instance_id: &str,
+    ) -> anyhow::Result<Option<StateMachineInstance>> {
+        self.store.find_instance(instance_id).await
+    }
+
+    /// 启动状态机；在全局事务中调用时作为其中一个分支，否则自行开启全局事务
+    pub async fn start(
+        &self,
+        machine_name: &str,
+        context: Map<String, Value>,
+    ) -> anyhow::Result<StateMachineInstance> {
+        let definition = self.definition(machine_name).await?;
+
+        // 外层全局事务由其 TM 提交或回滚，这里只作为其中一个分支
+        let session = RSEATA_CLIENT_SESSION.try_get().ok();
+        let owns_transaction = session.is_none();
+        let xid = match session.as_ref().and_then(|s| s.get_xid()) {
+            Some(xid) => xid,
+            None => {
+                let xid = RSEATA_TM
+                    .begin(
+                        RSEATA_TM.application_id.to_string(),
+                        RSEATA_TM.transaction_service_group.to_string(),
+                        machine_name.to_string(),
+                        definition.timeout_millis,
+                    )
+                    .await?;
+                if let Some(session) = &session {
+                    session.begin_global_transaction(xid.clone())?;
+                }
+                xid
+            }
+        };
+
+        let mut instance = StateMachineInstance {
+            id: Uuid::new_v4().to_string(),
+            machine_name: machine_name.to_string(),
+            xid: xid.clone(),
+            branch_id: None,
+            owns_transaction,
+            status: SagaStatus::Running,
+            context,
+            resume_state: Some(definition.start_state.clone()),
+            executed: vec![],
+            error: None,
+        };
+        self.store.save_instance(&instance).await?;
+
+        let branch_id = RSEATA_RM
+            .branch_transaction_registry(
+                BranchType::SAGA,
+                RSEATA_RM.resource_info.get_resource_id().await,
+                RSEATA_RM.resource_info.get_client_id().await,
+                xid.clone(),
+                serde_json::to_string(&SagaApplicationData {
+                    instance_id: instance.id.clone(),
+                })?,
+                String::new(),
+                Box::new(self.clone()),
+            )
+            .await?;
+        instance.branch_id = Some(branch_id);
+        self.store.save_instance(&instance).await?;
+
+        self.run(&mut instance, definition).await?;
+        self.report(&instance).await?;
+        Ok(instance)
+    }
+
+    /// 向前恢复：从失败的状态继续执行
+    pub async fn forward(&self, instance_id: &str) -> anyhow::Result<StateMachineInstance> {
+        let mut instance = self.load_instance(instance_id).await?;
+        if instance.status != SagaStatus::Failed && instance.status != SagaStatus::Running {
+            return Ok(instance);
+        }
+        let definition = self.definition(&instance.machine_name).await?;
+        self.run(&mut instance, definition).await?;
+        Ok(instance)
+    }
+
+    /// 反向补偿所有已执行且未补偿的状态
+    pub async fn compensate(&self, instance_id: &str) -> anyhow::Result<StateMachineInstance> {
+        let mut instance = self.load_instance(instance_id).await?;
+        if instance.status == SagaStatus::Compensated {
+            return Ok(instance);
+        }
+        self.run_compensation(&mut instance).await?;
+        Ok(instance)
+    }
+
+    async fn load_instance(&self, instance_id: &str) -> anyhow::Result<StateMachineInstance> {
+        self.store
+            .find_instance(instance_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("state machine instance not found: {instance_id}"))
+    }
+
+    async fn run(
+        &self,
+        instance: &mut StateMachineInstance,
+        definition: Arc<StateMachineDefinition>,
+    ) -> anyhow::Result<()> {
+        let start = instance
+            .resume_state
+            .clone()
+            .unwrap_or_else(|| definition.start_state.clone());
+        instance.status = SagaStatus::Running;
+        instance.error = None;
+
+        let outcome = self
+            .execute(instance, definition.clone(), start, true)
+            .await?;
+        match outcome {
+            execute::Outcome::Succeed => {
+                // CompensationTrigger 补偿后经 next 到达 Succeed 时，保留补偿结果
+                if instance.status == SagaStatus::Running {
+                    instance.status = SagaStatus::Succeed;
+                }
+                instance.resume_state = None;
+            }
+            execute::Outcome::Fail(error) => {
+                instance.error = Some(error);
+                if instance.status == SagaStatus::Running {
+                    match definition.recover_strategy {
+                        RecoverStrategy::Forward => instance.status = SagaStatus::Failed,
+                        RecoverStrategy::Compensate => self.run_compensation(instance).await?,
+                    }
+                }
+            }
+        }
+        self.store.save_instance(instance).await
+    }
+
+    /// 上报分支状态，自行开启的全局事务同时通过 GlobalReport 上报结果
+    async fn report(&self, instance: &StateMachineInstance) -> anyhow::Result<()> {
+        let branch_status = match (instance.owns_transaction, instance.status) {
+            (true, SagaStatus::Succeed) => BranchStatus::PhaseTwoCommitted,
+            (true, SagaStatus::Failed) => BranchStatus::PhaseTwoCommitFailedRetryable,
+            (true, SagaStatus::Compensated) => BranchStatus::PhaseTwoRollbacked,
+            (true, _) => BranchStatus::PhaseTwoRollbackFailedRetryable,
+            // 向前恢复交给外层提交时的分支提交继续执行
+            (false, SagaStatus::Succeed | SagaStatus::Failed) => BranchStatus::PhaseOneDone,
+            (false, _) => BranchStatus::PhaseOneFailed,
+        };
+        if let Some(branch_id) = instance.branch_id {
+            RSEATA_RM
+                .branch_report(
+                    BranchType::SAGA,
+                    instance.xid.clone(),
+                    branch_id,
+                    branch_status,
+                    String::new(),
+                )
+                .await?;
+        }
+        if instance.owns_transaction {
+            let global_status = match instance.status {
+                SagaStatus::Succeed => GlobalStatus::Committed,
+                SagaStatus::Failed => GlobalStatus::CommitRetrying,
+                SagaStatus::Compensated => GlobalStatus::Rollbacked,
+                SagaStatus::CompensateFailed | SagaStatus::Running => {
+                    GlobalStatus::RollbackRetrying
+                }
+            };
+            RSEATA_TM
+                .global_report(
+                    &instance.xid,
+                    RSEATA_TM.application_id.to_string(),
+                    RSEATA_TM.transaction_service_group.to_string(),
+                    global_status,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::StateLogStatus;
+    use crate::store::memory_state_log_store::MemoryStateLogStore;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    /// 记录调用顺序，前 failures 次调用失败
+    struct RecordingHandler {
+        service: String,
+        calls: Calls,
+        failures: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ServiceHandler for RecordingHandler {
+        async fn invoke(&self, _input: Value) -> anyhow::Result<Value> {
+            self.calls.lock().unwrap().push(self.service.clone());
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                anyhow::bail!("{} failed", self.service);
+            }
+            Ok(Value::Object(Map::new()))
+        }
+    }
+
+    const ORDER: &str = r#"
+name: order
+start_state: ReduceInventory
+states:
+  ReduceInventory:
+    type: ServiceTask
+    service: inventory.reduce
+    compensate_state: CompensateInventory
+    next: ChoiceAmount
+  ChoiceAmount:
+    type: Choice
+    choices:
+      - expression: "amount > 0"
+        next: ReduceBalance
+    default: Succeed
+  ReduceBalance:
+    type: ServiceTask
+    service: balance.reduce
+    compensate_state: CompensateBalance
+    next: CreateOrder
+  CreateOrder:
+    type: ServiceTask
+    service: order.create
+    catch: Compensate
+    next: Succeed
+  Compensate:
+    type: CompensationTrigger
+    next: Compensated
+  CompensateInventory:
+    type: ServiceTask
+    service: inventory.compensate
+  CompensateBalance:
+    type: ServiceTask
+    service: balance.compensate
+  Succeed:
+    type: Succeed
+  Compensated:
+    type: Succeed
+"#;
+
+    struct TestEngine {
+        engine: StateMachineEngine,
+        calls: Calls,
+    }
+
+    impl TestEngine {
+        // 不经过 RM 注册，直接装入定义和服务
+        async fn new(failures: &[(&str, usize)]) -> Self {
+            let engine = StateMachineEngine::new(Arc::new(MemoryStateLogStore::default()));
+            let definition = StateMachineDefinition::from_yaml(ORDER).unwrap();
+            engine
+                .definitions
+                .write()
+                .await
+                .insert(definition.name.clone(), Arc::new(definition));
+            let calls = Calls::default();
+            for service in [
+                "inventory.reduce",
+                "inventory.compensate",
+                "balance.reduce",
+                "balance.compensate",
+                "order.create",
+            ] {
+                let failures = failures
+                    .iter()
+                    .find(|(name, _)| *name == service)
+                    .map_or(0, |(_, n)| *n);
+                let handler = RecordingHandler {
+                    service: service.to_string(),
+                    calls: calls.clone(),
+                    failures: AtomicUsize::new(failures),
+                };
+                engine.register_handler(service, handler).await;
+            }
+            Self { engine, calls }
+        }
+
+        async fn run(&self, amount: i64) -> StateMachineInstance {
+            let mut context = Map::new();
+            context.insert("amount".to_string(), Value::from(amount));
+            let mut instance = StateMachineInstance {
+                id: Uuid::new_v4().to_string(),
+                machine_name: "order".to_string(),
+                xid: "xid".into(),
+                branch_id: None,
+                owns_transaction: true,
+                status: SagaStatus::Running,
+                context,
+                resume_state: None,
+                executed: vec![],
+                error: None,
+            };
+            let definition = self.engine.definition("order").await.unwrap();
+            self.engine.run(&mut instance, definition).await.unwrap();
+            instance
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn forward_execution_runs_every_state() {
+        let test = TestEngine::new(&[]).await;
+        let instance = test.run(10).await;
+
+        assert_eq!(instance.status, SagaStatus::Succeed);
+        assert_eq!(instance.resume_state, None);
+        assert_eq!(
+            test.calls(),
+            ["inventory.reduce", "balance.reduce", "order.create"]
+        );
+        let logs = test.engine.store.find_logs(&instance.id).await.unwrap();
+        assert!(logs.iter().all(|log| log.status == StateLogStatus::Succeed));
+        assert_eq!(logs.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn choice_routes_on_context() {
+        let test = TestEngine::new(&[]).await;
+        let instance = test.run(0).await;
+
+        // amount 不大于 0 时走 default，跳过扣减余额
+        assert_eq!(instance.status, SagaStatus::Succeed);
+        assert_eq!(test.calls(), ["inventory.reduce"]);
+    }
+
+    #[tokio::test]
+    async fn failure_compensates_in_reverse_order() {
+        let test = TestEngine::new(&[("order.create", 1)]).await;
+        let instance = test.run(10).await;
+
+        // 经 CompensationTrigger 的 next 到达 Succeed，仍然是补偿完成
+        assert_eq!(instance.status, SagaStatus::Compensated);
+        assert_eq!(
+            test.calls(),
+            [
+                "inventory.reduce",
+                "balance.reduce",
+                "order.create",
+                "balance.compensate",
+                "inventory.compensate",
+            ]
+        );
+        assert!(instance.executed.iter().all(|state| state.compensated));
+        let stored = test.engine.find_instance(&instance.id).await.unwrap();
+        assert_eq!(stored.unwrap().status, SagaStatus::Compensated);
+    }
+
+    #[tokio::test]
+    async fn failed_compensation_is_retried() {
+        let test = TestEngine::new(&[("order.create", 1), ("balance.compensate", 1)]).await;
+        let instance = test.run(10).await;
+
+        assert_eq!(instance.status, SagaStatus::CompensateFailed);
+        assert!(instance.executed.iter().all(|state| !state.compensated));
+
+        // TC 重试回滚时从未补偿的状态继续，已补偿的状态不再重复
+        let instance = test.engine.compensate(&instance.id).await.unwrap();
+        assert_eq!(instance.status, SagaStatus::Compensated);
+        assert_eq!(
+            test.calls()[3..],
+            [
+                "balance.compensate",
+                "balance.compensate",
+                "inventory.compensate",
+            ]
+        );
+        let instance = test.engine.compensate(&instance.id).await.unwrap();
+        assert_eq!(instance.status, SagaStatus::Compensated);
+        assert_eq!(test.calls().len(), 6);
+    }
+}
@@ -0,0 +1,10 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// ServiceTask / 补偿状态调用的业务服务
+///
+/// 返回对象会合并回状态机上下文；Saga 服务可能在恢复时被重复调用，需要保证幂等
+#[async_trait]
+pub trait ServiceHandler: Send + Sync + 'static {
+    async fn invoke(&self, input: Value) -> anyhow::Result<Value>;
+}
@@ -0,0 +1,10 @@
+pub mod definition;
+pub mod engine;
+pub mod handler;
+pub mod store;
+
+pub use definition::StateMachineDefinition;
+pub use engine::StateMachineEngine;
+pub use handler::ServiceHandler;
+pub use store::StateLogStore;
+pub use store::memory_state_log_store::MemoryStateLogStore;
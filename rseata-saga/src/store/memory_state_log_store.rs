@@ -0,0 +1,48 @@
+use crate::store::{StateLog, StateLogStore, StateMachineInstance};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+#[derive(Default)]
+pub struct MemoryStateLogStore {
+    instances: RwLock<HashMap<String, StateMachineInstance>>,
+    logs: RwLock<HashMap<String, Vec<StateLog>>>,
+}
+
+#[async_trait]
+impl StateLogStore for MemoryStateLogStore {
+    async fn save_instance(&self, instance: &StateMachineInstance) -> anyhow::Result<()> {
+        self.instances
+            .write()
+            .await
+            .insert(instance.id.clone(), instance.clone());
+        Ok(())
+    }
+
+    async fn find_instance(
+        &self,
+        instance_id: &str,
+    ) -> anyhow::Result<Option<StateMachineInstance>> {
+        Ok(self.instances.read().await.get(instance_id).cloned())
+    }
+
+    async fn append_log(&self, log: StateLog) -> anyhow::Result<()> {
+        self.logs
+            .write()
+            .await
+            .entry(log.instance_id.clone())
+            .or_default()
+            .push(log);
+        Ok(())
+    }
+
+    async fn find_logs(&self, instance_id: &str) -> anyhow::Result<Vec<StateLog>> {
+        Ok(self
+            .logs
+            .read()
+            .await
+            .get(instance_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,75 @@
+pub mod memory_state_log_store;
+
+use async_trait::async_trait;
+use rseata_core::branch::BranchId;
+use rseata_core::types::Xid;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SagaStatus {
+    Running,
+    Succeed,
+    /// 向前恢复策略下执行失败，等待 TC 重试
+    Failed,
+    Compensated,
+    /// 补偿失败，等待 TC 重试
+    CompensateFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutedState {
+    pub machine: String,
+    pub state: String,
+    pub compensated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMachineInstance {
+    pub id: String,
+    pub machine_name: String,
+    pub xid: Xid,
+    pub branch_id: Option<BranchId>,
+    /// 由状态机自己开启的全局事务，结束时通过 GlobalReport 上报结果
+    pub owns_transaction: bool,
+    pub status: SagaStatus,
+    pub context: Map<String, Value>,
+    /// 顶层状态机当前执行到的状态，向前恢复时从这里继续
+    pub resume_state: Option<String>,
+    pub executed: Vec<ExecutedState>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateLogStatus {
+    Succeed,
+    Failed,
+    Compensated,
+    CompensateFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateLog {
+    pub instance_id: String,
+    pub machine: String,
+    pub state: String,
+    pub status: StateLogStatus,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub timestamp_millis: u64,
+}
+
+/// 状态机实例与状态流转日志的持久化
+#[async_trait]
+pub trait StateLogStore: Send + Sync + 'static {
+    async fn save_instance(&self, instance: &StateMachineInstance) -> anyhow::Result<()>;
+
+    async fn find_instance(
+        &self,
+        instance_id: &str,
+    ) -> anyhow::Result<Option<StateMachineInstance>>;
+
+    async fn append_log(&self, log: StateLog) -> anyhow::Result<()>;
+
+    async fn find_logs(&self, instance_id: &str) -> anyhow::Result<Vec<StateLog>>;
+}
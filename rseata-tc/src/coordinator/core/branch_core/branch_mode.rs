@@ -0,0 +1,152 @@
+use rseata_core::branch::{BranchStatus, BranchType, XA_IDLE, XA_ONE_PHASE};
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+
+/// 分支类型之间的差异，其余流程由 `BranchCore` 统一处理
+pub trait BranchMode: Send + Sync + 'static {
+    fn branch_type(&self) -> BranchType;
+
+    /// 是否由 TC 登记全局行锁
+    fn global_lock(&self) -> bool {
+        false
+    }
+
+    /// 提交前是否需要 RM 先准备该分支
+    fn needs_prepare(&self, _branch_session: &DefaultBranchSession) -> bool {
+        false
+    }
+
+    /// 提交指令携带的 application_data
+    fn commit_data(
+        &self,
+        _global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+    ) -> String {
+        branch_session.application_data.clone().unwrap_or_default()
+    }
+
+    /// 无需通知 RM 即可认定已回滚的分支
+    fn rolled_back_locally(&self, _branch_session: &DefaultBranchSession) -> bool {
+        false
+    }
+
+    /// 删除分支时执行提交还是回滚
+    fn delete_by_commit(&self) -> bool {
+        false
+    }
+}
+
+/// 一阶段已提交本地事务，二阶段提交只删除 undo log，回滚完成前保留行锁
+pub struct AtMode;
+
+impl BranchMode for AtMode {
+    fn branch_type(&self) -> BranchType {
+        BranchType::AT
+    }
+
+    fn global_lock(&self) -> bool {
+        true
+    }
+
+    fn delete_by_commit(&self) -> bool {
+        true
+    }
+}
+
+/// 行锁由数据库在 XA 事务内持有，TC 不维护全局锁
+pub struct XaMode;
+
+impl BranchMode for XaMode {
+    fn branch_type(&self) -> BranchType {
+        BranchType::XA
+    }
+
+    // 一阶段只做了 END 的分支，在提交任何分支前先要求 RM PREPARE
+    fn needs_prepare(&self, branch_session: &DefaultBranchSession) -> bool {
+        branch_session.application_data.as_deref() == Some(XA_IDLE)
+    }
+
+    // 唯一分支时通知 RM 直接 ONE PHASE 提交，省去 PREPARE
+    fn commit_data(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+    ) -> String {
+        if global_session.branch_sessions.len() == 1 {
+            XA_ONE_PHASE.to_string()
+        } else {
+            branch_session.application_data.clone().unwrap_or_default()
+        }
+    }
+
+    // 一阶段失败时 RM 已经回滚了 XA 分支
+    fn rolled_back_locally(&self, branch_session: &DefaultBranchSession) -> bool {
+        branch_session.status == BranchStatus::PhaseOneFailed
+    }
+}
+
+/// TCC 分支由业务自行预留资源，二阶段下发 confirm / cancel
+pub struct TccMode;
+
+impl BranchMode for TccMode {
+    fn branch_type(&self) -> BranchType {
+        BranchType::TCC
+    }
+}
+
+/// Saga 依靠补偿保证最终一致，TC 重试提交时触发向前恢复，回滚时触发补偿
+pub struct SagaMode;
+
+impl BranchMode for SagaMode {
+    fn branch_type(&self) -> BranchType {
+        BranchType::SAGA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(application_data: &[Option<&str>]) -> DefaultGlobalSession {
+        let mut gs = DefaultGlobalSession::new(
+            "test".to_string(),
+            "test_group".to_string(),
+            "test_tx".to_string(),
+            60_000,
+            false,
+        );
+        for (i, data) in application_data.iter().enumerate() {
+            let mut bs = DefaultBranchSession::new(BranchType::XA);
+            bs.branch_id = (i as u64 + 1).into();
+            bs.application_data = data.map(str::to_string);
+            gs.add_branch(bs);
+        }
+        gs
+    }
+
+    #[test]
+    fn xa_sole_branch_commits_one_phase() {
+        let gs = session(&[Some(XA_IDLE)]);
+        assert_eq!(
+            XaMode.commit_data(&gs, &gs.branch_sessions[0]),
+            XA_ONE_PHASE
+        );
+    }
+
+    #[test]
+    fn xa_idle_branches_prepare_before_commit() {
+        let gs = session(&[Some(XA_IDLE), None]);
+        assert!(XaMode.needs_prepare(&gs.branch_sessions[0]));
+        assert!(!XaMode.needs_prepare(&gs.branch_sessions[1]));
+        assert_eq!(XaMode.commit_data(&gs, &gs.branch_sessions[0]), XA_IDLE);
+        assert!(!TccMode.needs_prepare(&gs.branch_sessions[0]));
+    }
+
+    #[test]
+    fn only_at_uses_global_lock() {
+        assert!(AtMode.global_lock());
+        assert!(!XaMode.global_lock());
+        assert!(!TccMode.global_lock());
+        assert!(!SagaMode.global_lock());
+    }
+}
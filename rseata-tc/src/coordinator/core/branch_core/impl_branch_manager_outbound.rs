@@ -0,0 +1,146 @@
+use crate::coordinator::core::branch_core::BranchCore;
+use crate::coordinator::core::branch_core::branch_mode::BranchMode;
+use async_trait::async_trait;
+use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
+use rseata_core::branch::{BranchId, BranchStatus, BranchType};
+use rseata_core::error::TransactionError;
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::lock_manager::LockManager;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::session_manager::SessionManager;
+use rseata_core::types::{ClientId, GlobalStatus, ResourceId, Xid};
+use uuid::Uuid;
+
+#[async_trait]
+impl<M: BranchMode> BranchManagerOutbound for BranchCore<M> {
+    async fn branch_register(
+        &self,
+        branch_type: BranchType,
+        resource_id: ResourceId,
+        client_id: ClientId,
+        xid: Xid,
+        application_data: String,
+        lock_keys: String,
+    ) -> anyhow::Result<BranchId> {
+        let global_session = self
+            .session_manager
+            .find_global_session(&xid)
+            .await
+            .ok_or_else(|| {
+                tonic::Status::invalid_argument(format!("no such global session {}", xid))
+            })?;
+
+        // 只有仍处于一阶段的全局事务允许注册分支
+        if global_session.status != GlobalStatus::Begin {
+            return Err(tonic::Status::failed_precondition(format!(
+                "global session {} is not active: {:?}",
+                xid, global_session.status
+            ))
+            .into());
+        }
+
+        let branch_id = BranchId::from(Uuid::new_v4().as_u128() as u64);
+        let branch_session = DefaultBranchSession {
+            xid: xid.clone(),
+            transaction_id: global_session.transaction_id,
+            branch_id,
+            resource_group_id: None,
+            resource_id: Some(resource_id),
+            // 只有使用全局锁的模式登记行锁
+            lock_key: (self.mode.global_lock() && !lock_keys.trim().is_empty())
+                .then_some(lock_keys),
+            branch_type,
+            status: BranchStatus::Registered,
+            client_id,
+            application_data: Some(application_data).filter(|d| !d.is_empty()),
+            lock_status: LockStatus::Locked,
+            lock_holder: Default::default(),
+            queued_instruction: None,
+        };
+
+        // 一次性获取分支的全部行锁，任一行被其他全局事务持有则整体失败
+        if self.mode.global_lock() {
+            match self.lock_manager.acquire_lock(&branch_session).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    return Err(TransactionError::LockConflict {
+                        info: format!("{}, {:?}", xid, branch_session.lock_key),
+                    }
+                    .into());
+                }
+                // 锁存储故障不是锁冲突，RM 不应按冲突重试
+                Err(e) => {
+                    return Err(
+                        anyhow::Error::from(e).context(format!("acquire lock failed: {}", xid))
+                    );
+                }
+            }
+        }
+
+        if let Err(e) = self
+            .session_manager
+            .add_branch_session(&global_session, &branch_session)
+            .await
+        {
+            self.release_branch_lock(&branch_session).await;
+            return Err(e.into());
+        }
+
+        Ok(branch_id)
+    }
+
+    async fn branch_report(
+        &self,
+        _branch_type: BranchType,
+        xid: Xid,
+        branch_id: BranchId,
+        status: BranchStatus,
+        _application_data: String,
+    ) -> anyhow::Result<()> {
+        let global_session = self
+            .session_manager
+            .find_global_session(&xid)
+            .await
+            .ok_or_else(|| {
+                tonic::Status::invalid_argument(format!("no such global session {}", xid))
+            })?;
+
+        let branch_session = global_session.get_branch(branch_id).await.ok_or_else(|| {
+            tonic::Status::invalid_argument(format!("no such branch session {}", branch_id))
+        })?;
+
+        self.session_manager
+            .update_branch_session_status(&global_session, &branch_session, status)
+            .await?;
+        Ok(())
+    }
+
+    async fn lock_query(
+        &self,
+        _branch_type: BranchType,
+        resource_id: ResourceId,
+        xid: Xid,
+        lock_keys: String,
+    ) -> anyhow::Result<bool> {
+        // 不使用全局锁的模式始终可锁
+        if !self.mode.global_lock() {
+            return Ok(true);
+        }
+        let global_session = self
+            .session_manager
+            .find_global_session(&xid)
+            .await
+            .ok_or_else(|| {
+                tonic::Status::invalid_argument(format!("no such global session {}", xid))
+            })?;
+        Ok(self
+            .lock_manager
+            .is_lockable(
+                &xid,
+                &resource_id,
+                global_session.transaction_id,
+                lock_keys.as_str(),
+            )
+            .await?)
+    }
+}
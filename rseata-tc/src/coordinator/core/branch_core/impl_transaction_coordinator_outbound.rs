@@ -0,0 +1,142 @@
+use crate::coordinator::core::branch_core::BranchCore;
+use crate::coordinator::core::branch_core::branch_mode::BranchMode;
+use async_trait::async_trait;
+use rseata_core::branch::BranchStatus;
+use rseata_core::coordinator::transaction_coordinator_outbound::TransactionCoordinatorOutbound;
+use rseata_core::error::TransactionError;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+
+#[async_trait]
+impl<M: BranchMode> TransactionCoordinatorOutbound for BranchCore<M> {
+    type GlobalSession = DefaultGlobalSession;
+    type BranchSession = DefaultBranchSession;
+
+    async fn branch_prepare(
+        &self,
+        _global_session: &Self::GlobalSession,
+        branch_session: &Self::BranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        if !self.mode.needs_prepare(branch_session) {
+            return Ok(BranchStatus::PhaseOneDone);
+        }
+
+        let result = self.outbound.request_prepare(branch_session).await;
+
+        // 尚未有分支提交，准备失败时全局事务可以安全回滚
+        match result {
+            Ok(status) => Ok(status),
+            Err(e) => {
+                tracing::warn!(
+                    "{:?} branch {} prepare failed: {}",
+                    branch_session.branch_type,
+                    branch_session.branch_id,
+                    e
+                );
+                Ok(BranchStatus::PhaseOneFailed)
+            }
+        }
+    }
+
+    async fn branch_commit(
+        &self,
+        global_session: &Self::GlobalSession,
+        branch_session: &Self::BranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        tracing::debug!("BranchCore branch_commit---{:?}", branch_session);
+
+        if branch_session.is_tm_driven() {
+            return awaiting_tm(branch_session, BranchStatus::PhaseTwoCommitted);
+        }
+
+        let application_data = self.mode.commit_data(global_session, branch_session);
+        let result = self
+            .outbound
+            .send_commit(global_session, branch_session, application_data)
+            .await;
+
+        // 一阶段已完成，提交失败后不能再回滚，只能重试
+        match result {
+            Ok(status) => {
+                if status == BranchStatus::PhaseTwoCommitted {
+                    self.release_branch_lock(branch_session).await;
+                }
+                Ok(status)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "{:?} branch {} commit failed, retry later: {}",
+                    branch_session.branch_type,
+                    branch_session.branch_id,
+                    e
+                );
+                Ok(BranchStatus::PhaseTwoCommitFailedRetryable)
+            }
+        }
+    }
+
+    async fn branch_rollback(
+        &self,
+        global_session: &Self::GlobalSession,
+        branch_session: &Self::BranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        tracing::debug!("BranchCore branch_rollback---{:?}", branch_session);
+
+        if branch_session.is_tm_driven() {
+            return awaiting_tm(branch_session, BranchStatus::PhaseTwoRollbacked);
+        }
+        if self.mode.rolled_back_locally(branch_session) {
+            return Ok(BranchStatus::PhaseTwoRollbacked);
+        }
+
+        let result = self
+            .outbound
+            .send_rollback(global_session, branch_session)
+            .await;
+
+        // 回滚完成前保留行锁，防止其他事务读写脏数据
+        match result {
+            Ok(status) => {
+                if status == BranchStatus::PhaseTwoRollbacked {
+                    self.release_branch_lock(branch_session).await;
+                }
+                Ok(status)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "{:?} branch {} rollback failed, retry later: {}",
+                    branch_session.branch_type,
+                    branch_session.branch_id,
+                    e
+                );
+                Ok(BranchStatus::PhaseTwoRollbackFailedRetryable)
+            }
+        }
+    }
+
+    async fn branch_delete(
+        &self,
+        global_session: &Self::GlobalSession,
+        branch_session: &Self::BranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        if self.mode.delete_by_commit() {
+            self.branch_commit(global_session, branch_session).await
+        } else {
+            self.branch_rollback(global_session, branch_session).await
+        }
+    }
+}
+
+// TM 驱动的分支只采用 TM 上报的结果，未完成时等待 GlobalReport
+fn awaiting_tm(
+    branch_session: &DefaultBranchSession,
+    settled: BranchStatus,
+) -> Result<BranchStatus, TransactionError> {
+    if branch_session.status == settled {
+        return Ok(settled);
+    }
+    Err(TransactionError::new(format!(
+        "branch {} is driven by its TM, waiting for global report",
+        branch_session.branch_id
+    )))
+}
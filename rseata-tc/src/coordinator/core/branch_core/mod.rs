@@ -0,0 +1,62 @@
+pub mod branch_mode;
+pub mod impl_branch_manager_outbound;
+pub mod impl_transaction_coordinator_outbound;
+
+use crate::coordinator::core::branch_core::branch_mode::BranchMode;
+use crate::resource::outbound_queue::OutboundQueue;
+use crate::store::DefaultLocker;
+use rseata_core::branch::BranchType;
+use rseata_core::coordinator::{AbstractCore, Core};
+use rseata_core::handle_branch_type::HandleBranchType;
+use rseata_core::lock::defaults::default_lock_manager::DefaultLockManager;
+use rseata_core::lock::lock_manager::LockManager;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
+use std::sync::Arc;
+
+/// 各分支类型共用的 Core：注册分支并通过指令流下发二阶段，模式差异由 `BranchMode` 提供
+pub struct BranchCore<M: BranchMode> {
+    pub(crate) mode: M,
+    pub(crate) session_manager: Arc<DefaultSessionManager>,
+    pub(crate) lock_manager: Arc<DefaultLockManager<DefaultLocker>>,
+    pub(crate) outbound: Arc<OutboundQueue>,
+}
+
+impl<M: BranchMode> BranchCore<M> {
+    pub(crate) fn new(
+        mode: M,
+        session_manager: Arc<DefaultSessionManager>,
+        lock_manager: Arc<DefaultLockManager<DefaultLocker>>,
+        outbound: Arc<OutboundQueue>,
+    ) -> Self {
+        Self {
+            mode,
+            session_manager,
+            lock_manager,
+            outbound,
+        }
+    }
+
+    pub(crate) async fn release_branch_lock(&self, branch_session: &DefaultBranchSession) {
+        if !self.mode.global_lock() {
+            return;
+        }
+        if let Err(e) = self.lock_manager.release_lock(branch_session).await {
+            tracing::error!(
+                "Release branch lock failed: {}, {:?}",
+                branch_session.branch_id,
+                e
+            );
+        }
+    }
+}
+
+impl<M: BranchMode> HandleBranchType for BranchCore<M> {
+    fn handle_branch_type(&self) -> BranchType {
+        self.mode.branch_type()
+    }
+}
+
+impl<M: BranchMode> Core for BranchCore<M> {}
+
+impl<M: BranchMode> AbstractCore for BranchCore<M> {}
@@ -1 +1 @@
-pub mod at_core;
\ No newline at end of file
+pub mod branch_core;
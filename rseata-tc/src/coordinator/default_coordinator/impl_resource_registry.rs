@@ -28,13 +28,47 @@ impl ResourceRegistry for DefaultCoordinator {
                 metadata: Default::default(),
             })
             .await;
-        self.resources
-            .write()
-            .await
-            .entry(resource.resource.resource_id.clone())
-            .or_default()
-            .push(resource.clone());
+        {
+            let mut resources = self.resources.write().await;
+            let instances = resources
+                .entry(resource.resource.resource_id.clone())
+                .or_default();
+            // 同一 RM 重连时替换旧连接
+            instances.retain(|rs| rs.resource.client_id != resource.resource.client_id);
+            instances.push(resource.clone());
+        }
+
+        // 重新下发该 RM 离线期间暂存的二阶段指令
+        let core = self.core.clone();
+        let resource_id = resource.resource.resource_id.clone();
+        let client_id = resource.resource.client_id;
+        tokio::spawn(async move { core.redeliver_queued(&resource_id, client_id).await });
     }
 
-    async fn unregister_resource(&mut self, resource: &Self::Resource) {}
+    async fn unregister_resource(&self, resource: &Self::Resource) {
+        tracing::info!("Unregistering resource {:?}", resource.resource);
+        {
+            let mut resources = self.resources.write().await;
+            let Some(instances) = resources.get_mut(&resource.resource.resource_id) else {
+                return;
+            };
+            instances.retain(|rs| rs.connection_id != resource.connection_id);
+            if instances.is_empty() {
+                resources.remove(&resource.resource.resource_id);
+            }
+        }
+        self.event_publisher
+            .publish(TransactionEvent {
+                event_id: Uuid::new_v4().to_string(),
+                timestamp: Default::default(),
+                event_type: TransactionEventType::ResourceUnregistered {
+                    resource_id: resource.resource.resource_id.clone(),
+                },
+                xid: Xid(String::new()),
+                application_id: "".to_string(),
+                transaction_name: "".to_string(),
+                metadata: Default::default(),
+            })
+            .await;
+    }
 }
@@ -41,9 +41,18 @@ impl TransactionManager for DefaultCoordinator {
     async fn global_report(
         &self,
         xid: &Xid,
+        application_id: String,
+        transaction_service_group: String,
         global_status: GlobalStatus,
     ) -> anyhow::Result<GlobalStatus> {
         tracing::info!("Global report {xid}");
-        self.core.global_report(xid, global_status).await
+        self.core
+            .global_report(
+                xid,
+                application_id,
+                transaction_service_group,
+                global_status,
+            )
+            .await
     }
 }
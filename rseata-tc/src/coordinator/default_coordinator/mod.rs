@@ -4,6 +4,8 @@ mod impl_transaction_manager;
 
 use crate::coordinator::default_core_holder::DefaultCoreHolder;
 use crate::resource::TCResource;
+use crate::store::raft::node::RaftNode;
+use crate::store::{DefaultLocker, DefaultTransactionStoreManager};
 use rseata_core::event::defaults::event_publisher::DefaultEventPublisher;
 use rseata_core::types::ResourceId;
 use std::collections::HashMap;
@@ -17,10 +19,21 @@ pub struct DefaultCoordinator {
     pub event_publisher: Arc<DefaultEventPublisher>,
 }
 impl DefaultCoordinator {
-    pub fn new(event_publisher: Arc<DefaultEventPublisher>) -> Self {
+    pub fn new(
+        event_publisher: Arc<DefaultEventPublisher>,
+        store: DefaultTransactionStoreManager,
+        locker: Arc<DefaultLocker>,
+        cluster: Option<Arc<RaftNode>>,
+    ) -> Self {
         let resources: Arc<RwLock<HashMap<ResourceId, Vec<TCResource>>>> =
             Arc::new(Default::default());
-        let core = DefaultCoreHolder::new_arc(resources.clone(), event_publisher.clone());
+        let core = DefaultCoreHolder::new_arc(
+            resources.clone(),
+            event_publisher.clone(),
+            store,
+            locker,
+            cluster,
+        );
         Self {
             resources,
             core,
@@ -21,8 +21,10 @@ impl CoreHolder for DefaultCoreHolder {
         + Sync,
     > {
         match branch_type {
-            _ => {}
+            BranchType::XA => self.xa_core.clone(),
+            BranchType::TCC => self.tcc_core.clone(),
+            BranchType::SAGA => self.saga_core.clone(),
+            _ => self.at_core.clone(),
         }
-        self.at_core.clone()
     }
 }
@@ -1,7 +1,7 @@
 use crate::coordinator::default_core_holder::DefaultCoreHolder;
+use crate::coordinator::default_core_holder::phase_two::{commit_status, rollback_status};
 use async_trait::async_trait;
-use rseata_core::branch::BranchStatus;
-use rseata_core::coordinator::core_holder::CoreHolder;
+use rseata_core::branch::{BranchStatus, BranchType};
 use rseata_core::event::event::TransactionEvent;
 use rseata_core::event::event_publisher::EventPublisher;
 use rseata_core::event::event_type::TransactionEventType;
@@ -9,7 +9,7 @@ use rseata_core::session::defaults::default_global_session::DefaultGlobalSession
 use rseata_core::session::session_manager::SessionManager;
 use rseata_core::transaction::transaction_manager::TransactionManager;
 use rseata_core::types::{GlobalStatus, Xid};
-use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 #[async_trait]
@@ -31,12 +31,14 @@ impl TransactionManager for DefaultCoreHolder {
             transaction_service_group,
             transaction_name,
             timeout_millis,
-            begin_time_millis: 0,
+            begin_time_millis: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
             application_data: None,
             lazy_load_branch: false,
-            active: false,
+            active: true,
             branch_sessions: Default::default(),
-            lifecycle_listeners: Arc::new(Default::default()),
         };
 
         self.event_publisher
@@ -64,128 +66,480 @@ impl TransactionManager for DefaultCoreHolder {
 
     async fn commit(&self, xid: Xid) -> anyhow::Result<GlobalStatus> {
         tracing::info!("Commit : {xid}");
-        let session = self
+        let Some(session) = self
             .session_manager
             .find_global_session_with_branches(&xid, true)
             .await
-            .ok_or_else(|| tonic::Status::cancelled(format!("no such global session {}", xid)))?;
+        else {
+            return self.finished_status(&xid);
+        };
+
+        // 已超时的全局事务不再提交，直接超时回滚
+        if session.status == GlobalStatus::Begin && session.is_timeout() {
+            return self.timeout_rollback(&session).await;
+        }
+        if session.status != GlobalStatus::Begin {
+            return Ok(session.status);
+        }
 
         let can_commit = session
             .branch_sessions
             .iter()
             .all(|b| b.status == BranchStatus::PhaseOneDone);
 
-        let can_rollback = session.branch_sessions.iter().all(|b| {
+        // 任一分支一阶段失败，整体回滚；XA 分支的失败视为 prepared 已回滚
+        let can_rollback = session.branch_sessions.iter().any(|b| {
             b.status == BranchStatus::PhaseOneFailed || b.status == BranchStatus::PhaseOneTimeout
         });
 
-        let mut global_status = self
-            .session_manager
-            .update_global_session_status(&session, GlobalStatus::Committing)
-            .await?;
+        if can_rollback {
+            return self.rollback(xid).await;
+        }
+        if !can_commit {
+            // 仍有分支未上报一阶段结果，保持 Begin，由 TM 重试或超时回滚
+            tracing::warn!("Commit with unfinished branches : {xid}");
+            return Ok(session.status);
+        }
 
-        if can_commit {
-            let commit_futures: Vec<_> = session
-                .branch_sessions
-                .iter()
-                .map(|branch_session| {
-                    let core = self.get_core(branch_session.branch_type);
-                    let session_ref = &session;
-                    async move { core.branch_commit(session_ref, branch_session).await }
-                })
-                .collect();
-            use futures::future::try_join_all;
-            match try_join_all(commit_futures).await {
-                Ok(_) => {
-                    // 所有分支提交成功
-                    global_status = self
-                        .session_manager
-                        .update_global_session_status(&session, GlobalStatus::Committed)
-                        .await?;
-                }
-                Err(_) => {
-                    // 有分支提交失败，开始回滚
-                    global_status = self
-                        .session_manager
-                        .update_global_session_status(&session, GlobalStatus::Rollbacking)
-                        .await?;
-                    self.rollback(xid).await?;
-                }
-            }
-        } else if can_rollback {
-            // 直接回滚
-            global_status = self
-                .session_manager
-                .update_global_session_status(&session, GlobalStatus::Rollbacking)
-                .await?;
-            self.rollback(xid).await?;
+        // 唯一的 XA 分支走 ONE PHASE 提交
+        let one_phase = session.branch_sessions.len() == 1
+            && session.branch_sessions[0].branch_type == BranchType::XA;
+
+        // 多个 AT 分支时先落盘 AsyncCommitting 再返回，二阶段由后台任务完成；没有分支或只有一个 AT 分支时无需通知 RM，直接提交
+        if !one_phase && session.branch_sessions.len() > 1 && session.can_be_committed_async() {
+            return Ok(self
+                .claim_global_status(&session, GlobalStatus::AsyncCommitting)
+                .await?
+                .unwrap_or(GlobalStatus::AsyncCommitting));
+        }
+
+        // 多分支时先确认所有分支已就绪，此时还没有分支提交，失败时整体回滚
+        if !one_phase && !self.prepare_branches(&session).await {
+            tracing::warn!("Prepare branches failed, rollback : {xid}");
+            return self.rollback(xid).await;
         }
 
+        if let Some(current) = self
+            .claim_global_status(&session, GlobalStatus::Committing)
+            .await?
+        {
+            return Ok(current);
+        }
+
+        // 部分分支可能已提交，失败时不能再回滚，交给重试任务继续提交
+        let results = self.commit_branches(&session).await;
+        let global_status = self
+            .change_global_status(&session, commit_status(&results))
+            .await?;
+
         Ok(global_status)
     }
 
     async fn rollback(&self, xid: Xid) -> anyhow::Result<GlobalStatus> {
         tracing::info!("Rollback : {xid}");
-        let session = self
+        let Some(session) = self
             .session_manager
             .find_global_session_with_branches(&xid, true)
             .await
-            .ok_or_else(|| tonic::Status::cancelled(format!("no such global session {}", xid)))?;
-
-        let mut global_status = self
-            .session_manager
-            .update_global_session_status(&session, GlobalStatus::Rollbacking)
-            .await?;
+        else {
+            return self.finished_status(&xid);
+        };
 
-        let rollback_futures: Vec<_> = session
-            .branch_sessions
-            .iter()
-            .map(|branch_session| {
-                let core = self.get_core(branch_session.branch_type);
-                let session_ref = &session;
-                async move {
-                    core.branch_rollback(session_ref, branch_session)
-                        .await
-                        .map_err(|e| {
-                            tracing::error!("Branch rollback failed: {:?}", e);
-                            e
-                        })
-                }
-            })
-            .collect();
-        use futures::future::join_all;
-        let results = join_all(rollback_futures).await;
-        if results.iter().any(|r| r.is_err()) {
-            global_status = self
-                .session_manager
-                .update_global_session_status(&session, GlobalStatus::RollbackFailed)
-                .await?;
+        // 已结束或已被提交、超时回滚抢先的全局事务直接返回当前状态
+        if session.status != GlobalStatus::Begin {
+            return Ok(session.status);
+        }
+        if let Some(current) = self
+            .claim_global_status(&session, GlobalStatus::Rollbacking)
+            .await?
+        {
+            return Ok(current);
         }
 
+        let results = self.rollback_branches(&session).await;
+        let global_status = self
+            .change_global_status(&session, rollback_status(&results, false))
+            .await?;
+
         Ok(global_status)
     }
 
     async fn get_status(&self, xid: Xid) -> anyhow::Result<GlobalStatus> {
         tracing::info!("Get status : {xid}");
-        let session = self
+        let Some(session) = self
             .session_manager
             .find_global_session_with_branches(&xid, true)
             .await
-            .ok_or_else(|| tonic::Status::cancelled(format!("no such global session {}", xid)))?;
+        else {
+            return self.finished_status(&xid);
+        };
         Ok(session.status)
     }
 
     async fn global_report(
         &self,
         xid: &Xid,
+        application_id: String,
+        transaction_service_group: String,
         global_status: GlobalStatus,
     ) -> anyhow::Result<GlobalStatus> {
         tracing::info!("Global report : {xid}, {global_status:?}");
         let session = self
             .session_manager
-            .find_global_session_with_branches(&xid, true)
+            .find_global_session_with_branches(xid, true)
             .await
             .ok_or_else(|| tonic::Status::cancelled(format!("no such global session {}", xid)))?;
-        Ok(session.status)
+
+        // 只有开启该事务的应用可以上报，且只接受 Saga 事务，其他模式的二阶段由 TC 驱动
+        if session.application_id != application_id
+            || session.transaction_service_group != transaction_service_group
+        {
+            return Err(tonic::Status::permission_denied(format!(
+                "global session {} is not owned by {}/{}",
+                xid, application_id, transaction_service_group
+            ))
+            .into());
+        }
+        if !session.is_saga() {
+            return Err(tonic::Status::failed_precondition(format!(
+                "global session {} is not a saga transaction",
+                xid
+            ))
+            .into());
+        }
+
+        // Saga 由 RM 侧的状态机驱动，TC 只记录其上报的结果；首次上报时先抢占对应的二阶段状态
+        if session.status == GlobalStatus::Begin {
+            let phase_two = match global_status {
+                GlobalStatus::Committed
+                | GlobalStatus::CommitRetrying
+                | GlobalStatus::CommitFailed => GlobalStatus::Committing,
+                GlobalStatus::TimeoutRollbacked
+                | GlobalStatus::TimeoutRollbackRetrying
+                | GlobalStatus::TimeoutRollbackFailed => GlobalStatus::TimeoutRollbacking,
+                _ => GlobalStatus::Rollbacking,
+            };
+            if let Some(current) = self.claim_global_status(&session, phase_two).await? {
+                return Ok(current);
+            }
+        }
+        let global_status = self.change_global_status(&session, global_status).await?;
+
+        Ok(global_status)
+    }
+}
+
+impl DefaultCoreHolder {
+    // 会话已不在存储中：已结束的返回其最终状态，从未存在或记录已被淘汰的报错
+    fn finished_status(&self, xid: &Xid) -> anyhow::Result<GlobalStatus> {
+        self.session_manager.finished_status(xid).ok_or_else(|| {
+            tonic::Status::not_found(format!("no such global session {}", xid)).into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coordinator::default_core_holder::retry_worker::{RetryConfig, RetryKind};
+    use crate::coordinator::default_core_holder::tests::{
+        RESOURCE, StatusRecorder, connect_rm, memory_holder, memory_holder_with_resources, reload,
+        session_with,
+    };
+    use crate::store::raft::node::tests::wait_until;
+    use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
+    use rseata_core::branch::{BranchStatus, BranchType, TM_DRIVEN_RESOURCE_PREFIX, XA_IDLE};
+    use rseata_core::session::session_manager::SessionManager;
+    use rseata_core::transaction::transaction_manager::TransactionManager;
+    use rseata_core::types::{ClientId, GlobalStatus};
+    use rseata_proto::rseata_proto::proto::resource_instruction::Instruction;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const AT_BRANCHES: [(BranchType, &str); 2] = [(BranchType::AT, ""), (BranchType::AT, "")];
+
+    fn retry_config() -> RetryConfig {
+        RetryConfig {
+            interval: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+            max_commit_retry_timeout: None,
+            max_rollback_retry_timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn finished_xids_keep_their_final_status() {
+        let holder = memory_holder();
+        let xid = holder
+            .begin("app".into(), "group".into(), "tx".into(), 60_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            holder.commit(xid.clone()).await.unwrap(),
+            GlobalStatus::Committed
+        );
+        assert!(
+            holder
+                .session_manager
+                .find_global_session(&xid)
+                .await
+                .is_none()
+        );
+
+        // 已结束的事务返回最终状态，不会变成 Finished 或被再次回滚
+        assert_eq!(
+            holder.commit(xid.clone()).await.unwrap(),
+            GlobalStatus::Committed
+        );
+        assert_eq!(
+            holder.rollback(xid.clone()).await.unwrap(),
+            GlobalStatus::Committed
+        );
+        assert_eq!(
+            holder.get_status(xid).await.unwrap(),
+            GlobalStatus::Committed
+        );
+
+        let unknown = holder.get_status("unknown".into()).await.unwrap_err();
+        assert_eq!(
+            unknown.downcast::<tonic::Status>().unwrap().code(),
+            tonic::Code::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn global_report_requires_saga_owner() {
+        let holder = memory_holder();
+        let xid = holder
+            .begin("app".into(), "group".into(), "saga".into(), 60_000)
+            .await
+            .unwrap();
+        holder
+            .branch_register(
+                BranchType::SAGA,
+                "saga:order".into(),
+                ClientId::from(1),
+                xid.clone(),
+                String::new(),
+                String::new(),
+            )
+            .await
+            .unwrap();
+
+        let report = |application_id: &str| {
+            holder.global_report(
+                &xid,
+                application_id.to_string(),
+                "group".to_string(),
+                GlobalStatus::Rollbacked,
+            )
+        };
+        assert!(report("other").await.is_err());
+        assert_eq!(report("app").await.unwrap(), GlobalStatus::Rollbacked);
+    }
+
+    #[tokio::test]
+    async fn global_report_rejects_tc_driven_transactions() {
+        let holder = memory_holder();
+        let xid = holder
+            .begin("app".into(), "group".into(), "at".into(), 60_000)
+            .await
+            .unwrap();
+        holder
+            .branch_register(
+                BranchType::AT,
+                "jdbc:order".into(),
+                ClientId::from(1),
+                xid.clone(),
+                String::new(),
+                String::new(),
+            )
+            .await
+            .unwrap();
+
+        assert!(
+            holder
+                .global_report(
+                    &xid,
+                    "app".to_string(),
+                    "group".to_string(),
+                    GlobalStatus::Committed,
+                )
+                .await
+                .is_err()
+        );
+        assert_eq!(holder.get_status(xid).await.unwrap(), GlobalStatus::Begin);
+    }
+
+    #[tokio::test]
+    async fn tm_driven_branches_wait_for_global_report() {
+        let holder = memory_holder();
+        let xid = holder
+            .begin("app".into(), "group".into(), "saga".into(), 60_000)
+            .await
+            .unwrap();
+        holder
+            .branch_register(
+                BranchType::SAGA,
+                format!("{TM_DRIVEN_RESOURCE_PREFIX}order").into(),
+                ClientId::from(0),
+                xid.clone(),
+                String::new(),
+                String::new(),
+            )
+            .await
+            .unwrap();
+
+        // TC 不向 TM 驱动的分支下发指令，也不暂存指令
+        assert_eq!(
+            holder.rollback(xid.clone()).await.unwrap(),
+            GlobalStatus::RollbackRetrying
+        );
+        let session = holder
+            .session_manager
+            .find_global_session_with_branches(&xid, true)
+            .await
+            .unwrap();
+        assert!(session.branch_sessions[0].queued_instruction.is_none());
+
+        assert_eq!(
+            holder
+                .global_report(
+                    &xid,
+                    "app".to_string(),
+                    "group".to_string(),
+                    GlobalStatus::Rollbacked,
+                )
+                .await
+                .unwrap(),
+            GlobalStatus::Rollbacked
+        );
+    }
+
+    #[tokio::test]
+    async fn at_commit_returns_once_async_committing_is_persisted() {
+        let (holder, resources) = memory_holder_with_resources();
+        let rm = connect_rm(&resources, RESOURCE, 1, |_| BranchStatus::PhaseTwoCommitted).await;
+        let (xid, _) = session_with(&holder, &AT_BRANCHES).await;
+
+        assert_eq!(
+            holder.commit(xid.clone()).await.unwrap(),
+            GlobalStatus::AsyncCommitting
+        );
+        // 返回时二阶段尚未开始
+        assert_eq!(
+            reload(&holder, &xid).await.status,
+            GlobalStatus::AsyncCommitting
+        );
+        assert!(rm.received().is_empty());
+
+        holder.start_retry_workers(retry_config());
+        wait_until(|| async { holder.session_manager.finished_status(&xid) }).await;
+        assert_eq!(
+            holder.session_manager.finished_status(&xid),
+            Some(GlobalStatus::Committed)
+        );
+        assert!(matches!(
+            rm.received()[..],
+            [Instruction::Commit(_), Instruction::Commit(_)]
+        ));
+    }
+
+    #[tokio::test]
+    async fn idle_xa_branches_are_prepared_before_any_commit() {
+        let (holder, resources) = memory_holder_with_resources();
+        let rm = connect_rm(&resources, RESOURCE, 1, |instruction| match instruction {
+            Instruction::Prepare(_) => BranchStatus::PhaseOneDone,
+            _ => BranchStatus::PhaseTwoCommitted,
+        })
+        .await;
+        let (xid, _) = session_with(
+            &holder,
+            &[(BranchType::XA, XA_IDLE), (BranchType::XA, XA_IDLE)],
+        )
+        .await;
+
+        assert_eq!(
+            holder.commit(xid.clone()).await.unwrap(),
+            GlobalStatus::Committed
+        );
+        assert!(matches!(
+            rm.received()[..],
+            [
+                Instruction::Prepare(_),
+                Instruction::Prepare(_),
+                Instruction::Commit(_),
+                Instruction::Commit(_)
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn failed_xa_prepare_rolls_back() {
+        let (holder, resources) = memory_holder_with_resources();
+        let rm = connect_rm(&resources, RESOURCE, 1, |instruction| match instruction {
+            Instruction::Prepare(_) => BranchStatus::PhaseOneFailed,
+            _ => BranchStatus::PhaseTwoRollbacked,
+        })
+        .await;
+        let (xid, _) = session_with(
+            &holder,
+            &[(BranchType::XA, XA_IDLE), (BranchType::XA, XA_IDLE)],
+        )
+        .await;
+
+        assert_eq!(
+            holder.commit(xid.clone()).await.unwrap(),
+            GlobalStatus::Rollbacked
+        );
+        assert!(
+            !rm.received()
+                .iter()
+                .any(|instruction| matches!(instruction, Instruction::Commit(_)))
+        );
+    }
+
+    #[tokio::test]
+    async fn retryable_async_commit_failure_stays_async_committing() {
+        let (holder, resources) = memory_holder_with_resources();
+        let recorder = StatusRecorder::attach(&holder);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let _rm = connect_rm(&resources, RESOURCE, 1, move |_| {
+            // 前两轮提交都失败
+            if counter.fetch_add(1, Ordering::SeqCst) < 4 {
+                BranchStatus::PhaseTwoCommitFailedRetryable
+            } else {
+                BranchStatus::PhaseTwoCommitted
+            }
+        })
+        .await;
+        let (xid, _) = session_with(&holder, &AT_BRANCHES).await;
+        holder.commit(xid.clone()).await.unwrap();
+
+        let session = reload(&holder, &xid).await;
+        let retried = holder
+            .retry_session(RetryKind::AsyncCommit, &session, None)
+            .await
+            .unwrap();
+        assert_eq!(retried, Some(GlobalStatus::AsyncCommitting));
+        assert_eq!(
+            reload(&holder, &xid).await.status,
+            GlobalStatus::AsyncCommitting
+        );
+
+        holder.start_retry_workers(retry_config());
+        wait_until(|| async { holder.session_manager.finished_status(&xid) }).await;
+        assert_eq!(
+            holder.session_manager.finished_status(&xid),
+            Some(GlobalStatus::Committed)
+        );
+        assert!(calls.load(Ordering::SeqCst) >= 6);
+        assert!(
+            recorder
+                .statuses()
+                .ends_with(&[GlobalStatus::AsyncCommitting, GlobalStatus::Committed])
+        );
+        assert!(!recorder.statuses().contains(&GlobalStatus::CommitRetrying));
     }
 }
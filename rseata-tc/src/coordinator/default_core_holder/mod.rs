@@ -1,25 +1,35 @@
 pub mod impl_branch_manager_outbound;
 pub mod impl_core_holder;
 pub mod impl_transaction_manager;
+pub mod phase_two;
+pub mod phase_two_policy;
+pub mod recovery;
+pub mod retry_worker;
+pub mod session_retention;
+pub mod timeout_check;
 
-use crate::coordinator::core::at_core::ATCore;
+use crate::config;
+use crate::coordinator::core::branch_core::BranchCore;
+use crate::coordinator::core::branch_core::branch_mode::{AtMode, SagaMode, TccMode, XaMode};
+use crate::coordinator::default_core_holder::phase_two_policy::PhaseTwoPolicies;
+use crate::coordinator::default_core_holder::retry_worker::RetryingSessions;
 use crate::resource::TCResource;
+use crate::resource::outbound_queue::OutboundQueue;
+use crate::store::raft::node::RaftNode;
+use crate::store::{DefaultLocker, DefaultTransactionStoreManager};
+use rseata_core::coordinator::core_holder::CoreHolder;
+use rseata_core::coordinator::core_service::CoreService;
+use rseata_core::coordinator::{AbstractCore, Core};
 use rseata_core::event::defaults::event_publisher::DefaultEventPublisher;
 use rseata_core::lock::defaults::default_lock_manager::DefaultLockManager;
-use rseata_core::lock::defaults::default_locker::MemoryLocker;
 use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
-use rseata_core::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
 use rseata_core::types::ResourceId;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use rseata_core::coordinator::{AbstractCore, Core};
-use rseata_core::coordinator::core_holder::CoreHolder;
-use rseata_core::coordinator::core_service::CoreService;
 
 pub struct DefaultCoreHolder {
     pub(crate) session_manager: Arc<DefaultSessionManager>,
-    pub(crate) resources: Arc<RwLock<HashMap<ResourceId, Vec<TCResource>>>>,
     pub(crate) at_core: Arc<
         dyn AbstractCore<
                 BranchSession = <Self as CoreHolder>::BranchSession,
@@ -27,32 +37,297 @@ pub struct DefaultCoreHolder {
             > + Send
             + Sync,
     >,
+    pub(crate) xa_core: Arc<
+        dyn AbstractCore<
+                BranchSession = <Self as CoreHolder>::BranchSession,
+                GlobalSession = <Self as CoreHolder>::GlobalSession,
+            > + Send
+            + Sync,
+    >,
+    pub(crate) tcc_core: Arc<
+        dyn AbstractCore<
+                BranchSession = <Self as CoreHolder>::BranchSession,
+                GlobalSession = <Self as CoreHolder>::GlobalSession,
+            > + Send
+            + Sync,
+    >,
+    pub(crate) saga_core: Arc<
+        dyn AbstractCore<
+                BranchSession = <Self as CoreHolder>::BranchSession,
+                GlobalSession = <Self as CoreHolder>::GlobalSession,
+            > + Send
+            + Sync,
+    >,
+    // 与 AT 模式的 BranchCore 共享，全局事务结束时统一释放行锁
+    pub(crate) lock_manager: Arc<DefaultLockManager<DefaultLocker>>,
+    pub(crate) outbound: Arc<OutboundQueue>,
     pub(crate) event_publisher: Arc<DefaultEventPublisher>,
+    pub(crate) phase_two_policies: PhaseTwoPolicies,
+    pub(crate) retrying: RetryingSessions,
+    // raft 集群模式下的本地节点，只有 leader 执行超时检测和二阶段重试
+    pub(crate) cluster: Option<Arc<RaftNode>>,
 }
 impl DefaultCoreHolder {
     pub(crate) fn new_arc(
         resources: Arc<RwLock<HashMap<ResourceId, Vec<TCResource>>>>,
         event_publisher: Arc<DefaultEventPublisher>,
+        store: DefaultTransactionStoreManager,
+        locker: Arc<DefaultLocker>,
+        cluster: Option<Arc<RaftNode>>,
     ) -> Arc<Self> {
-        let session_manager = Arc::new(DefaultSessionManager::new(
-            String::from("DefaultSessionManager"),
-            Box::new(MemeryTransactionStoreManager::default()),
-        ));
+        let session_manager = Arc::new(
+            DefaultSessionManager::new(String::from("DefaultSessionManager"), store)
+                .with_event_publisher(event_publisher.clone())
+                .with_finished_session_capacity(config::get_env_finished_session_capacity()),
+        );
+        let lock_manager = Arc::new(DefaultLockManager::new(locker));
+        let outbound = Arc::new(OutboundQueue::new(resources, session_manager.clone()));
 
         Arc::new(Self {
             session_manager: session_manager.clone(),
-            resources: resources.clone(),
-            at_core: Arc::new(ATCore {
-                session_manager: session_manager.clone(),
-                lock_manager: Arc::new(DefaultLockManager::new(Arc::new(MemoryLocker::default()))),
-                resources,
-                event_publisher: event_publisher.clone(),
-            }),
+            at_core: Arc::new(BranchCore::new(
+                AtMode,
+                session_manager.clone(),
+                lock_manager.clone(),
+                outbound.clone(),
+            )),
+            xa_core: Arc::new(BranchCore::new(
+                XaMode,
+                session_manager.clone(),
+                lock_manager.clone(),
+                outbound.clone(),
+            )),
+            tcc_core: Arc::new(BranchCore::new(
+                TccMode,
+                session_manager.clone(),
+                lock_manager.clone(),
+                outbound.clone(),
+            )),
+            saga_core: Arc::new(BranchCore::new(
+                SagaMode,
+                session_manager.clone(),
+                lock_manager.clone(),
+                outbound.clone(),
+            )),
+            lock_manager,
+            outbound,
             event_publisher,
+            phase_two_policies: PhaseTwoPolicies::from_env(),
+            retrying: RetryingSessions::default(),
+            cluster,
         })
     }
+
+    /// 非集群模式下始终为 true
+    pub(crate) fn is_leader(&self) -> bool {
+        self.cluster
+            .as_ref()
+            .is_none_or(|cluster| cluster.is_leader())
+    }
 }
 
 impl Core for DefaultCoreHolder {}
 
 impl CoreService for DefaultCoreHolder {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::resource::pending_instructions::PendingInstructions;
+    use async_trait::async_trait;
+    use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
+    use rseata_core::branch::{BranchStatus, BranchType};
+    use rseata_core::error::TransactionError;
+    use rseata_core::event::defaults::default_event_handler_chain::DefaultEventHandlerChain;
+    use rseata_core::lock::defaults::default_locker::MemoryLocker;
+    use rseata_core::resource::DefaultResource;
+    use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+    use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+    use rseata_core::session::defaults::default_session_manager::DefaultSessionLifecycleListener;
+    use rseata_core::session::session_lifecycle_listener::SessionLifecycleListener;
+    use rseata_core::session::session_manager::SessionManager;
+    use rseata_core::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+    use rseata_core::transaction::transaction_manager::TransactionManager;
+    use rseata_core::types::{ClientId, GlobalStatus, Xid};
+    use rseata_proto::rseata_proto::proto::resource_instruction::Instruction;
+    use tokio::sync::mpsc;
+
+    pub(crate) const RESOURCE: &str = "jdbc:mysql://order";
+
+    pub(crate) type Resources = Arc<RwLock<HashMap<ResourceId, Vec<TCResource>>>>;
+
+    /// 使用内存存储、没有 RM 连接的 TC
+    pub(crate) fn memory_holder() -> Arc<DefaultCoreHolder> {
+        memory_holder_with_resources().0
+    }
+
+    /// 同 `memory_holder`，同时返回在线 RM 表，供测试接入模拟 RM
+    pub(crate) fn memory_holder_with_resources() -> (Arc<DefaultCoreHolder>, Resources) {
+        memory_holder_in_cluster(None)
+    }
+
+    /// 同 `memory_holder_with_resources`，挂在给定的 raft 节点上，由该节点决定是否为 leader
+    pub(crate) fn memory_holder_in_cluster(
+        cluster: Option<Arc<RaftNode>>,
+    ) -> (Arc<DefaultCoreHolder>, Resources) {
+        let resources = Resources::default();
+        let holder = DefaultCoreHolder::new_arc(
+            resources.clone(),
+            Arc::new(DefaultEventPublisher::new(Arc::new(
+                DefaultEventHandlerChain::default(),
+            ))),
+            Box::new(MemeryTransactionStoreManager::default()),
+            Arc::new(MemoryLocker::default()),
+            cluster,
+        );
+        (holder, resources)
+    }
+
+    /// 在线的模拟 RM，按 reply 回传每条指令的执行结果
+    pub(crate) struct FakeRm {
+        pub(crate) received: Arc<std::sync::Mutex<Vec<Instruction>>>,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    impl FakeRm {
+        pub(crate) fn received(&self) -> Vec<Instruction> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for FakeRm {
+        // 断开连接，TC 之后向它发送指令会失败
+        fn drop(&mut self) {
+            self.task.abort();
+        }
+    }
+
+    pub(crate) async fn connect_rm(
+        resources: &Resources,
+        resource_id: &str,
+        client_id: u64,
+        reply: impl Fn(&Instruction) -> BranchStatus + Send + 'static,
+    ) -> FakeRm {
+        let (response_tx, mut response_rx) = mpsc::channel(16);
+        let pending = Arc::new(PendingInstructions::default());
+        let resource = TCResource {
+            connection_id: client_id.into(),
+            resource: DefaultResource {
+                group_id: String::from("group"),
+                resource_id: ResourceId::from(resource_id),
+                branch_type: BranchType::AT,
+                client_id: client_id.into(),
+            },
+            response_tx,
+            pending: pending.clone(),
+        };
+        resources
+            .write()
+            .await
+            .entry(ResourceId::from(resource_id))
+            .or_default()
+            .push(resource);
+
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = received.clone();
+        let task = tokio::spawn(async move {
+            while let Some(Ok(message)) = response_rx.recv().await {
+                let Some(instruction) = message.instruction else {
+                    continue;
+                };
+                let status = reply(&instruction);
+                log.lock().unwrap().push(instruction);
+                pending.complete(message.instruction_id, status).await;
+            }
+        });
+        FakeRm { received, task }
+    }
+
+    /// 开启全局事务并注册一阶段已完成的分支，返回带分支的会话
+    pub(crate) async fn session_with(
+        holder: &DefaultCoreHolder,
+        branches: &[(BranchType, &str)],
+    ) -> (Xid, DefaultGlobalSession) {
+        session_with_timeout(holder, 60_000, branches).await
+    }
+
+    pub(crate) async fn session_with_timeout(
+        holder: &DefaultCoreHolder,
+        timeout_millis: u64,
+        branches: &[(BranchType, &str)],
+    ) -> (Xid, DefaultGlobalSession) {
+        let xid = holder
+            .begin("app".into(), "group".into(), "tx".into(), timeout_millis)
+            .await
+            .unwrap();
+        for (i, (branch_type, application_data)) in branches.iter().enumerate() {
+            let branch_id = holder
+                .branch_register(
+                    *branch_type,
+                    ResourceId::from(RESOURCE),
+                    ClientId::from(1),
+                    xid.clone(),
+                    application_data.to_string(),
+                    format!("t_order:{i}"),
+                )
+                .await
+                .unwrap();
+            holder
+                .branch_report(
+                    *branch_type,
+                    xid.clone(),
+                    branch_id,
+                    BranchStatus::PhaseOneDone,
+                    application_data.to_string(),
+                )
+                .await
+                .unwrap();
+        }
+        let session = reload(holder, &xid).await;
+        (xid, session)
+    }
+
+    pub(crate) async fn reload(holder: &DefaultCoreHolder, xid: &Xid) -> DefaultGlobalSession {
+        holder
+            .session_manager
+            .find_global_session_with_branches(xid, true)
+            .await
+            .unwrap()
+    }
+
+    /// 按顺序记录全局事务经历的状态
+    #[derive(Default)]
+    pub(crate) struct StatusRecorder {
+        statuses: std::sync::Mutex<Vec<GlobalStatus>>,
+    }
+
+    impl StatusRecorder {
+        pub(crate) fn attach(holder: &DefaultCoreHolder) -> Arc<Self> {
+            let recorder = Arc::new(Self::default());
+            let listener: Arc<DefaultSessionLifecycleListener> = recorder.clone();
+            holder
+                .session_manager
+                .add_session_lifecycle_listener(listener);
+            recorder
+        }
+
+        pub(crate) fn statuses(&self) -> Vec<GlobalStatus> {
+            self.statuses.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl SessionLifecycleListener for StatusRecorder {
+        type GlobalSession = DefaultGlobalSession;
+        type BranchSession = DefaultBranchSession;
+
+        async fn on_status_change(
+            &self,
+            _: &DefaultGlobalSession,
+            status: GlobalStatus,
+        ) -> Result<(), TransactionError> {
+            self.statuses.lock().unwrap().push(status);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use crate::coordinator::default_core_holder::DefaultCoreHolder;
+use crate::coordinator::default_core_holder::phase_two_policy::PhaseTwoMode;
+use futures::future::join_all;
+use futures::{StreamExt, stream};
+use rseata_core::branch::{BranchStatus, BranchType};
+use rseata_core::coordinator::core_holder::CoreHolder;
+use rseata_core::error::TransactionError;
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::lock_manager::LockManager;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::session_manager::SessionManager;
+use rseata_core::types::{GlobalStatus, ResourceId};
+use std::collections::HashMap;
+
+impl DefaultCoreHolder {
+    /// 提交前并发确认各分支已就绪，全部就绪时返回 true
+    pub(crate) async fn prepare_branches(&self, session: &DefaultGlobalSession) -> bool {
+        let results = join_all(session.branch_sessions.iter().map(|branch_session| {
+            let core = self.get_core(branch_session.branch_type);
+            async move { core.branch_prepare(session, branch_session).await }
+        }))
+        .await;
+        all_prepared(&results)
+    }
+
+    /// 按事务分组的策略提交尚未提交成功的分支，并记录每个分支的二阶段状态
+    pub(crate) async fn commit_branches(
+        &self,
+        session: &DefaultGlobalSession,
+    ) -> Vec<Result<BranchStatus, TransactionError>> {
+        let policy = self
+            .phase_two_policies
+            .for_group(&session.transaction_service_group);
+        let branches: Vec<_> = session
+            .sorted_branches()
+            .await
+            .into_iter()
+            .filter(|b| b.status != BranchStatus::PhaseTwoCommitted)
+            .collect();
+        let results = self
+            .execute_phase_two(
+                &branches,
+                policy.mode,
+                BranchStatus::PhaseTwoCommitted,
+                |branch_session| {
+                    let core = self.get_core(branch_session.branch_type);
+                    async move {
+                        if let Some(status) = settled_locally(session, branch_session, true) {
+                            return Ok(status);
+                        }
+                        core.branch_commit(session, branch_session)
+                            .await
+                            .map_err(|e| {
+                                tracing::error!("Branch commit failed: {:?}", e);
+                                e
+                            })
+                    }
+                },
+            )
+            .await;
+        self.record_branch_status(session, &branches, &results)
+            .await;
+        results
+    }
+
+    /// 按事务分组的策略回滚尚未回滚成功的分支，有序的模式默认按注册顺序的逆序下发
+    pub(crate) async fn rollback_branches(
+        &self,
+        session: &DefaultGlobalSession,
+    ) -> Vec<Result<BranchStatus, TransactionError>> {
+        let policy = self
+            .phase_two_policies
+            .for_group(&session.transaction_service_group);
+        let branches = if policy.reverse_rollback {
+            session.reverse_sorted_branches().await
+        } else {
+            session.sorted_branches().await.into_iter().collect()
+        };
+        let branches: Vec<_> = branches
+            .into_iter()
+            .filter(|b| b.status != BranchStatus::PhaseTwoRollbacked)
+            .collect();
+        let results = self
+            .execute_phase_two(
+                &branches,
+                policy.mode,
+                BranchStatus::PhaseTwoRollbacked,
+                |branch_session| {
+                    let core = self.get_core(branch_session.branch_type);
+                    async move {
+                        if let Some(status) = settled_locally(session, branch_session, false) {
+                            return Ok(status);
+                        }
+                        core.branch_rollback(session, branch_session)
+                            .await
+                            .map_err(|e| {
+                                tracing::error!("Branch rollback failed: {:?}", e);
+                                e
+                            })
+                    }
+                },
+            )
+            .await;
+        self.record_branch_status(session, &branches, &results)
+            .await;
+        results
+    }
+
+    // 每个分支都有一个结果：顺序执行时遇到未达到 success 的分支即停止，其后的分支记为 NotAttempted，交给重试任务
+    async fn execute_phase_two<'a, F, Fut>(
+        &self,
+        branches: &'a [DefaultBranchSession],
+        mode: PhaseTwoMode,
+        success: BranchStatus,
+        phase_two: F,
+    ) -> Vec<Result<BranchStatus, TransactionError>>
+    where
+        F: Fn(&'a DefaultBranchSession) -> Fut,
+        Fut: Future<Output = Result<BranchStatus, TransactionError>>,
+    {
+        match mode {
+            PhaseTwoMode::Sequential => {
+                let mut results = Vec::with_capacity(branches.len());
+                let mut stopped = false;
+                for branch_session in branches {
+                    if stopped {
+                        results.push(Err(TransactionError::NotAttempted {
+                            branch_id: branch_session.branch_id,
+                        }));
+                        continue;
+                    }
+                    let result = phase_two(branch_session).await;
+                    stopped = !matches!(result, Ok(status) if status == success);
+                    results.push(result);
+                }
+                results
+            }
+            PhaseTwoMode::Parallel {
+                max_per_resource: 0,
+            } => join_all(branches.iter().map(phase_two)).await,
+            PhaseTwoMode::Parallel { max_per_resource } => {
+                // 按资源分组，避免大量分支同时压到同一个 RM；组内按给定顺序开始
+                let mut groups: HashMap<Option<&ResourceId>, Vec<usize>> = HashMap::new();
+                for (index, branch_session) in branches.iter().enumerate() {
+                    groups
+                        .entry(branch_session.resource_id.as_ref())
+                        .or_default()
+                        .push(index);
+                }
+                let phase_two = &phase_two;
+                let mut results: Vec<_> = join_all(groups.into_values().map(|indexes| {
+                    stream::iter(indexes)
+                        .map(|index| {
+                            let future = phase_two(&branches[index]);
+                            async move { (index, future.await) }
+                        })
+                        .buffered(max_per_resource)
+                        .collect::<Vec<_>>()
+                }))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+                results.sort_by_key(|(index, _)| *index);
+                results.into_iter().map(|(_, result)| result).collect()
+            }
+        }
+    }
+
+    // 逐个写入，避免并发读改写覆盖彼此的分支状态
+    async fn record_branch_status(
+        &self,
+        session: &DefaultGlobalSession,
+        branches: &[DefaultBranchSession],
+        results: &[Result<BranchStatus, TransactionError>],
+    ) {
+        for (branch_session, result) in branches.iter().zip(results) {
+            if let Ok(status) = result
+                && let Err(e) = self
+                    .session_manager
+                    .update_branch_session_status(session, branch_session, *status)
+                    .await
+            {
+                tracing::error!("Update branch status failed: {:?}", e);
+            }
+        }
+    }
+
+    /// 更新全局状态，并同步行锁：回滚期间标记为 Rollbacking，事务结束时释放
+    pub(crate) async fn change_global_status(
+        &self,
+        session: &DefaultGlobalSession,
+        status: GlobalStatus,
+    ) -> anyhow::Result<GlobalStatus> {
+        let status = self
+            .session_manager
+            .update_global_session_status(session, status)
+            .await?;
+        self.sync_lock_status(session, status).await?;
+        // 终态会话进入收尾：删除成功结束的会话，关闭失败结束的会话
+        if status.is_end() {
+            self.session_manager.end_global_session(session).await?;
+        }
+        Ok(status)
+    }
+
+    /// 从 session.status 抢占迁移到 status，返回 Some(当前状态) 表示已被并发的提交、回滚或超时抢先
+    pub(crate) async fn claim_global_status(
+        &self,
+        session: &DefaultGlobalSession,
+        status: GlobalStatus,
+    ) -> anyhow::Result<Option<GlobalStatus>> {
+        match self
+            .session_manager
+            .compare_and_set_global_status(session, session.status, status)
+            .await
+        {
+            Ok(status) => {
+                self.sync_lock_status(session, status).await?;
+                Ok(None)
+            }
+            Err(TransactionError::IllegalGlobalTransition { from, to, .. }) => {
+                tracing::warn!(
+                    "Lost status transition : {}, {:?} -> {:?}",
+                    session.xid,
+                    from,
+                    to
+                );
+                Ok(Some(from))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn sync_lock_status(
+        &self,
+        session: &DefaultGlobalSession,
+        status: GlobalStatus,
+    ) -> anyhow::Result<()> {
+        match status {
+            GlobalStatus::Rollbacking | GlobalStatus::TimeoutRollbacking => {
+                self.lock_manager
+                    .update_lock_status(&session.xid, LockStatus::Rollbacking)
+                    .await?;
+            }
+            // 一阶段数据已提交或已回滚，不再需要全局隔离；回滚失败时保留行锁防止脏写
+            GlobalStatus::AsyncCommitting
+            | GlobalStatus::Committed
+            | GlobalStatus::CommitFailed
+            | GlobalStatus::CommitRetryTimeout
+            | GlobalStatus::Rollbacked
+            | GlobalStatus::TimeoutRollbacked => {
+                self.lock_manager
+                    .release_global_session_lock(session)
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// 唯一的 AT 分支在一阶段已经提交或回滚了本地事务，二阶段无需再通知 RM
+fn settled_locally(
+    session: &DefaultGlobalSession,
+    branch_session: &DefaultBranchSession,
+    commit: bool,
+) -> Option<BranchStatus> {
+    if session.branch_sessions.len() != 1 || branch_session.branch_type != BranchType::AT {
+        return None;
+    }
+    match (commit, branch_session.status) {
+        (true, BranchStatus::PhaseOneDone) => Some(BranchStatus::PhaseTwoCommitted),
+        (false, BranchStatus::PhaseOneFailed) => Some(BranchStatus::PhaseTwoRollbacked),
+        _ => None,
+    }
+}
+
+/// 根据分支提交结果决定全局状态：投递失败与可重试失败进入 CommitRetrying
+pub(crate) fn commit_status(results: &[Result<BranchStatus, TransactionError>]) -> GlobalStatus {
+    if results
+        .iter()
+        .any(|r| matches!(r, Ok(BranchStatus::PhaseTwoCommitFailedUnretryable)))
+    {
+        GlobalStatus::CommitFailed
+    } else if results.iter().any(|r| {
+        r.is_err()
+            || matches!(
+                r,
+                Ok(BranchStatus::PhaseTwoCommitFailedRetryable | BranchStatus::PhaseTwoTimeout)
+            )
+    }) {
+        GlobalStatus::CommitRetrying
+    } else {
+        GlobalStatus::Committed
+    }
+}
+
+/// 根据分支回滚结果决定全局状态，超时回滚使用对应的 Timeout* 状态
+pub(crate) fn rollback_status(
+    results: &[Result<BranchStatus, TransactionError>],
+    timeout: bool,
+) -> GlobalStatus {
+    if results
+        .iter()
+        .any(|r| matches!(r, Ok(BranchStatus::PhaseTwoRollbackFailedUnretryable)))
+    {
+        if timeout {
+            GlobalStatus::TimeoutRollbackFailed
+        } else {
+            GlobalStatus::RollbackFailed
+        }
+    } else if results.iter().any(|r| {
+        r.is_err()
+            || matches!(
+                r,
+                Ok(BranchStatus::PhaseTwoRollbackFailedRetryable | BranchStatus::PhaseTwoTimeout)
+            )
+    }) {
+        if timeout {
+            GlobalStatus::TimeoutRollbackRetrying
+        } else {
+            GlobalStatus::RollbackRetrying
+        }
+    } else if timeout {
+        GlobalStatus::TimeoutRollbacked
+    } else {
+        GlobalStatus::Rollbacked
+    }
+}
+
+/// 所有分支都已就绪才能开始提交，投递失败同样视为未就绪
+pub(crate) fn all_prepared(results: &[Result<BranchStatus, TransactionError>]) -> bool {
+    results
+        .iter()
+        .all(|r| matches!(r, Ok(BranchStatus::PhaseOneDone)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branches(resources: &[&str]) -> Vec<DefaultBranchSession> {
+        resources
+            .iter()
+            .enumerate()
+            .map(|(i, resource_id)| {
+                let mut branch_session = DefaultBranchSession::new(BranchType::TCC);
+                branch_session.branch_id = (i as u64).into();
+                branch_session.resource_id = Some((*resource_id).into());
+                branch_session
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sequential_marks_remaining_branches_not_attempted() {
+        let holder = crate::coordinator::default_core_holder::tests::memory_holder();
+        let branches = branches(&["a", "b", "c"]);
+        let results = holder
+            .execute_phase_two(
+                &branches,
+                PhaseTwoMode::Sequential,
+                BranchStatus::PhaseTwoCommitted,
+                |branch_session| async move {
+                    Ok(match u64::from(branch_session.branch_id) {
+                        0 => BranchStatus::PhaseTwoCommitted,
+                        _ => BranchStatus::PhaseTwoCommitFailedRetryable,
+                    })
+                },
+            )
+            .await;
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[1],
+            Ok(BranchStatus::PhaseTwoCommitFailedRetryable)
+        ));
+        assert!(matches!(
+            results[2],
+            Err(TransactionError::NotAttempted { .. })
+        ));
+        assert_eq!(commit_status(&results), GlobalStatus::CommitRetrying);
+    }
+
+    #[tokio::test]
+    async fn bounded_parallel_keeps_order_per_resource() {
+        let holder = crate::coordinator::default_core_holder::tests::memory_holder();
+        let branches = branches(&["a", "b", "a", "b", "a"]);
+        let started = std::sync::Mutex::new(Vec::new());
+        let results = holder
+            .execute_phase_two(
+                &branches,
+                PhaseTwoMode::Parallel {
+                    max_per_resource: 1,
+                },
+                BranchStatus::PhaseTwoRollbacked,
+                |branch_session| {
+                    let started = &started;
+                    async move {
+                        started
+                            .lock()
+                            .unwrap()
+                            .push(u64::from(branch_session.branch_id));
+                        // 后注册的分支完成得更快，组内仍按给定顺序开始
+                        let delay = 10 - u64::from(branch_session.branch_id) * 2;
+                        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                        Ok(BranchStatus::PhaseTwoRollbacked)
+                    }
+                },
+            )
+            .await;
+        assert_eq!(results.len(), 5);
+        let started = started.into_inner().unwrap();
+        let order = |resource: &[u64]| {
+            started
+                .iter()
+                .filter(|id| resource.contains(id))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(&[0, 2, 4]), vec![0, 2, 4]);
+        assert_eq!(order(&[1, 3]), vec![1, 3]);
+    }
+
+    #[test]
+    fn prepare_round_requires_every_branch() {
+        assert!(all_prepared(&[]));
+        assert!(all_prepared(&[
+            Ok(BranchStatus::PhaseOneDone),
+            Ok(BranchStatus::PhaseOneDone)
+        ]));
+        assert!(!all_prepared(&[
+            Ok(BranchStatus::PhaseOneDone),
+            Ok(BranchStatus::PhaseOneFailed)
+        ]));
+        assert!(!all_prepared(&[
+            Ok(BranchStatus::PhaseOneDone),
+            Err(TransactionError::new(String::from("unreachable")))
+        ]));
+    }
+
+    #[test]
+    fn commit_status_from_branch_results() {
+        assert_eq!(
+            commit_status(&[Ok(BranchStatus::PhaseTwoCommitted)]),
+            GlobalStatus::Committed
+        );
+        assert_eq!(
+            commit_status(&[
+                Ok(BranchStatus::PhaseTwoCommitted),
+                Ok(BranchStatus::PhaseTwoCommitFailedRetryable)
+            ]),
+            GlobalStatus::CommitRetrying
+        );
+        assert_eq!(
+            commit_status(&[
+                Err(TransactionError::new(String::from("unreachable"))),
+                Ok(BranchStatus::PhaseTwoCommitFailedUnretryable)
+            ]),
+            GlobalStatus::CommitFailed
+        );
+    }
+}
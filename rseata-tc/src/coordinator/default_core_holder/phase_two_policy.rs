@@ -0,0 +1,140 @@
+use crate::config;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PhaseTwoMode {
+    // 并发下发，同一资源上同时执行的分支数不超过 max_per_resource，且按顺序开始；0 表示不限制，也不保证顺序
+    Parallel { max_per_resource: usize },
+    // 逐个下发，遇到未成功的分支即停止，剩余分支交给重试任务
+    Sequential,
+}
+
+impl PhaseTwoMode {
+    /// 格式：sequential | parallel | parallel:<max_per_resource>
+    fn parse(value: &str) -> Option<Self> {
+        let (mode, max) = match value.trim().split_once(':') {
+            Some((mode, max)) => (mode.trim(), Some(max.trim())),
+            None => (value.trim(), None),
+        };
+        match (mode.to_ascii_lowercase().as_str(), max) {
+            ("sequential", None) => Some(Self::Sequential),
+            ("parallel", None) => Some(Self::Parallel {
+                max_per_resource: 0,
+            }),
+            ("parallel", Some(max)) => max
+                .parse()
+                .ok()
+                .map(|max_per_resource| Self::Parallel { max_per_resource }),
+            _ => None,
+        }
+    }
+
+    // 不限并发时所有分支同时下发，不存在执行顺序
+    fn is_ordered(&self) -> bool {
+        !matches!(
+            self,
+            Self::Parallel {
+                max_per_resource: 0
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PhaseTwoPolicy {
+    pub(crate) mode: PhaseTwoMode,
+    // 回滚按注册顺序的逆序下发，限制并发时在每个资源内逆序
+    pub(crate) reverse_rollback: bool,
+}
+
+impl PhaseTwoPolicy {
+    // 未配置逆序时有序的模式默认逆序回滚；不限并发时忽略逆序配置
+    fn new(mode: PhaseTwoMode, reverse_rollback: Option<bool>, scope: &str) -> Self {
+        let reverse_rollback = match reverse_rollback {
+            Some(true) if !mode.is_ordered() => {
+                tracing::warn!(
+                    "Reverse rollback order is ignored for {scope}: unbounded parallel phase two has no order"
+                );
+                false
+            }
+            Some(reverse_rollback) => reverse_rollback,
+            None => mode.is_ordered(),
+        };
+        Self {
+            mode,
+            reverse_rollback,
+        }
+    }
+}
+
+/// 按事务分组配置的二阶段执行策略，未配置的分组使用默认策略
+#[derive(Debug, Clone)]
+pub(crate) struct PhaseTwoPolicies {
+    default: PhaseTwoPolicy,
+    groups: HashMap<String, PhaseTwoPolicy>,
+}
+
+impl PhaseTwoPolicies {
+    pub(crate) fn from_env() -> Self {
+        let reverse_rollback = config::get_env_rollback_reverse_order();
+        let default = PhaseTwoPolicy::new(
+            PhaseTwoMode::parse(&config::get_env_phase_two_mode()).unwrap_or_else(|| {
+                tracing::warn!("Illegal phase two mode, fallback to parallel");
+                PhaseTwoMode::Parallel {
+                    max_per_resource: 0,
+                }
+            }),
+            reverse_rollback,
+            "default group",
+        );
+
+        // 格式：order_group=sequential;user_group=parallel:8
+        let mut groups = HashMap::new();
+        for item in config::get_env_phase_two_group_modes()
+            .split(';')
+            .filter(|item| !item.trim().is_empty())
+        {
+            match item
+                .split_once('=')
+                .and_then(|(group, mode)| Some((group.trim(), PhaseTwoMode::parse(mode)?)))
+            {
+                Some((group, mode)) => {
+                    groups.insert(
+                        group.to_string(),
+                        PhaseTwoPolicy::new(mode, reverse_rollback, group),
+                    );
+                }
+                None => tracing::warn!("Illegal phase two group mode : {item}"),
+            }
+        }
+
+        Self { default, groups }
+    }
+
+    pub(crate) fn for_group(&self, transaction_service_group: &str) -> PhaseTwoPolicy {
+        self.groups
+            .get(transaction_service_group)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_rollback_needs_an_ordered_mode() {
+        let unbounded = PhaseTwoMode::Parallel {
+            max_per_resource: 0,
+        };
+        let bounded = PhaseTwoMode::Parallel {
+            max_per_resource: 4,
+        };
+        assert!(!PhaseTwoPolicy::new(unbounded, Some(true), "g").reverse_rollback);
+        assert!(!PhaseTwoPolicy::new(unbounded, None, "g").reverse_rollback);
+        assert!(PhaseTwoPolicy::new(bounded, None, "g").reverse_rollback);
+        assert!(PhaseTwoPolicy::new(PhaseTwoMode::Sequential, None, "g").reverse_rollback);
+        assert!(!PhaseTwoPolicy::new(PhaseTwoMode::Sequential, Some(false), "g").reverse_rollback);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::coordinator::default_core_holder::DefaultCoreHolder;
+use rseata_core::branch::{BranchStatus, BranchType};
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::lock_manager::LockManager;
+use rseata_core::types::GlobalStatus;
+
+impl DefaultCoreHolder {
+    /// TC 重启后恢复存储中未结束的全局事务
+    ///
+    /// 内存行锁需要按分支重新登记（db、redis、raft 行锁已持久化，重复登记不影响）；中断的二阶段转为重试状态交给后台重试任务；
+    /// raft 集群模式下每次当选 leader 时执行
+    pub(crate) async fn recover_sessions(&self) -> anyhow::Result<()> {
+        let sessions = self
+            .session_manager
+            .find_sessions_by_status(&[
+                GlobalStatus::Begin,
+                GlobalStatus::Committing,
+                GlobalStatus::CommitRetrying,
+                GlobalStatus::Rollbacking,
+                GlobalStatus::RollbackRetrying,
+                GlobalStatus::TimeoutRollbacking,
+                GlobalStatus::TimeoutRollbackRetrying,
+                GlobalStatus::StopCommitOrCommitRetry,
+                GlobalStatus::StopRollbackOrRollbackRetry,
+            ])
+            .await;
+
+        for session in sessions {
+            for branch_session in &session.branch_sessions {
+                if branch_session.branch_type != BranchType::AT
+                    || matches!(
+                        branch_session.status,
+                        BranchStatus::PhaseTwoCommitted | BranchStatus::PhaseTwoRollbacked
+                    )
+                {
+                    continue;
+                }
+                if !self.lock_manager.acquire_lock(branch_session).await? {
+                    tracing::error!(
+                        "Recover branch lock conflict: {}, {:?}",
+                        branch_session.branch_id,
+                        branch_session.lock_key
+                    );
+                }
+            }
+
+            let retry_status = match session.status {
+                GlobalStatus::Committing => Some(GlobalStatus::CommitRetrying),
+                GlobalStatus::Rollbacking => Some(GlobalStatus::RollbackRetrying),
+                GlobalStatus::TimeoutRollbacking => Some(GlobalStatus::TimeoutRollbackRetrying),
+                _ => None,
+            };
+            if matches!(
+                session.status,
+                GlobalStatus::Rollbacking
+                    | GlobalStatus::RollbackRetrying
+                    | GlobalStatus::TimeoutRollbacking
+                    | GlobalStatus::TimeoutRollbackRetrying
+            ) {
+                self.lock_manager
+                    .update_lock_status(&session.xid, LockStatus::Rollbacking)
+                    .await?;
+            }
+            if let Some(status) = retry_status {
+                tracing::info!(
+                    "Recover {} : {:?} -> {:?}",
+                    session.xid,
+                    session.status,
+                    status
+                );
+                self.change_global_status(&session, status).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,520 @@
+use crate::config;
+use crate::coordinator::default_core_holder::DefaultCoreHolder;
+use crate::coordinator::default_core_holder::phase_two::{commit_status, rollback_status};
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::session_manager::SessionManager;
+use rseata_core::types::{ClientId, GlobalStatus, ResourceId, Xid};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryConfig {
+    // 扫描间隔，同时作为退避的初始间隔
+    pub(crate) interval: Duration,
+    pub(crate) max_backoff: Duration,
+    // 从全局事务开始计时，超过后不再重试
+    pub(crate) max_commit_retry_timeout: Option<Duration>,
+    pub(crate) max_rollback_retry_timeout: Option<Duration>,
+}
+
+impl RetryConfig {
+    pub(crate) fn from_env() -> Self {
+        let limit = |millis: u64| (millis > 0).then(|| Duration::from_millis(millis));
+        Self {
+            interval: Duration::from_millis(config::get_env_retry_interval_millis()),
+            max_backoff: Duration::from_millis(config::get_env_retry_max_backoff_millis()),
+            max_commit_retry_timeout: limit(config::get_env_max_commit_retry_timeout_millis()),
+            max_rollback_retry_timeout: limit(config::get_env_max_rollback_retry_timeout_millis()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RetryKind {
+    // AsyncCommitting 的后台提交
+    AsyncCommit,
+    Commit,
+    Rollback,
+}
+
+impl RetryKind {
+    fn statuses(&self) -> Vec<GlobalStatus> {
+        match self {
+            RetryKind::AsyncCommit => vec![GlobalStatus::AsyncCommitting],
+            RetryKind::Commit => vec![GlobalStatus::CommitRetrying],
+            RetryKind::Rollback => vec![
+                GlobalStatus::RollbackRetrying,
+                GlobalStatus::TimeoutRollbackRetrying,
+            ],
+        }
+    }
+
+    fn retry_timeout_status(&self) -> GlobalStatus {
+        match self {
+            RetryKind::AsyncCommit | RetryKind::Commit => GlobalStatus::CommitRetryTimeout,
+            RetryKind::Rollback => GlobalStatus::RollbackRetryTimeout,
+        }
+    }
+
+    fn max_retry_timeout(&self, config: &RetryConfig) -> Option<Duration> {
+        match self {
+            // AT 的异步提交一直重试，直到 undo log 清理完成
+            RetryKind::AsyncCommit => None,
+            RetryKind::Commit => config.max_commit_retry_timeout,
+            RetryKind::Rollback => config.max_rollback_retry_timeout,
+        }
+    }
+}
+
+/// 每个全局事务的指数退避状态，只保存在重试任务内存中
+struct RetryBackoff {
+    base: Duration,
+    max: Duration,
+    entries: HashMap<Xid, (u32, Instant)>,
+}
+
+impl RetryBackoff {
+    fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn ready(&self, xid: &Xid) -> bool {
+        self.entries
+            .get(xid)
+            .is_none_or(|(_, next_retry)| Instant::now() >= *next_retry)
+    }
+
+    fn failed(&mut self, xid: &Xid) {
+        let entry = self
+            .entries
+            .entry(xid.clone())
+            .or_insert((0, Instant::now()));
+        entry.0 = entry.0.saturating_add(1);
+        let delay = self
+            .base
+            .saturating_mul(2u32.saturating_pow(entry.0 - 1))
+            .min(self.max);
+        entry.1 = Instant::now() + delay;
+    }
+
+    // 丢弃已经离开重试状态的事务
+    fn retain(&mut self, sessions: &[DefaultGlobalSession]) {
+        self.entries
+            .retain(|xid, _| sessions.iter().any(|s| &s.xid == xid));
+    }
+}
+
+/// 正在重试二阶段的全局事务，后台重试任务与 RM 重连后的重新下发互斥
+#[derive(Default)]
+pub(crate) struct RetryingSessions(Mutex<HashSet<Xid>>);
+
+impl RetryingSessions {
+    /// 已有重试在进行时返回 None
+    fn claim(&self, xid: &Xid) -> Option<RetryClaim<'_>> {
+        let mut xids = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        xids.insert(xid.clone()).then(|| RetryClaim {
+            sessions: self,
+            xid: xid.clone(),
+        })
+    }
+}
+
+struct RetryClaim<'a> {
+    sessions: &'a RetryingSessions,
+    xid: Xid,
+}
+
+impl Drop for RetryClaim<'_> {
+    fn drop(&mut self) {
+        let mut xids = self.sessions.0.lock().unwrap_or_else(|e| e.into_inner());
+        xids.remove(&self.xid);
+    }
+}
+
+impl DefaultCoreHolder {
+    /// 启动 AsyncCommitting / CommitRetrying / RollbackRetrying 的二阶段后台任务
+    pub(crate) fn start_retry_workers(self: &Arc<Self>, config: RetryConfig) {
+        for kind in [
+            RetryKind::AsyncCommit,
+            RetryKind::Commit,
+            RetryKind::Rollback,
+        ] {
+            let holder = self.clone();
+            tokio::spawn(async move { holder.retry_loop(kind, config).await });
+        }
+    }
+
+    async fn retry_loop(&self, kind: RetryKind, config: RetryConfig) {
+        let mut backoff = RetryBackoff::new(config.interval, config.max_backoff);
+        let mut ticker = tokio::time::interval(config.interval);
+        loop {
+            ticker.tick().await;
+            if !self.is_leader() {
+                continue;
+            }
+            let sessions = self
+                .session_manager
+                .find_sessions_by_status(&kind.statuses())
+                .await;
+            backoff.retain(&sessions);
+            for session in sessions {
+                if !backoff.ready(&session.xid) {
+                    continue;
+                }
+                match self
+                    .retry_session(kind, &session, kind.max_retry_timeout(&config))
+                    .await
+                {
+                    Ok(Some(status)) if status == session.status => backoff.failed(&session.xid),
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!("Retry {:?} failed: {}, {:?}", kind, session.xid, e);
+                        backoff.failed(&session.xid);
+                    }
+                }
+            }
+        }
+    }
+
+    /// RM 重新注册后，立即重试暂存了该 RM 指令的全局事务
+    pub(crate) async fn redeliver_queued(&self, resource_id: &ResourceId, client_id: ClientId) {
+        for kind in [
+            RetryKind::AsyncCommit,
+            RetryKind::Commit,
+            RetryKind::Rollback,
+        ] {
+            let sessions = self
+                .session_manager
+                .find_sessions_by_status(&kind.statuses())
+                .await;
+            for session in sessions {
+                if !session
+                    .branch_sessions
+                    .iter()
+                    .any(|bs| self.outbound.deliverable_to(bs, resource_id, client_id))
+                {
+                    continue;
+                }
+                tracing::info!(
+                    "Redeliver queued instructions : {}, {}",
+                    session.xid,
+                    resource_id
+                );
+                // 重试超时仍由后台重试任务判断
+                if let Err(e) = self.retry_session(kind, &session, None).await {
+                    tracing::error!("Redeliver {:?} failed: {}, {:?}", kind, session.xid, e);
+                }
+            }
+        }
+    }
+
+    /// 重试一次二阶段，返回重试后的全局状态
+    ///
+    /// 同一全局事务已有重试在进行，或会话已离开扫描时的状态时跳过并返回 None
+    pub(crate) async fn retry_session(
+        &self,
+        kind: RetryKind,
+        session: &DefaultGlobalSession,
+        max_retry_timeout: Option<Duration>,
+    ) -> anyhow::Result<Option<GlobalStatus>> {
+        let Some(_claim) = self.retrying.claim(&session.xid) else {
+            return Ok(None);
+        };
+        // 取得重试权后重新读取，扫描结果可能已被另一次重试推进
+        let Some(current) = self
+            .session_manager
+            .find_global_session_with_branches(&session.xid, true)
+            .await
+            .filter(|current| current.status == session.status)
+        else {
+            return Ok(None);
+        };
+        let session = &current;
+        let from = session.status;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let elapsed = Duration::from_millis(now.saturating_sub(session.begin_time_millis));
+
+        let to = if max_retry_timeout.is_some_and(|max| elapsed > max) {
+            tracing::warn!("Retry timeout : {}, {:?}", session.xid, from);
+            kind.retry_timeout_status()
+        } else {
+            tracing::info!("Retry {:?} : {}", kind, session.xid);
+            match kind {
+                RetryKind::AsyncCommit => {
+                    match commit_status(&self.commit_branches(session).await) {
+                        // 可重试的失败保持 AsyncCommitting，等待下一轮
+                        GlobalStatus::CommitRetrying => from,
+                        status => status,
+                    }
+                }
+                RetryKind::Commit => commit_status(&self.commit_branches(session).await),
+                RetryKind::Rollback => rollback_status(
+                    &self.rollback_branches(session).await,
+                    from == GlobalStatus::TimeoutRollbackRetrying,
+                ),
+            }
+        };
+
+        if to != from {
+            self.change_global_status(session, to).await?;
+        }
+        Ok(Some(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::default_core_holder::tests::{
+        RESOURCE, StatusRecorder, connect_rm, memory_holder, memory_holder_with_resources, reload,
+        session_with,
+    };
+    use crate::store::raft::node::tests::wait_until;
+    use rseata_core::branch::{BranchStatus, BranchType};
+    use rseata_core::transaction::transaction_manager::TransactionManager;
+    use rseata_proto::rseata_proto::proto::resource_instruction::Instruction;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // 按顺序推进全局状态，模拟二阶段进入重试
+    async fn move_to(
+        holder: &DefaultCoreHolder,
+        session: &DefaultGlobalSession,
+        statuses: &[GlobalStatus],
+    ) -> DefaultGlobalSession {
+        for status in statuses {
+            holder
+                .session_manager
+                .update_global_session_status(session, *status)
+                .await
+                .unwrap();
+        }
+        reload(holder, &session.xid).await
+    }
+
+    // 前 failures 次返回 failed，之后返回 done
+    fn fail_times(
+        failures: usize,
+        failed: BranchStatus,
+        done: BranchStatus,
+    ) -> impl Fn(&Instruction) -> BranchStatus + Send + 'static {
+        let calls = AtomicUsize::new(0);
+        move |_| {
+            if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                failed
+            } else {
+                done
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_retries_of_one_xid_are_skipped() {
+        let holder = memory_holder();
+        let xid = holder
+            .begin("app".into(), "group".into(), "tx".into(), 60_000)
+            .await
+            .unwrap();
+        let mut session = holder
+            .session_manager
+            .find_global_session(&xid)
+            .await
+            .unwrap();
+        for status in [GlobalStatus::Rollbacking, GlobalStatus::RollbackRetrying] {
+            holder
+                .session_manager
+                .update_global_session_status(&session, status)
+                .await
+                .unwrap();
+        }
+        session.status = GlobalStatus::RollbackRetrying;
+
+        // 重试任务持有该事务时，重新下发直接跳过
+        let claim = holder.retrying.claim(&xid).unwrap();
+        assert!(holder.retrying.claim(&xid).is_none());
+        let skipped = holder.retry_session(RetryKind::Rollback, &session, None);
+        assert_eq!(skipped.await.unwrap(), None);
+        drop(claim);
+
+        let retried = holder.retry_session(RetryKind::Rollback, &session, None);
+        assert_eq!(retried.await.unwrap(), Some(GlobalStatus::Rollbacked));
+        // 扫描结果已过期，不会再次执行二阶段
+        let stale = holder.retry_session(RetryKind::Rollback, &session, None);
+        assert_eq!(stale.await.unwrap(), None);
+    }
+
+    #[test]
+    fn backoff_doubles_until_max() {
+        let mut backoff = RetryBackoff::new(Duration::from_millis(100), Duration::from_millis(350));
+        let xid = Xid::from("xid");
+        assert!(backoff.ready(&xid));
+        for expected in [100, 200, 350, 350] {
+            backoff.failed(&xid);
+            assert!(!backoff.ready(&xid));
+            let delay = backoff.entries[&xid].1 - Instant::now();
+            assert!(delay <= Duration::from_millis(expected));
+            assert!(delay > Duration::from_millis(expected - 50));
+        }
+        // 事务离开重试状态后退避记录被清除
+        backoff.retain(&[]);
+        assert!(backoff.ready(&xid));
+    }
+
+    #[tokio::test]
+    async fn failed_commit_is_retried_with_growing_spacing() {
+        let (holder, resources) = memory_holder_with_resources();
+        let sent_at = Arc::new(Mutex::new(Vec::new()));
+        let log = sent_at.clone();
+        let reply = fail_times(
+            3,
+            BranchStatus::PhaseTwoCommitFailedRetryable,
+            BranchStatus::PhaseTwoCommitted,
+        );
+        let _rm = connect_rm(&resources, RESOURCE, 1, move |instruction| {
+            log.lock().unwrap().push(Instant::now());
+            reply(instruction)
+        })
+        .await;
+        let (xid, session) = session_with(&holder, &[(BranchType::TCC, "order_action")]).await;
+        move_to(
+            &holder,
+            &session,
+            &[GlobalStatus::Committing, GlobalStatus::CommitRetrying],
+        )
+        .await;
+
+        let base = Duration::from_millis(20);
+        holder.start_retry_workers(RetryConfig {
+            interval: base,
+            max_backoff: Duration::from_secs(1),
+            max_commit_retry_timeout: None,
+            max_rollback_retry_timeout: None,
+        });
+        wait_until(|| async { holder.session_manager.finished_status(&xid) }).await;
+
+        assert_eq!(
+            holder.session_manager.finished_status(&xid),
+            Some(GlobalStatus::Committed)
+        );
+        let sent_at = sent_at.lock().unwrap().clone();
+        assert_eq!(sent_at.len(), 4);
+        // 第 n 次失败后至少等待 base * 2^(n-1)
+        for (n, pair) in sent_at.windows(2).enumerate() {
+            assert!(pair[1] - pair[0] >= base * 2u32.pow(n as u32));
+        }
+    }
+
+    #[tokio::test]
+    async fn retryable_failure_keeps_retrying_status() {
+        let (holder, resources) = memory_holder_with_resources();
+        let _rm = connect_rm(&resources, RESOURCE, 1, |instruction| match instruction {
+            Instruction::Commit(_) => BranchStatus::PhaseTwoCommitFailedRetryable,
+            _ => BranchStatus::PhaseTwoRollbackFailedRetryable,
+        })
+        .await;
+        let cases = [
+            (
+                RetryKind::Commit,
+                vec![GlobalStatus::Committing, GlobalStatus::CommitRetrying],
+            ),
+            (
+                RetryKind::Rollback,
+                vec![GlobalStatus::Rollbacking, GlobalStatus::RollbackRetrying],
+            ),
+            (
+                RetryKind::Rollback,
+                vec![
+                    GlobalStatus::TimeoutRollbacking,
+                    GlobalStatus::TimeoutRollbackRetrying,
+                ],
+            ),
+        ];
+        for (kind, statuses) in cases {
+            let (xid, session) = session_with(&holder, &[(BranchType::TCC, "order_action")]).await;
+            let session = move_to(&holder, &session, &statuses).await;
+            let retried = holder.retry_session(kind, &session, None).await.unwrap();
+            assert_eq!(retried, Some(session.status));
+            assert_eq!(reload(&holder, &xid).await.status, session.status);
+        }
+    }
+
+    #[tokio::test]
+    async fn successful_retry_leaves_retrying_status() {
+        let (holder, resources) = memory_holder_with_resources();
+        let recorder = StatusRecorder::attach(&holder);
+        let _rm = connect_rm(&resources, RESOURCE, 1, |instruction| match instruction {
+            Instruction::Commit(_) => BranchStatus::PhaseTwoCommitted,
+            _ => BranchStatus::PhaseTwoRollbacked,
+        })
+        .await;
+        let cases = [
+            (
+                RetryKind::Commit,
+                vec![GlobalStatus::Committing, GlobalStatus::CommitRetrying],
+                GlobalStatus::Committed,
+            ),
+            (
+                RetryKind::Rollback,
+                vec![GlobalStatus::Rollbacking, GlobalStatus::RollbackRetrying],
+                GlobalStatus::Rollbacked,
+            ),
+            (
+                RetryKind::Rollback,
+                vec![
+                    GlobalStatus::TimeoutRollbacking,
+                    GlobalStatus::TimeoutRollbackRetrying,
+                ],
+                GlobalStatus::TimeoutRollbacked,
+            ),
+        ];
+        for (kind, statuses, finished) in cases {
+            let (xid, session) = session_with(&holder, &[(BranchType::TCC, "order_action")]).await;
+            let session = move_to(&holder, &session, &statuses).await;
+            let retried = holder.retry_session(kind, &session, None).await.unwrap();
+            assert_eq!(retried, Some(finished));
+            assert_eq!(holder.session_manager.finished_status(&xid), Some(finished));
+            assert!(recorder.statuses().ends_with(&[session.status, finished]));
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_deadline_counts_from_begin_time() {
+        let (holder, resources) = memory_holder_with_resources();
+        let rm = connect_rm(&resources, RESOURCE, 1, |_| BranchStatus::PhaseTwoCommitted).await;
+        let cases = [
+            (
+                RetryKind::Commit,
+                vec![GlobalStatus::Committing, GlobalStatus::CommitRetrying],
+                GlobalStatus::CommitRetryTimeout,
+            ),
+            (
+                RetryKind::Rollback,
+                vec![GlobalStatus::Rollbacking, GlobalStatus::RollbackRetrying],
+                GlobalStatus::RollbackRetryTimeout,
+            ),
+        ];
+        for (kind, statuses, timeout_status) in cases {
+            let (xid, session) = session_with(&holder, &[(BranchType::TCC, "order_action")]).await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            // 刚进入重试状态，但距全局事务开始已超过重试时限
+            let session = move_to(&holder, &session, &statuses).await;
+            let max_retry_timeout = Some(Duration::from_millis(30));
+            let retried = holder
+                .retry_session(kind, &session, max_retry_timeout)
+                .await
+                .unwrap();
+            assert_eq!(retried, Some(timeout_status));
+            // 重试超时的事务保留在存储中等待人工处理
+            assert_eq!(reload(&holder, &xid).await.status, timeout_status);
+        }
+        // 超时的事务不再下发二阶段
+        assert!(rm.received().is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::coordinator::default_core_holder::DefaultCoreHolder;
+use rseata_core::lock::lock_manager::LockManager;
+use rseata_core::session::session_condition::SessionCondition;
+use rseata_core::session::session_manager::SessionManager;
+use rseata_core::types::GlobalStatus;
+use std::sync::Arc;
+use std::time::Duration;
+
+// 失败结束、需要人工处理的全局状态
+const FAILED_STATUSES: [GlobalStatus; 6] = [
+    GlobalStatus::CommitFailed,
+    GlobalStatus::CommitRetryTimeout,
+    GlobalStatus::RollbackFailed,
+    GlobalStatus::RollbackRetryTimeout,
+    GlobalStatus::TimeoutRollbackFailed,
+    GlobalStatus::Finished,
+];
+
+impl DefaultCoreHolder {
+    /// 启动失败会话清理任务，retention_millis 为 0 时不启动
+    pub(crate) fn start_failed_session_purger(
+        self: &Arc<Self>,
+        interval: Duration,
+        retention_millis: u64,
+    ) {
+        if retention_millis == 0 {
+            return;
+        }
+        let holder = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if !holder.is_leader() {
+                    continue;
+                }
+                if let Err(e) = holder.purge_failed_sessions(retention_millis).await {
+                    tracing::error!("Purge failed sessions failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// 移除开始时间早于 retention_millis 之前的失败会话，返回本次移除的会话数
+    pub(crate) async fn purge_failed_sessions(
+        &self,
+        retention_millis: u64,
+    ) -> anyhow::Result<usize> {
+        let condition = SessionCondition {
+            over_time_alive_mills: Some(retention_millis),
+            ..SessionCondition::with_statuses(FAILED_STATUSES.to_vec())
+        };
+        let sessions = self.session_manager.find_global_sessions(&condition).await;
+        for session in &sessions {
+            tracing::warn!(
+                "Purge failed global session : {}, {:?}",
+                session.xid,
+                session.status
+            );
+            // 回滚失败时保留的行锁随会话一起释放
+            self.lock_manager
+                .release_global_session_lock(session)
+                .await?;
+            self.session_manager.purge_global_session(session).await?;
+        }
+        Ok(sessions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coordinator::default_core_holder::tests::memory_holder;
+    use rseata_core::session::session_manager::SessionManager;
+    use rseata_core::transaction::transaction_manager::TransactionManager;
+    use rseata_core::types::GlobalStatus;
+
+    #[tokio::test]
+    async fn purges_expired_failed_sessions() {
+        let holder = memory_holder();
+        let xid = holder
+            .begin("app".into(), "group".into(), "tx".into(), 60_000)
+            .await
+            .unwrap();
+        let session = holder
+            .session_manager
+            .find_global_session(&xid)
+            .await
+            .unwrap();
+        for status in [GlobalStatus::Rollbacking, GlobalStatus::RollbackFailed] {
+            holder
+                .session_manager
+                .update_global_session_status(&session, status)
+                .await
+                .unwrap();
+        }
+
+        // 未到保留期限的失败会话不会被移除
+        assert_eq!(holder.purge_failed_sessions(60_000).await.unwrap(), 0);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        assert_eq!(holder.purge_failed_sessions(1).await.unwrap(), 1);
+
+        assert!(
+            holder
+                .session_manager
+                .find_global_session(&xid)
+                .await
+                .is_none()
+        );
+        assert_eq!(
+            holder.get_status(xid).await.unwrap(),
+            GlobalStatus::RollbackFailed
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::coordinator::default_core_holder::DefaultCoreHolder;
+use crate::coordinator::default_core_holder::phase_two::rollback_status;
+use rseata_core::event::event::TransactionEvent;
+use rseata_core::event::event_publisher::EventPublisher;
+use rseata_core::event::event_type::TransactionEventType;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::session_manager::SessionManager;
+use rseata_core::types::{GlobalStatus, Xid};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+impl DefaultCoreHolder {
+    /// 启动超时检测任务，周期性回滚已超时的全局事务
+    pub(crate) fn start_timeout_checker(self: &Arc<Self>, interval: Duration) {
+        let holder = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if !holder.is_leader() {
+                    continue;
+                }
+                if let Err(e) = holder.timeout_check().await {
+                    tracing::error!("Timeout check failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// 扫描一次超时的 Begin 会话，返回本次超时回滚的会话数
+    pub(crate) async fn timeout_check(&self) -> anyhow::Result<usize> {
+        let sessions = self.session_manager.find_timeout_sessions().await;
+        let mut session_count = 0;
+        for session in sessions {
+            // 扫描期间 TM 可能已经提交或回滚，以最新状态为准
+            let Some(session) = self
+                .session_manager
+                .find_global_session_with_branches(&session.xid, true)
+                .await
+            else {
+                continue;
+            };
+            if session.status != GlobalStatus::Begin {
+                continue;
+            }
+            tracing::info!(
+                "Global transaction timeout : {}, begin at {}, timeout {}ms",
+                session.xid,
+                session.begin_time_millis,
+                session.timeout_millis
+            );
+            session_count += 1;
+            if let Err(e) = self.timeout_rollback(&session).await {
+                tracing::error!("Timeout rollback failed: {}, {:?}", session.xid, e);
+            }
+        }
+
+        if session_count > 0 {
+            self.event_publisher
+                .publish(TransactionEvent {
+                    event_id: Uuid::new_v4().to_string(),
+                    timestamp: Default::default(),
+                    event_type: TransactionEventType::SessionTimeout { session_count },
+                    xid: Xid(String::new()),
+                    application_id: "".to_string(),
+                    transaction_name: "".to_string(),
+                    metadata: Default::default(),
+                })
+                .await;
+        }
+        Ok(session_count)
+    }
+
+    /// 超时回滚：TimeoutRollbacking -> TimeoutRollbacked / TimeoutRollbackRetrying / TimeoutRollbackFailed
+    pub(crate) async fn timeout_rollback(
+        &self,
+        session: &DefaultGlobalSession,
+    ) -> anyhow::Result<GlobalStatus> {
+        if let Some(current) = self
+            .claim_global_status(session, GlobalStatus::TimeoutRollbacking)
+            .await?
+        {
+            return Ok(current);
+        }
+
+        let results = self.rollback_branches(session).await;
+        let global_status = self
+            .change_global_status(session, rollback_status(&results, true))
+            .await?;
+
+        Ok(global_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coordinator::default_core_holder::tests::{
+        RESOURCE, StatusRecorder, connect_rm, memory_holder_in_cluster,
+        memory_holder_with_resources, reload, session_with_timeout,
+    };
+    use crate::store::raft::node::tests::{TestCluster, wait_until};
+    use rseata_core::branch::{BranchStatus, BranchType};
+    use rseata_core::session::session_manager::SessionManager;
+    use rseata_core::types::GlobalStatus;
+    use rseata_proto::rseata_proto::proto::resource_instruction::Instruction;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn expired_session_is_rolled_back() {
+        let (holder, resources) = memory_holder_with_resources();
+        let recorder = StatusRecorder::attach(&holder);
+        let rm = connect_rm(&resources, RESOURCE, 1, |_| {
+            BranchStatus::PhaseTwoRollbacked
+        })
+        .await;
+        let (xid, _) = session_with_timeout(&holder, 1, &[(BranchType::TCC, "order_action")]).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(holder.timeout_check().await.unwrap(), 1);
+        assert!(matches!(rm.received()[..], [Instruction::Rollback(_)]));
+        assert!(recorder.statuses().ends_with(&[
+            GlobalStatus::TimeoutRollbacking,
+            GlobalStatus::TimeoutRollbacked
+        ]));
+        assert_eq!(
+            holder.session_manager.finished_status(&xid),
+            Some(GlobalStatus::TimeoutRollbacked)
+        );
+        // 已结束的会话不会被再次扫描
+        assert_eq!(holder.timeout_check().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn session_claimed_by_tm_is_skipped() {
+        let (holder, resources) = memory_holder_with_resources();
+        let rm = connect_rm(&resources, RESOURCE, 1, |_| {
+            BranchStatus::PhaseTwoRollbacked
+        })
+        .await;
+        let (xid, stale) =
+            session_with_timeout(&holder, 1, &[(BranchType::TCC, "order_action")]).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // 扫描之后、抢占状态之前，TM 已发起提交
+        holder
+            .session_manager
+            .update_global_session_status(&stale, GlobalStatus::Committing)
+            .await
+            .unwrap();
+
+        let status = holder.timeout_rollback(&stale).await.unwrap();
+        assert_eq!(status, GlobalStatus::Committing);
+        assert!(rm.received().is_empty());
+        assert_eq!(reload(&holder, &xid).await.status, GlobalStatus::Committing);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn only_leader_checks_timeouts() {
+        let cluster = TestCluster::start(3, 1_000).await;
+        let (leader_id, leader) = cluster.leader().await;
+        let follower = cluster
+            .running
+            .iter()
+            .find(|(id, _)| **id != leader_id)
+            .map(|(_, node)| node.clone())
+            .unwrap();
+
+        let (on_leader, leader_resources) = memory_holder_in_cluster(Some(leader));
+        let (on_follower, follower_resources) = memory_holder_in_cluster(Some(follower));
+        let mut rms = Vec::new();
+        let mut xids = Vec::new();
+        for (holder, resources) in [
+            (&on_leader, &leader_resources),
+            (&on_follower, &follower_resources),
+        ] {
+            rms.push(
+                connect_rm(resources, RESOURCE, 1, |_| BranchStatus::PhaseTwoRollbacked).await,
+            );
+            let (xid, _) =
+                session_with_timeout(holder, 1, &[(BranchType::TCC, "order_action")]).await;
+            xids.push(xid);
+            holder.start_timeout_checker(Duration::from_millis(10));
+        }
+
+        wait_until(|| async {
+            on_leader
+                .session_manager
+                .finished_status(&xids[0])
+                .filter(|status| *status == GlobalStatus::TimeoutRollbacked)
+        })
+        .await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rms[1].received().is_empty());
+        assert_eq!(
+            reload(&on_follower, &xids[1]).await.status,
+            GlobalStatus::Begin
+        );
+    }
+}
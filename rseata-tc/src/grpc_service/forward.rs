@@ -0,0 +1,263 @@
+use crate::grpc_service::TCGrpcService;
+use crate::store::raft::node::RaftNode;
+use rseata_proto::rseata_proto::proto::resource_manager_service_client::ResourceManagerServiceClient;
+use rseata_proto::rseata_proto::proto::transaction_manager_service_client::TransactionManagerServiceClient;
+use rseata_proto::rseata_proto::proto::{ResourceInstruction, ResourceRequest, resource_request};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
+use tonic::codegen::tokio_stream::{Stream, StreamExt};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Status, Streaming};
+
+// 暂无 leader 或到 leader 的流断开后，等待这么久再重新挂接
+const REATTACH_BACKOFF: Duration = Duration::from_millis(500);
+
+type InstructionStream = Pin<Box<dyn Stream<Item = Result<ResourceInstruction, Status>> + Send>>;
+
+impl TCGrpcService {
+    /// raft 集群中本节点不是 leader 时返回到 leader 的连接，请求由 leader 处理
+    async fn leader_channel(&self) -> Result<Option<Channel>, Status> {
+        let Some(cluster) = &self.coordinator.core.cluster else {
+            return Ok(None);
+        };
+        if cluster.is_leader() {
+            return Ok(None);
+        }
+        let Some(endpoint) = cluster.leader_endpoint().await else {
+            return Err(Status::unavailable("TC cluster has no leader"));
+        };
+
+        let mut channels = self.leader_channels.lock().unwrap();
+        if let Some(channel) = channels.get(&endpoint) {
+            return Ok(Some(channel.clone()));
+        }
+        let channel = Endpoint::from_shared(format!("http://{endpoint}"))
+            .map_err(|e| Status::internal(e.to_string()))?
+            .connect_lazy();
+        channels.insert(endpoint, channel.clone());
+        Ok(Some(channel))
+    }
+
+    pub(crate) async fn forward_tm(
+        &self,
+    ) -> Result<Option<TransactionManagerServiceClient<Channel>>, Status> {
+        Ok(self
+            .leader_channel()
+            .await?
+            .map(TransactionManagerServiceClient::new))
+    }
+
+    pub(crate) async fn forward_rm(
+        &self,
+    ) -> Result<Option<ResourceManagerServiceClient<Channel>>, Status> {
+        Ok(self
+            .leader_channel()
+            .await?
+            .map(ResourceManagerServiceClient::new))
+    }
+
+    /// 集群模式下 RM 流固定在入口节点：本节点是 leader 时在本地注册，否则代理到 leader
+    ///
+    /// leader 变化或代理断开后重新挂接并重放资源注册，RM 不需要重连
+    pub(crate) fn route_resource_stream(
+        &self,
+        cluster: Arc<RaftNode>,
+        incoming: Streaming<ResourceRequest>,
+    ) -> ReceiverStream<Result<ResourceInstruction, Status>> {
+        let (response_tx, response_rx) = mpsc::channel(128);
+        let service = self.clone();
+        tokio::spawn(async move {
+            service
+                .relay_resource_stream(cluster, incoming, response_tx)
+                .await;
+            tracing::debug!("Routed resource stream finished");
+        });
+        ReceiverStream::new(response_rx)
+    }
+
+    async fn relay_resource_stream(
+        &self,
+        cluster: Arc<RaftNode>,
+        mut incoming: Streaming<ResourceRequest>,
+        response_tx: mpsc::Sender<Result<ResourceInstruction, Status>>,
+    ) {
+        let mut ready = cluster.subscribe();
+        let mut leader = cluster.subscribe_leader();
+        // 流上注册过的资源，挂接到新 leader 时重放
+        let mut resources: Vec<ResourceRequest> = Vec::new();
+        loop {
+            ready.borrow_and_update();
+            leader.borrow_and_update();
+            let (upstream_tx, upstream_rx) = mpsc::channel(128);
+            if let Some(mut attached) = self.attach_upstream(upstream_rx).await {
+                for resource in &resources {
+                    let _ = upstream_tx.send(resource.clone()).await;
+                }
+                let leader_changed = loop {
+                    tokio::select! {
+                        message = incoming.next() => match message {
+                            Some(Ok(message)) => {
+                                if let Some(resource_request::Request::Resource(_)) = message.request {
+                                    resources.push(message.clone());
+                                }
+                                let _ = upstream_tx.send(message).await;
+                            }
+                            // RM 断开，丢弃上游发送端后挂接随之结束
+                            _ => return,
+                        },
+                        instruction = attached.next() => match instruction {
+                            Some(Ok(instruction)) => {
+                                if response_tx.send(Ok(instruction)).await.is_err() {
+                                    return;
+                                }
+                            }
+                            _ => break false,
+                        },
+                        _ = ready.changed() => break true,
+                        _ = leader.changed() => break true,
+                    }
+                };
+                if leader_changed {
+                    continue;
+                }
+            }
+            tokio::select! {
+                _ = ready.changed() => {}
+                _ = leader.changed() => {}
+                _ = tokio::time::sleep(REATTACH_BACKOFF) => {}
+            }
+        }
+    }
+
+    // 挂接到本节点或当前 leader，暂无 leader 或 leader 不可达时返回 None
+    async fn attach_upstream(
+        &self,
+        upstream: mpsc::Receiver<ResourceRequest>,
+    ) -> Option<InstructionStream> {
+        let upstream = ReceiverStream::new(upstream);
+        match self.forward_rm().await {
+            Ok(None) => Some(Box::pin(self.attach_resource_stream(upstream.map(Ok)))),
+            Ok(Some(mut leader)) => match leader.register_resource(upstream).await {
+                Ok(response) => Some(Box::pin(response.into_inner())),
+                Err(e) => {
+                    tracing::debug!("Attach resource stream to leader failed: {}", e);
+                    None
+                }
+            },
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::default_coordinator::DefaultCoordinator;
+    use crate::resource::TCResource;
+    use crate::store::raft::RaftTransactionStoreManager;
+    use crate::store::raft::node::tests::{TestCluster, wait_until};
+    use crate::store::raft::raft_locker::RaftLocker;
+    use rseata_core::types::ResourceId;
+    use rseata_proto::rseata_proto::proto::ResourceProto;
+    use rseata_proto::rseata_proto::proto::resource_manager_service_server::ResourceManagerServiceServer;
+    use std::collections::BTreeMap;
+    use tonic::transport::Server;
+
+    const RESOURCE_ID: &str = "jdbc:mysql://127.0.0.1:3306/test";
+
+    // 为每个运行中的节点启动 TC 的 RM 服务
+    async fn serve(cluster: &TestCluster) -> BTreeMap<u64, Arc<DefaultCoordinator>> {
+        let mut coordinators = BTreeMap::new();
+        for (id, node) in &cluster.running {
+            let (event_publisher, _) = crate::start_event_system().await;
+            let coordinator = Arc::new(DefaultCoordinator::new(
+                event_publisher,
+                Box::new(RaftTransactionStoreManager::new(node.clone())),
+                Arc::new(RaftLocker::new(node.clone())),
+                Some(node.clone()),
+            ));
+            let service = TCGrpcService::new(coordinator.clone());
+            let addr = cluster.nodes[id].grpc_addr.parse().unwrap();
+            tokio::spawn(
+                Server::builder()
+                    .add_service(ResourceManagerServiceServer::new(service))
+                    .serve(addr),
+            );
+            coordinators.insert(*id, coordinator);
+        }
+        coordinators
+    }
+
+    async fn registered(coordinator: &DefaultCoordinator) -> Option<TCResource> {
+        let resources = coordinator.resources.read().await;
+        resources
+            .get(&ResourceId::from(RESOURCE_ID.to_string()))
+            .and_then(|instances| instances.first().cloned())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn resource_stream_follows_leader_after_failover() {
+        let mut cluster = TestCluster::start(3, 1_000).await;
+        let coordinators = serve(&cluster).await;
+        let (leader_id, _) = cluster.leader().await;
+        let entry_id = *cluster.nodes.keys().find(|id| **id != leader_id).unwrap();
+
+        let endpoint = format!("http://{}", cluster.nodes[&entry_id].grpc_addr);
+        let mut client = wait_until(|| async {
+            ResourceManagerServiceClient::connect(endpoint.clone())
+                .await
+                .ok()
+        })
+        .await;
+        let (request_tx, request_rx) = mpsc::channel(8);
+        request_tx
+            .send(ResourceRequest {
+                request: Some(resource_request::Request::Resource(ResourceProto {
+                    resource_group_id: "group".to_string(),
+                    resource_id: RESOURCE_ID.to_string(),
+                    client_id: 7,
+                    branch_type: 0,
+                })),
+            })
+            .await
+            .unwrap();
+        let mut instructions = client
+            .register_resource(ReceiverStream::new(request_rx))
+            .await
+            .unwrap()
+            .into_inner();
+        wait_until(|| async { registered(&coordinators[&leader_id]).await }).await;
+
+        // leader 宕机后，入口节点把同一条 RM 流挂接到新 leader 并重放注册
+        cluster.stop(leader_id);
+        let (new_leader_id, _) = cluster.leader().await;
+        let resource =
+            wait_until(|| async { registered(&coordinators[&new_leader_id]).await }).await;
+        wait_until(|| async {
+            registered(&coordinators[&leader_id])
+                .await
+                .is_none()
+                .then_some(())
+        })
+        .await;
+
+        resource
+            .response_tx
+            .send(Ok(ResourceInstruction {
+                instruction: None,
+                instruction_id: 42,
+            }))
+            .await
+            .unwrap();
+        let instruction = tokio::time::timeout(Duration::from_secs(5), instructions.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(instruction.instruction_id, 42);
+        drop(request_tx);
+    }
+}
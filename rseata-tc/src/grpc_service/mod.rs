@@ -1,3 +1,4 @@
+mod forward;
 mod rm_service;
 mod tm_service;
 
@@ -6,8 +7,9 @@ use crate::Context;
 use rseata_proto::rseata_proto::proto::resource_manager_service_server::ResourceManagerServiceServer;
 use rseata_proto::rseata_proto::proto::transaction_manager_service_server::TransactionManagerServiceServer;
 
-use std::sync::Arc;
-use tonic::transport::Server;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tonic::transport::{Channel, Server};
 use crate::coordinator::default_coordinator::DefaultCoordinator;
 
 pub(crate) async fn start(ctx: Arc<Context>) -> anyhow::Result<()> {
@@ -30,12 +32,23 @@ pub(crate) async fn start(ctx: Arc<Context>) -> anyhow::Result<()> {
 #[derive(Clone)]
 pub(crate) struct TCGrpcService {
     coordinator: Arc<DefaultCoordinator>,
+    // raft 集群模式下到各 leader 的连接，跟随者据此转发请求
+    leader_channels: Arc<Mutex<HashMap<String, Channel>>>,
 }
 
 impl TCGrpcService {
     pub fn new(coordinator: Arc<DefaultCoordinator>) -> Self {
         Self {
             coordinator,
+            leader_channels: Default::default(),
         }
     }
 }
+
+// 已带状态码的错误原样返回，其余都是 TC 内部错误
+fn to_status(e: anyhow::Error) -> tonic::Status {
+    match e.downcast::<tonic::Status>() {
+        Ok(status) => status,
+        Err(e) => tonic::Status::internal(format!("{e:#}")),
+    }
+}
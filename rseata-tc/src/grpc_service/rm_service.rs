@@ -1,20 +1,24 @@
-use crate::grpc_service::TCGrpcService;
+use crate::grpc_service::{TCGrpcService, to_status};
 use crate::resource::TCResource;
+use crate::resource::pending_instructions::PendingInstructions;
 use crate::types::ConnectionId;
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
+use rseata_core::branch::BranchStatus;
 use rseata_core::branch::branch_manager_outbound::BranchManagerOutbound;
+use rseata_core::error::TransactionError;
 use rseata_core::resource::DefaultResource;
 use rseata_core::resource::resource_registry::ResourceRegistry;
 use rseata_proto::rseata_proto::proto::resource_manager_service_server::ResourceManagerService;
 use rseata_proto::rseata_proto::proto::{
     BaseResponse, BranchRegisterRequest, BranchRegisterResponse, BranchReportRequest,
     BranchReportResponse, LockQueryRequest, LockQueryResponse, ResourceInstruction, ResourceProto,
-    UnregisterResourceResponse,
+    ResourceRequest, UnregisterResourceResponse, resource_request,
 };
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::sync::mpsc;
-use tonic::codegen::tokio_stream::Stream;
+use tonic::codegen::tokio_stream::{Stream, StreamExt};
 use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
 use uuid::Uuid;
@@ -27,82 +31,14 @@ impl ResourceManagerService for TCGrpcService {
 
     async fn register_resource(
         &self,
-        request: Request<Streaming<ResourceProto>>,
+        request: Request<Streaming<ResourceRequest>>,
     ) -> std::result::Result<Response<Self::RegisterResourceStream>, Status> {
-        let mut incoming_stream = request.into_inner();
-        let (response_tx, response_rx) = mpsc::channel::<Result<ResourceInstruction, Status>>(128);
-
-        let (request_tx, request_rx) = mpsc::channel::<Result<ResourceProto>>(128);
-
-        let cm = self.coordinator.clone();
-
-        tokio::spawn(async move {
-            loop {
-                let response_tx_cloned = response_tx.clone();
-                match incoming_stream.message().await {
-                    Ok(Some(resource_msg)) => {
-                        tracing::debug!("Resource message: {:?}", resource_msg); 
-                        let resource_info = DefaultResource {
-                            group_id: resource_msg.resource_group_id.clone(),
-                            resource_id: resource_msg.resource_id.clone().into(),
-                            branch_type: resource_msg.branch_type.into(),
-                            client_id: resource_msg.client_id.into(),
-                        };
-                        let resource_holder = TCResource {
-                            connection_id: ConnectionId::from(Uuid::new_v4().as_u128() as u64),
-                            resource: resource_info,
-                            response_tx: response_tx_cloned,
-                        };
-
-                        // 判断注册成功否？  失败则发送消息？
-                        cm.register_resource(&resource_holder).await;
-                    }
-                    Ok(None) => {
-                        tracing::debug!("Resource stream ended normally for connection ",);
-                        request_tx
-                            .send(Err(anyhow!("RegisterResourceStream ended!")))
-                            .await
-                            .ok();
-                        break;
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "Error receiving branch register request for connection {}",
-                            e
-                        );
-                        request_tx
-                            .send(Err(anyhow!("RegisterResourceStream ended!")))
-                            .await
-                            .ok();
-                        break;
-                    }
-                }
-            }
-
-            // 连接关闭时清理资源
-            {
-                request_tx.closed().await;
-                // let mut managers = registered_managers.write().await;
-                // let closed = managers
-                //     .values()
-                //     .flatten()
-                //     .find(|e| e.connection_id == connection_id);
-                // if let Some(closed) = closed {
-                //     let rm_id = ResourceId(closed.resource_id.clone());
-                //     let rms = managers.get_mut(&rm_id);
-                //     if let Some(rm_vec) = rms {
-                //         let find = rm_vec.iter().find(|e| e.connection_id == connection_id);
-                //         if let Some(r) = find {
-                //             rm_vec.retain(|r| r.connection_id != connection_id);
-                //         }
-                //     }
-                // }
-            }
-
-            tracing::debug!("Branch register stream processing finished for connection ");
-        });
-
-        let output_stream = ReceiverStream::new(response_rx);
+        let incoming_stream = request.into_inner();
+        // 集群模式下由入口节点把流挂接到 leader，leader 变化时重新挂接
+        let output_stream = match self.coordinator.core.cluster.clone() {
+            Some(cluster) => self.route_resource_stream(cluster, incoming_stream),
+            None => self.attach_resource_stream(incoming_stream),
+        };
         tracing::debug!("register_resource registered");
         Ok(Response::new(
             Box::pin(output_stream) as Self::RegisterResourceStream
@@ -111,7 +47,7 @@ impl ResourceManagerService for TCGrpcService {
 
     async fn unregister_resource(
         &self,
-        request: Request<ResourceProto>,
+        _request: Request<ResourceProto>,
     ) -> std::result::Result<Response<UnregisterResourceResponse>, Status> {
         todo!()
     }
@@ -119,10 +55,13 @@ impl ResourceManagerService for TCGrpcService {
     async fn branch_register(
         &self,
         request: Request<BranchRegisterRequest>,
-    ) -> std::result::Result<Response<BranchRegisterResponse>, Status> { 
+    ) -> std::result::Result<Response<BranchRegisterResponse>, Status> {
+        if let Some(mut leader) = self.forward_rm().await? {
+            return leader.branch_register(request.into_inner()).await;
+        }
         let request = request.into_inner();
         tracing::info!("Branch register----{:?}", request);
-        let branch_id = self
+        let branch_id = match self
             .coordinator
             .branch_register(
                 request.branch_type.into(),
@@ -133,7 +72,15 @@ impl ResourceManagerService for TCGrpcService {
                 request.lock_keys,
             )
             .await
-            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        {
+            Ok(branch_id) => branch_id,
+            Err(e) => {
+                return Ok(Response::new(BranchRegisterResponse {
+                    branch_id: 0,
+                    base: register_failure(e)?.some(),
+                }));
+            }
+        };
 
         Ok(Response::new(BranchRegisterResponse {
             branch_id: branch_id.into(),
@@ -145,6 +92,9 @@ impl ResourceManagerService for TCGrpcService {
         &self,
         request: Request<BranchReportRequest>,
     ) -> std::result::Result<Response<BranchReportResponse>, Status> {
+        if let Some(mut leader) = self.forward_rm().await? {
+            return leader.branch_report(request.into_inner()).await;
+        }
         let request = request.into_inner();
         tracing::info!("Branch report----{:?}", request);
         self.coordinator
@@ -164,6 +114,9 @@ impl ResourceManagerService for TCGrpcService {
         &self,
         request: Request<LockQueryRequest>,
     ) -> std::result::Result<Response<LockQueryResponse>, Status> {
+        if let Some(mut leader) = self.forward_rm().await? {
+            return leader.lock_query(request.into_inner()).await;
+        }
         let request = request.into_inner();
         tracing::info!("Lock query----{:?}", request);
 
@@ -184,3 +137,130 @@ impl ResourceManagerService for TCGrpcService {
         }))
     }
 }
+
+impl TCGrpcService {
+    /// 在本节点注册 RM 流上的资源，返回下发给 RM 的二阶段指令流
+    pub(crate) fn attach_resource_stream<S>(
+        &self,
+        mut incoming_stream: S,
+    ) -> ReceiverStream<Result<ResourceInstruction, Status>>
+    where
+        S: Stream<Item = Result<ResourceRequest, Status>> + Send + Unpin + 'static,
+    {
+        let (response_tx, response_rx) = mpsc::channel::<Result<ResourceInstruction, Status>>(128);
+
+        let (request_tx, _request_rx) = mpsc::channel::<Result<ResourceProto>>(128);
+
+        let cm = self.coordinator.clone();
+        let pending = Arc::new(PendingInstructions::default());
+        let connection_id = ConnectionId::from(Uuid::new_v4().as_u128() as u64);
+
+        tokio::spawn(async move {
+            let mut registered = Vec::new();
+            loop {
+                let response_tx_cloned = response_tx.clone();
+                match incoming_stream.next().await {
+                    Some(Ok(ResourceRequest {
+                        request: Some(resource_request::Request::Resource(resource_msg)),
+                    })) => {
+                        tracing::debug!("Resource message: {:?}", resource_msg);
+                        let resource_info = DefaultResource {
+                            group_id: resource_msg.resource_group_id.clone(),
+                            resource_id: resource_msg.resource_id.clone().into(),
+                            branch_type: resource_msg.branch_type.into(),
+                            client_id: resource_msg.client_id.into(),
+                        };
+                        let resource_holder = TCResource {
+                            connection_id,
+                            resource: resource_info,
+                            response_tx: response_tx_cloned,
+                            pending: pending.clone(),
+                        };
+
+                        // 判断注册成功否？  失败则发送消息？
+                        cm.register_resource(&resource_holder).await;
+                        registered.push(resource_holder);
+                    }
+                    Some(Ok(ResourceRequest {
+                        request: Some(resource_request::Request::Result(result)),
+                    })) => {
+                        tracing::debug!("Instruction result: {:?}", result);
+                        let status = BranchStatus::from(result.status);
+                        if !pending.complete(result.instruction_id, status).await {
+                            tracing::warn!(
+                                "Instruction {} result {:?} arrived after timeout, message: {:?}",
+                                result.instruction_id,
+                                status,
+                                result.message
+                            );
+                        }
+                    }
+                    Some(Ok(ResourceRequest { request: None })) => {}
+                    None => {
+                        tracing::debug!("Resource stream ended normally for connection ",);
+                        request_tx
+                            .send(Err(anyhow!("RegisterResourceStream ended!")))
+                            .await
+                            .ok();
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        eprintln!(
+                            "Error receiving branch register request for connection {}",
+                            e
+                        );
+                        request_tx
+                            .send(Err(anyhow!("RegisterResourceStream ended!")))
+                            .await
+                            .ok();
+                        break;
+                    }
+                }
+            }
+
+            // 连接关闭时注销该连接上的资源，未完成的指令立即失败并由 OutboundQueue 暂存
+            request_tx.closed().await;
+            pending.close().await;
+            for resource in registered {
+                cm.unregister_resource(&resource).await;
+            }
+
+            tracing::debug!("Branch register stream processing finished for connection ");
+        });
+
+        ReceiverStream::new(response_rx)
+    }
+}
+
+// 只有锁冲突属于业务结果，通过 result_code 返回；已带状态码的错误原样返回，其余都是 TC 内部错误
+fn register_failure(e: anyhow::Error) -> Result<BaseResponse, Status> {
+    if let Some(TransactionError::LockConflict { .. }) = e.downcast_ref::<TransactionError>() {
+        return Ok(BaseResponse::lock_conflict(&e.to_string()));
+    }
+    Err(to_status(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_lock_conflicts_map_to_lock_conflict() {
+        let conflict = anyhow::Error::from(TransactionError::LockConflict {
+            info: "xid, t:1".to_string(),
+        });
+        assert!(register_failure(conflict).unwrap().is_lock_conflict());
+
+        let store = anyhow::anyhow!("connection refused").context("acquire lock failed: xid");
+        assert_eq!(
+            register_failure(store).unwrap_err().code(),
+            tonic::Code::Internal
+        );
+
+        let missing = anyhow::Error::from(Status::invalid_argument("no such global session"));
+        assert_eq!(
+            register_failure(missing).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::grpc_service::TCGrpcService;
+use crate::grpc_service::{TCGrpcService, to_status};
 use async_trait::async_trait;
 use rseata_core::transaction::transaction_manager::TransactionManager;
 use rseata_core::types::GlobalStatus;
@@ -16,6 +16,9 @@ impl TransactionManagerService for TCGrpcService {
         request: tonic::Request<GlobalBeginRequest>,
     ) -> Result<tonic::Response<GlobalBeginResponse>, tonic::Status> {
         tracing::debug!("global_begin ----: {:?}", request);
+        if let Some(mut leader) = self.forward_tm().await? {
+            return leader.global_begin(request.into_inner()).await;
+        }
         let request = request.into_inner();
 
         let xid = self
@@ -40,12 +43,15 @@ impl TransactionManagerService for TCGrpcService {
         request: tonic::Request<GlobalCommitRequest>,
     ) -> Result<tonic::Response<GlobalCommitResponse>, tonic::Status> {
         tracing::debug!("global_commit ----: {:?}", request);
+        if let Some(mut leader) = self.forward_tm().await? {
+            return leader.global_commit(request.into_inner()).await;
+        }
         let request = request.into_inner();
         let status = self
             .coordinator
             .commit(request.xid.into())
             .await
-            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(tonic::Response::new(GlobalCommitResponse {
             global_status: status.code(),
@@ -58,12 +64,15 @@ impl TransactionManagerService for TCGrpcService {
         request: tonic::Request<GlobalRollbackRequest>,
     ) -> Result<tonic::Response<GlobalRollbackResponse>, tonic::Status> {
         tracing::debug!("global_rollback ----: {:?}", request);
+        if let Some(mut leader) = self.forward_tm().await? {
+            return leader.global_rollback(request.into_inner()).await;
+        }
         let request = request.into_inner();
         let status = self
             .coordinator
             .rollback(request.xid.into())
             .await
-            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(tonic::Response::new(GlobalRollbackResponse {
             global_status: status.code(),
@@ -76,12 +85,15 @@ impl TransactionManagerService for TCGrpcService {
         request: tonic::Request<GlobalStatusRequest>,
     ) -> Result<tonic::Response<GlobalStatusResponse>, tonic::Status> {
         tracing::debug!("get_global_status ----: {:?}", request);
+        if let Some(mut leader) = self.forward_tm().await? {
+            return leader.get_global_status(request.into_inner()).await;
+        }
         let request = request.into_inner();
         let status = self
             .coordinator
             .get_status(request.xid.into())
             .await
-            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(tonic::Response::new(GlobalStatusResponse {
             global_status: status.code(),
@@ -94,16 +106,21 @@ impl TransactionManagerService for TCGrpcService {
         request: tonic::Request<GlobalReportRequest>,
     ) -> Result<tonic::Response<GlobalReportResponse>, tonic::Status> {
         tracing::debug!("global_report ----: {:?}", request);
+        if let Some(mut leader) = self.forward_tm().await? {
+            return leader.global_report(request.into_inner()).await;
+        }
         let request = request.into_inner();
         let status = self
             .coordinator
             .global_report(
                 &request.xid.into(),
+                request.application_id,
+                request.transaction_service_group,
                 GlobalStatus::from_code(request.global_status)
                     .map_err(|e| tonic::Status::internal(e.to_string()))?,
             )
             .await
-            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(tonic::Response::new(GlobalReportResponse {
             global_status: status.code(),
@@ -1,36 +1,89 @@
 use crate::audit_logger::AuditLogger;
 use crate::context::Context;
+use crate::coordinator::default_coordinator::DefaultCoordinator;
+use crate::coordinator::default_core_holder::retry_worker::RetryConfig;
 use crate::event::audit_log_event_handler::AuditLogEventHandler;
 use rseata_core::event::defaults::default_event_handler_chain::DefaultEventHandlerChain;
 use rseata_core::event::defaults::event_publisher::DefaultEventPublisher;
 use rseata_core::event::event_handler_chain::EventHandlerChain;
-use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
-use rseata_core::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+use rseata_core::session::defaults::default_session_manager::DefaultSessionLifecycleListener;
 use std::sync::Arc;
-use crate::coordinator::default_coordinator::DefaultCoordinator;
+use std::time::Duration;
 
 mod audit_logger;
 mod config;
 mod context;
 pub(crate) mod coordinator;
-pub(crate) mod error;
 mod event;
 pub(crate) mod grpc_service;
 pub(crate) mod init;
 pub(crate) mod resource;
+pub(crate) mod store;
 pub(crate) mod types;
 pub(crate) mod web;
 
 pub async fn start_server() -> anyhow::Result<()> {
+    start_server_with_listeners(vec![]).await
+}
+
+/// 启动 TC，并注册自定义的会话生命周期监听器
+pub async fn start_server_with_listeners(
+    listeners: Vec<Arc<DefaultSessionLifecycleListener>>,
+) -> anyhow::Result<()> {
     init::init().await?;
-    let session_manager = Arc::new(DefaultSessionManager::new(
-        String::from("DefaultSessionManager"),
-        Box::new(MemeryTransactionStoreManager::default()),
-    ));
 
     let (event_publisher, audit_logger) = start_event_system().await;
 
-    let coordinator_manager = Arc::new(DefaultCoordinator::new(event_publisher));
+    let store_config = config::get_store_config();
+    let cluster = store::start_cluster(&store_config).await?;
+    let store =
+        store::open_transaction_store(&store_config, event_publisher.clone(), cluster.clone())
+            .await?;
+    let locker = store::open_locker(&store_config, cluster.clone()).await?;
+    let coordinator_manager = Arc::new(DefaultCoordinator::new(
+        event_publisher,
+        store,
+        locker,
+        cluster.clone(),
+    ));
+    for listener in listeners {
+        coordinator_manager
+            .core
+            .session_manager
+            .add_session_lifecycle_listener(listener);
+    }
+    match cluster {
+        // 集群模式下由新当选的 leader 接管未结束的全局事务
+        Some(cluster) => {
+            let core = coordinator_manager.core.clone();
+            let mut leader = cluster.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    if *leader.borrow_and_update()
+                        && let Err(e) = core.recover_sessions().await
+                    {
+                        tracing::error!("Recover sessions failed: {:?}", e);
+                    }
+                    if leader.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        None => coordinator_manager.core.recover_sessions().await?,
+    }
+    coordinator_manager
+        .core
+        .start_timeout_checker(Duration::from_millis(
+            config::get_env_timeout_check_interval_millis(),
+        ));
+    coordinator_manager
+        .core
+        .start_retry_workers(RetryConfig::from_env());
+    coordinator_manager.core.start_failed_session_purger(
+        Duration::from_millis(config::get_env_failed_session_purge_interval_millis()),
+        config::get_env_failed_session_retention_millis(),
+    );
     let ctx = Context::new_arc(coordinator_manager, audit_logger);
     tokio::spawn(grpc_service::start(ctx.clone()));
     web::start(ctx).await?;
@@ -1,9 +1,14 @@
+pub mod outbound_queue;
+pub mod pending_instructions;
+
+use crate::resource::pending_instructions::PendingInstructions;
 use crate::types::ConnectionId;
 use async_trait::async_trait;
 use rseata_core::branch::BranchType;
 use rseata_core::resource::{DefaultResource, Resource};
 use rseata_core::types::{ClientId, ResourceId};
 use rseata_proto::rseata_proto::proto::ResourceInstruction;
+use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 use tonic::Status;
 
@@ -12,6 +17,8 @@ pub struct TCResource {
     pub connection_id: ConnectionId,
     pub resource: DefaultResource,
     pub response_tx: Sender<Result<ResourceInstruction, Status>>,
+    // 同一条 RegisterResource 流上等待回传结果的指令
+    pub pending: Arc<PendingInstructions>,
 }
 
 #[async_trait]
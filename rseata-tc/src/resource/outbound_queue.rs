@@ -0,0 +1,446 @@
+use crate::config;
+use crate::resource::TCResource;
+use rseata_core::branch::{BranchStatus, BranchType};
+use rseata_core::error::TransactionError;
+use rseata_core::session::defaults::default_branch_session::{
+    BranchInstructionKind, DefaultBranchSession, QueuedInstruction,
+};
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
+use rseata_core::types::{ClientId, ResourceId};
+use rseata_proto::rseata_proto::proto::{
+    BranchCommitInstruction, BranchPrepareInstruction, BranchRollbackInstruction,
+    ResourceInstruction, resource_instruction,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// TC 向 RM 下发二阶段指令的出口
+///
+/// RM 不在线时指令随分支会话持久化，RM 重连后重新下发；
+/// 原 RM 超过宽限期仍未恢复时，可由其他实例执行的分支交给同一资源的其他在线实例
+pub struct OutboundQueue {
+    resources: Arc<RwLock<HashMap<ResourceId, Vec<TCResource>>>>,
+    session_manager: Arc<DefaultSessionManager>,
+    failover_grace: Duration,
+    instruction_timeout: Duration,
+}
+
+impl OutboundQueue {
+    pub(crate) fn new(
+        resources: Arc<RwLock<HashMap<ResourceId, Vec<TCResource>>>>,
+        session_manager: Arc<DefaultSessionManager>,
+    ) -> Self {
+        Self {
+            resources,
+            session_manager,
+            failover_grace: Duration::from_millis(
+                config::get_env_instruction_failover_grace_millis(),
+            ),
+            instruction_timeout: Duration::from_millis(config::get_env_instruction_timeout_millis()),
+        }
+    }
+
+    /// 下发二阶段提交指令
+    pub(crate) async fn send_commit(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+        application_data: String,
+    ) -> Result<BranchStatus, TransactionError> {
+        let instruction = resource_instruction::Instruction::Commit(BranchCommitInstruction {
+            branch_type: branch_session.branch_type.into(),
+            xid: branch_session.xid.to_string(),
+            branch_id: branch_session.branch_id.into(),
+            resource_id: resource_id_string(branch_session),
+            application_data,
+        });
+        self.send(
+            global_session,
+            branch_session,
+            BranchInstructionKind::Commit,
+            instruction,
+        )
+        .await
+    }
+
+    /// 下发二阶段回滚指令
+    pub(crate) async fn send_rollback(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        let instruction = resource_instruction::Instruction::Rollback(BranchRollbackInstruction {
+            branch_type: branch_session.branch_type.into(),
+            xid: branch_session.xid.to_string(),
+            branch_id: branch_session.branch_id.into(),
+            resource_id: resource_id_string(branch_session),
+            application_data: branch_session.application_data.clone().unwrap_or_default(),
+        });
+        self.send(
+            global_session,
+            branch_session,
+            BranchInstructionKind::Rollback,
+            instruction,
+        )
+        .await
+    }
+
+    /// 要求分支注册时的 RM 准备分支，准备在全局事务提交前完成，不暂存
+    pub(crate) async fn request_prepare(
+        &self,
+        branch_session: &DefaultBranchSession,
+    ) -> Result<BranchStatus, TransactionError> {
+        let instruction = resource_instruction::Instruction::Prepare(BranchPrepareInstruction {
+            branch_type: branch_session.branch_type.into(),
+            xid: branch_session.xid.to_string(),
+            branch_id: branch_session.branch_id.into(),
+            resource_id: resource_id_string(branch_session),
+            application_data: branch_session.application_data.clone().unwrap_or_default(),
+        });
+        self.request(branch_session, instruction).await
+    }
+
+    /// 向分支所属的 RM 下发指令，并等待 RM 回传的分支状态
+    ///
+    /// RM 不可达时记录暂存指令并返回 ResourceUnavailable
+    async fn send(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+        kind: BranchInstructionKind,
+        instruction: resource_instruction::Instruction,
+    ) -> Result<BranchStatus, TransactionError> {
+        let resource_id = resource_id_of(branch_session)?;
+
+        let delivery = match self.select_resource(&resource_id, branch_session).await {
+            Some(resource) => self.deliver(&resource, instruction).await,
+            None => Delivery::Unreachable,
+        };
+        match delivery {
+            Delivery::Done(status) => {
+                if branch_session.queued_instruction.is_some() {
+                    self.session_manager
+                        .update_branch_queued_instruction(global_session, branch_session, None)
+                        .await?;
+                }
+                Ok(status)
+            }
+            Delivery::Unreachable => Err(self
+                .enqueue(global_session, branch_session, resource_id, kind)
+                .await),
+            Delivery::Failed(e) => Err(e),
+        }
+    }
+
+    /// 向分支注册时的 RM 发送一次性指令并等待结果，RM 不可达时直接失败，不暂存
+    async fn request(
+        &self,
+        branch_session: &DefaultBranchSession,
+        instruction: resource_instruction::Instruction,
+    ) -> Result<BranchStatus, TransactionError> {
+        let resource_id = resource_id_of(branch_session)?;
+        let unavailable = || TransactionError::ResourceUnavailable {
+            resource_id: resource_id.clone(),
+            client_id: branch_session.client_id,
+        };
+        let Some(resource) = self.select_resource(&resource_id, branch_session).await else {
+            return Err(unavailable());
+        };
+        match self.deliver(&resource, instruction).await {
+            Delivery::Done(status) => Ok(status),
+            Delivery::Unreachable => Err(unavailable()),
+            Delivery::Failed(e) => Err(e),
+        }
+    }
+
+    async fn deliver(
+        &self,
+        resource: &TCResource,
+        instruction: resource_instruction::Instruction,
+    ) -> Delivery {
+        let (instruction_id, result_rx) = resource.pending.register().await;
+        if resource
+            .response_tx
+            .send(Ok(ResourceInstruction {
+                instruction_id,
+                instruction: Some(instruction),
+            }))
+            .await
+            .is_err()
+        {
+            resource.pending.cancel(instruction_id).await;
+            return Delivery::Unreachable;
+        }
+
+        match tokio::time::timeout(self.instruction_timeout, result_rx).await {
+            Ok(Ok(status)) => Delivery::Done(status),
+            // RM 断开连接，等待者被丢弃
+            Ok(Err(_)) => Delivery::Unreachable,
+            Err(_) => {
+                resource.pending.cancel(instruction_id).await;
+                Delivery::Failed(TransactionError::new(format!(
+                    "instruction {instruction_id} timeout after {}ms",
+                    self.instruction_timeout.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// 判断暂存的指令能否由该 RM 实例执行
+    pub(crate) fn deliverable_to(
+        &self,
+        branch_session: &DefaultBranchSession,
+        resource_id: &ResourceId,
+        client_id: ClientId,
+    ) -> bool {
+        let Some(queued) = branch_session.queued_instruction else {
+            return false;
+        };
+        branch_session.resource_id.as_ref() == Some(resource_id)
+            && (branch_session.client_id == client_id
+                || self.can_fail_over(branch_session, &queued))
+    }
+
+    // 优先选择分支注册时的 RM，允许接管时选择同一资源的任一在线实例
+    async fn select_resource(
+        &self,
+        resource_id: &ResourceId,
+        branch_session: &DefaultBranchSession,
+    ) -> Option<TCResource> {
+        let resources = self.resources.read().await;
+        let online = resources
+            .get(resource_id)?
+            .iter()
+            .filter(|rs| !rs.response_tx.is_closed());
+        let mut fallback = None;
+        for resource in online {
+            if resource.resource.client_id == branch_session.client_id {
+                return Some(resource.clone());
+            }
+            fallback.get_or_insert(resource);
+        }
+        branch_session
+            .queued_instruction
+            .is_some_and(|queued| self.can_fail_over(branch_session, &queued))
+            .then(|| fallback.cloned())
+            .flatten()
+    }
+
+    // AT 的 undo log 在共享的数据库中，TCC、Saga 按 application_data 中的动作名找回处理器，
+    // 其他实例都能执行；XA 分支绑定在原 RM 的数据库连接上，只能等原 RM 恢复
+    fn can_fail_over(
+        &self,
+        branch_session: &DefaultBranchSession,
+        queued: &QueuedInstruction,
+    ) -> bool {
+        let executable_elsewhere = match branch_session.branch_type {
+            BranchType::AT | BranchType::TCC | BranchType::SAGA => true,
+            BranchType::XA => false,
+        };
+        executable_elsewhere
+            && Duration::from_millis(now_millis().saturating_sub(queued.queued_at_millis))
+                >= self.failover_grace
+    }
+
+    // 保留首次入队时间，宽限期从 RM 第一次不可达开始计算
+    async fn enqueue(
+        &self,
+        global_session: &DefaultGlobalSession,
+        branch_session: &DefaultBranchSession,
+        resource_id: ResourceId,
+        kind: BranchInstructionKind,
+    ) -> TransactionError {
+        let queued = match branch_session.queued_instruction {
+            Some(queued) if queued.kind == kind => queued,
+            _ => QueuedInstruction {
+                kind,
+                queued_at_millis: now_millis(),
+            },
+        };
+        if let Err(e) = self
+            .session_manager
+            .update_branch_queued_instruction(global_session, branch_session, Some(queued))
+            .await
+        {
+            tracing::error!(
+                "Queue instruction failed: {}, {:?}",
+                branch_session.branch_id,
+                e
+            );
+        }
+        TransactionError::ResourceUnavailable {
+            resource_id,
+            client_id: branch_session.client_id,
+        }
+    }
+}
+
+enum Delivery {
+    Done(BranchStatus),
+    Unreachable,
+    Failed(TransactionError),
+}
+
+fn resource_id_of(branch_session: &DefaultBranchSession) -> Result<ResourceId, TransactionError> {
+    branch_session
+        .resource_id
+        .clone()
+        .ok_or_else(|| TransactionError::new(String::from("resource_id not set")))
+}
+
+fn resource_id_string(branch_session: &DefaultBranchSession) -> String {
+    branch_session
+        .resource_id
+        .as_ref()
+        .map(|r| r.0.clone())
+        .unwrap_or_default()
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::default_core_holder::DefaultCoreHolder;
+    use crate::coordinator::default_core_holder::tests::{
+        RESOURCE, Resources, connect_rm, memory_holder_with_resources, reload, session_with,
+    };
+    use rseata_core::branch::XA_IDLE;
+    use rseata_core::session::session_manager::SessionManager;
+    use rseata_core::types::GlobalStatus;
+
+    fn queue(holder: &DefaultCoreHolder, resources: &Resources) -> OutboundQueue {
+        OutboundQueue {
+            resources: resources.clone(),
+            session_manager: holder.session_manager.clone(),
+            failover_grace: Duration::ZERO,
+            instruction_timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_rm_queues_instruction() {
+        let (holder, _resources) = memory_holder_with_resources();
+        let (xid, session) = session_with(&holder, &[(BranchType::AT, "")]).await;
+
+        let result = holder
+            .outbound
+            .send_commit(&session, &session.branch_sessions[0], String::new())
+            .await;
+        assert!(matches!(
+            result,
+            Err(TransactionError::ResourceUnavailable { .. })
+        ));
+        let session = reload(&holder, &xid).await;
+        let queued = session.branch_sessions[0].queued_instruction.unwrap();
+        assert_eq!(queued.kind, BranchInstructionKind::Commit);
+
+        // 再次失败时保留首次入队时间，改为回滚时重新计时
+        holder
+            .outbound
+            .send_commit(&session, &session.branch_sessions[0], String::new())
+            .await
+            .unwrap_err();
+        let session = reload(&holder, &xid).await;
+        assert_eq!(session.branch_sessions[0].queued_instruction, Some(queued));
+        holder
+            .outbound
+            .send_rollback(&session, &session.branch_sessions[0])
+            .await
+            .unwrap_err();
+        let session = reload(&holder, &xid).await;
+        let requeued = session.branch_sessions[0].queued_instruction.unwrap();
+        assert_eq!(requeued.kind, BranchInstructionKind::Rollback);
+    }
+
+    #[tokio::test]
+    async fn queued_instruction_is_redelivered_on_reconnect() {
+        let (holder, resources) = memory_holder_with_resources();
+        // 唯一的 AT 分支由 TC 直接认定提交，这里用 TCC 分支
+        let (xid, session) = session_with(&holder, &[(BranchType::TCC, "order_action")]).await;
+        for status in [GlobalStatus::Committing, GlobalStatus::CommitRetrying] {
+            holder
+                .session_manager
+                .update_global_session_status(&session, status)
+                .await
+                .unwrap();
+        }
+        holder
+            .outbound
+            .send_commit(&session, &session.branch_sessions[0], String::new())
+            .await
+            .unwrap_err();
+
+        let rm = connect_rm(&resources, RESOURCE, 1, |_| BranchStatus::PhaseTwoCommitted).await;
+        holder
+            .redeliver_queued(&ResourceId::from(RESOURCE), ClientId::from(1))
+            .await;
+
+        assert!(matches!(
+            rm.received()[..],
+            [resource_instruction::Instruction::Commit(_)]
+        ));
+        assert_eq!(
+            holder.session_manager.finished_status(&xid),
+            Some(GlobalStatus::Committed)
+        );
+    }
+
+    #[tokio::test]
+    async fn only_branches_other_instances_can_execute_fail_over() {
+        let (holder, resources) = memory_holder_with_resources();
+        let outbound = queue(&holder, &resources);
+        let (xid, session) = session_with(
+            &holder,
+            &[
+                (BranchType::AT, ""),
+                (BranchType::TCC, "order_action"),
+                (BranchType::XA, XA_IDLE),
+            ],
+        )
+        .await;
+        // 注册分支的 RM 离线，指令先入队
+        for branch_session in &session.branch_sessions {
+            outbound
+                .send_rollback(&session, branch_session)
+                .await
+                .unwrap_err();
+        }
+
+        let other = connect_rm(&resources, RESOURCE, 2, |_| {
+            BranchStatus::PhaseTwoRollbacked
+        })
+        .await;
+        let session = reload(&holder, &xid).await;
+        let resource_id = ResourceId::from(RESOURCE);
+        for branch_session in &session.branch_sessions {
+            let fails_over = branch_session.branch_type != BranchType::XA;
+            assert_eq!(
+                outbound.deliverable_to(branch_session, &resource_id, ClientId::from(2)),
+                fails_over
+            );
+            let result = outbound.send_rollback(&session, branch_session).await;
+            if fails_over {
+                assert_eq!(result.unwrap(), BranchStatus::PhaseTwoRollbacked);
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(TransactionError::ResourceUnavailable { .. })
+                ));
+            }
+        }
+        // XA 分支只会交给注册它的 RM
+        assert_eq!(other.received().len(), 2);
+        let session = reload(&holder, &xid).await;
+        let xa = &session.branch_sessions[2];
+        assert!(outbound.deliverable_to(xa, &resource_id, ClientId::from(1)));
+    }
+}
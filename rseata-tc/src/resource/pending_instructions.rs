@@ -0,0 +1,74 @@
+use rseata_core::branch::BranchStatus;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::{Mutex, oneshot};
+
+// 指令 ID 在 TC 进程内全局唯一
+static NEXT_INSTRUCTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 已下发给 RM、等待其回传执行结果的指令
+#[derive(Debug, Default)]
+pub struct PendingInstructions {
+    state: Mutex<PendingState>,
+}
+
+#[derive(Debug, Default)]
+struct PendingState {
+    waiters: HashMap<u64, oneshot::Sender<BranchStatus>>,
+    // RM 连接已断开，不再接受新的等待者
+    closed: bool,
+}
+
+impl PendingInstructions {
+    /// 登记等待者；连接已断开时返回的接收端立即失败
+    pub(crate) async fn register(&self) -> (u64, oneshot::Receiver<BranchStatus>) {
+        let instruction_id = NEXT_INSTRUCTION_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        let mut state = self.state.lock().await;
+        if !state.closed {
+            state.waiters.insert(instruction_id, tx);
+        }
+        (instruction_id, rx)
+    }
+
+    /// RM 回传结果，返回是否有等待者
+    pub(crate) async fn complete(&self, instruction_id: u64, status: BranchStatus) -> bool {
+        match self.state.lock().await.waiters.remove(&instruction_id) {
+            Some(tx) => tx.send(status).is_ok(),
+            None => false,
+        }
+    }
+
+    pub(crate) async fn cancel(&self, instruction_id: u64) {
+        self.state.lock().await.waiters.remove(&instruction_id);
+    }
+
+    /// RM 连接断开，让所有等待者立即失败，由 OutboundQueue 暂存指令
+    pub(crate) async fn close(&self) {
+        let mut state = self.state.lock().await;
+        state.closed = true;
+        state.waiters.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn close_fails_waiters() {
+        let pending = PendingInstructions::default();
+        let (done_id, done_rx) = pending.register().await;
+        let (_, waiting_rx) = pending.register().await;
+        assert!(pending.complete(done_id, BranchStatus::PhaseTwoCommitted).await);
+        assert_eq!(done_rx.await.unwrap(), BranchStatus::PhaseTwoCommitted);
+
+        pending.close().await;
+        assert!(waiting_rx.await.is_err());
+
+        // 断开后登记的等待者不会一直等到超时
+        let (late_id, late_rx) = pending.register().await;
+        assert!(late_rx.await.is_err());
+        assert!(!pending.complete(late_id, BranchStatus::PhaseTwoCommitted).await);
+    }
+}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "branch_table")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub branch_id: i64,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub xid: String,
+    pub transaction_id: i64,
+    // 分支在全局事务中的注册顺序，逆序回滚依赖该顺序
+    pub branch_order: i32,
+    #[sea_orm(column_type = "String(StringLen::N(128))", nullable)]
+    pub resource_group_id: Option<String>,
+    #[sea_orm(column_type = "String(StringLen::N(256))", nullable)]
+    pub resource_id: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub lock_key: Option<String>,
+    pub branch_type: i32,
+    pub status: i32,
+    pub client_id: i64,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub application_data: Option<String>,
+    pub lock_status: i32,
+    // 未送达 RM 的二阶段指令，JSON 格式
+    #[sea_orm(column_type = "Text", nullable)]
+    pub queued_instruction: Option<String>,
+    pub gmt_create: i64,
+    pub gmt_modified: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,367 @@
+use crate::store::db::{lock_table, now_millis};
+use async_trait::async_trait;
+use rseata_core::branch::BranchId;
+use rseata_core::error::TransactionError;
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::defaults::default_row_lock::DefaultRowLock;
+use rseata_core::lock::locker::Locker;
+use rseata_core::lock::row_lock::RowLock;
+use rseata_core::store::StoreConfig;
+use rseata_core::types::Xid;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ActiveValue, ColumnTrait, Database, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, SqlErr, TransactionTrait,
+};
+use std::collections::{BTreeMap, HashMap};
+
+// 单条语句携带的行数上限，避免超过数据库的参数个数限制
+const BATCH_SIZE: usize = 500;
+
+/// 基于 lock_table 的行锁实现，与 db 会话存储配合时多个 TC 实例共享锁状态
+#[derive(Debug)]
+pub struct DbLocker {
+    conn: DatabaseConnection,
+}
+
+impl DbLocker {
+    /// 连接 db_url 并执行未完成的表结构迁移
+    pub async fn connect(config: &StoreConfig) -> anyhow::Result<Self> {
+        let conn = Database::connect(&config.db_url).await?;
+        super::migration::migrate(&conn).await?;
+        Ok(Self { conn })
+    }
+
+    fn build_row_key(row_lock: &DefaultRowLock) -> String {
+        format!(
+            "{}:{}:{}",
+            row_lock.resource_id(),
+            row_lock.table_name(),
+            row_lock.pk()
+        )
+    }
+
+    // 同一批行锁按 row_key 去重，保持顺序稳定以减少并发加锁时的死锁
+    fn group_by_row_key(row_locks: &[DefaultRowLock]) -> BTreeMap<String, &DefaultRowLock> {
+        row_locks
+            .iter()
+            .map(|row_lock| (Self::build_row_key(row_lock), row_lock))
+            .collect()
+    }
+
+    fn lock_row(row_key: String, row_lock: &DefaultRowLock, now: i64) -> lock_table::ActiveModel {
+        lock_table::ActiveModel {
+            row_key: ActiveValue::set(row_key),
+            xid: ActiveValue::set(row_lock.xid().to_string()),
+            transaction_id: ActiveValue::set(row_lock.transaction_id() as i64),
+            branch_id: ActiveValue::set(row_lock.branch_id().map(|id| id.0 as i64)),
+            resource_id: ActiveValue::set(row_lock.resource_id().to_string()),
+            table_name: ActiveValue::set(row_lock.table_name().to_string()),
+            pk: ActiveValue::set(row_lock.pk().to_string()),
+            status: ActiveValue::set(LockStatus::Locked as i32),
+            gmt_create: ActiveValue::set(now),
+            gmt_modified: ActiveValue::set(now),
+        }
+    }
+}
+
+#[async_trait]
+impl Locker for DbLocker {
+    type RowLock = DefaultRowLock;
+
+    async fn acquire_lock(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        self.acquire_lock_with_options(row_locks, true, false).await
+    }
+
+    /// 在一个数据库事务中获取分支的全部行锁，任一行被其他全局事务持有则整体失败
+    async fn acquire_lock_with_options(
+        &self,
+        row_locks: &[Self::RowLock],
+        auto_commit: bool,
+        skip_check_lock: bool,
+    ) -> Result<bool, TransactionError> {
+        if row_locks.is_empty() {
+            return Ok(true);
+        }
+        let mut rows = Self::group_by_row_key(row_locks);
+        let now = now_millis();
+        let txn = self.conn.begin().await.map_err(db_err)?;
+
+        if skip_check_lock {
+            let models: Vec<_> = rows
+                .into_iter()
+                .map(|(row_key, row_lock)| Self::lock_row(row_key, row_lock, now))
+                .collect();
+            for chunk in models.chunks(BATCH_SIZE) {
+                lock_table::Entity::insert_many(chunk.to_vec())
+                    .on_conflict(
+                        OnConflict::column(lock_table::Column::RowKey)
+                            .update_columns([
+                                lock_table::Column::Xid,
+                                lock_table::Column::TransactionId,
+                                lock_table::Column::BranchId,
+                                lock_table::Column::Status,
+                                lock_table::Column::GmtModified,
+                            ])
+                            .to_owned(),
+                    )
+                    .exec_without_returning(&txn)
+                    .await
+                    .map_err(db_err)?;
+            }
+            txn.commit().await.map_err(db_err)?;
+            return Ok(true);
+        }
+
+        let row_keys: Vec<String> = rows.keys().cloned().collect();
+        for chunk in row_keys.chunks(BATCH_SIZE) {
+            let existing = lock_table::Entity::find()
+                .filter(lock_table::Column::RowKey.is_in(chunk.iter().cloned()))
+                .lock_exclusive()
+                .all(&txn)
+                .await
+                .map_err(db_err)?;
+            for lock in existing {
+                let row_lock = rows[&lock.row_key];
+                if lock.xid != row_lock.xid().to_string() {
+                    // 如果处于回滚状态且非自动提交，快速失败
+                    if !auto_commit && lock.status == LockStatus::Rollbacking as i32 {
+                        return Err(TransactionError::new("Lock is rollbacking".to_string()));
+                    }
+                    return Ok(false);
+                }
+                // 本事务已持有的行无需重复插入
+                rows.remove(&lock.row_key);
+            }
+        }
+
+        let models: Vec<_> = rows
+            .into_iter()
+            .map(|(row_key, row_lock)| Self::lock_row(row_key, row_lock, now))
+            .collect();
+        for chunk in models.chunks(BATCH_SIZE) {
+            match lock_table::Entity::insert_many(chunk.to_vec())
+                .exec_without_returning(&txn)
+                .await
+            {
+                Ok(_) => {}
+                // 检查之后被其他 TC 实例抢先锁定
+                Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                    return Ok(false);
+                }
+                Err(e) => return Err(db_err(e)),
+            }
+        }
+        txn.commit().await.map_err(db_err)?;
+        Ok(true)
+    }
+
+    async fn release_lock(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        let mut by_xid: HashMap<String, Vec<String>> = HashMap::new();
+        for (row_key, row_lock) in Self::group_by_row_key(row_locks) {
+            by_xid
+                .entry(row_lock.xid().to_string())
+                .or_default()
+                .push(row_key);
+        }
+        for (xid, row_keys) in by_xid {
+            for chunk in row_keys.chunks(BATCH_SIZE) {
+                lock_table::Entity::delete_many()
+                    .filter(lock_table::Column::Xid.eq(xid.as_str()))
+                    .filter(lock_table::Column::RowKey.is_in(chunk.iter().cloned()))
+                    .exec(&self.conn)
+                    .await
+                    .map_err(db_err)?;
+            }
+        }
+        Ok(true)
+    }
+
+    async fn release_lock_by_xid_branch_id(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Result<bool, TransactionError> {
+        lock_table::Entity::delete_many()
+            .filter(lock_table::Column::BranchId.eq(branch_id.0 as i64))
+            .filter(lock_table::Column::Xid.eq(xid.to_string()))
+            .exec(&self.conn)
+            .await
+            .map_err(db_err)?;
+        Ok(true)
+    }
+
+    async fn release_lock_by_xid(&self, xid: &Xid) -> Result<bool, TransactionError> {
+        lock_table::Entity::delete_many()
+            .filter(lock_table::Column::Xid.eq(xid.to_string()))
+            .exec(&self.conn)
+            .await
+            .map_err(db_err)?;
+        Ok(true)
+    }
+
+    async fn is_lockable(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        if row_locks.is_empty() {
+            return Ok(true);
+        }
+        let xid = row_locks[0].xid().to_string();
+        let row_keys: Vec<String> = Self::group_by_row_key(row_locks).into_keys().collect();
+        for chunk in row_keys.chunks(BATCH_SIZE) {
+            let conflict = lock_table::Entity::find()
+                .filter(lock_table::Column::RowKey.is_in(chunk.iter().cloned()))
+                .filter(lock_table::Column::Xid.ne(xid.as_str()))
+                .one(&self.conn)
+                .await
+                .map_err(db_err)?;
+            if conflict.is_some() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn clean_all_locks(&self) -> Result<(), TransactionError> {
+        lock_table::Entity::delete_many()
+            .exec(&self.conn)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    async fn update_lock_status(
+        &self,
+        xid: &Xid,
+        lock_status: LockStatus,
+    ) -> Result<(), TransactionError> {
+        lock_table::Entity::update_many()
+            .col_expr(lock_table::Column::Status, Expr::value(lock_status as i32))
+            .col_expr(lock_table::Column::GmtModified, Expr::value(now_millis()))
+            .filter(lock_table::Column::Xid.eq(xid.to_string()))
+            .exec(&self.conn)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+}
+
+fn db_err(e: DbErr) -> TransactionError {
+    TransactionError::new(format!("lock_table: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::db::tests::SqliteFile;
+
+    fn row_lock(xid: &str, branch_id: u64, pk: &str) -> DefaultRowLock {
+        DefaultRowLock::new(
+            Xid::from(xid),
+            1,
+            "jdbc:mysql://order".into(),
+            Some(BranchId(branch_id)),
+            "t_order".to_string(),
+            pk.to_string(),
+        )
+    }
+
+    // 按 xid 读出已持有的 pk
+    async fn held(locker: &DbLocker, xid: &str) -> Vec<String> {
+        let mut pks: Vec<_> = lock_table::Entity::find()
+            .filter(lock_table::Column::Xid.eq(xid))
+            .all(&locker.conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|lock| lock.pk)
+            .collect();
+        pks.sort();
+        pks
+    }
+
+    #[tokio::test]
+    async fn conflicting_lock_takes_no_rows() {
+        let file = SqliteFile::new();
+        let locker = DbLocker::connect(&file.config()).await.unwrap();
+        assert!(locker.acquire_lock(&[row_lock("a", 1, "1")]).await.unwrap());
+
+        let wanted = [row_lock("b", 2, "1"), row_lock("b", 2, "2")];
+        assert!(!locker.acquire_lock(&wanted).await.unwrap());
+        assert!(!locker.is_lockable(&wanted).await.unwrap());
+        // 整体失败，没有冲突的行也不会被锁定
+        assert!(held(&locker, "b").await.is_empty());
+        assert_eq!(held(&locker, "a").await, ["1"]);
+    }
+
+    #[tokio::test]
+    async fn relocking_own_rows_succeeds() {
+        let file = SqliteFile::new();
+        let locker = DbLocker::connect(&file.config()).await.unwrap();
+        let rows = [row_lock("a", 1, "1"), row_lock("a", 1, "2")];
+        assert!(locker.acquire_lock(&rows).await.unwrap());
+
+        let more = [
+            row_lock("a", 1, "1"),
+            row_lock("a", 1, "2"),
+            row_lock("a", 1, "3"),
+        ];
+        assert!(locker.acquire_lock(&more).await.unwrap());
+        assert!(locker.is_lockable(&more).await.unwrap());
+        assert_eq!(held(&locker, "a").await, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn rollbacking_lock_fails_fast_without_auto_commit() {
+        let file = SqliteFile::new();
+        let locker = DbLocker::connect(&file.config()).await.unwrap();
+        assert!(locker.acquire_lock(&[row_lock("a", 1, "1")]).await.unwrap());
+        locker
+            .update_lock_status(&Xid::from("a"), LockStatus::Rollbacking)
+            .await
+            .unwrap();
+
+        let wanted = [row_lock("b", 2, "1")];
+        let result = locker
+            .acquire_lock_with_options(&wanted, false, false)
+            .await;
+        assert!(result.is_err());
+        // 自动提交的本地事务按普通冲突处理，由 RM 重试
+        let result = locker.acquire_lock_with_options(&wanted, true, false).await;
+        assert!(!result.unwrap());
+        assert!(held(&locker, "b").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn release_removes_only_own_rows() {
+        let file = SqliteFile::new();
+        let locker = DbLocker::connect(&file.config()).await.unwrap();
+        let rows = [
+            row_lock("a", 1, "1"),
+            row_lock("a", 2, "2"),
+            row_lock("b", 3, "3"),
+        ];
+        for row in &rows {
+            assert!(
+                locker
+                    .acquire_lock(std::slice::from_ref(row))
+                    .await
+                    .unwrap()
+            );
+        }
+
+        locker
+            .release_lock_by_xid_branch_id(&Xid::from("a"), BranchId(1))
+            .await
+            .unwrap();
+        assert_eq!(held(&locker, "a").await, ["2"]);
+        // 其他全局事务的同名分支不受影响
+        locker
+            .release_lock_by_xid_branch_id(&Xid::from("a"), BranchId(3))
+            .await
+            .unwrap();
+        assert_eq!(held(&locker, "b").await, ["3"]);
+
+        locker.release_lock_by_xid(&Xid::from("a")).await.unwrap();
+        assert!(held(&locker, "a").await.is_empty());
+        assert_eq!(held(&locker, "b").await, ["3"]);
+    }
+}
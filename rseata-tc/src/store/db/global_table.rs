@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "global_table")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "String(StringLen::N(128))"
+    )]
+    pub xid: String,
+    pub transaction_id: i64,
+    pub status: i32,
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub application_id: String,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub transaction_service_group: String,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub transaction_name: String,
+    pub timeout: i64,
+    pub begin_time: i64,
+    // begin_time + timeout，用于超时扫描
+    pub deadline: i64,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub application_data: Option<String>,
+    pub active: bool,
+    pub gmt_create: i64,
+    pub gmt_modified: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
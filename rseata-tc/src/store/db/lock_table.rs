@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "lock_table")]
+pub struct Model {
+    // resource_id:table_name:pk，主键唯一保证同一行只能被一个全局事务锁定
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "String(StringLen::N(512))"
+    )]
+    pub row_key: String,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub xid: String,
+    pub transaction_id: i64,
+    #[sea_orm(nullable)]
+    pub branch_id: Option<i64>,
+    #[sea_orm(column_type = "String(StringLen::N(256))")]
+    pub resource_id: String,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub table_name: String,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub pk: String,
+    pub status: i32,
+    pub gmt_create: i64,
+    pub gmt_modified: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
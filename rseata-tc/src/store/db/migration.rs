@@ -0,0 +1,189 @@
+use crate::store::db::{branch_table, global_table, lock_table, now_millis};
+use sea_orm::sea_query::{Expr, ExprTrait, Index, OnConflict};
+use sea_orm::{
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Schema, TransactionTrait,
+};
+use std::collections::BTreeSet;
+
+// 当前代码需要的表结构版本，新增迁移时递增并在 apply 中追加分支
+const LATEST_VERSION: i32 = 4;
+
+mod store_migration {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+    #[sea_orm(table_name = "rseata_store_migration")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub version: i32,
+        pub applied_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// 按版本执行未完成的迁移，已执行的版本记录在 rseata_store_migration 表中
+pub(crate) async fn migrate(conn: &DatabaseConnection) -> anyhow::Result<()> {
+    let schema = Schema::new(conn.get_database_backend());
+    let mut stmt = schema.create_table_from_entity(store_migration::Entity);
+    stmt.if_not_exists();
+    conn.execute(&stmt).await?;
+
+    for version in 1..=LATEST_VERSION {
+        if applied(conn, version).await? {
+            continue;
+        }
+        tracing::info!("Apply session store migration v{version}");
+        if let Err(e) = apply(conn, &schema, version).await {
+            // 多个 TC 同时首次启动时，其他实例可能已完成该版本
+            if applied(conn, version).await? {
+                continue;
+            }
+            return Err(e);
+        }
+        store_migration::Entity::insert(store_migration::ActiveModel {
+            version: ActiveValue::set(version),
+            applied_at: ActiveValue::set(now_millis()),
+        })
+        .on_conflict(
+            OnConflict::column(store_migration::Column::Version)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn applied(conn: &DatabaseConnection, version: i32) -> anyhow::Result<bool> {
+    Ok(store_migration::Entity::find_by_id(version)
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+async fn apply(conn: &DatabaseConnection, schema: &Schema, version: i32) -> anyhow::Result<()> {
+    match version {
+        1 => {
+            for mut table in [
+                schema.create_table_from_entity(global_table::Entity),
+                schema.create_table_from_entity(branch_table::Entity),
+            ] {
+                table.if_not_exists();
+                conn.execute(&table).await?;
+            }
+            for index in [
+                // 按状态查询及超时扫描
+                Index::create()
+                    .name("idx_global_status_deadline")
+                    .table(global_table::Entity)
+                    .col(global_table::Column::Status)
+                    .col(global_table::Column::Deadline)
+                    .to_owned(),
+                Index::create()
+                    .name("idx_global_transaction_id")
+                    .table(global_table::Entity)
+                    .col(global_table::Column::TransactionId)
+                    .to_owned(),
+                Index::create()
+                    .name("idx_branch_xid")
+                    .table(branch_table::Entity)
+                    .col(branch_table::Column::Xid)
+                    .to_owned(),
+            ] {
+                conn.execute(&index).await?;
+            }
+        }
+        2 => {
+            let mut table = schema.create_table_from_entity(lock_table::Entity);
+            table.if_not_exists();
+            conn.execute(&table).await?;
+            for index in [
+                // 按全局事务、分支释放行锁
+                Index::create()
+                    .name("idx_lock_xid")
+                    .table(lock_table::Entity)
+                    .col(lock_table::Column::Xid)
+                    .to_owned(),
+                Index::create()
+                    .name("idx_lock_branch_id")
+                    .table(lock_table::Entity)
+                    .col(lock_table::Column::BranchId)
+                    .to_owned(),
+            ] {
+                conn.execute(&index).await?;
+            }
+        }
+        3 => {
+            for index in [
+                // 条件查询按排序值和 xid 分页
+                Index::create()
+                    .name("idx_global_begin_time_xid")
+                    .table(global_table::Entity)
+                    .col(global_table::Column::BeginTime)
+                    .col(global_table::Column::Xid)
+                    .to_owned(),
+                Index::create()
+                    .name("idx_global_deadline_xid")
+                    .table(global_table::Entity)
+                    .col(global_table::Column::Deadline)
+                    .col(global_table::Column::Xid)
+                    .to_owned(),
+            ] {
+                conn.execute(&index).await?;
+            }
+        }
+        4 => {
+            // 旧版本并发注册分支可能留下重复顺序，建唯一索引前先重新编号
+            renumber_duplicate_branch_orders(conn).await?;
+            let index = Index::create()
+                .name("uk_branch_xid_order")
+                .table(branch_table::Entity)
+                .col(branch_table::Column::Xid)
+                .col(branch_table::Column::BranchOrder)
+                .unique()
+                .to_owned();
+            conn.execute(&index).await?;
+        }
+        _ => anyhow::bail!("unknown session store migration v{version}"),
+    }
+    Ok(())
+}
+
+// 有重复顺序的全局事务按原顺序、分支 id 依次重排
+async fn renumber_duplicate_branch_orders(conn: &DatabaseConnection) -> anyhow::Result<()> {
+    let xids: BTreeSet<String> = branch_table::Entity::find()
+        .select_only()
+        .column(branch_table::Column::Xid)
+        .group_by(branch_table::Column::Xid)
+        .group_by(branch_table::Column::BranchOrder)
+        .having(Expr::col(branch_table::Column::BranchId).count().gt(1))
+        .into_tuple::<String>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .collect();
+    let txn = conn.begin().await?;
+    for xid in xids {
+        let rows = branch_table::Entity::find()
+            .filter(branch_table::Column::Xid.eq(xid))
+            .order_by_asc(branch_table::Column::BranchOrder)
+            .order_by_asc(branch_table::Column::BranchId)
+            .all(&txn)
+            .await?;
+        for (order, row) in rows.into_iter().enumerate() {
+            branch_table::Entity::update_many()
+                .col_expr(branch_table::Column::BranchOrder, Expr::value(order as i32))
+                .filter(branch_table::Column::BranchId.eq(row.branch_id))
+                .exec(&txn)
+                .await?;
+        }
+    }
+    txn.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,958 @@
+pub mod branch_table;
+pub mod db_locker;
+pub mod global_table;
+pub mod lock_table;
+pub mod migration;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use rseata_core::branch::{BranchId, BranchStatus};
+use rseata_core::lock::LockStatus;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::session_condition::{SessionCondition, SessionSortBy};
+use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+use rseata_core::store::{LogOperation, StoreConfig};
+use rseata_core::types::{GlobalStatus, Xid};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveValue, ColumnTrait, Condition, ConnectionTrait, Database, DatabaseConnection,
+    EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, Select, SqlErr, TransactionTrait,
+};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 分支顺序冲突时重新取号的次数
+const BRANCH_ORDER_RETRIES: usize = 8;
+
+/// 基于数据库的会话存储，支持 MySQL、Postgres、SQLite
+///
+/// 状态更新只在库中的状态仍为读到的状态时生效，多个 TC 实例可以共享同一个库
+#[derive(Debug)]
+pub struct DbTransactionStoreManager {
+    conn: DatabaseConnection,
+}
+
+impl DbTransactionStoreManager {
+    /// 连接 db_url 并执行未完成的表结构迁移
+    pub async fn connect(config: &StoreConfig) -> anyhow::Result<Self> {
+        let conn = Database::connect(&config.db_url).await?;
+        migration::migrate(&conn).await?;
+        Ok(Self { conn })
+    }
+
+    async fn insert_global(
+        &self,
+        conn: &impl ConnectionTrait,
+        session: &DefaultGlobalSession,
+    ) -> anyhow::Result<()> {
+        let now = now_millis();
+        global_table::Entity::insert(global_table::ActiveModel {
+            xid: ActiveValue::set(session.xid.to_string()),
+            transaction_id: ActiveValue::set(session.transaction_id as i64),
+            status: ActiveValue::set(session.status.code()),
+            application_id: ActiveValue::set(session.application_id.clone()),
+            transaction_service_group: ActiveValue::set(session.transaction_service_group.clone()),
+            transaction_name: ActiveValue::set(session.transaction_name.clone()),
+            timeout: ActiveValue::set(session.timeout_millis as i64),
+            begin_time: ActiveValue::set(session.begin_time_millis as i64),
+            deadline: ActiveValue::set(
+                session
+                    .begin_time_millis
+                    .saturating_add(session.timeout_millis)
+                    .min(i64::MAX as u64) as i64,
+            ),
+            application_data: ActiveValue::set(session.application_data.clone()),
+            active: ActiveValue::set(session.active),
+            gmt_create: ActiveValue::set(now),
+            gmt_modified: ActiveValue::set(now),
+        })
+        .exec_without_returning(conn)
+        .await?;
+        Ok(())
+    }
+
+    // 只有存储中的状态仍为 expected 时才更新，返回 false 表示状态已被其他 TC 实例修改
+    async fn update_global(
+        &self,
+        session: &DefaultGlobalSession,
+        expected: GlobalStatus,
+    ) -> anyhow::Result<bool> {
+        let xid = session.xid.to_string();
+        let result = global_table::Entity::update_many()
+            .col_expr(
+                global_table::Column::Status,
+                Expr::value(session.status.code()),
+            )
+            .col_expr(global_table::Column::Active, Expr::value(session.active))
+            .col_expr(
+                global_table::Column::ApplicationData,
+                Expr::value(session.application_data.clone()),
+            )
+            .col_expr(global_table::Column::GmtModified, Expr::value(now_millis()))
+            .filter(global_table::Column::Xid.eq(xid.as_str()))
+            .filter(global_table::Column::Status.eq(expected.code()))
+            .exec(&self.conn)
+            .await?;
+        if result.rows_affected > 0 {
+            return Ok(true);
+        }
+        // MySQL 对未变化的行返回 0，状态不变的更新需确认行仍处于 expected
+        if expected != session.status {
+            return Ok(false);
+        }
+        let current = global_table::Entity::find_by_id(xid)
+            .one(&self.conn)
+            .await?;
+        Ok(current.is_some_and(|row| row.status == expected.code()))
+    }
+
+    async fn remove_global(&self, session: &DefaultGlobalSession) -> anyhow::Result<()> {
+        let txn = self.conn.begin().await?;
+        branch_table::Entity::delete_many()
+            .filter(branch_table::Column::Xid.eq(session.xid.to_string()))
+            .exec(&txn)
+            .await?;
+        global_table::Entity::delete_by_id(session.xid.to_string())
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// 在单独的事务中同步 branch_table
+    async fn sync_branches(
+        &self,
+        session: &DefaultGlobalSession,
+        remove: bool,
+    ) -> anyhow::Result<()> {
+        let txn = self.conn.begin().await?;
+        sync_branches(&txn, session, remove).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// 只读写一个分支行：新分支排在已有分支之后，更新按读到的状态做比较更新
+    async fn write_branch(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        let branch_id = Into::<u64>::into(branch_session.branch_id) as i64;
+        let current = branch_table::Entity::find_by_id(branch_id)
+            .one(&self.conn)
+            .await?;
+        let now = now_millis();
+        match (log_operation, current) {
+            (LogOperation::BranchAdd | LogOperation::BranchUpdate, Some(current)) => {
+                let row = branch_row(branch_session, current.branch_order, now);
+                update_branch_row(&self.conn, &current, branch_session, row, now).await
+            }
+            (LogOperation::BranchAdd | LogOperation::BranchUpdate, None) => {
+                // 多个 TC 实例同时为同一全局事务注册分支时可能取到相同的顺序，
+                // 由 (xid, branch_order) 唯一索引拒绝后重新取号
+                for _ in 0..BRANCH_ORDER_RETRIES {
+                    let order = next_branch_order(&self.conn, &branch_session.xid).await?;
+                    match branch_table::Entity::insert(branch_row(branch_session, order, now))
+                        .exec_without_returning(&self.conn)
+                        .await
+                    {
+                        Ok(_) => return Ok(()),
+                        Err(e)
+                            if matches!(
+                                e.sql_err(),
+                                Some(SqlErr::UniqueConstraintViolation(_))
+                            ) =>
+                        {
+                            continue;
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                bail!(
+                    "branch order of {} kept conflicting after {BRANCH_ORDER_RETRIES} attempts",
+                    branch_session.xid
+                )
+            }
+            (LogOperation::BranchRemove, _) => {
+                branch_table::Entity::delete_by_id(branch_id)
+                    .exec(&self.conn)
+                    .await?;
+                Ok(())
+            }
+            (operation, _) => bail!("{operation:?} is not a branch operation"),
+        }
+    }
+
+    async fn query_sessions(
+        &self,
+        query: Select<global_table::Entity>,
+        with_branch_sessions: bool,
+    ) -> anyhow::Result<Vec<DefaultGlobalSession>> {
+        let rows = query.all(&self.conn).await?;
+        let mut branches: HashMap<String, Vec<DefaultBranchSession>> = HashMap::new();
+        if with_branch_sessions && !rows.is_empty() {
+            for row in branch_table::Entity::find()
+                .filter(branch_table::Column::Xid.is_in(rows.iter().map(|row| row.xid.clone())))
+                .order_by_asc(branch_table::Column::BranchOrder)
+                .all(&self.conn)
+                .await?
+            {
+                branches
+                    .entry(row.xid.clone())
+                    .or_default()
+                    .push(branch_session(row)?);
+            }
+        }
+        rows.into_iter()
+            .map(|row| {
+                let branch_sessions = branches.remove(&row.xid).unwrap_or_default();
+                global_session(row, branch_sessions)
+            })
+            .collect()
+    }
+
+    async fn read_sessions(
+        &self,
+        condition: Condition,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        let query = global_table::Entity::find()
+            .filter(condition)
+            .order_by_asc(global_table::Column::Deadline);
+        self.read_query(query, with_branch_sessions).await
+    }
+
+    async fn read_query(
+        &self,
+        query: Select<global_table::Entity>,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.query_sessions(query, with_branch_sessions)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Read sessions from db failed: {:?}", e);
+                Vec::new()
+            })
+    }
+}
+
+#[async_trait]
+impl TransactionStoreManager for DbTransactionStoreManager {
+    type GlobalSession = DefaultGlobalSession;
+
+    async fn write_session(
+        &self,
+        log_operation: LogOperation,
+        session: &Self::GlobalSession,
+    ) -> anyhow::Result<()> {
+        match log_operation {
+            LogOperation::GlobalAdd => {
+                // 全局会话与其分支在同一事务中插入
+                let txn = self.conn.begin().await?;
+                self.insert_global(&txn, session).await?;
+                if !session.branch_sessions.is_empty() {
+                    sync_branches(&txn, session, false).await?;
+                }
+                txn.commit().await?;
+                Ok(())
+            }
+            LogOperation::GlobalUpdate => {
+                // 不改变状态的更新同样要求存储中的状态未被修改
+                if !self.update_global(session, session.status).await? {
+                    bail!(
+                        "global session {} status changed concurrently, expected {:?}",
+                        session.xid,
+                        session.status
+                    );
+                }
+                Ok(())
+            }
+            LogOperation::GlobalRemove => self.remove_global(session).await,
+            LogOperation::BranchAdd | LogOperation::BranchUpdate => {
+                self.sync_branches(session, false).await
+            }
+            LogOperation::BranchRemove => self.sync_branches(session, true).await,
+        }
+    }
+
+    async fn compare_and_set_global_session(
+        &self,
+        session: &Self::GlobalSession,
+        expected: GlobalStatus,
+    ) -> anyhow::Result<bool> {
+        self.update_global(session, expected).await
+    }
+
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        self.write_branch(log_operation, branch_session).await
+    }
+
+    async fn read_branch_session(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Option<DefaultBranchSession> {
+        let row = branch_table::Entity::find_by_id(Into::<u64>::into(branch_id) as i64)
+            .filter(branch_table::Column::Xid.eq(xid.to_string()))
+            .one(&self.conn)
+            .await
+            .inspect_err(|e| tracing::error!("Read branch session from db failed: {:?}", e))
+            .ok()??;
+        branch_session(row)
+            .inspect_err(|e| tracing::error!("Decode branch session {} failed: {:?}", xid, e))
+            .ok()
+    }
+
+    async fn read_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.read_global_session(xid, true).await
+    }
+
+    async fn read_session_with_branches(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.read_global_session(xid, true).await
+    }
+
+    async fn read_global_session(
+        &self,
+        xid: &Xid,
+        with_branch_sessions: bool,
+    ) -> Option<DefaultGlobalSession> {
+        self.read_sessions(
+            Condition::all().add(global_table::Column::Xid.eq(xid.to_string())),
+            with_branch_sessions,
+        )
+        .await
+        .pop()
+    }
+
+    async fn read_sort_by_timeout_begin_sessions(
+        &self,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        // 只读出已过截止时间的会话，按截止时间升序
+        self.read_sessions(
+            Condition::all()
+                .add(global_table::Column::Status.eq(GlobalStatus::Begin.code()))
+                .add(global_table::Column::Deadline.lt(now_millis())),
+            with_branch_sessions,
+        )
+        .await
+    }
+
+    async fn read_session_by_global_status(
+        &self,
+        statuses: &[GlobalStatus],
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.read_sessions(
+            Condition::all().add(
+                global_table::Column::Status.is_in(statuses.iter().map(|status| status.code())),
+            ),
+            with_branch_sessions,
+        )
+        .await
+    }
+
+    async fn read_session_by_session_condition(
+        &self,
+        condition: &SessionCondition,
+    ) -> Vec<DefaultGlobalSession> {
+        let mut filter = Condition::all();
+        if let Some(xid) = &condition.xid {
+            filter = filter.add(global_table::Column::Xid.eq(xid.to_string()));
+        }
+        if let Some(transaction_id) = condition.transaction_id {
+            filter = filter.add(global_table::Column::TransactionId.eq(transaction_id as i64));
+        }
+        let statuses = condition.all_statuses();
+        if !statuses.is_empty() {
+            filter =
+                filter.add(global_table::Column::Status.is_in(statuses.iter().map(|s| s.code())));
+        }
+        if let Some(over_time) = condition.over_time_alive_mills {
+            filter = filter.add(
+                global_table::Column::BeginTime.lt(now_millis().saturating_sub(over_time as i64)),
+            );
+        }
+        let sort_column = match condition.sort_by {
+            SessionSortBy::BeginTime => global_table::Column::BeginTime,
+            SessionSortBy::Deadline => global_table::Column::Deadline,
+        };
+        // 游标条件 (sort_key, xid) > (k, x) 展开为 sort_key > k OR (sort_key = k AND xid > x)
+        if let Some(cursor) = &condition.cursor {
+            let sort_key = cursor.sort_key.min(i64::MAX as u64) as i64;
+            let xid = cursor.xid.to_string();
+            let after = match condition.descending {
+                true => Condition::any().add(sort_column.lt(sort_key)).add(
+                    Condition::all()
+                        .add(sort_column.eq(sort_key))
+                        .add(global_table::Column::Xid.lt(xid)),
+                ),
+                false => Condition::any().add(sort_column.gt(sort_key)).add(
+                    Condition::all()
+                        .add(sort_column.eq(sort_key))
+                        .add(global_table::Column::Xid.gt(xid)),
+                ),
+            };
+            filter = filter.add(after);
+        }
+        let order = match condition.descending {
+            true => Order::Desc,
+            false => Order::Asc,
+        };
+        let mut query = global_table::Entity::find()
+            .filter(filter)
+            .order_by(sort_column, order.clone())
+            .order_by(global_table::Column::Xid, order);
+        if let Some(limit) = condition.limit {
+            query = query.limit(limit as u64);
+        }
+        self.read_query(query, !condition.lazy_load_branch).await
+    }
+}
+
+/// 按会话中的分支同步 branch_table：新增的分支插入，变化的分支按读到的状态做比较更新，
+/// remove 为 true 时删除会话中已不存在的分支
+async fn sync_branches(
+    txn: &impl ConnectionTrait,
+    session: &DefaultGlobalSession,
+    remove: bool,
+) -> anyhow::Result<()> {
+    let existing: HashMap<i64, branch_table::Model> = branch_table::Entity::find()
+        .filter(branch_table::Column::Xid.eq(session.xid.to_string()))
+        .all(txn)
+        .await?
+        .into_iter()
+        .map(|row| (row.branch_id, row))
+        .collect();
+
+    let now = now_millis();
+    // 新分支排在已有分支之后，已有分支保留原顺序
+    let next_order = existing
+        .values()
+        .map(|row| row.branch_order + 1)
+        .max()
+        .unwrap_or(0);
+    let (current, added): (Vec<_>, Vec<_>) = session
+        .branch_sessions
+        .iter()
+        .partition(|bs| existing.contains_key(&(Into::<u64>::into(bs.branch_id) as i64)));
+    for branch_session in current {
+        let current = &existing[&(Into::<u64>::into(branch_session.branch_id) as i64)];
+        let row = branch_row(branch_session, current.branch_order, now);
+        update_branch_row(txn, current, branch_session, row, now).await?;
+    }
+    for (order, branch_session) in (next_order..).zip(added) {
+        branch_table::Entity::insert(branch_row(branch_session, order, now))
+            .exec_without_returning(txn)
+            .await?;
+    }
+
+    if remove {
+        let kept: HashSet<i64> = session
+            .branch_sessions
+            .iter()
+            .map(|bs| Into::<u64>::into(bs.branch_id) as i64)
+            .collect();
+        let removed: Vec<i64> = existing
+            .into_keys()
+            .filter(|id| !kept.contains(id))
+            .collect();
+        if !removed.is_empty() {
+            branch_table::Entity::delete_many()
+                .filter(branch_table::Column::BranchId.is_in(removed))
+                .exec(txn)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn next_branch_order(conn: &impl ConnectionTrait, xid: &Xid) -> anyhow::Result<i32> {
+    let last = branch_table::Entity::find()
+        .filter(branch_table::Column::Xid.eq(xid.to_string()))
+        .order_by_desc(branch_table::Column::BranchOrder)
+        .one(conn)
+        .await?;
+    Ok(last.map_or(0, |row| row.branch_order + 1))
+}
+
+// 分支行有变化时按读到的状态做比较更新
+async fn update_branch_row(
+    txn: &impl ConnectionTrait,
+    current: &branch_table::Model,
+    branch_session: &DefaultBranchSession,
+    row: branch_table::ActiveModel,
+    now: i64,
+) -> anyhow::Result<()> {
+    if current.status == *row.status.as_ref()
+        && current.lock_status == *row.lock_status.as_ref()
+        && current.application_data == *row.application_data.as_ref()
+        && current.queued_instruction == *row.queued_instruction.as_ref()
+    {
+        return Ok(());
+    }
+    let from = BranchStatus::from(current.status);
+    if !from.can_transition_to(branch_session.status) {
+        bail!(
+            "branch session {} cannot transition: {:?} -> {:?}",
+            branch_session.branch_id,
+            from,
+            branch_session.status
+        );
+    }
+    let result = branch_table::Entity::update_many()
+        .col_expr(
+            branch_table::Column::Status,
+            Expr::value(*row.status.as_ref()),
+        )
+        .col_expr(
+            branch_table::Column::LockStatus,
+            Expr::value(*row.lock_status.as_ref()),
+        )
+        .col_expr(
+            branch_table::Column::ApplicationData,
+            Expr::value(row.application_data.as_ref().clone()),
+        )
+        .col_expr(
+            branch_table::Column::QueuedInstruction,
+            Expr::value(row.queued_instruction.as_ref().clone()),
+        )
+        .col_expr(branch_table::Column::GmtModified, Expr::value(now))
+        .filter(branch_table::Column::BranchId.eq(current.branch_id))
+        .filter(branch_table::Column::Status.eq(current.status))
+        .exec(txn)
+        .await?;
+    if result.rows_affected == 0 {
+        bail!(
+            "branch session {} status changed concurrently: {:?} -> {:?}",
+            branch_session.branch_id,
+            from,
+            branch_session.status
+        );
+    }
+    Ok(())
+}
+
+fn branch_row(
+    branch_session: &DefaultBranchSession,
+    branch_order: i32,
+    now: i64,
+) -> branch_table::ActiveModel {
+    branch_table::ActiveModel {
+        branch_id: ActiveValue::set(Into::<u64>::into(branch_session.branch_id) as i64),
+        xid: ActiveValue::set(branch_session.xid.to_string()),
+        transaction_id: ActiveValue::set(branch_session.transaction_id as i64),
+        branch_order: ActiveValue::set(branch_order),
+        resource_group_id: ActiveValue::set(branch_session.resource_group_id.clone()),
+        resource_id: ActiveValue::set(branch_session.resource_id.as_ref().map(|r| r.0.clone())),
+        lock_key: ActiveValue::set(branch_session.lock_key.clone()),
+        branch_type: ActiveValue::set(branch_session.branch_type.into()),
+        status: ActiveValue::set(branch_session.status.into()),
+        client_id: ActiveValue::set(Into::<u64>::into(branch_session.client_id) as i64),
+        application_data: ActiveValue::set(branch_session.application_data.clone()),
+        lock_status: ActiveValue::set(branch_session.lock_status as i32),
+        queued_instruction: ActiveValue::set(
+            branch_session
+                .queued_instruction
+                .and_then(|queued| serde_json::to_string(&queued).ok()),
+        ),
+        gmt_create: ActiveValue::set(now),
+        gmt_modified: ActiveValue::set(now),
+    }
+}
+
+fn branch_session(row: branch_table::Model) -> anyhow::Result<DefaultBranchSession> {
+    Ok(DefaultBranchSession {
+        xid: Xid::from(row.xid),
+        transaction_id: row.transaction_id as u64,
+        branch_id: (row.branch_id as u64).into(),
+        resource_group_id: row.resource_group_id,
+        resource_id: row.resource_id.map(Into::into),
+        lock_key: row.lock_key,
+        branch_type: row.branch_type.into(),
+        status: row.status.into(),
+        client_id: (row.client_id as u64).into(),
+        application_data: row.application_data,
+        lock_status: match row.lock_status {
+            2 => LockStatus::Rollbacking,
+            _ => LockStatus::Locked,
+        },
+        lock_holder: Default::default(),
+        queued_instruction: row
+            .queued_instruction
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?,
+    })
+}
+
+fn global_session(
+    row: global_table::Model,
+    branch_sessions: Vec<DefaultBranchSession>,
+) -> anyhow::Result<DefaultGlobalSession> {
+    Ok(DefaultGlobalSession {
+        xid: Xid::from(row.xid),
+        transaction_id: row.transaction_id as u64,
+        status: GlobalStatus::from_code(row.status).map_err(anyhow::Error::msg)?,
+        application_id: row.application_id,
+        transaction_service_group: row.transaction_service_group,
+        transaction_name: row.transaction_name,
+        timeout_millis: row.timeout as u64,
+        begin_time_millis: row.begin_time as u64,
+        application_data: row.application_data,
+        lazy_load_branch: false,
+        active: row.active,
+        branch_sessions: branch_sessions.into(),
+    })
+}
+
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseata_core::branch::BranchType;
+    use rseata_core::error::TransactionError;
+    use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
+    use rseata_core::session::session_manager::SessionManager;
+    use std::path::PathBuf;
+
+    pub(super) struct SqliteFile(PathBuf);
+
+    impl SqliteFile {
+        pub(super) fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("rseata-db-store-{}.db", uuid::Uuid::new_v4())))
+        }
+
+        pub(super) fn config(&self) -> StoreConfig {
+            StoreConfig {
+                db_url: format!("sqlite://{}?mode=rwc", self.0.display()),
+                ..StoreConfig::default()
+            }
+        }
+
+        // 每次连接都是独立的连接池，相当于一个 TC 实例
+        async fn store(&self) -> DbTransactionStoreManager {
+            DbTransactionStoreManager::connect(&self.config())
+                .await
+                .unwrap()
+        }
+
+        async fn session_manager(&self, name: &str) -> DefaultSessionManager {
+            DefaultSessionManager::new(name.to_string(), Box::new(self.store().await))
+        }
+    }
+
+    impl Drop for SqliteFile {
+        fn drop(&mut self) {
+            std::fs::remove_file(&self.0).ok();
+        }
+    }
+
+    fn new_session(timeout_millis: u64, branch_id: u64) -> DefaultGlobalSession {
+        let mut session = DefaultGlobalSession::new(
+            "app".to_string(),
+            "group".to_string(),
+            "tx".to_string(),
+            timeout_millis,
+            false,
+        );
+        let mut branch = DefaultBranchSession::new(BranchType::AT);
+        branch.xid = session.xid.clone();
+        branch.transaction_id = session.transaction_id;
+        branch.branch_id = branch_id.into();
+        branch.status = BranchStatus::Registered;
+        session.add_branch(branch);
+        session
+    }
+
+    #[tokio::test]
+    async fn tc_instances_sharing_a_database_race_through_cas() {
+        let db = SqliteFile::new();
+        let tc1 = db.session_manager("tc1").await;
+        let tc2 = db.session_manager("tc2").await;
+
+        let session = new_session(60_000, 1);
+        tc1.add_global_session(&session).await.unwrap();
+        let seen = tc2
+            .find_global_session_with_branches(&session.xid, true)
+            .await
+            .unwrap();
+        assert_eq!(seen.branch_sessions.len(), 1);
+
+        // 两个实例同时从 Begin 抢占，只有一个成功
+        let (commit, rollback) = tokio::join!(
+            tc1.compare_and_set_global_status(
+                &session,
+                GlobalStatus::Begin,
+                GlobalStatus::Committing
+            ),
+            tc2.compare_and_set_global_status(
+                &seen,
+                GlobalStatus::Begin,
+                GlobalStatus::Rollbacking
+            ),
+        );
+        assert_eq!(commit.is_ok() as u8 + rollback.is_ok() as u8, 1);
+        let winner = if commit.is_ok() {
+            GlobalStatus::Committing
+        } else {
+            GlobalStatus::Rollbacking
+        };
+        let loser = commit.err().or(rollback.err()).unwrap();
+        assert!(matches!(
+            loser,
+            TransactionError::IllegalGlobalTransition { .. }
+        ));
+        for tc in [&tc1, &tc2] {
+            let current = tc.find_global_session(&session.xid).await.unwrap();
+            assert_eq!(current.status, winner);
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_expected_status_is_a_lost_race() {
+        let db = SqliteFile::new();
+        let tc1 = db.session_manager("tc1").await;
+        let store = db.store().await;
+
+        let mut session = new_session(60_000, 1);
+        tc1.add_global_session(&session).await.unwrap();
+        tc1.update_global_session_status(&session, GlobalStatus::Committing)
+            .await
+            .unwrap();
+
+        session.status = GlobalStatus::Rollbacking;
+        assert!(
+            !store
+                .compare_and_set_global_session(&session, GlobalStatus::Begin)
+                .await
+                .unwrap()
+        );
+        session.status = GlobalStatus::Committed;
+        assert!(
+            store
+                .compare_and_set_global_session(&session, GlobalStatus::Committing)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn only_expired_begin_sessions_are_read_for_timeout() {
+        let db = SqliteFile::new();
+        let tc = db.session_manager("tc").await;
+        let expired = new_session(0, 1);
+        let alive = new_session(60_000, 2);
+        tc.add_global_session(&expired).await.unwrap();
+        tc.add_global_session(&alive).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let store = db.store().await;
+        let sessions = store.read_sort_by_timeout_begin_sessions(false).await;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].xid, expired.xid);
+    }
+
+    #[tokio::test]
+    async fn session_condition_pages_in_sql_order() {
+        let db = SqliteFile::new();
+        let store = db.store().await;
+        // 开始时间有重复，相同时按 xid 排序
+        let mut sessions = Vec::new();
+        for (i, begin_time) in [300u64, 100, 200, 100, 300].into_iter().enumerate() {
+            let mut session = new_session(60_000, i as u64 + 1);
+            session.begin_time_millis = begin_time;
+            store
+                .write_session(LogOperation::GlobalAdd, &session)
+                .await
+                .unwrap();
+            sessions.push(session);
+        }
+
+        for descending in [false, true] {
+            let base = SessionCondition::new().sorted_by(SessionSortBy::BeginTime, descending);
+            let mut expected = sessions.clone();
+            expected.sort_by(|a, b| base.compare(a, b));
+
+            let mut cursor = None;
+            let mut paged = Vec::new();
+            loop {
+                let page = store
+                    .read_session_by_session_condition(&base.clone().page(cursor, 2))
+                    .await;
+                assert!(page.len() <= 2);
+                let Some(last) = page.last() else {
+                    break;
+                };
+                cursor = Some(base.cursor_of(last));
+                assert_eq!(page[0].branch_sessions.len(), 1);
+                paged.extend(page.into_iter().map(|session| session.xid));
+            }
+            let expected: Vec<_> = expected.into_iter().map(|session| session.xid).collect();
+            assert_eq!(paged, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn branch_writes_keep_registration_order() {
+        let db = SqliteFile::new();
+        let tc = db.session_manager("tc").await;
+        let store = db.store().await;
+        let session = new_session(60_000, 1);
+        tc.add_global_session(&session).await.unwrap();
+
+        let mut first = session.branch_sessions[0].clone();
+        let mut second = first.clone();
+        second.branch_id = 2u64.into();
+        tc.add_branch_session(&session, &second).await.unwrap();
+        tc.update_branch_session_status(&session, &first, BranchStatus::PhaseOneDone)
+            .await
+            .unwrap();
+        // 以过期的分支状态回退必须失败
+        first.status = BranchStatus::Registered;
+        assert!(
+            store
+                .write_branch_session(LogOperation::BranchUpdate, &first)
+                .await
+                .is_err()
+        );
+
+        let stored = tc
+            .find_global_session_with_branches(&session.xid, true)
+            .await
+            .unwrap();
+        let branches: Vec<_> = stored
+            .branch_sessions
+            .iter()
+            .map(|bs| (Into::<u64>::into(bs.branch_id), bs.status))
+            .collect();
+        assert_eq!(
+            branches,
+            vec![
+                (1, BranchStatus::PhaseOneDone),
+                (2, BranchStatus::Registered)
+            ]
+        );
+
+        tc.remove_branch_session(&session, &second).await.unwrap();
+        assert!(
+            store
+                .read_branch_session(&session.xid, 2u64.into())
+                .await
+                .is_none()
+        );
+        // 其他会话的 xid 读不到该分支
+        assert!(
+            store
+                .read_branch_session(&new_session(60_000, 3).xid, 1u64.into())
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_branch_registration_gets_distinct_orders() {
+        let db = SqliteFile::new();
+        let tc1 = db.store().await;
+        let tc2 = db.store().await;
+        let session = new_session(60_000, 1);
+        tc1.write_session(LogOperation::GlobalAdd, &session)
+            .await
+            .unwrap();
+
+        async fn register(
+            store: &DbTransactionStoreManager,
+            template: &DefaultBranchSession,
+            ids: std::ops::Range<u64>,
+        ) {
+            for id in ids {
+                let mut branch = template.clone();
+                branch.branch_id = id.into();
+                store
+                    .write_branch_session(LogOperation::BranchAdd, &branch)
+                    .await
+                    .unwrap();
+            }
+        }
+        // 两个实例同时为同一全局事务注册分支
+        let template = &session.branch_sessions[0];
+        tokio::join!(
+            register(&tc1, template, 2..12),
+            register(&tc2, template, 12..22),
+        );
+
+        let mut orders: Vec<i32> = branch_table::Entity::find()
+            .filter(branch_table::Column::Xid.eq(session.xid.to_string()))
+            .all(&tc1.conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.branch_order)
+            .collect();
+        orders.sort();
+        assert_eq!(orders, (0..21).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn migration_renumbers_duplicate_branch_orders() {
+        let db = SqliteFile::new();
+        let store = db.store().await;
+        // 退回到 v3 的表结构，模拟旧版本留下的重复顺序
+        store
+            .conn
+            .execute_unprepared("DROP INDEX uk_branch_xid_order")
+            .await
+            .unwrap();
+        store
+            .conn
+            .execute_unprepared("DELETE FROM rseata_store_migration WHERE version = 4")
+            .await
+            .unwrap();
+        let session = new_session(60_000, 1);
+        for (id, order) in [(3u64, 1), (1, 0), (2, 1), (4, 1)] {
+            let mut branch = session.branch_sessions[0].clone();
+            branch.branch_id = id.into();
+            branch_table::Entity::insert(branch_row(&branch, order, now_millis()))
+                .exec_without_returning(&store.conn)
+                .await
+                .unwrap();
+        }
+
+        let store = db.store().await;
+        let orders: Vec<(i64, i32)> = branch_table::Entity::find()
+            .filter(branch_table::Column::Xid.eq(session.xid.to_string()))
+            .order_by_asc(branch_table::Column::BranchOrder)
+            .all(&store.conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.branch_id, row.branch_order))
+            .collect();
+        assert_eq!(orders, vec![(1, 0), (2, 1), (3, 2), (4, 3)]);
+    }
+
+    #[tokio::test]
+    async fn global_add_with_branches_is_atomic() {
+        let db = SqliteFile::new();
+        let tc = db.session_manager("tc").await;
+        tc.add_global_session(&new_session(60_000, 1))
+            .await
+            .unwrap();
+
+        // 分支插入失败时全局会话也不会留在库中
+        let conflicting = new_session(60_000, 1);
+        assert!(tc.add_global_session(&conflicting).await.is_err());
+        assert!(tc.find_global_session(&conflicting.xid).await.is_none());
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(feature = "sea_orm")]
+pub mod db;
+pub mod raft;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+use crate::store::raft::node::RaftNode;
+use crate::store::raft::raft_locker::RaftLocker;
+use crate::store::raft::{RaftConfig, RaftTransactionStoreManager};
+use anyhow::bail;
+use rseata_core::event::defaults::event_publisher::DefaultEventPublisher;
+use rseata_core::lock::defaults::default_locker::MemoryLocker;
+use rseata_core::lock::defaults::default_row_lock::DefaultRowLock;
+use rseata_core::lock::locker::Locker;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::store::file_transaction_store_manager::FileTransactionStoreManager;
+use rseata_core::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+use rseata_core::store::{StoreConfig, StoreMode};
+use std::sync::Arc;
+
+pub type DefaultTransactionStoreManager =
+    Box<dyn TransactionStoreManager<GlobalSession = DefaultGlobalSession>>;
+
+pub type DefaultLocker = dyn Locker<RowLock = DefaultRowLock>;
+
+/// session_mode 为 raft 时启动本节点，加入 TC 集群
+pub(crate) async fn start_cluster(config: &StoreConfig) -> anyhow::Result<Option<Arc<RaftNode>>> {
+    if config.session_mode != StoreMode::Raft {
+        return Ok(None);
+    }
+    Ok(Some(RaftNode::start(RaftConfig::from_env()?).await?))
+}
+
+/// 按 session_mode 创建会话存储，持久化存储会在这里完成恢复
+pub(crate) async fn open_transaction_store(
+    config: &StoreConfig,
+    event_publisher: Arc<DefaultEventPublisher>,
+    cluster: Option<Arc<RaftNode>>,
+) -> anyhow::Result<DefaultTransactionStoreManager> {
+    tracing::info!("Session store mode: {:?}", config.session_mode);
+    match config.session_mode {
+        StoreMode::File => Ok(Box::new(
+            FileTransactionStoreManager::open(config, Some(event_publisher)).await?,
+        )),
+        #[cfg(feature = "sea_orm")]
+        StoreMode::Db => Ok(Box::new(
+            db::DbTransactionStoreManager::connect(config).await?,
+        )),
+        #[cfg(feature = "redis")]
+        StoreMode::Redis => Ok(Box::new(
+            self::redis::RedisTransactionStoreManager::connect(config).await?,
+        )),
+        StoreMode::Raft => match cluster {
+            Some(node) => Ok(Box::new(RaftTransactionStoreManager::new(node))),
+            None => bail!("raft cluster is not started"),
+        },
+        StoreMode::Memory => Ok(Box::new(MemeryTransactionStoreManager::default())),
+        // 关闭 sea_orm 或 redis 特性时对应的存储方式不可用
+        #[allow(unreachable_patterns)]
+        mode => bail!("session store mode {mode:?} is not supported yet"),
+    }
+}
+
+/// 按 lock_mode 创建行锁存储
+pub(crate) async fn open_locker(
+    config: &StoreConfig,
+    cluster: Option<Arc<RaftNode>>,
+) -> anyhow::Result<Arc<DefaultLocker>> {
+    tracing::info!("Lock store mode: {:?}", config.lock_mode);
+    match config.lock_mode {
+        StoreMode::Memory => Ok(Arc::new(MemoryLocker::default())),
+        #[cfg(feature = "sea_orm")]
+        StoreMode::Db => Ok(Arc::new(db::db_locker::DbLocker::connect(config).await?)),
+        #[cfg(feature = "redis")]
+        StoreMode::Redis => Ok(Arc::new(
+            self::redis::redis_locker::RedisLocker::connect(config).await?,
+        )),
+        StoreMode::Raft => match cluster {
+            Some(node) => Ok(Arc::new(RaftLocker::new(node))),
+            None => bail!("lock store mode raft requires session store mode raft"),
+        },
+        mode => bail!("lock store mode {mode:?} is not supported yet"),
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{Context, bail};
+use crc::{CRC_32_ISO_HDLC, Crc};
+use prost::Message;
+use rseata_proto::rseata_proto::proto::RaftEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const HARD_STATE_FILE: &str = "raft-state.json";
+const LOG_FILE: &str = "raft.log";
+const SNAPSHOT_FILE: &str = "raft.snap";
+// 记录头：4 字节长度 + 4 字节 CRC32，均为小端
+const RECORD_HEADER_SIZE: usize = 8;
+// 快照头：8 字节索引 + 8 字节任期 + 4 字节 CRC32
+const SNAPSHOT_HEADER_SIZE: usize = 20;
+const CHECKSUM: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// 需要在响应 RPC 前落盘的任期和投票
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub(crate) struct HardState {
+    pub(crate) current_term: u64,
+    pub(crate) voted_for: Option<u64>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    pub(crate) last_included_index: u64,
+    pub(crate) last_included_term: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Raft 的持久化存储：任期投票、快照之后的日志条目和最新快照
+///
+/// 日志追加写入并落盘；截断或压缩时整体重写日志文件
+#[derive(Debug)]
+pub(crate) struct LogStore {
+    dir: PathBuf,
+    hard_state: HardState,
+    snapshot_index: u64,
+    snapshot_term: u64,
+    entries: VecDeque<RaftEntry>,
+    file: File,
+}
+
+impl LogStore {
+    /// 打开数据目录，返回日志存储和最新快照
+    pub(crate) fn open(dir: &Path) -> anyhow::Result<(Self, Option<Snapshot>)> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("create raft data dir {}", dir.display()))?;
+        let hard_state = match fs::read(dir.join(HARD_STATE_FILE)) {
+            Ok(data) => serde_json::from_slice(&data).context("corrupt raft hard state")?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HardState::default(),
+            Err(e) => return Err(e.into()),
+        };
+        let snapshot = read_snapshot(dir)?;
+        let (snapshot_index, snapshot_term) = snapshot
+            .as_ref()
+            .map(|s| (s.last_included_index, s.last_included_term))
+            .unwrap_or_default();
+
+        let mut entries = VecDeque::new();
+        let (records, valid_len) = read_records(&dir.join(LOG_FILE))?;
+        for entry in records {
+            // 快照之后重写日志前崩溃时，文件中可能残留快照已包含的条目
+            if entry.index <= snapshot_index {
+                continue;
+            }
+            let expected = snapshot_index + entries.len() as u64 + 1;
+            if entry.index != expected {
+                bail!(
+                    "raft log is not contiguous: expect {expected}, got {}",
+                    entry.index
+                );
+            }
+            entries.push_back(entry);
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        // 丢弃末尾写了一半的记录
+        if file.metadata()?.len() > valid_len {
+            tracing::warn!("Truncate torn raft log tail at {valid_len}");
+            file.set_len(valid_len)?;
+        }
+
+        Ok((
+            Self {
+                dir: dir.to_path_buf(),
+                hard_state,
+                snapshot_index,
+                snapshot_term,
+                entries,
+                file,
+            },
+            snapshot,
+        ))
+    }
+
+    pub(crate) fn hard_state(&self) -> HardState {
+        self.hard_state
+    }
+
+    pub(crate) fn set_hard_state(&mut self, hard_state: HardState) -> anyhow::Result<()> {
+        write_atomic(
+            &self.dir.join(HARD_STATE_FILE),
+            &serde_json::to_vec(&hard_state)?,
+        )?;
+        self.hard_state = hard_state;
+        Ok(())
+    }
+
+    pub(crate) fn snapshot_index(&self) -> u64 {
+        self.snapshot_index
+    }
+
+    pub(crate) fn last_index(&self) -> u64 {
+        self.snapshot_index + self.entries.len() as u64
+    }
+
+    pub(crate) fn last_term(&self) -> u64 {
+        self.entries
+            .back()
+            .map(|entry| entry.term)
+            .unwrap_or(self.snapshot_term)
+    }
+
+    /// 索引处条目的任期，已被快照压缩或不存在时返回 None
+    pub(crate) fn term(&self, index: u64) -> Option<u64> {
+        if index == self.snapshot_index {
+            return Some(self.snapshot_term);
+        }
+        self.entry(index).map(|entry| entry.term)
+    }
+
+    pub(crate) fn entry(&self, index: u64) -> Option<&RaftEntry> {
+        if index <= self.snapshot_index {
+            return None;
+        }
+        self.entries.get((index - self.snapshot_index - 1) as usize)
+    }
+
+    pub(crate) fn entries_from(&self, index: u64, max: usize) -> Vec<RaftEntry> {
+        let start = index.saturating_sub(self.snapshot_index + 1) as usize;
+        self.entries.iter().skip(start).take(max).cloned().collect()
+    }
+
+    /// 任期 term 在日志中的第一个索引，用于快速回退 next_index
+    pub(crate) fn first_index_of_term(&self, term: u64, before: u64) -> u64 {
+        let mut index = before;
+        while index > self.snapshot_index + 1 && self.term(index - 1) == Some(term) {
+            index -= 1;
+        }
+        index
+    }
+
+    pub(crate) fn append(&mut self, entries: Vec<RaftEntry>) -> anyhow::Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut buf = Vec::new();
+        for entry in &entries {
+            encode_record(&mut buf, entry);
+        }
+        self.file.write_all(&buf)?;
+        self.file.sync_data()?;
+        self.entries.extend(entries);
+        Ok(())
+    }
+
+    /// 删除 index 及之后的条目
+    pub(crate) fn truncate_from(&mut self, index: u64) -> anyhow::Result<()> {
+        let keep = index.saturating_sub(self.snapshot_index + 1) as usize;
+        self.entries.truncate(keep);
+        self.rewrite()
+    }
+
+    /// 保存快照并压缩日志；日志中与快照一致的后续条目保留，否则全部丢弃
+    pub(crate) fn install_snapshot(&mut self, snapshot: &Snapshot) -> anyhow::Result<()> {
+        let mut buf = Vec::with_capacity(SNAPSHOT_HEADER_SIZE + snapshot.data.len());
+        buf.extend_from_slice(&snapshot.last_included_index.to_le_bytes());
+        buf.extend_from_slice(&snapshot.last_included_term.to_le_bytes());
+        buf.extend_from_slice(&CHECKSUM.checksum(&snapshot.data).to_le_bytes());
+        buf.extend_from_slice(&snapshot.data);
+        write_atomic(&self.dir.join(SNAPSHOT_FILE), &buf)?;
+
+        if self.term(snapshot.last_included_index) == Some(snapshot.last_included_term) {
+            let drop = (snapshot.last_included_index - self.snapshot_index) as usize;
+            self.entries.drain(..drop.min(self.entries.len()));
+        } else {
+            self.entries.clear();
+        }
+        self.snapshot_index = snapshot.last_included_index;
+        self.snapshot_term = snapshot.last_included_term;
+        self.rewrite()
+    }
+
+    pub(crate) fn read_snapshot(&self) -> anyhow::Result<Option<Snapshot>> {
+        read_snapshot(&self.dir)
+    }
+
+    fn rewrite(&mut self) -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        for entry in &self.entries {
+            encode_record(&mut buf, entry);
+        }
+        let path = self.dir.join(LOG_FILE);
+        write_atomic(&path, &buf)?;
+        self.file = OpenOptions::new().append(true).open(path)?;
+        Ok(())
+    }
+}
+
+fn encode_record(buf: &mut Vec<u8>, entry: &RaftEntry) {
+    let payload = entry.encode_to_vec();
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&CHECKSUM.checksum(&payload).to_le_bytes());
+    buf.extend_from_slice(&payload);
+}
+
+// 返回完整的记录及其占用的字节数
+fn read_records(path: &Path) -> anyhow::Result<(Vec<RaftEntry>, u64)> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset + RECORD_HEADER_SIZE <= data.len() {
+        let len = u32::from_le_bytes(data[offset..offset + 4].try_into()?) as usize;
+        let crc = u32::from_le_bytes(data[offset + 4..offset + 8].try_into()?);
+        let start = offset + RECORD_HEADER_SIZE;
+        let Some(payload) = data.get(start..start + len) else {
+            break;
+        };
+        if CHECKSUM.checksum(payload) != crc {
+            break;
+        }
+        records.push(RaftEntry::decode(payload)?);
+        offset = start + len;
+    }
+    Ok((records, offset as u64))
+}
+
+fn read_snapshot(dir: &Path) -> anyhow::Result<Option<Snapshot>> {
+    let data = match fs::read(dir.join(SNAPSHOT_FILE)) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if data.len() < SNAPSHOT_HEADER_SIZE {
+        bail!("corrupt raft snapshot header");
+    }
+    let payload = &data[SNAPSHOT_HEADER_SIZE..];
+    if CHECKSUM.checksum(payload) != u32::from_le_bytes(data[16..20].try_into()?) {
+        bail!("corrupt raft snapshot checksum");
+    }
+    Ok(Some(Snapshot {
+        last_included_index: u64::from_le_bytes(data[0..8].try_into()?),
+        last_included_term: u64::from_le_bytes(data[8..16].try_into()?),
+        data: payload.to_vec(),
+    }))
+}
+
+// 先写临时文件再重命名，避免崩溃时留下不完整的文件；目录也要落盘，重命名才不会在掉电后丢失
+fn write_atomic(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
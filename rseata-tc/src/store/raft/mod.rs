@@ -0,0 +1,224 @@
+mod log_store;
+pub mod node;
+pub mod raft_locker;
+mod service;
+mod state_machine;
+
+use crate::config::{
+    get_env_raft_data_dir, get_env_raft_election_timeout_millis,
+    get_env_raft_heartbeat_interval_millis, get_env_raft_node_id, get_env_raft_nodes,
+    get_env_raft_propose_timeout_millis, get_env_raft_snapshot_interval,
+};
+use crate::store::raft::node::RaftNode;
+use crate::store::raft::state_machine::RaftCommand;
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use rseata_core::branch::BranchId;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::session_condition::SessionCondition;
+use rseata_core::store::LogOperation;
+use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+use rseata_core::types::{GlobalStatus, Xid};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct RaftNodeAddr {
+    // 节点间 Raft 通信地址
+    pub raft_addr: String,
+    // 对 TM/RM 提供服务的 gRPC 地址，跟随者据此转发请求
+    pub grpc_addr: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RaftConfig {
+    pub node_id: u64,
+    pub nodes: BTreeMap<u64, RaftNodeAddr>,
+    pub data_dir: PathBuf,
+    pub election_timeout: Duration,
+    pub heartbeat_interval: Duration,
+    // 每应用多少条日志生成一次快照并压缩日志
+    pub snapshot_interval: u64,
+    pub propose_timeout: Duration,
+}
+
+impl RaftConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let nodes = parse_nodes(&get_env_raft_nodes())?;
+        let node_id = get_env_raft_node_id();
+        if !nodes.contains_key(&node_id) {
+            bail!("raft node {node_id} not found in RSEATA_TC_RAFT_NODES");
+        }
+        Ok(Self {
+            node_id,
+            nodes,
+            data_dir: PathBuf::from(get_env_raft_data_dir()),
+            election_timeout: Duration::from_millis(get_env_raft_election_timeout_millis()),
+            heartbeat_interval: Duration::from_millis(get_env_raft_heartbeat_interval_millis()),
+            snapshot_interval: get_env_raft_snapshot_interval().max(1),
+            propose_timeout: Duration::from_millis(get_env_raft_propose_timeout_millis()),
+        })
+    }
+}
+
+// 格式：1=127.0.0.1:9911|127.0.0.1:9811,2=...，即 节点ID=Raft地址|gRPC地址
+fn parse_nodes(value: &str) -> anyhow::Result<BTreeMap<u64, RaftNodeAddr>> {
+    let mut nodes = BTreeMap::new();
+    for item in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let (id, addrs) = item
+            .split_once('=')
+            .with_context(|| format!("invalid raft node: {item}"))?;
+        let (raft_addr, grpc_addr) = addrs
+            .split_once('|')
+            .with_context(|| format!("invalid raft node: {item}"))?;
+        nodes.insert(
+            id.trim().parse()?,
+            RaftNodeAddr {
+                raft_addr: raft_addr.trim().to_string(),
+                grpc_addr: grpc_addr.trim().to_string(),
+            },
+        );
+    }
+    if nodes.is_empty() {
+        bail!("RSEATA_TC_RAFT_NODES is empty");
+    }
+    Ok(nodes)
+}
+
+/// 基于 Raft 复制的会话存储
+///
+/// 写操作作为日志提交到多数节点后应用到各节点的内存状态机，读操作直接读取本地状态机
+pub struct RaftTransactionStoreManager {
+    node: Arc<RaftNode>,
+}
+
+impl RaftTransactionStoreManager {
+    pub fn new(node: Arc<RaftNode>) -> Self {
+        Self { node }
+    }
+}
+
+impl Debug for RaftTransactionStoreManager {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RaftTransactionStoreManager")
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl TransactionStoreManager for RaftTransactionStoreManager {
+    type GlobalSession = DefaultGlobalSession;
+
+    async fn write_session(
+        &self,
+        log_operation: LogOperation,
+        session: &Self::GlobalSession,
+    ) -> anyhow::Result<()> {
+        self.node
+            .propose(RaftCommand::Session {
+                operation: log_operation,
+                session: session.clone(),
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        self.node
+            .propose(RaftCommand::Branch {
+                operation: log_operation,
+                branch: branch_session.clone(),
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn read_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_session(xid)
+            .await
+    }
+
+    async fn read_branch_session(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Option<DefaultBranchSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_branch_session(xid, branch_id)
+            .await
+    }
+
+    async fn read_session_with_branches(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_session_with_branches(xid)
+            .await
+    }
+
+    async fn read_global_session(
+        &self,
+        xid: &Xid,
+        with_branch_sessions: bool,
+    ) -> Option<DefaultGlobalSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_global_session(xid, with_branch_sessions)
+            .await
+    }
+
+    async fn read_sort_by_timeout_begin_sessions(
+        &self,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_sort_by_timeout_begin_sessions(with_branch_sessions)
+            .await
+    }
+
+    async fn read_session_by_global_status(
+        &self,
+        statuses: &[GlobalStatus],
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_session_by_global_status(statuses, with_branch_sessions)
+            .await
+    }
+
+    async fn read_session_by_session_condition(
+        &self,
+        condition: &SessionCondition,
+    ) -> Vec<DefaultGlobalSession> {
+        self.node
+            .state_machine()
+            .await
+            .sessions
+            .read_session_by_session_condition(condition)
+            .await
+    }
+}
@@ -0,0 +1,1060 @@
+use crate::store::raft::RaftConfig;
+use crate::store::raft::log_store::{HardState, LogStore, Snapshot};
+use crate::store::raft::service::RaftGrpcService;
+use crate::store::raft::state_machine::{ApplyResult, RaftCommand, StateMachine};
+use anyhow::{Context, bail};
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use rseata_proto::rseata_proto::proto::raft_service_client::RaftServiceClient;
+use rseata_proto::rseata_proto::proto::raft_service_server::RaftServiceServer;
+use rseata_proto::rseata_proto::proto::{
+    AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
+    RaftEntry, VoteRequest, VoteResponse,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, RwLock, RwLockReadGuard, oneshot, watch};
+use tokio::time::Instant;
+use tonic::transport::{Channel, Endpoint, Server};
+use uuid::Uuid;
+
+// 单次 AppendEntries 携带的最大条目数
+const MAX_ENTRIES_PER_APPEND: usize = 256;
+// 快照整体发送，放宽 gRPC 消息大小限制
+const MAX_MESSAGE_SIZE: usize = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+struct RaftState {
+    role: Role,
+    leader_id: Option<u64>,
+    log: LogStore,
+    commit_index: u64,
+    election_deadline: Instant,
+    next_index: HashMap<u64, u64>,
+    match_index: HashMap<u64, u64>,
+}
+
+struct Peer {
+    client: RaftServiceClient<Channel>,
+    // 有新日志或需要立即追赶时唤醒复制任务
+    notify: Notify,
+}
+
+enum ReplicateRequest {
+    Append(AppendEntriesRequest),
+    Snapshot(InstallSnapshotRequest),
+}
+
+/// 内嵌的 Raft 节点，复制会话和行锁命令
+///
+/// 只有 leader 接受提案；提交后的命令按顺序应用到内存状态机，
+/// 日志超过快照间隔后生成快照并压缩，落后过多的跟随者通过快照追赶
+pub struct RaftNode {
+    config: RaftConfig,
+    peers: HashMap<u64, Peer>,
+    state: Mutex<RaftState>,
+    state_machine: RwLock<StateMachine>,
+    // 等待提交结果的提案：索引 -> (任期, 结果)
+    waiters: std::sync::Mutex<HashMap<u64, (u64, oneshot::Sender<ApplyResult>)>>,
+    apply_notify: Notify,
+    // leader 的空条目应用到状态机后才对外提供服务
+    leader_ready: watch::Sender<bool>,
+    // 当前已知的 leader，变化时通知转发中的 RM 流重新挂接
+    leader_id: watch::Sender<Option<u64>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl RaftNode {
+    /// 从数据目录恢复并启动节点，同时启动节点间通信的 gRPC 服务
+    pub(crate) async fn start(config: RaftConfig) -> anyhow::Result<Arc<Self>> {
+        let local = config
+            .nodes
+            .get(&config.node_id)
+            .with_context(|| format!("raft node {} not in cluster nodes", config.node_id))?;
+        let bind = tokio::net::lookup_host(&local.raft_addr)
+            .await?
+            .next()
+            .with_context(|| format!("resolve raft address {}", local.raft_addr))?;
+
+        let (log, snapshot) =
+            LogStore::open(&config.data_dir.join(format!("node-{}", config.node_id)))?;
+        let state_machine = match snapshot {
+            Some(snapshot) => {
+                StateMachine::restore(
+                    snapshot.last_included_index,
+                    snapshot.last_included_term,
+                    &snapshot.data,
+                )
+                .await?
+            }
+            None => StateMachine::default(),
+        };
+
+        let mut peers = HashMap::new();
+        for (id, addr) in &config.nodes {
+            if *id == config.node_id {
+                continue;
+            }
+            let channel = Endpoint::from_shared(format!("http://{}", addr.raft_addr))?
+                .connect_timeout(config.election_timeout)
+                .connect_lazy();
+            peers.insert(
+                *id,
+                Peer {
+                    client: RaftServiceClient::new(channel)
+                        .max_decoding_message_size(MAX_MESSAGE_SIZE)
+                        .max_encoding_message_size(MAX_MESSAGE_SIZE),
+                    notify: Notify::new(),
+                },
+            );
+        }
+
+        tracing::info!(
+            "Raft node {} starts at {}, term {}, log {}..={}",
+            config.node_id,
+            bind,
+            log.hard_state().current_term,
+            log.snapshot_index(),
+            log.last_index()
+        );
+        let node = Arc::new(Self {
+            state: Mutex::new(RaftState {
+                role: Role::Follower,
+                leader_id: None,
+                commit_index: state_machine.last_applied,
+                log,
+                election_deadline: Instant::now() + random_timeout(config.election_timeout),
+                next_index: HashMap::new(),
+                match_index: HashMap::new(),
+            }),
+            state_machine: RwLock::new(state_machine),
+            peers,
+            waiters: Default::default(),
+            apply_notify: Notify::new(),
+            leader_ready: watch::channel(false).0,
+            leader_id: watch::channel(None).0,
+            shutdown: watch::channel(false).0,
+            config,
+        });
+
+        let service = RaftServiceServer::new(RaftGrpcService::new(node.clone()))
+            .max_decoding_message_size(MAX_MESSAGE_SIZE)
+            .max_encoding_message_size(MAX_MESSAGE_SIZE);
+        let server = node.clone();
+        tokio::spawn(async move {
+            if let Err(e) = Server::builder()
+                .add_service(service)
+                .serve_with_shutdown(bind, server.stopped())
+                .await
+            {
+                tracing::error!("Raft service stopped: {:?}", e);
+            }
+        });
+        tokio::spawn(node.clone().run_election_timer());
+        tokio::spawn(node.clone().run_apply());
+        for peer_id in node.peers.keys() {
+            tokio::spawn(node.clone().run_replicator(*peer_id));
+        }
+        Ok(node)
+    }
+
+    /// 本节点是否为可以服务的 leader
+    pub(crate) fn is_leader(&self) -> bool {
+        *self.leader_ready.borrow()
+    }
+
+    /// 订阅 leader 状态变化
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.leader_ready.subscribe()
+    }
+
+    /// 订阅集群 leader 变化
+    pub(crate) fn subscribe_leader(&self) -> watch::Receiver<Option<u64>> {
+        self.leader_id.subscribe()
+    }
+
+    /// 停止节点的全部后台任务和 Raft 服务，用于模拟节点宕机
+    #[cfg(test)]
+    pub(crate) fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+        self.leader_ready.send_replace(false);
+    }
+
+    async fn stopped(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+        let _ = shutdown.wait_for(|stopped| *stopped).await;
+    }
+
+    /// 当前 leader 的 TC gRPC 地址，本节点为 leader 或尚无 leader 时返回 None
+    pub(crate) async fn leader_endpoint(&self) -> Option<String> {
+        let leader_id = self.state.lock().await.leader_id?;
+        if leader_id == self.config.node_id {
+            return None;
+        }
+        self.config
+            .nodes
+            .get(&leader_id)
+            .map(|addr| addr.grpc_addr.clone())
+    }
+
+    pub(crate) async fn state_machine(&self) -> RwLockReadGuard<'_, StateMachine> {
+        self.state_machine.read().await
+    }
+
+    /// 提交命令并等待其应用到状态机
+    pub(crate) async fn propose(&self, command: RaftCommand) -> anyhow::Result<bool> {
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut st = self.state.lock().await;
+            if st.role != Role::Leader || !self.is_leader() {
+                bail!(
+                    "raft node {} is not the serving leader, leader: {:?}",
+                    self.config.node_id,
+                    st.leader_id
+                );
+            }
+            let (index, term) = self.append_local(&mut st, &command)?;
+            self.waiters.lock().unwrap().insert(index, (term, tx));
+            self.advance_commit(&mut st);
+        }
+        self.notify_peers();
+
+        match tokio::time::timeout(self.config.propose_timeout, rx).await {
+            Ok(Ok(result)) => result.map_err(anyhow::Error::msg),
+            Ok(Err(_)) => bail!("raft proposal dropped"),
+            Err(_) => bail!(
+                "raft proposal timeout after {}ms",
+                self.config.propose_timeout.as_millis()
+            ),
+        }
+    }
+
+    pub(crate) async fn handle_request_vote(&self, request: VoteRequest) -> VoteResponse {
+        let mut st = self.state.lock().await;
+        if request.term > st.log.hard_state().current_term {
+            self.step_down(&mut st, request.term);
+        }
+        let hard_state = st.log.hard_state();
+        let up_to_date = request.last_log_term > st.log.last_term()
+            || (request.last_log_term == st.log.last_term()
+                && request.last_log_index >= st.log.last_index());
+        let vote_granted = request.term == hard_state.current_term
+            && hard_state
+                .voted_for
+                .is_none_or(|id| id == request.candidate_id)
+            && up_to_date;
+        if vote_granted {
+            self.persist_hard_state(
+                &mut st,
+                HardState {
+                    voted_for: Some(request.candidate_id),
+                    ..hard_state
+                },
+            );
+            st.election_deadline = Instant::now() + random_timeout(self.config.election_timeout);
+        }
+        VoteResponse {
+            term: hard_state.current_term,
+            vote_granted,
+        }
+    }
+
+    pub(crate) async fn handle_append_entries(
+        &self,
+        request: AppendEntriesRequest,
+    ) -> AppendEntriesResponse {
+        let mut st = self.state.lock().await;
+        let current_term = st.log.hard_state().current_term;
+        let reject = |term, conflict_index| AppendEntriesResponse {
+            term,
+            success: false,
+            match_index: 0,
+            conflict_index,
+        };
+        if request.term < current_term {
+            return reject(current_term, 0);
+        }
+        if request.term > current_term || st.role != Role::Follower {
+            self.step_down(&mut st, request.term);
+        }
+        self.set_leader(&mut st, Some(request.leader_id));
+        st.election_deadline = Instant::now() + random_timeout(self.config.election_timeout);
+
+        // 日志缺失或前一条目任期不一致时，告知 leader 回退的位置
+        if request.prev_log_index > st.log.last_index() {
+            return reject(request.term, st.log.last_index() + 1);
+        }
+        if request.prev_log_index >= st.log.snapshot_index() {
+            let local_term = st.log.term(request.prev_log_index);
+            if local_term != Some(request.prev_log_term) {
+                let conflict_index = st
+                    .log
+                    .first_index_of_term(local_term.unwrap_or_default(), request.prev_log_index);
+                return reject(request.term, conflict_index);
+            }
+        }
+
+        let last_new = request.prev_log_index + request.entries.len() as u64;
+        let mut new_entries = Vec::new();
+        for entry in request.entries {
+            if !new_entries.is_empty() {
+                new_entries.push(entry);
+                continue;
+            }
+            if entry.index <= st.log.snapshot_index() {
+                continue;
+            }
+            match st.log.term(entry.index) {
+                Some(term) if term == entry.term => {}
+                Some(_) => {
+                    if let Err(e) = st.log.truncate_from(entry.index) {
+                        tracing::error!("Truncate raft log failed: {:?}", e);
+                        return reject(request.term, entry.index);
+                    }
+                    new_entries.push(entry);
+                }
+                None => new_entries.push(entry),
+            }
+        }
+        if let Err(e) = st.log.append(new_entries) {
+            tracing::error!("Append raft log failed: {:?}", e);
+            return reject(request.term, st.log.last_index() + 1);
+        }
+
+        if request.leader_commit > st.commit_index {
+            st.commit_index = st.commit_index.max(request.leader_commit.min(last_new));
+            self.apply_notify.notify_one();
+        }
+        AppendEntriesResponse {
+            term: request.term,
+            success: true,
+            // 快照中的条目均已提交，与 leader 一致
+            match_index: last_new.max(st.log.snapshot_index()),
+            conflict_index: 0,
+        }
+    }
+
+    pub(crate) async fn handle_install_snapshot(
+        &self,
+        request: InstallSnapshotRequest,
+    ) -> InstallSnapshotResponse {
+        {
+            let mut st = self.state.lock().await;
+            let current_term = st.log.hard_state().current_term;
+            if request.term < current_term {
+                return InstallSnapshotResponse { term: current_term };
+            }
+            if request.term > current_term || st.role != Role::Follower {
+                self.step_down(&mut st, request.term);
+            }
+            self.set_leader(&mut st, Some(request.leader_id));
+            st.election_deadline = Instant::now() + random_timeout(self.config.election_timeout);
+        }
+
+        // 先状态机后日志的加锁顺序与生成快照一致
+        let mut state_machine = self.state_machine.write().await;
+        let mut st = self.state.lock().await;
+        let term = st.log.hard_state().current_term;
+        if request.last_included_index <= state_machine.last_applied
+            || request.last_included_index <= st.log.snapshot_index()
+        {
+            return InstallSnapshotResponse { term };
+        }
+        let restored = StateMachine::restore(
+            request.last_included_index,
+            request.last_included_term,
+            &request.data,
+        )
+        .await;
+        let result = restored.and_then(|restored| {
+            st.log.install_snapshot(&Snapshot {
+                last_included_index: request.last_included_index,
+                last_included_term: request.last_included_term,
+                data: request.data,
+            })?;
+            Ok(restored)
+        });
+        match result {
+            Ok(restored) => {
+                *state_machine = restored;
+                st.commit_index = st.commit_index.max(request.last_included_index);
+                tracing::info!(
+                    "Raft node {} installed snapshot at {}",
+                    self.config.node_id,
+                    request.last_included_index
+                );
+            }
+            Err(e) => tracing::error!("Install raft snapshot failed: {:?}", e),
+        }
+        InstallSnapshotResponse { term }
+    }
+
+    fn quorum(&self) -> usize {
+        self.config.nodes.len() / 2 + 1
+    }
+
+    fn notify_peers(&self) {
+        for peer in self.peers.values() {
+            peer.notify.notify_one();
+        }
+    }
+
+    fn append_local(
+        &self,
+        st: &mut RaftState,
+        command: &RaftCommand,
+    ) -> anyhow::Result<(u64, u64)> {
+        let index = st.log.last_index() + 1;
+        let term = st.log.hard_state().current_term;
+        st.log.append(vec![RaftEntry {
+            term,
+            index,
+            command: serde_json::to_vec(command)?,
+        }])?;
+        Ok((index, term))
+    }
+
+    // 任期和投票必须先落盘再对外可见，写入失败后继续运行可能在同一任期投出两票，只能终止进程
+    fn persist_hard_state(&self, st: &mut RaftState, hard_state: HardState) {
+        if let Err(e) = st.log.set_hard_state(hard_state) {
+            tracing::error!(
+                "Persist raft hard state {:?} of node {} failed, abort: {:?}",
+                hard_state,
+                self.config.node_id,
+                e
+            );
+            std::process::abort();
+        }
+    }
+
+    fn set_leader(&self, st: &mut RaftState, leader_id: Option<u64>) {
+        st.leader_id = leader_id;
+        self.leader_id.send_if_modified(|current| {
+            let changed = *current != leader_id;
+            *current = leader_id;
+            changed
+        });
+    }
+
+    fn step_down(&self, st: &mut RaftState, term: u64) {
+        if term > st.log.hard_state().current_term {
+            self.persist_hard_state(
+                st,
+                HardState {
+                    current_term: term,
+                    voted_for: None,
+                },
+            );
+        }
+        if st.role == Role::Leader {
+            tracing::info!(
+                "Raft node {} steps down at term {}",
+                self.config.node_id,
+                term
+            );
+            self.set_leader(st, None);
+            st.election_deadline = Instant::now() + random_timeout(self.config.election_timeout);
+            self.leader_ready.send_replace(false);
+            self.fail_waiters("raft leadership lost, proposal result unknown");
+        }
+        st.role = Role::Follower;
+    }
+
+    fn become_leader(&self, st: &mut RaftState) {
+        st.role = Role::Leader;
+        self.set_leader(st, Some(self.config.node_id));
+        let next_index = st.log.last_index() + 1;
+        for peer_id in self.peers.keys() {
+            st.next_index.insert(*peer_id, next_index);
+            st.match_index.insert(*peer_id, 0);
+        }
+        tracing::info!(
+            "Raft node {} becomes leader of term {}",
+            self.config.node_id,
+            st.log.hard_state().current_term
+        );
+        // 空条目提交后，此前任期的日志随之提交
+        if let Err(e) = self.append_local(st, &RaftCommand::Noop) {
+            tracing::error!("Append raft noop failed: {:?}", e);
+        }
+        self.advance_commit(st);
+        self.notify_peers();
+    }
+
+    // 只提交当前任期且已复制到多数节点的日志
+    fn advance_commit(&self, st: &mut RaftState) {
+        if st.role != Role::Leader {
+            return;
+        }
+        let current_term = st.log.hard_state().current_term;
+        let mut index = st.log.last_index();
+        while index > st.commit_index && st.log.term(index) == Some(current_term) {
+            let replicated = 1 + st.match_index.values().filter(|m| **m >= index).count();
+            if replicated >= self.quorum() {
+                st.commit_index = index;
+                self.apply_notify.notify_one();
+                return;
+            }
+            index -= 1;
+        }
+    }
+
+    fn fail_waiters(&self, reason: &str) {
+        for (_, (_, tx)) in self.waiters.lock().unwrap().drain() {
+            let _ = tx.send(Err(reason.to_string()));
+        }
+    }
+
+    fn complete_waiter(&self, index: u64, term: u64, result: ApplyResult) {
+        if let Some((waiter_term, tx)) = self.waiters.lock().unwrap().remove(&index) {
+            let result = match waiter_term == term {
+                true => result,
+                false => Err("raft entry replaced by another leader".to_string()),
+            };
+            let _ = tx.send(result);
+        }
+    }
+
+    async fn run_election_timer(self: Arc<Self>) {
+        loop {
+            let deadline = {
+                let st = self.state.lock().await;
+                match st.role {
+                    Role::Leader => Instant::now() + self.config.election_timeout,
+                    _ => st.election_deadline,
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => {}
+                _ = self.stopped() => return,
+            }
+            // 等待期间可能收到心跳或投出选票，超时时间已经顺延
+            let expired = {
+                let st = self.state.lock().await;
+                st.role != Role::Leader && Instant::now() >= st.election_deadline
+            };
+            if expired {
+                self.start_election().await;
+            }
+        }
+    }
+
+    async fn start_election(&self) {
+        let request = {
+            let mut st = self.state.lock().await;
+            let term = st.log.hard_state().current_term + 1;
+            self.persist_hard_state(
+                &mut st,
+                HardState {
+                    current_term: term,
+                    voted_for: Some(self.config.node_id),
+                },
+            );
+            st.role = Role::Candidate;
+            self.set_leader(&mut st, None);
+            st.election_deadline = Instant::now() + random_timeout(self.config.election_timeout);
+            tracing::info!(
+                "Raft node {} starts election for term {}",
+                self.config.node_id,
+                term
+            );
+            if self.quorum() == 1 {
+                self.become_leader(&mut st);
+                return;
+            }
+            VoteRequest {
+                term,
+                candidate_id: self.config.node_id,
+                last_log_index: st.log.last_index(),
+                last_log_term: st.log.last_term(),
+            }
+        };
+
+        let mut pending: FuturesUnordered<_> = self
+            .peers
+            .values()
+            .map(|peer| {
+                let mut client = peer.client.clone();
+                async move {
+                    tokio::time::timeout(self.config.election_timeout, client.request_vote(request))
+                        .await
+                }
+            })
+            .collect();
+        let mut votes = 1;
+        while let Some(result) = pending.next().await {
+            let Ok(Ok(response)) = result else {
+                continue;
+            };
+            let response = response.into_inner();
+            let mut st = self.state.lock().await;
+            if response.term > st.log.hard_state().current_term {
+                self.step_down(&mut st, response.term);
+                return;
+            }
+            if st.role != Role::Candidate || st.log.hard_state().current_term != request.term {
+                return;
+            }
+            if response.vote_granted {
+                votes += 1;
+                if votes >= self.quorum() {
+                    self.become_leader(&mut st);
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn run_replicator(self: Arc<Self>, peer_id: u64) {
+        let peer = &self.peers[&peer_id];
+        loop {
+            tokio::select! {
+                _ = peer.notify.notified() => {}
+                _ = tokio::time::sleep(self.config.heartbeat_interval) => {}
+                _ = self.stopped() => return,
+            }
+            // 跟随者落后时连续发送，直到与 leader 日志一致
+            while self.replicate_once(peer_id, peer).await {}
+        }
+    }
+
+    // 向跟随者发送一次日志或快照，返回是否需要继续发送
+    async fn replicate_once(&self, peer_id: u64, peer: &Peer) -> bool {
+        let request = {
+            let st = self.state.lock().await;
+            if st.role != Role::Leader {
+                return false;
+            }
+            let term = st.log.hard_state().current_term;
+            let next_index = st.next_index.get(&peer_id).copied().unwrap_or(1);
+            if next_index <= st.log.snapshot_index() {
+                match st.log.read_snapshot() {
+                    Ok(Some(snapshot)) => ReplicateRequest::Snapshot(InstallSnapshotRequest {
+                        term,
+                        leader_id: self.config.node_id,
+                        last_included_index: snapshot.last_included_index,
+                        last_included_term: snapshot.last_included_term,
+                        data: snapshot.data,
+                    }),
+                    other => {
+                        tracing::error!("Read raft snapshot failed: {:?}", other.err());
+                        return false;
+                    }
+                }
+            } else {
+                let prev_log_index = next_index - 1;
+                ReplicateRequest::Append(AppendEntriesRequest {
+                    term,
+                    leader_id: self.config.node_id,
+                    prev_log_index,
+                    prev_log_term: st.log.term(prev_log_index).unwrap_or_default(),
+                    entries: st.log.entries_from(next_index, MAX_ENTRIES_PER_APPEND),
+                    leader_commit: st.commit_index,
+                })
+            }
+        };
+
+        let mut client = peer.client.clone();
+        match request {
+            ReplicateRequest::Append(request) => {
+                let term = request.term;
+                let response = match tokio::time::timeout(
+                    self.config.election_timeout,
+                    client.append_entries(request),
+                )
+                .await
+                {
+                    Ok(Ok(response)) => response.into_inner(),
+                    Ok(Err(e)) => {
+                        tracing::debug!("Append entries to raft node {} failed: {}", peer_id, e);
+                        return false;
+                    }
+                    Err(_) => return false,
+                };
+                let mut st = self.state.lock().await;
+                if response.term > st.log.hard_state().current_term {
+                    self.step_down(&mut st, response.term);
+                    return false;
+                }
+                if st.role != Role::Leader || st.log.hard_state().current_term != term {
+                    return false;
+                }
+                let next_index = st.next_index.get(&peer_id).copied().unwrap_or(1);
+                if response.success {
+                    let match_index = st.match_index.entry(peer_id).or_default();
+                    *match_index = (*match_index).max(response.match_index);
+                    let match_index = *match_index;
+                    st.next_index.insert(peer_id, match_index + 1);
+                    self.advance_commit(&mut st);
+                    match_index < st.log.last_index()
+                } else {
+                    let retry = response
+                        .conflict_index
+                        .clamp(1, next_index.saturating_sub(1).max(1));
+                    st.next_index.insert(peer_id, retry);
+                    true
+                }
+            }
+            ReplicateRequest::Snapshot(request) => {
+                let term = request.term;
+                let last_included_index = request.last_included_index;
+                let response = match tokio::time::timeout(
+                    self.config.propose_timeout,
+                    client.install_snapshot(request),
+                )
+                .await
+                {
+                    Ok(Ok(response)) => response.into_inner(),
+                    Ok(Err(e)) => {
+                        tracing::debug!("Install snapshot to raft node {} failed: {}", peer_id, e);
+                        return false;
+                    }
+                    Err(_) => return false,
+                };
+                let mut st = self.state.lock().await;
+                if response.term > st.log.hard_state().current_term {
+                    self.step_down(&mut st, response.term);
+                    return false;
+                }
+                if st.role != Role::Leader || st.log.hard_state().current_term != term {
+                    return false;
+                }
+                let match_index = st.match_index.entry(peer_id).or_default();
+                *match_index = (*match_index).max(last_included_index);
+                let match_index = *match_index;
+                st.next_index.insert(peer_id, match_index + 1);
+                self.advance_commit(&mut st);
+                match_index < st.log.last_index()
+            }
+        }
+    }
+
+    async fn run_apply(self: Arc<Self>) {
+        loop {
+            tokio::select! {
+                _ = self.apply_notify.notified() => {}
+                _ = self.stopped() => return,
+            }
+            if let Err(e) = self.apply_committed().await {
+                tracing::error!("Apply raft log failed: {:?}", e);
+            }
+        }
+    }
+
+    async fn apply_committed(&self) -> anyhow::Result<()> {
+        loop {
+            let next = self.state_machine.read().await.last_applied + 1;
+            let entry = {
+                let st = self.state.lock().await;
+                if next > st.commit_index {
+                    return Ok(());
+                }
+                st.log.entry(next).cloned()
+            };
+            // 条目已被安装的快照覆盖，重新读取状态机位置
+            let Some(entry) = entry else {
+                tokio::task::yield_now().await;
+                continue;
+            };
+
+            let command = serde_json::from_slice::<RaftCommand>(&entry.command);
+            let noop = matches!(command, Ok(RaftCommand::Noop));
+            let result = {
+                let mut state_machine = self.state_machine.write().await;
+                if state_machine.last_applied + 1 != entry.index {
+                    continue;
+                }
+                let result = match command {
+                    Ok(command) => state_machine.apply(command).await,
+                    Err(e) => Err(format!("decode raft command failed: {e}")),
+                };
+                state_machine.last_applied = entry.index;
+                state_machine.last_applied_term = entry.term;
+                result
+            };
+            self.complete_waiter(entry.index, entry.term, result);
+
+            if noop {
+                let st = self.state.lock().await;
+                if st.role == Role::Leader && st.log.hard_state().current_term == entry.term {
+                    tracing::info!(
+                        "Raft node {} serves as leader from index {}",
+                        self.config.node_id,
+                        entry.index
+                    );
+                    self.leader_ready.send_replace(true);
+                }
+            }
+            self.maybe_snapshot().await?;
+        }
+    }
+
+    async fn maybe_snapshot(&self) -> anyhow::Result<()> {
+        let state_machine = self.state_machine.read().await;
+        let snapshot_index = self.state.lock().await.log.snapshot_index();
+        if state_machine.last_applied - snapshot_index < self.config.snapshot_interval {
+            return Ok(());
+        }
+        let snapshot = Snapshot {
+            last_included_index: state_machine.last_applied,
+            last_included_term: state_machine.last_applied_term,
+            data: state_machine.snapshot().await?,
+        };
+        let mut st = self.state.lock().await;
+        if snapshot.last_included_index > st.log.snapshot_index() {
+            st.log.install_snapshot(&snapshot)?;
+            tracing::info!(
+                "Raft node {} compacted log at {}",
+                self.config.node_id,
+                snapshot.last_included_index
+            );
+        }
+        Ok(())
+    }
+}
+
+// 选举超时在 [timeout, 2 * timeout) 之间随机，避免同时发起选举
+fn random_timeout(timeout: Duration) -> Duration {
+    let jitter = Uuid::new_v4().as_u128() % timeout.as_millis().max(1);
+    timeout + Duration::from_millis(jitter as u64)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::store::raft::RaftNodeAddr;
+    use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+    use rseata_core::store::LogOperation;
+    use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+    use rseata_core::types::Xid;
+    use std::collections::BTreeMap;
+    use std::future::Future;
+    use std::path::PathBuf;
+
+    const WAIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+    /// 同一进程内的多节点集群，各节点使用本机随机端口和独立数据目录
+    pub(crate) struct TestCluster {
+        pub(crate) nodes: BTreeMap<u64, RaftNodeAddr>,
+        pub(crate) running: BTreeMap<u64, Arc<RaftNode>>,
+        data_dir: PathBuf,
+        snapshot_interval: u64,
+    }
+
+    impl TestCluster {
+        pub(crate) async fn start(size: u64, snapshot_interval: u64) -> Self {
+            let nodes = (1..=size)
+                .map(|id| {
+                    let addr = RaftNodeAddr {
+                        raft_addr: free_addr(),
+                        grpc_addr: free_addr(),
+                    };
+                    (id, addr)
+                })
+                .collect();
+            let mut cluster = Self {
+                nodes,
+                running: BTreeMap::new(),
+                data_dir: std::env::temp_dir().join(format!("rseata-raft-{}", Uuid::new_v4())),
+                snapshot_interval,
+            };
+            for id in 1..=size {
+                cluster.restart(id).await;
+            }
+            cluster
+        }
+
+        pub(crate) async fn restart(&mut self, node_id: u64) {
+            let config = RaftConfig {
+                node_id,
+                nodes: self.nodes.clone(),
+                data_dir: self.data_dir.clone(),
+                election_timeout: Duration::from_millis(150),
+                heartbeat_interval: Duration::from_millis(30),
+                snapshot_interval: self.snapshot_interval,
+                propose_timeout: Duration::from_secs(3),
+            };
+            let node = RaftNode::start(config).await.unwrap();
+            self.running.insert(node_id, node);
+        }
+
+        pub(crate) fn stop(&mut self, node_id: u64) -> Arc<RaftNode> {
+            let node = self.running.remove(&node_id).unwrap();
+            node.shutdown();
+            node
+        }
+
+        /// 等待运行中的节点选出唯一可服务的 leader
+        pub(crate) async fn leader(&self) -> (u64, Arc<RaftNode>) {
+            wait_until(|| async {
+                let leaders: Vec<_> = self
+                    .running
+                    .iter()
+                    .filter(|(_, node)| node.is_leader())
+                    .collect();
+                match leaders.as_slice() {
+                    [(id, node)] => Some((**id, (*node).clone())),
+                    _ => None,
+                }
+            })
+            .await
+        }
+    }
+
+    impl Drop for TestCluster {
+        fn drop(&mut self) {
+            for node in self.running.values() {
+                node.shutdown();
+            }
+            let _ = std::fs::remove_dir_all(&self.data_dir);
+        }
+    }
+
+    fn free_addr() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    pub(crate) async fn wait_until<T, F, Fut>(mut check: F) -> T
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Option<T>>,
+    {
+        tokio::time::timeout(WAIT_TIMEOUT, async {
+            loop {
+                if let Some(value) = check().await {
+                    return value;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("raft cluster did not converge in time")
+    }
+
+    async fn add_session(leader: &RaftNode) -> Xid {
+        let session =
+            DefaultGlobalSession::new("app".into(), "group".into(), "tx".into(), 60_000, false);
+        let xid = session.xid.clone();
+        let command = RaftCommand::Session {
+            operation: LogOperation::GlobalAdd,
+            session,
+        };
+        assert!(leader.propose(command).await.unwrap());
+        xid
+    }
+
+    async fn has_sessions(node: &RaftNode, xids: &[Xid]) -> bool {
+        let state_machine = node.state_machine().await;
+        for xid in xids {
+            if state_machine.sessions.read_session(xid).await.is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn term(node: &RaftNode) -> u64 {
+        node.state.lock().await.log.hard_state().current_term
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn elects_one_leader_known_by_all_nodes() {
+        let cluster = TestCluster::start(3, 1_000).await;
+        let (leader_id, leader) = cluster.leader().await;
+
+        wait_until(|| async {
+            for node in cluster.running.values() {
+                if node.state.lock().await.leader_id != Some(leader_id) {
+                    return None;
+                }
+            }
+            Some(())
+        })
+        .await;
+        let followers = cluster.running.values().filter(|n| !n.is_leader());
+        assert_eq!(followers.count(), 2);
+        for node in cluster.running.values() {
+            assert_eq!(term(node).await, term(&leader).await);
+        }
+        assert!(leader.leader_endpoint().await.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn new_leader_keeps_committed_entries_after_failover() {
+        let mut cluster = TestCluster::start(3, 1_000).await;
+        let (old_id, old_leader) = cluster.leader().await;
+        let first = add_session(&old_leader).await;
+        let old_term = term(&old_leader).await;
+
+        cluster.stop(old_id);
+        let (new_id, new_leader) = cluster.leader().await;
+        assert_ne!(new_id, old_id);
+        assert!(term(&new_leader).await > old_term);
+        assert!(has_sessions(&new_leader, std::slice::from_ref(&first)).await);
+        assert!(old_leader.propose(RaftCommand::Noop).await.is_err());
+
+        // 剩余两个节点仍构成多数，可以继续提交
+        let second = add_session(&new_leader).await;
+        let xids = [first, second];
+        for node in cluster.running.values() {
+            wait_until(|| async { has_sessions(node, &xids).await.then_some(()) }).await;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn restarted_follower_catches_up_missing_log() {
+        let mut cluster = TestCluster::start(3, 1_000).await;
+        let (leader_id, leader) = cluster.leader().await;
+        let follower_id = *cluster.nodes.keys().find(|id| **id != leader_id).unwrap();
+        cluster.stop(follower_id);
+
+        let mut xids = Vec::new();
+        for _ in 0..5 {
+            xids.push(add_session(&leader).await);
+        }
+        cluster.restart(follower_id).await;
+        let follower = cluster.running[&follower_id].clone();
+        wait_until(|| async { has_sessions(&follower, &xids).await.then_some(()) }).await;
+
+        let st = follower.state.lock().await;
+        assert_eq!(st.log.snapshot_index(), 0);
+        assert_eq!(
+            st.log.last_index(),
+            leader.state.lock().await.log.last_index()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn lagging_follower_installs_leader_snapshot() {
+        let mut cluster = TestCluster::start(3, 4).await;
+        let (leader_id, leader) = cluster.leader().await;
+        let follower_id = *cluster.nodes.keys().find(|id| **id != leader_id).unwrap();
+        cluster.stop(follower_id);
+
+        let mut xids = Vec::new();
+        for _ in 0..10 {
+            xids.push(add_session(&leader).await);
+        }
+        let compacted = leader.state.lock().await.log.snapshot_index();
+        assert!(compacted > 0);
+
+        // 跟随者缺失的日志已被压缩，只能通过快照追赶
+        cluster.restart(follower_id).await;
+        let follower = cluster.running[&follower_id].clone();
+        wait_until(|| async { has_sessions(&follower, &xids).await.then_some(()) }).await;
+        assert!(follower.state.lock().await.log.snapshot_index() >= compacted);
+        let state_machine = follower.state_machine().await;
+        assert_eq!(
+            state_machine.sessions.all_sessions().await.len(),
+            xids.len()
+        );
+    }
+}
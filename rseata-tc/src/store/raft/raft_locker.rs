@@ -0,0 +1,101 @@
+use crate::store::raft::node::RaftNode;
+use crate::store::raft::state_machine::RaftCommand;
+use async_trait::async_trait;
+use rseata_core::branch::BranchId;
+use rseata_core::error::TransactionError;
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::defaults::default_row_lock::DefaultRowLock;
+use rseata_core::lock::locker::Locker;
+use rseata_core::types::Xid;
+use std::sync::Arc;
+
+/// 基于 Raft 复制的行锁，加锁、释放都作为日志提交后在各节点的内存行锁上执行
+pub struct RaftLocker {
+    node: Arc<RaftNode>,
+}
+
+impl RaftLocker {
+    pub fn new(node: Arc<RaftNode>) -> Self {
+        Self { node }
+    }
+
+    async fn propose(&self, command: RaftCommand) -> Result<bool, TransactionError> {
+        self.node
+            .propose(command)
+            .await
+            .map_err(|e| TransactionError::new(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Locker for RaftLocker {
+    type RowLock = DefaultRowLock;
+
+    async fn acquire_lock(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        self.acquire_lock_with_options(row_locks, true, false).await
+    }
+
+    async fn acquire_lock_with_options(
+        &self,
+        row_locks: &[Self::RowLock],
+        auto_commit: bool,
+        skip_check_lock: bool,
+    ) -> Result<bool, TransactionError> {
+        self.propose(RaftCommand::AcquireLock {
+            row_locks: row_locks.to_vec(),
+            auto_commit,
+            skip_check_lock,
+        })
+        .await
+    }
+
+    async fn release_lock(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        self.propose(RaftCommand::ReleaseLock {
+            row_locks: row_locks.to_vec(),
+        })
+        .await
+    }
+
+    async fn release_lock_by_xid_branch_id(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Result<bool, TransactionError> {
+        self.propose(RaftCommand::ReleaseLockByXidBranchId {
+            xid: xid.clone(),
+            branch_id,
+        })
+        .await
+    }
+
+    async fn release_lock_by_xid(&self, xid: &Xid) -> Result<bool, TransactionError> {
+        self.propose(RaftCommand::ReleaseLockByXid { xid: xid.clone() })
+            .await
+    }
+
+    async fn is_lockable(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        self.node
+            .state_machine()
+            .await
+            .locker
+            .is_lockable(row_locks)
+            .await
+    }
+
+    async fn clean_all_locks(&self) -> Result<(), TransactionError> {
+        self.propose(RaftCommand::CleanAllLocks).await.map(|_| ())
+    }
+
+    async fn update_lock_status(
+        &self,
+        xid: &Xid,
+        lock_status: LockStatus,
+    ) -> Result<(), TransactionError> {
+        self.propose(RaftCommand::UpdateLockStatus {
+            xid: xid.clone(),
+            lock_status,
+        })
+        .await
+        .map(|_| ())
+    }
+}
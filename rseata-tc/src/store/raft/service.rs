@@ -0,0 +1,52 @@
+use crate::store::raft::node::RaftNode;
+use async_trait::async_trait;
+use rseata_proto::rseata_proto::proto::raft_service_server::RaftService;
+use rseata_proto::rseata_proto::proto::{
+    AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
+    VoteRequest, VoteResponse,
+};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+/// 节点间通信的 gRPC 服务
+pub(crate) struct RaftGrpcService {
+    node: Arc<RaftNode>,
+}
+
+impl RaftGrpcService {
+    pub(crate) fn new(node: Arc<RaftNode>) -> Self {
+        Self { node }
+    }
+}
+
+#[async_trait]
+impl RaftService for RaftGrpcService {
+    async fn request_vote(
+        &self,
+        request: Request<VoteRequest>,
+    ) -> Result<Response<VoteResponse>, Status> {
+        Ok(Response::new(
+            self.node.handle_request_vote(request.into_inner()).await,
+        ))
+    }
+
+    async fn append_entries(
+        &self,
+        request: Request<AppendEntriesRequest>,
+    ) -> Result<Response<AppendEntriesResponse>, Status> {
+        Ok(Response::new(
+            self.node.handle_append_entries(request.into_inner()).await,
+        ))
+    }
+
+    async fn install_snapshot(
+        &self,
+        request: Request<InstallSnapshotRequest>,
+    ) -> Result<Response<InstallSnapshotResponse>, Status> {
+        Ok(Response::new(
+            self.node
+                .handle_install_snapshot(request.into_inner())
+                .await,
+        ))
+    }
+}
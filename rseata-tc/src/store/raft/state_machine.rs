@@ -0,0 +1,146 @@
+use rseata_core::branch::BranchId;
+use rseata_core::error::TransactionError;
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::defaults::default_locker::{LockEntry, MemoryLocker};
+use rseata_core::lock::defaults::default_row_lock::DefaultRowLock;
+use rseata_core::lock::locker::Locker;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::store::LogOperation;
+use rseata_core::store::memery_transaction_store_manager::MemeryTransactionStoreManager;
+use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+use rseata_core::types::Xid;
+use serde::{Deserialize, Serialize};
+
+/// 通过 Raft 日志复制的状态机命令
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum RaftCommand {
+    // leader 当选后追加的空条目，提交后此前任期的日志也随之提交
+    Noop,
+    Session {
+        operation: LogOperation,
+        session: DefaultGlobalSession,
+    },
+    // 只涉及单个分支的会话操作
+    Branch {
+        operation: LogOperation,
+        branch: DefaultBranchSession,
+    },
+    AcquireLock {
+        row_locks: Vec<DefaultRowLock>,
+        auto_commit: bool,
+        skip_check_lock: bool,
+    },
+    ReleaseLock {
+        row_locks: Vec<DefaultRowLock>,
+    },
+    ReleaseLockByXidBranchId {
+        xid: Xid,
+        branch_id: BranchId,
+    },
+    ReleaseLockByXid {
+        xid: Xid,
+    },
+    CleanAllLocks,
+    UpdateLockStatus {
+        xid: Xid,
+        lock_status: LockStatus,
+    },
+}
+
+/// 命令在状态机上的执行结果，各节点按相同顺序执行得到相同结果
+pub(crate) type ApplyResult = Result<bool, String>;
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotData {
+    sessions: Vec<DefaultGlobalSession>,
+    locks: Vec<(String, LockEntry)>,
+}
+
+/// 会话和行锁的内存状态机
+#[derive(Debug, Default)]
+pub(crate) struct StateMachine {
+    pub(crate) sessions: MemeryTransactionStoreManager,
+    pub(crate) locker: MemoryLocker,
+    pub(crate) last_applied: u64,
+    pub(crate) last_applied_term: u64,
+}
+
+impl StateMachine {
+    pub(crate) async fn apply(&self, command: RaftCommand) -> ApplyResult {
+        let result = match command {
+            RaftCommand::Noop => return Ok(true),
+            RaftCommand::Session { operation, session } => {
+                return self
+                    .sessions
+                    .write_session(operation, &session)
+                    .await
+                    .map(|_| true)
+                    .map_err(|e| e.to_string());
+            }
+            RaftCommand::Branch { operation, branch } => {
+                return self
+                    .sessions
+                    .write_branch_session(operation, &branch)
+                    .await
+                    .map(|_| true)
+                    .map_err(|e| e.to_string());
+            }
+            RaftCommand::AcquireLock {
+                row_locks,
+                auto_commit,
+                skip_check_lock,
+            } => {
+                self.locker
+                    .acquire_lock_with_options(&row_locks, auto_commit, skip_check_lock)
+                    .await
+            }
+            RaftCommand::ReleaseLock { row_locks } => self.locker.release_lock(&row_locks).await,
+            RaftCommand::ReleaseLockByXidBranchId { xid, branch_id } => {
+                self.locker
+                    .release_lock_by_xid_branch_id(&xid, branch_id)
+                    .await
+            }
+            RaftCommand::ReleaseLockByXid { xid } => self.locker.release_lock_by_xid(&xid).await,
+            RaftCommand::CleanAllLocks => self.locker.clean_all_locks().await.map(|_| true),
+            RaftCommand::UpdateLockStatus { xid, lock_status } => self
+                .locker
+                .update_lock_status(&xid, lock_status)
+                .await
+                .map(|_| true),
+        };
+        result.map_err(|e| match e {
+            TransactionError::ErrorInfo { info } => info,
+            e => e.to_string(),
+        })
+    }
+
+    /// 序列化全部会话和行锁
+    pub(crate) async fn snapshot(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&SnapshotData {
+            sessions: self.sessions.all_sessions().await,
+            locks: self.locker.export().await,
+        })?)
+    }
+
+    pub(crate) async fn restore(
+        last_applied: u64,
+        last_applied_term: u64,
+        data: &[u8],
+    ) -> anyhow::Result<Self> {
+        let snapshot: SnapshotData = serde_json::from_slice(data)?;
+        let state_machine = Self {
+            last_applied,
+            last_applied_term,
+            ..Default::default()
+        };
+        for session in snapshot.sessions {
+            state_machine
+                .sessions
+                .write_session(LogOperation::GlobalAdd, &session)
+                .await?;
+        }
+        state_machine.locker.import(snapshot.locks).await;
+        Ok(state_machine)
+    }
+}
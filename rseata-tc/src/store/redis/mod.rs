@@ -0,0 +1,765 @@
+pub mod redis_locker;
+#[cfg(test)]
+pub(crate) mod test_server;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Client, Script};
+use rseata_core::branch::BranchId;
+use rseata_core::session::defaults::default_branch_session::DefaultBranchSession;
+use rseata_core::session::defaults::default_global_session::DefaultGlobalSession;
+use rseata_core::session::session_condition::SessionCondition;
+use rseata_core::store::transaction_store_manager::TransactionStoreManager;
+use rseata_core::store::{LogOperation, StoreConfig};
+use rseata_core::types::{GlobalStatus, Xid};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const GLOBAL_KEY: &str = "rseata:global:";
+const BRANCH_KEY: &str = "rseata:branch:";
+const TIMEOUT_INDEX: &str = "rseata:index:timeout";
+const STATUS_INDEX: &str = "rseata:index:status:";
+// 脚本涉及的 key 依赖读取到的数据时，数据被并发修改后的最大尝试次数
+pub(crate) const MAX_SCRIPT_ATTEMPTS: usize = 5;
+
+// 只有当前状态仍为 ARGV[1] 时才更新，同时维护状态索引
+// KEYS[1] 全局会话，KEYS[2] 原状态索引，KEYS[3] 新状态索引；ARGV[1] 期望状态，ARGV[2] 新状态，ARGV[3] 会话数据
+// 返回 {1} 成功，{0, 当前状态} 状态冲突，{-1} 会话不存在
+const UPDATE_GLOBAL_SCRIPT: &str = r#"
+local current = redis.call('HGET', KEYS[1], 'status')
+if not current then
+    return {-1}
+end
+if current ~= ARGV[1] then
+    return {0, tonumber(current)}
+end
+if ARGV[1] ~= ARGV[2] then
+    local session = redis.call('HMGET', KEYS[1], 'xid', 'deadline')
+    redis.call('ZREM', KEYS[2], session[1])
+    redis.call('ZADD', KEYS[3], session[2], session[1])
+end
+redis.call('HSET', KEYS[1], 'status', ARGV[2], 'data', ARGV[3])
+return {1}
+"#;
+
+// KEYS[1] 全局会话，KEYS[2] 分支，KEYS[3] 超时索引，KEYS[4] 会话所在的状态索引；ARGV[1] 读取时的状态
+// 返回 {1} 成功，{0, 当前状态} 状态已变化需按新状态重试
+const REMOVE_GLOBAL_SCRIPT: &str = r#"
+local session = redis.call('HMGET', KEYS[1], 'xid', 'status')
+if session[1] then
+    if session[2] ~= ARGV[1] then
+        return {0, tonumber(session[2])}
+    end
+    redis.call('ZREM', KEYS[4], session[1])
+    redis.call('ZREM', KEYS[3], session[1])
+end
+redis.call('DEL', KEYS[1], KEYS[2])
+return {1}
+"#;
+
+// 按读取时的值比较后批量写入分支，任一分支被并发修改则整体放弃并返回其 branch_id
+// ARGV 每三个一组：branch_id、读取时的值（空串表示不存在）、新值（空串表示删除）
+const SYNC_BRANCHES_SCRIPT: &str = r#"
+for i = 1, #ARGV, 3 do
+    local current = redis.call('HGET', KEYS[1], ARGV[i]) or ''
+    if current ~= ARGV[i + 1] then
+        return ARGV[i]
+    end
+end
+for i = 1, #ARGV, 3 do
+    if ARGV[i + 2] == '' then
+        redis.call('HDEL', KEYS[1], ARGV[i])
+    else
+        redis.call('HSET', KEYS[1], ARGV[i], ARGV[i + 2])
+    end
+end
+return false
+"#;
+
+/// 基于 Redis 的会话存储
+///
+/// 每个全局会话保存为一个 hash，分支保存在按 xid 划分的 hash 中；
+/// 超时和状态索引使用以截止时间为分数的有序集合，状态变更通过 Lua 脚本原子完成
+pub struct RedisTransactionStoreManager {
+    conn: ConnectionManager,
+    update_global: Script,
+    remove_global: Script,
+    sync_branches: Script,
+}
+
+impl std::fmt::Debug for RedisTransactionStoreManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisTransactionStoreManager")
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisTransactionStoreManager {
+    /// 连接 redis_host:redis_port
+    pub async fn connect(config: &StoreConfig) -> anyhow::Result<Self> {
+        Ok(Self::new(connect(config).await?))
+    }
+
+    pub(crate) fn new(conn: ConnectionManager) -> Self {
+        Self {
+            conn,
+            update_global: Script::new(UPDATE_GLOBAL_SCRIPT),
+            remove_global: Script::new(REMOVE_GLOBAL_SCRIPT),
+            sync_branches: Script::new(SYNC_BRANCHES_SCRIPT),
+        }
+    }
+
+    async fn insert_global(&self, session: &DefaultGlobalSession) -> anyhow::Result<()> {
+        let xid = session.xid.to_string();
+        let deadline = deadline(session);
+        let mut conn = self.conn.clone();
+        redis::pipe()
+            .atomic()
+            .hset_multiple(
+                format!("{GLOBAL_KEY}{xid}"),
+                &[
+                    ("xid", xid.clone()),
+                    ("transaction_id", session.transaction_id.to_string()),
+                    ("status", session.status.code().to_string()),
+                    ("begin_time", session.begin_time_millis.to_string()),
+                    ("deadline", deadline.to_string()),
+                    ("data", global_data(session)?),
+                ],
+            )
+            .ignore()
+            .zadd(TIMEOUT_INDEX, &xid, deadline)
+            .ignore()
+            .zadd(status_index(session.status), &xid, deadline)
+            .ignore()
+            .exec_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    // 只有存储中的状态仍为 expected 时才更新，返回 false 表示状态已被其他 TC 实例修改
+    async fn update_global(
+        &self,
+        session: &DefaultGlobalSession,
+        expected: GlobalStatus,
+    ) -> anyhow::Result<bool> {
+        let mut conn = self.conn.clone();
+        let result: Vec<i32> = self
+            .update_global
+            .key(global_key(&session.xid))
+            .key(status_index(expected))
+            .key(status_index(session.status))
+            .arg(expected.code())
+            .arg(session.status.code())
+            .arg(global_data(session)?)
+            .invoke_async(&mut conn)
+            .await?;
+        match result.as_slice() {
+            [1] => Ok(true),
+            [0, _] => Ok(false),
+            _ => bail!("no such global session {}", session.xid),
+        }
+    }
+
+    // 状态索引的 key 由状态决定，读取时的状态过期则按脚本返回的状态重试
+    async fn remove_global(&self, session: &DefaultGlobalSession) -> anyhow::Result<()> {
+        let mut conn = self.conn.clone();
+        let mut status = session.status.code();
+        for _ in 0..MAX_SCRIPT_ATTEMPTS {
+            let result: Vec<i32> = self
+                .remove_global
+                .key(global_key(&session.xid))
+                .key(branch_key(&session.xid))
+                .key(TIMEOUT_INDEX)
+                .key(format!("{STATUS_INDEX}{status}"))
+                .arg(status)
+                .invoke_async(&mut conn)
+                .await?;
+            match result.as_slice() {
+                [0, current] => status = *current,
+                _ => return Ok(()),
+            }
+        }
+        bail!("global session {} changed concurrently", session.xid)
+    }
+
+    /// 按会话中的分支同步分支 hash：新增的分支写入，变化的分支检查状态迁移后写入，
+    /// remove 为 true 时删除会话中已不存在的分支
+    async fn sync_branches(
+        &self,
+        session: &DefaultGlobalSession,
+        remove: bool,
+    ) -> anyhow::Result<()> {
+        let key = branch_key(&session.xid);
+        let mut conn = self.conn.clone();
+        let existing: HashMap<String, String> = conn.hgetall(&key).await?;
+
+        let mut changes = Vec::new();
+        for (order, branch_session) in session.branch_sessions.iter().enumerate() {
+            let branch_id = Into::<u64>::into(branch_session.branch_id).to_string();
+            let value = serde_json::to_string(&(order, branch_session))?;
+            let current = existing.get(&branch_id).cloned().unwrap_or_default();
+            if current == value {
+                continue;
+            }
+            if !current.is_empty() {
+                let (_, stored): (usize, DefaultBranchSession) = serde_json::from_str(&current)?;
+                if stored.status != branch_session.status
+                    && !stored.status.can_transition_to(branch_session.status)
+                {
+                    bail!(
+                        "branch session {} status changed concurrently: {:?} -> {:?}",
+                        branch_session.branch_id,
+                        stored.status,
+                        branch_session.status
+                    );
+                }
+            }
+            changes.push((branch_id, current, value));
+        }
+        if remove {
+            let kept: HashSet<String> = session
+                .branch_sessions
+                .iter()
+                .map(|bs| Into::<u64>::into(bs.branch_id).to_string())
+                .collect();
+            for (branch_id, current) in existing {
+                if !kept.contains(&branch_id) {
+                    changes.push((branch_id, current, String::new()));
+                }
+            }
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut invocation = self.sync_branches.key(&key);
+        for (branch_id, current, value) in changes {
+            invocation.arg(branch_id).arg(current).arg(value);
+        }
+        let conflict: Option<String> = invocation.invoke_async(&mut conn).await?;
+        if let Some(branch_id) = conflict {
+            bail!("branch session {branch_id} changed concurrently");
+        }
+        Ok(())
+    }
+
+    /// 只读写一个分支字段：新分支排在已有分支之后，更新保留原有顺序并检查状态迁移
+    async fn write_branch(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        let key = branch_key(&branch_session.xid);
+        let field = Into::<u64>::into(branch_session.branch_id).to_string();
+        let mut conn = self.conn.clone();
+        let (current, count): (Option<String>, usize) = redis::pipe()
+            .hget(&key, &field)
+            .hlen(&key)
+            .query_async(&mut conn)
+            .await?;
+        let current = current.unwrap_or_default();
+        let value = match log_operation {
+            LogOperation::BranchAdd | LogOperation::BranchUpdate => {
+                let order = match current.is_empty() {
+                    true => count,
+                    false => {
+                        let (order, stored): (usize, DefaultBranchSession) =
+                            serde_json::from_str(&current)?;
+                        if stored.status != branch_session.status
+                            && !stored.status.can_transition_to(branch_session.status)
+                        {
+                            bail!(
+                                "branch session {} status changed concurrently: {:?} -> {:?}",
+                                branch_session.branch_id,
+                                stored.status,
+                                branch_session.status
+                            );
+                        }
+                        order
+                    }
+                };
+                serde_json::to_string(&(order, branch_session))?
+            }
+            LogOperation::BranchRemove => String::new(),
+            operation => bail!("{operation:?} is not a branch operation"),
+        };
+        if value == current {
+            return Ok(());
+        }
+        let conflict: Option<String> = self
+            .sync_branches
+            .key(&key)
+            .arg(field)
+            .arg(current)
+            .arg(value)
+            .invoke_async(&mut conn)
+            .await?;
+        if let Some(branch_id) = conflict {
+            bail!("branch session {branch_id} changed concurrently");
+        }
+        Ok(())
+    }
+
+    async fn load_sessions(
+        &self,
+        xids: Vec<String>,
+        with_branch_sessions: bool,
+    ) -> anyhow::Result<Vec<DefaultGlobalSession>> {
+        if xids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.conn.clone();
+        let mut pipe = redis::pipe();
+        for xid in &xids {
+            pipe.hget(format!("{GLOBAL_KEY}{xid}"), "data");
+            if with_branch_sessions {
+                pipe.hgetall(format!("{BRANCH_KEY}{xid}"));
+            }
+        }
+        let values: Vec<redis::Value> = pipe.query_async(&mut conn).await?;
+
+        let mut sessions = Vec::with_capacity(xids.len());
+        let mut values = values.into_iter();
+        while let Some(data) = values.next() {
+            let data: Option<String> = redis::from_redis_value(&data)?;
+            let branches: HashMap<String, String> = match with_branch_sessions {
+                true => redis::from_redis_value(&values.next().unwrap_or(redis::Value::Nil))?,
+                false => HashMap::new(),
+            };
+            // 读取期间被删除的会话
+            let Some(data) = data else {
+                continue;
+            };
+            let mut session: DefaultGlobalSession = serde_json::from_str(&data)?;
+            let mut branches = branches
+                .values()
+                .map(|value| serde_json::from_str::<(usize, DefaultBranchSession)>(value))
+                .collect::<Result<Vec<_>, _>>()?;
+            // 删除分支后新注册的分支可能与已有分支顺序相同
+            branches.sort_by_key(|(order, bs)| (*order, Into::<u64>::into(bs.branch_id)));
+            session.branch_sessions = branches.into_iter().map(|(_, bs)| bs).collect();
+            sessions.push(session);
+        }
+        Ok(sessions)
+    }
+
+    // 读取多个有序集合中的 xid，按截止时间排序
+    async fn index_members(&self, indices: Vec<String>) -> anyhow::Result<Vec<String>> {
+        let mut conn = self.conn.clone();
+        let mut members: Vec<(String, f64)> = Vec::new();
+        for index in indices {
+            let part: Vec<(String, f64)> = conn.zrange_withscores(index, 0, -1).await?;
+            members.extend(part);
+        }
+        members.sort_by(|a, b| a.1.total_cmp(&b.1));
+        Ok(members.into_iter().map(|(xid, _)| xid).collect())
+    }
+
+    async fn read_sessions(
+        &self,
+        indices: Vec<String>,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        let result = match self.index_members(indices).await {
+            Ok(xids) => self.load_sessions(xids, with_branch_sessions).await,
+            Err(e) => Err(e),
+        };
+        result.unwrap_or_else(|e| {
+            tracing::error!("Read sessions from redis failed: {:?}", e);
+            Vec::new()
+        })
+    }
+}
+
+#[async_trait]
+impl TransactionStoreManager for RedisTransactionStoreManager {
+    type GlobalSession = DefaultGlobalSession;
+
+    async fn write_session(
+        &self,
+        log_operation: LogOperation,
+        session: &Self::GlobalSession,
+    ) -> anyhow::Result<()> {
+        match log_operation {
+            LogOperation::GlobalAdd => {
+                self.insert_global(session).await?;
+                if !session.branch_sessions.is_empty() {
+                    self.sync_branches(session, false).await?;
+                }
+                Ok(())
+            }
+            LogOperation::GlobalUpdate => {
+                // 不改变状态的更新同样要求存储中的状态未被修改
+                if !self.update_global(session, session.status).await? {
+                    bail!(
+                        "global session {} status changed concurrently, expected {:?}",
+                        session.xid,
+                        session.status
+                    );
+                }
+                Ok(())
+            }
+            LogOperation::GlobalRemove => self.remove_global(session).await,
+            LogOperation::BranchAdd | LogOperation::BranchUpdate => {
+                self.sync_branches(session, false).await
+            }
+            LogOperation::BranchRemove => self.sync_branches(session, true).await,
+        }
+    }
+
+    async fn compare_and_set_global_session(
+        &self,
+        session: &Self::GlobalSession,
+        expected: GlobalStatus,
+    ) -> anyhow::Result<bool> {
+        self.update_global(session, expected).await
+    }
+
+    async fn write_branch_session(
+        &self,
+        log_operation: LogOperation,
+        branch_session: &DefaultBranchSession,
+    ) -> anyhow::Result<()> {
+        self.write_branch(log_operation, branch_session).await
+    }
+
+    async fn read_branch_session(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Option<DefaultBranchSession> {
+        let mut conn = self.conn.clone();
+        let field = Into::<u64>::into(branch_id).to_string();
+        let value: Option<String> = conn
+            .hget(branch_key(xid), field)
+            .await
+            .inspect_err(|e| tracing::error!("Read branch session from redis failed: {:?}", e))
+            .ok()?;
+        let value = value?;
+        serde_json::from_str::<(usize, DefaultBranchSession)>(&value)
+            .inspect_err(|e| tracing::error!("Decode branch session {} failed: {:?}", xid, e))
+            .ok()
+            .map(|(_, branch_session)| branch_session)
+    }
+
+    async fn read_session(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.read_global_session(xid, true).await
+    }
+
+    async fn read_session_with_branches(&self, xid: &Xid) -> Option<DefaultGlobalSession> {
+        self.read_global_session(xid, true).await
+    }
+
+    async fn read_global_session(
+        &self,
+        xid: &Xid,
+        with_branch_sessions: bool,
+    ) -> Option<DefaultGlobalSession> {
+        self.load_sessions(vec![xid.to_string()], with_branch_sessions)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Read session {} from redis failed: {:?}", xid, e);
+                Vec::new()
+            })
+            .pop()
+    }
+
+    async fn read_sort_by_timeout_begin_sessions(
+        &self,
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.read_sessions(
+            vec![status_index(GlobalStatus::Begin)],
+            with_branch_sessions,
+        )
+        .await
+    }
+
+    async fn read_session_by_global_status(
+        &self,
+        statuses: &[GlobalStatus],
+        with_branch_sessions: bool,
+    ) -> Vec<DefaultGlobalSession> {
+        self.read_sessions(status_indices(statuses), with_branch_sessions)
+            .await
+    }
+
+    async fn read_session_by_session_condition(
+        &self,
+        condition: &SessionCondition,
+    ) -> Vec<DefaultGlobalSession> {
+        let with_branch_sessions = !condition.lazy_load_branch;
+        let statuses = condition.all_statuses();
+        let mut sessions = if let Some(xid) = &condition.xid {
+            self.read_global_session(xid, with_branch_sessions)
+                .await
+                .into_iter()
+                .collect()
+        } else if statuses.is_empty() {
+            self.read_sessions(vec![TIMEOUT_INDEX.to_string()], with_branch_sessions)
+                .await
+        } else {
+            self.read_sessions(status_indices(&statuses), with_branch_sessions)
+                .await
+        };
+        let now = now_millis();
+        sessions.retain(|session| condition.matches(session, now));
+        condition.paginate(sessions)
+    }
+}
+
+/// 创建带自动重连的 Redis 连接
+pub(crate) async fn connect(config: &StoreConfig) -> anyhow::Result<ConnectionManager> {
+    let client = Client::open(format!(
+        "redis://{}:{}/",
+        config.redis_host, config.redis_port
+    ))?;
+    Ok(ConnectionManager::new(client).await?)
+}
+
+fn global_key(xid: &Xid) -> String {
+    format!("{GLOBAL_KEY}{xid}")
+}
+
+fn branch_key(xid: &Xid) -> String {
+    format!("{BRANCH_KEY}{xid}")
+}
+
+fn status_index(status: GlobalStatus) -> String {
+    format!("{STATUS_INDEX}{}", status.code())
+}
+
+fn status_indices(statuses: &[GlobalStatus]) -> Vec<String> {
+    statuses
+        .iter()
+        .map(|status| status_index(*status))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+// 分支单独保存，全局会话数据中不重复存储
+fn global_data(session: &DefaultGlobalSession) -> anyhow::Result<String> {
+    let mut data = session.clone();
+    data.branch_sessions.clear();
+    Ok(serde_json::to_string(&data)?)
+}
+
+fn deadline(session: &DefaultGlobalSession) -> u64 {
+    session
+        .begin_time_millis
+        .saturating_add(session.timeout_millis)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::redis::test_server::TestRedis;
+    use rseata_core::branch::{BranchStatus, BranchType};
+    use rseata_core::error::TransactionError;
+    use rseata_core::session::defaults::default_session_manager::DefaultSessionManager;
+    use rseata_core::session::session_manager::SessionManager;
+
+    async fn session_manager(redis: &TestRedis, name: &str) -> DefaultSessionManager {
+        let store = RedisTransactionStoreManager::new(redis.connect().await);
+        DefaultSessionManager::new(name.to_string(), Box::new(store))
+    }
+
+    fn new_session() -> DefaultGlobalSession {
+        let mut session = DefaultGlobalSession::new(
+            "app".to_string(),
+            "group".to_string(),
+            "tx".to_string(),
+            60_000,
+            false,
+        );
+        let mut branch = DefaultBranchSession::new(BranchType::AT);
+        branch.xid = session.xid.clone();
+        branch.transaction_id = session.transaction_id;
+        branch.branch_id = 1u64.into();
+        branch.status = BranchStatus::Registered;
+        session.add_branch(branch);
+        session
+    }
+
+    #[tokio::test]
+    async fn tc_instances_sharing_redis_race_through_cas() {
+        let redis = TestRedis::start().await;
+        let tc1 = session_manager(&redis, "tc1").await;
+        let tc2 = session_manager(&redis, "tc2").await;
+
+        let session = new_session();
+        tc1.add_global_session(&session).await.unwrap();
+        let seen = tc2
+            .find_global_session_with_branches(&session.xid, true)
+            .await
+            .unwrap();
+        assert_eq!(seen.branch_sessions.len(), 1);
+
+        // 两个实例同时从 Begin 抢占，只有一个成功
+        let (commit, rollback) = tokio::join!(
+            tc1.compare_and_set_global_status(
+                &session,
+                GlobalStatus::Begin,
+                GlobalStatus::Committing
+            ),
+            tc2.compare_and_set_global_status(
+                &seen,
+                GlobalStatus::Begin,
+                GlobalStatus::Rollbacking
+            ),
+        );
+        assert_eq!(commit.is_ok() as u8 + rollback.is_ok() as u8, 1);
+        let winner = if commit.is_ok() {
+            GlobalStatus::Committing
+        } else {
+            GlobalStatus::Rollbacking
+        };
+        let loser = commit.err().or(rollback.err()).unwrap();
+        assert!(matches!(
+            loser,
+            TransactionError::IllegalGlobalTransition { .. }
+        ));
+
+        // 状态索引随状态迁移
+        assert!(redis.members(&status_index(GlobalStatus::Begin)).is_empty());
+        assert_eq!(
+            redis.members(&status_index(winner)),
+            vec![session.xid.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn update_compares_against_the_expected_status() {
+        let redis = TestRedis::start().await;
+        let store = RedisTransactionStoreManager::new(redis.connect().await);
+        let mut session = new_session();
+        store
+            .write_session(LogOperation::GlobalAdd, &session)
+            .await
+            .unwrap();
+
+        session.status = GlobalStatus::Committing;
+        assert!(
+            store
+                .compare_and_set_global_session(&session, GlobalStatus::Begin)
+                .await
+                .unwrap()
+        );
+        // Committing 可以迁移到 Rollbacking 以外的状态，但期望状态已过期时必须失败
+        session.status = GlobalStatus::Committed;
+        assert!(
+            !store
+                .compare_and_set_global_session(&session, GlobalStatus::Begin)
+                .await
+                .unwrap()
+        );
+        assert!(
+            store
+                .compare_and_set_global_session(&session, GlobalStatus::Committing)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn branch_writes_touch_a_single_field() {
+        let redis = TestRedis::start().await;
+        let store = RedisTransactionStoreManager::new(redis.connect().await);
+        let session = new_session();
+        store
+            .write_session(LogOperation::GlobalAdd, &session)
+            .await
+            .unwrap();
+
+        let mut second = session.branch_sessions[0].clone();
+        second.branch_id = 2u64.into();
+        store
+            .write_branch_session(LogOperation::BranchAdd, &second)
+            .await
+            .unwrap();
+        // 更新第一个分支后仍排在新增分支之前
+        let mut first = session.branch_sessions[0].clone();
+        first.status = BranchStatus::PhaseOneDone;
+        store
+            .write_branch_session(LogOperation::BranchUpdate, &first)
+            .await
+            .unwrap();
+        first.status = BranchStatus::Registered;
+        assert!(
+            store
+                .write_branch_session(LogOperation::BranchUpdate, &first)
+                .await
+                .is_err()
+        );
+
+        let stored = store
+            .read_session_with_branches(&session.xid)
+            .await
+            .unwrap();
+        let branches: Vec<_> = stored
+            .branch_sessions
+            .iter()
+            .map(|bs| (Into::<u64>::into(bs.branch_id), bs.status))
+            .collect();
+        assert_eq!(
+            branches,
+            vec![
+                (1, BranchStatus::PhaseOneDone),
+                (2, BranchStatus::Registered)
+            ]
+        );
+
+        store
+            .write_branch_session(LogOperation::BranchRemove, &second)
+            .await
+            .unwrap();
+        assert!(
+            store
+                .read_branch_session(&session.xid, 2u64.into())
+                .await
+                .is_none()
+        );
+        assert_eq!(
+            store
+                .read_branch_session(&session.xid, 1u64.into())
+                .await
+                .map(|bs| bs.status),
+            Some(BranchStatus::PhaseOneDone)
+        );
+    }
+
+    #[tokio::test]
+    async fn removing_a_session_clears_every_index() {
+        let redis = TestRedis::start().await;
+        let store = RedisTransactionStoreManager::new(redis.connect().await);
+        let mut session = new_session();
+        store
+            .write_session(LogOperation::GlobalAdd, &session)
+            .await
+            .unwrap();
+        let stale = session.clone();
+        session.status = GlobalStatus::Rollbacking;
+        assert!(
+            store
+                .compare_and_set_global_session(&session, GlobalStatus::Begin)
+                .await
+                .unwrap()
+        );
+
+        // 以过期的状态删除时按存储中的状态重试
+        store
+            .write_session(LogOperation::GlobalRemove, &stale)
+            .await
+            .unwrap();
+        assert!(redis.keys().is_empty());
+    }
+}
@@ -0,0 +1,490 @@
+use crate::store::redis::{MAX_SCRIPT_ATTEMPTS, connect};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, RedisError, Script};
+use rseata_core::branch::BranchId;
+use rseata_core::error::TransactionError;
+use rseata_core::lock::LockStatus;
+use rseata_core::lock::defaults::default_row_lock::DefaultRowLock;
+use rseata_core::lock::locker::Locker;
+use rseata_core::lock::row_lock::RowLock;
+use rseata_core::store::StoreConfig;
+use rseata_core::types::Xid;
+use std::collections::BTreeMap;
+
+const LOCK_PREFIX: &str = "rseata:lock:";
+const ROW_KEY: &str = "rseata:lock:row:";
+const XID_INDEX: &str = "rseata:lock:xid:";
+const BRANCH_INDEX: &str = "rseata:lock:branch:";
+
+// 原子获取同一分支的一批行锁：任一行被其他全局事务持有则全部放弃
+// KEYS[1] xid 索引、KEYS[2] 分支索引，之后每三个一组：行锁、原持有者的 xid 索引、原持有者的分支索引；
+// ARGV[1] auto_commit、ARGV[2] skip_check_lock、ARGV[3] xid、ARGV[4] transaction_id、ARGV[5] branch_id，
+// 之后每五个一组：读取时的持有者 xid、持有者 branch_id、resource_id、table_name、pk
+// 返回 1 成功，0 锁冲突，-1 持有者正在回滚，-2 持有者已变化需重新读取
+const ACQUIRE_SCRIPT: &str = r#"
+local xid, branch_id = ARGV[3], ARGV[5]
+local owners = {}
+for i = 3, #KEYS, 3 do
+    local base = 5 + (i - 3) / 3 * 5
+    local owner = redis.call('HMGET', KEYS[i], 'xid', 'branch_id', 'status')
+    if (owner[1] or '') ~= ARGV[base + 1] or (owner[2] or '') ~= ARGV[base + 2] then
+        return -2
+    end
+    owners[i] = owner
+end
+if ARGV[2] ~= '1' then
+    for i = 3, #KEYS, 3 do
+        local owner = owners[i]
+        if owner[1] and owner[1] ~= xid then
+            if ARGV[1] ~= '1' and owner[3] == '2' then
+                return -1
+            end
+            return 0
+        end
+    end
+end
+for i = 3, #KEYS, 3 do
+    local base = 5 + (i - 3) / 3 * 5
+    local owner = owners[i]
+    if owner[1] ~= xid then
+        if owner[1] then
+            redis.call('SREM', KEYS[i + 1], KEYS[i])
+            if owner[2] ~= '' then
+                redis.call('SREM', KEYS[i + 2], KEYS[i])
+            end
+        end
+        redis.call('HSET', KEYS[i],
+            'xid', xid,
+            'transaction_id', ARGV[4],
+            'branch_id', branch_id,
+            'resource_id', ARGV[base + 3],
+            'table_name', ARGV[base + 4],
+            'pk', ARGV[base + 5],
+            'status', '1')
+        redis.call('SADD', KEYS[1], KEYS[i])
+        if branch_id ~= '' then
+            redis.call('SADD', KEYS[2], KEYS[i])
+        end
+    end
+end
+return 1
+"#;
+
+// 释放行锁：每三个 KEYS 一组：行锁、持有者的 xid 索引、持有者的分支索引；
+// ARGV 每三个一组：要释放的 xid、读取时的持有者 xid、持有者 branch_id；
+// KEYS 多出一个时为被释放的索引，处理过的行从中移除
+// 返回 1 成功，0 持有者已变化需重新读取
+const RELEASE_SCRIPT: &str = r#"
+local rows = #KEYS - (#KEYS % 3)
+for i = 1, rows, 3 do
+    local owner = redis.call('HMGET', KEYS[i], 'xid', 'branch_id')
+    if (owner[1] or '') ~= ARGV[i + 1] or (owner[2] or '') ~= ARGV[i + 2] then
+        return 0
+    end
+end
+for i = 1, rows, 3 do
+    if ARGV[i + 1] == ARGV[i] then
+        redis.call('DEL', KEYS[i])
+        redis.call('SREM', KEYS[i + 1], KEYS[i])
+        if ARGV[i + 2] ~= '' then
+            redis.call('SREM', KEYS[i + 2], KEYS[i])
+        end
+    end
+    if rows < #KEYS then
+        redis.call('SREM', KEYS[#KEYS], KEYS[i])
+    end
+end
+return 1
+"#;
+
+// 更新全局事务持有的行锁状态，KEYS 为行锁，ARGV[1] xid，ARGV[2] 新状态
+const UPDATE_STATUS_SCRIPT: &str = r#"
+for i = 1, #KEYS do
+    if redis.call('HGET', KEYS[i], 'xid') == ARGV[1] then
+        redis.call('HSET', KEYS[i], 'status', ARGV[2])
+    end
+end
+return 1
+"#;
+
+/// 基于 Redis 的行锁实现，每行一个 hash，并按 xid、分支维护索引集合，
+/// 加锁和释放通过 Lua 脚本原子完成
+///
+/// 脚本只访问通过 KEYS 传入的 key：依赖行锁持有者的索引 key 先读取持有者再调用脚本，
+/// 脚本发现持有者已变化时重新读取
+pub struct RedisLocker {
+    conn: ConnectionManager,
+    acquire: Script,
+    release: Script,
+    update_status: Script,
+}
+
+impl std::fmt::Debug for RedisLocker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisLocker").finish_non_exhaustive()
+    }
+}
+
+impl RedisLocker {
+    /// 连接 redis_host:redis_port
+    pub async fn connect(config: &StoreConfig) -> anyhow::Result<Self> {
+        Ok(Self::new(connect(config).await?))
+    }
+
+    pub(crate) fn new(conn: ConnectionManager) -> Self {
+        Self {
+            conn,
+            acquire: Script::new(ACQUIRE_SCRIPT),
+            release: Script::new(RELEASE_SCRIPT),
+            update_status: Script::new(UPDATE_STATUS_SCRIPT),
+        }
+    }
+
+    // 同一批行锁按 key 去重，保持顺序稳定
+    fn group_by_key(row_locks: &[DefaultRowLock]) -> BTreeMap<String, &DefaultRowLock> {
+        row_locks
+            .iter()
+            .map(|row_lock| {
+                (
+                    format!(
+                        "{ROW_KEY}{}:{}:{}",
+                        row_lock.resource_id(),
+                        row_lock.table_name(),
+                        row_lock.pk()
+                    ),
+                    row_lock,
+                )
+            })
+            .collect()
+    }
+
+    // 读取行锁当前的持有者 (xid, branch_id)，未被持有时为空串
+    async fn owners(&self, keys: &[&String]) -> Result<Vec<(String, String)>, TransactionError> {
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.cmd("HMGET").arg(*key).arg("xid").arg("branch_id");
+        }
+        let mut conn = self.conn.clone();
+        let owners: Vec<(Option<String>, Option<String>)> =
+            pipe.query_async(&mut conn).await.map_err(redis_err)?;
+        Ok(owners
+            .into_iter()
+            .map(|(xid, branch_id)| (xid.unwrap_or_default(), branch_id.unwrap_or_default()))
+            .collect())
+    }
+
+    // 释放 rows 中属于 release_xid 的行锁，index 不为空时同时从该索引中移除这些行
+    async fn release_rows(
+        &self,
+        rows: &[(&String, String)],
+        index: Option<&str>,
+    ) -> Result<(), TransactionError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<&String> = rows.iter().map(|(key, _)| *key).collect();
+        let mut conn = self.conn.clone();
+        for _ in 0..MAX_SCRIPT_ATTEMPTS {
+            let owners = self.owners(&keys).await?;
+            let mut invocation = self.release.prepare_invoke();
+            for ((key, release_xid), (owner_xid, owner_branch)) in rows.iter().zip(&owners) {
+                invocation
+                    .key(*key)
+                    .key(xid_index(owner_xid))
+                    .key(branch_index(owner_branch))
+                    .arg(release_xid)
+                    .arg(owner_xid)
+                    .arg(owner_branch);
+            }
+            if let Some(index) = index {
+                invocation.key(index);
+            }
+            let released: i32 = invocation
+                .invoke_async(&mut conn)
+                .await
+                .map_err(redis_err)?;
+            if released == 1 {
+                return Ok(());
+            }
+        }
+        Err(changed_concurrently())
+    }
+
+    // 释放索引集合中属于 xid 的行锁
+    async fn release_index(&self, index: String, xid: &Xid) -> Result<bool, TransactionError> {
+        let mut conn = self.conn.clone();
+        let keys: Vec<String> = conn.smembers(&index).await.map_err(redis_err)?;
+        let rows: Vec<(&String, String)> = keys.iter().map(|key| (key, xid.to_string())).collect();
+        self.release_rows(&rows, Some(&index)).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Locker for RedisLocker {
+    type RowLock = DefaultRowLock;
+
+    async fn acquire_lock(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        self.acquire_lock_with_options(row_locks, true, false).await
+    }
+
+    // 一批行锁来自同一个分支
+    async fn acquire_lock_with_options(
+        &self,
+        row_locks: &[Self::RowLock],
+        auto_commit: bool,
+        skip_check_lock: bool,
+    ) -> Result<bool, TransactionError> {
+        let Some(first) = row_locks.first() else {
+            return Ok(true);
+        };
+        let xid = first.xid().to_string();
+        let branch_id = first
+            .branch_id()
+            .map(|id| id.0.to_string())
+            .unwrap_or_default();
+        if row_locks.iter().any(|row_lock| {
+            row_lock.xid() != first.xid() || row_lock.branch_id() != first.branch_id()
+        }) {
+            return Err(TransactionError::new(
+                "redis lock: row locks must belong to one branch".to_string(),
+            ));
+        }
+
+        let rows = Self::group_by_key(row_locks);
+        let keys: Vec<&String> = rows.keys().collect();
+        let mut conn = self.conn.clone();
+        for _ in 0..MAX_SCRIPT_ATTEMPTS {
+            let owners = self.owners(&keys).await?;
+            let mut invocation = self.acquire.prepare_invoke();
+            invocation
+                .key(xid_index(&xid))
+                .key(branch_index(&branch_id))
+                .arg(auto_commit as i32)
+                .arg(skip_check_lock as i32)
+                .arg(&xid)
+                .arg(first.transaction_id())
+                .arg(&branch_id);
+            for ((key, row_lock), (owner_xid, owner_branch)) in rows.iter().zip(&owners) {
+                invocation
+                    .key(key)
+                    .key(xid_index(owner_xid))
+                    .key(branch_index(owner_branch))
+                    .arg(owner_xid)
+                    .arg(owner_branch)
+                    .arg(row_lock.resource_id().to_string())
+                    .arg(row_lock.table_name())
+                    .arg(row_lock.pk());
+            }
+            let result: i32 = invocation
+                .invoke_async(&mut conn)
+                .await
+                .map_err(redis_err)?;
+            match result {
+                1 => return Ok(true),
+                // 如果处于回滚状态且非自动提交，快速失败
+                -1 => return Err(TransactionError::new("Lock is rollbacking".to_string())),
+                // 读取持有者之后行锁被修改，重新读取
+                -2 => continue,
+                _ => return Ok(false),
+            }
+        }
+        Err(changed_concurrently())
+    }
+
+    async fn release_lock(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        let rows = Self::group_by_key(row_locks);
+        let rows: Vec<(&String, String)> = rows
+            .iter()
+            .map(|(key, row_lock)| (key, row_lock.xid().to_string()))
+            .collect();
+        self.release_rows(&rows, None).await?;
+        Ok(true)
+    }
+
+    async fn release_lock_by_xid_branch_id(
+        &self,
+        xid: &Xid,
+        branch_id: BranchId,
+    ) -> Result<bool, TransactionError> {
+        self.release_index(branch_index(&branch_id.0.to_string()), xid)
+            .await
+    }
+
+    async fn release_lock_by_xid(&self, xid: &Xid) -> Result<bool, TransactionError> {
+        self.release_index(xid_index(&xid.to_string()), xid).await
+    }
+
+    async fn is_lockable(&self, row_locks: &[Self::RowLock]) -> Result<bool, TransactionError> {
+        if row_locks.is_empty() {
+            return Ok(true);
+        }
+        let xid = row_locks[0].xid().to_string();
+        let mut pipe = redis::pipe();
+        for key in Self::group_by_key(row_locks).into_keys() {
+            pipe.hget(key, "xid");
+        }
+        let mut conn = self.conn.clone();
+        let owners: Vec<Option<String>> = pipe.query_async(&mut conn).await.map_err(redis_err)?;
+        Ok(owners.into_iter().flatten().all(|owner| owner == xid))
+    }
+
+    async fn clean_all_locks(&self) -> Result<(), TransactionError> {
+        let mut conn = self.conn.clone();
+        let mut cursor = 0u64;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(format!("{LOCK_PREFIX}*"))
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut conn)
+                .await
+                .map_err(redis_err)?;
+            if !keys.is_empty() {
+                let _: i64 = redis::cmd("DEL")
+                    .arg(keys)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(redis_err)?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+
+    async fn update_lock_status(
+        &self,
+        xid: &Xid,
+        lock_status: LockStatus,
+    ) -> Result<(), TransactionError> {
+        let mut conn = self.conn.clone();
+        let keys: Vec<String> = conn
+            .smembers(xid_index(&xid.to_string()))
+            .await
+            .map_err(redis_err)?;
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let _: i32 = self
+            .update_status
+            .key(keys)
+            .arg(xid.to_string())
+            .arg(lock_status as i32)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(redis_err)?;
+        Ok(())
+    }
+}
+
+fn xid_index(xid: &str) -> String {
+    format!("{XID_INDEX}{xid}")
+}
+
+fn branch_index(branch_id: &str) -> String {
+    format!("{BRANCH_INDEX}{branch_id}")
+}
+
+fn changed_concurrently() -> TransactionError {
+    TransactionError::new("redis lock: rows changed concurrently".to_string())
+}
+
+fn redis_err(e: RedisError) -> TransactionError {
+    TransactionError::new(format!("redis lock: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::redis::test_server::TestRedis;
+
+    fn row_lock(xid: &str, branch_id: u64, pk: &str) -> DefaultRowLock {
+        DefaultRowLock {
+            xid: Xid::from(xid.to_string()),
+            transaction_id: 1,
+            branch_id: Some(BranchId(branch_id)),
+            resource_id: "jdbc:mysql://db/order".into(),
+            table_name: "t_order".to_string(),
+            pk: pk.to_string(),
+            row_key: None,
+            feature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn locks_conflict_and_release_through_declared_keys() {
+        let redis = TestRedis::start().await;
+        let locker = RedisLocker::new(redis.connect().await);
+        let tx1 = [row_lock("tx1", 11, "1"), row_lock("tx1", 11, "2")];
+        let tx2 = [row_lock("tx2", 21, "2"), row_lock("tx2", 21, "3")];
+
+        assert!(locker.acquire_lock(&tx1).await.unwrap());
+        assert!(!locker.acquire_lock(&tx2).await.unwrap());
+        assert!(!locker.is_lockable(&tx2).await.unwrap());
+
+        // 持有者回滚中时非自动提交的请求快速失败
+        locker
+            .update_lock_status(&Xid::from("tx1".to_string()), LockStatus::Rollbacking)
+            .await
+            .unwrap();
+        assert!(
+            locker
+                .acquire_lock_with_options(&tx2, false, false)
+                .await
+                .is_err()
+        );
+
+        locker
+            .release_lock_by_xid_branch_id(&Xid::from("tx1".to_string()), BranchId(11))
+            .await
+            .unwrap();
+        assert!(locker.acquire_lock(&tx2).await.unwrap());
+        locker
+            .release_lock_by_xid(&Xid::from("tx2".to_string()))
+            .await
+            .unwrap();
+        assert!(redis.keys().is_empty(), "{:?}", redis.keys());
+    }
+
+    #[tokio::test]
+    async fn skipping_the_check_moves_rows_between_owner_indexes() {
+        let redis = TestRedis::start().await;
+        let locker = RedisLocker::new(redis.connect().await);
+        assert!(
+            locker
+                .acquire_lock(&[row_lock("tx1", 11, "1")])
+                .await
+                .unwrap()
+        );
+        assert!(
+            locker
+                .acquire_lock_with_options(&[row_lock("tx2", 21, "1")], true, true)
+                .await
+                .unwrap()
+        );
+        assert!(redis.members(&xid_index("tx1")).is_empty());
+        assert_eq!(redis.members(&xid_index("tx2")).len(), 1);
+
+        // 原持有者释放时不会删除已被接管的行锁
+        locker
+            .release_lock(&[row_lock("tx1", 11, "1")])
+            .await
+            .unwrap();
+        assert!(
+            !locker
+                .is_lockable(&[row_lock("tx1", 11, "1")])
+                .await
+                .unwrap()
+        );
+        locker
+            .release_lock(&[row_lock("tx2", 21, "1")])
+            .await
+            .unwrap();
+        assert!(redis.keys().is_empty(), "{:?}", redis.keys());
+    }
+}
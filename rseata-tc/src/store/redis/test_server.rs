@@ -0,0 +1,493 @@
+//! 测试用的进程内 Redis 替身
+//!
+//! 实现存储和行锁用到的 RESP2 命令，Lua 脚本由 mlua 执行；
+//! 脚本中的 redis.call 访问未通过 KEYS 声明的 key 时直接报错
+
+use mlua::{Lua, MultiValue, Value as LuaValue, Variadic};
+use redis::aio::ConnectionManager;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone)]
+enum Entry {
+    Hash(HashMap<String, String>),
+    Set(BTreeSet<String>),
+    ZSet(HashMap<String, f64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Reply {
+    Status(String),
+    Error(String),
+    Int(i64),
+    Bulk(Option<String>),
+    Array(Vec<Reply>),
+}
+
+#[derive(Debug, Default)]
+struct Db {
+    entries: HashMap<String, Entry>,
+    scripts: HashMap<String, String>,
+}
+
+/// 监听本地随机端口的 Redis 替身，随 tokio 运行时一起结束
+#[derive(Clone)]
+pub(crate) struct TestRedis {
+    db: Arc<Mutex<Db>>,
+    port: u16,
+}
+
+impl TestRedis {
+    pub(crate) async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Self {
+            db: Arc::default(),
+            port: listener.local_addr().unwrap().port(),
+        };
+        let db = server.db.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, db.clone()));
+            }
+        });
+        server
+    }
+
+    pub(crate) async fn connect(&self) -> ConnectionManager {
+        let client = redis::Client::open(format!("redis://127.0.0.1:{}/", self.port)).unwrap();
+        ConnectionManager::new(client).await.unwrap()
+    }
+
+    /// 当前存在的全部 key，按字典序
+    pub(crate) fn keys(&self) -> Vec<String> {
+        let db = self.db.lock().unwrap();
+        let mut keys: Vec<String> = db.entries.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    pub(crate) fn members(&self, key: &str) -> Vec<String> {
+        match self.db.lock().unwrap().entries.get(key) {
+            Some(Entry::Set(set)) => set.iter().cloned().collect(),
+            Some(Entry::ZSet(zset)) => zset.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+async fn serve(stream: TcpStream, db: Arc<Mutex<Db>>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut queued: Option<Vec<Vec<String>>> = None;
+    while let Some(args) = read_command(&mut reader).await {
+        let name = args
+            .first()
+            .map(|a| a.to_ascii_uppercase())
+            .unwrap_or_default();
+        let reply = match (name.as_str(), &mut queued) {
+            ("MULTI", _) => {
+                queued = Some(Vec::new());
+                Reply::Status("OK".into())
+            }
+            ("EXEC", Some(_)) => {
+                let commands = queued.take().unwrap_or_default();
+                let mut db = db.lock().unwrap();
+                Reply::Array(commands.iter().map(|c| execute(&mut db, c)).collect())
+            }
+            (_, Some(commands)) => {
+                commands.push(args);
+                Reply::Status("QUEUED".into())
+            }
+            _ => execute(&mut db.lock().unwrap(), &args),
+        };
+        let mut out = Vec::new();
+        encode(&reply, &mut out);
+        if writer.write_all(&out).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn read_command<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<Vec<String>> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).await.ok()?;
+        let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+        let mut buf = vec![0; len + 2];
+        reader.read_exact(&mut buf).await.ok()?;
+        buf.truncate(len);
+        args.push(String::from_utf8_lossy(&buf).into_owned());
+    }
+    Some(args)
+}
+
+fn encode(reply: &Reply, out: &mut Vec<u8>) {
+    match reply {
+        Reply::Status(s) => out.extend_from_slice(format!("+{s}\r\n").as_bytes()),
+        Reply::Error(e) => out.extend_from_slice(format!("-{e}\r\n").as_bytes()),
+        Reply::Int(i) => out.extend_from_slice(format!(":{i}\r\n").as_bytes()),
+        Reply::Bulk(None) => out.extend_from_slice(b"$-1\r\n"),
+        Reply::Bulk(Some(s)) => {
+            out.extend_from_slice(format!("${}\r\n{s}\r\n", s.len()).as_bytes())
+        }
+        Reply::Array(items) => {
+            out.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+            for item in items {
+                encode(item, out);
+            }
+        }
+    }
+}
+
+fn bulk(value: Option<&String>) -> Reply {
+    Reply::Bulk(value.cloned())
+}
+
+fn wrong_type() -> Reply {
+    Reply::Error("WRONGTYPE Operation against a key holding the wrong kind of value".into())
+}
+
+// 集合类型为空时删除 key，与 Redis 一致
+fn remove_if_empty(db: &mut Db, key: &str) {
+    let empty = match db.entries.get(key) {
+        Some(Entry::Hash(h)) => h.is_empty(),
+        Some(Entry::Set(s)) => s.is_empty(),
+        Some(Entry::ZSet(z)) => z.is_empty(),
+        None => false,
+    };
+    if empty {
+        db.entries.remove(key);
+    }
+}
+
+fn execute(db: &mut Db, args: &[String]) -> Reply {
+    let Some(name) = args.first() else {
+        return Reply::Error("ERR empty command".into());
+    };
+    let name = name.to_ascii_uppercase();
+    let key = args.get(1).cloned().unwrap_or_default();
+    match name.as_str() {
+        "PING" => Reply::Status("PONG".into()),
+        "CLIENT" | "SELECT" => Reply::Status("OK".into()),
+        "HSET" | "HMSET" => {
+            let entry = db
+                .entries
+                .entry(key)
+                .or_insert_with(|| Entry::Hash(HashMap::new()));
+            let Entry::Hash(hash) = entry else {
+                return wrong_type();
+            };
+            let mut added = 0;
+            for pair in args[2..].chunks(2) {
+                if hash.insert(pair[0].clone(), pair[1].clone()).is_none() {
+                    added += 1;
+                }
+            }
+            match name.as_str() {
+                "HMSET" => Reply::Status("OK".into()),
+                _ => Reply::Int(added),
+            }
+        }
+        "HGET" => match db.entries.get(&key) {
+            Some(Entry::Hash(hash)) => bulk(hash.get(&args[2])),
+            None => Reply::Bulk(None),
+            _ => wrong_type(),
+        },
+        "HMGET" => match db.entries.get(&key) {
+            Some(Entry::Hash(hash)) => {
+                Reply::Array(args[2..].iter().map(|f| bulk(hash.get(f))).collect())
+            }
+            None => Reply::Array(args[2..].iter().map(|_| Reply::Bulk(None)).collect()),
+            _ => wrong_type(),
+        },
+        "HGETALL" => match db.entries.get(&key) {
+            Some(Entry::Hash(hash)) => Reply::Array(
+                hash.iter()
+                    .flat_map(|(f, v)| [bulk(Some(f)), bulk(Some(v))])
+                    .collect(),
+            ),
+            None => Reply::Array(Vec::new()),
+            _ => wrong_type(),
+        },
+        "HLEN" => match db.entries.get(&key) {
+            Some(Entry::Hash(hash)) => Reply::Int(hash.len() as i64),
+            None => Reply::Int(0),
+            _ => wrong_type(),
+        },
+        "HDEL" => {
+            let removed = match db.entries.get_mut(&key) {
+                Some(Entry::Hash(hash)) => args[2..]
+                    .iter()
+                    .filter(|f| hash.remove(*f).is_some())
+                    .count(),
+                None => 0,
+                _ => return wrong_type(),
+            };
+            remove_if_empty(db, &key);
+            Reply::Int(removed as i64)
+        }
+        "DEL" => Reply::Int(
+            args[1..]
+                .iter()
+                .filter(|k| db.entries.remove(*k).is_some())
+                .count() as i64,
+        ),
+        "SADD" => {
+            let entry = db
+                .entries
+                .entry(key)
+                .or_insert_with(|| Entry::Set(BTreeSet::new()));
+            let Entry::Set(set) = entry else {
+                return wrong_type();
+            };
+            Reply::Int(
+                args[2..]
+                    .iter()
+                    .filter(|m| set.insert((*m).clone()))
+                    .count() as i64,
+            )
+        }
+        "SREM" => {
+            let removed = match db.entries.get_mut(&key) {
+                Some(Entry::Set(set)) => args[2..].iter().filter(|m| set.remove(*m)).count(),
+                None => 0,
+                _ => return wrong_type(),
+            };
+            remove_if_empty(db, &key);
+            Reply::Int(removed as i64)
+        }
+        "SMEMBERS" => match db.entries.get(&key) {
+            Some(Entry::Set(set)) => Reply::Array(set.iter().map(|m| bulk(Some(m))).collect()),
+            None => Reply::Array(Vec::new()),
+            _ => wrong_type(),
+        },
+        "ZADD" => {
+            let entry = db
+                .entries
+                .entry(key)
+                .or_insert_with(|| Entry::ZSet(HashMap::new()));
+            let Entry::ZSet(zset) = entry else {
+                return wrong_type();
+            };
+            let mut added = 0;
+            for pair in args[2..].chunks(2) {
+                let Ok(score) = pair[0].parse::<f64>() else {
+                    return Reply::Error("ERR value is not a valid float".into());
+                };
+                if zset.insert(pair[1].clone(), score).is_none() {
+                    added += 1;
+                }
+            }
+            Reply::Int(added)
+        }
+        "ZREM" => {
+            let removed = match db.entries.get_mut(&key) {
+                Some(Entry::ZSet(zset)) => args[2..]
+                    .iter()
+                    .filter(|m| zset.remove(*m).is_some())
+                    .count(),
+                None => 0,
+                _ => return wrong_type(),
+            };
+            remove_if_empty(db, &key);
+            Reply::Int(removed as i64)
+        }
+        // 只支持 ZRANGE key 0 -1 [WITHSCORES]
+        "ZRANGE" => match db.entries.get(&key) {
+            Some(Entry::ZSet(zset)) => {
+                let mut members: Vec<(&String, &f64)> = zset.iter().collect();
+                members.sort_by(|a, b| a.1.total_cmp(b.1).then(a.0.cmp(b.0)));
+                let with_scores = args.iter().any(|a| a.eq_ignore_ascii_case("WITHSCORES"));
+                Reply::Array(
+                    members
+                        .into_iter()
+                        .flat_map(|(m, score)| {
+                            let mut items = vec![bulk(Some(m))];
+                            if with_scores {
+                                items.push(bulk(Some(&score.to_string())));
+                            }
+                            items
+                        })
+                        .collect(),
+                )
+            }
+            None => Reply::Array(Vec::new()),
+            _ => wrong_type(),
+        },
+        // 一次返回全部匹配的 key，只支持以 * 结尾的前缀模式
+        "SCAN" => {
+            let prefix = args
+                .iter()
+                .position(|a| a.eq_ignore_ascii_case("MATCH"))
+                .and_then(|i| args.get(i + 1))
+                .map(|p| p.trim_end_matches('*').to_string())
+                .unwrap_or_default();
+            let keys = db
+                .entries
+                .keys()
+                .filter(|k| k.starts_with(&prefix))
+                .map(|k| bulk(Some(k)))
+                .collect();
+            Reply::Array(vec![bulk(Some(&"0".to_string())), Reply::Array(keys)])
+        }
+        "SCRIPT" if key.eq_ignore_ascii_case("LOAD") => {
+            let source = args[2].clone();
+            let sha = sha1_smol::Sha1::from(&source).digest().to_string();
+            db.scripts.insert(sha.clone(), source);
+            Reply::Bulk(Some(sha))
+        }
+        "EVALSHA" => match db.scripts.get(&key).cloned() {
+            Some(source) => eval(db, &source, &args[2..]),
+            None => Reply::Error("NOSCRIPT No matching script. Please use EVAL.".into()),
+        },
+        "EVAL" => eval(db, &key, &args[2..]),
+        _ => Reply::Error(format!("ERR unknown command '{name}'")),
+    }
+}
+
+// redis.call 中作为 key 的参数位置
+fn command_keys(args: &[String]) -> &[String] {
+    let name = args[0].to_ascii_uppercase();
+    match name.as_str() {
+        "DEL" => &args[1..],
+        _ if args.len() > 1 => &args[1..2],
+        _ => &[],
+    }
+}
+
+fn eval(db: &mut Db, source: &str, args: &[String]) -> Reply {
+    let Some(numkeys) = args.first().and_then(|n| n.parse::<usize>().ok()) else {
+        return Reply::Error("ERR value is not an integer".into());
+    };
+    let keys = &args[1..1 + numkeys];
+    let argv = &args[1 + numkeys..];
+    let declared: HashSet<&String> = keys.iter().collect();
+
+    let lua = Lua::new();
+    let result = lua.scope(|scope| {
+        let globals = lua.globals();
+        globals.set("KEYS", keys.to_vec())?;
+        globals.set("ARGV", argv.to_vec())?;
+        let redis = lua.create_table()?;
+        let call = scope.create_function_mut(|lua, call_args: Variadic<LuaValue>| {
+            let call_args: Vec<String> = call_args
+                .iter()
+                .map(|value| match value {
+                    LuaValue::String(s) => Ok(s.to_str()?.to_string()),
+                    LuaValue::Integer(i) => Ok(i.to_string()),
+                    LuaValue::Number(n) if n.fract() == 0.0 => Ok((*n as i64).to_string()),
+                    LuaValue::Number(n) => Ok(n.to_string()),
+                    other => Err(mlua::Error::runtime(format!(
+                        "Lua redis() command arguments must be strings or integers, got {}",
+                        other.type_name()
+                    ))),
+                })
+                .collect::<mlua::Result<_>>()?;
+            for key in command_keys(&call_args) {
+                if !declared.contains(key) {
+                    return Err(mlua::Error::runtime(format!(
+                        "script accessed undeclared key {key} in {}",
+                        call_args[0]
+                    )));
+                }
+            }
+            match execute(db, &call_args) {
+                Reply::Error(e) => Err(mlua::Error::runtime(e)),
+                reply => to_lua(lua, reply),
+            }
+        })?;
+        redis.set("call", call)?;
+        globals.set("redis", redis)?;
+        let values: MultiValue = lua.load(source).eval()?;
+        Ok(values
+            .into_iter()
+            .next()
+            .map_or(Reply::Bulk(None), from_lua))
+    });
+    result.unwrap_or_else(|e| Reply::Error(format!("ERR Error running script: {e}")))
+}
+
+fn to_lua(lua: &Lua, reply: Reply) -> mlua::Result<LuaValue<'_>> {
+    Ok(match reply {
+        Reply::Status(s) => {
+            let table = lua.create_table()?;
+            table.set("ok", s)?;
+            LuaValue::Table(table)
+        }
+        Reply::Error(e) => return Err(mlua::Error::runtime(e)),
+        Reply::Int(i) => LuaValue::Number(i as f64),
+        Reply::Bulk(None) => LuaValue::Boolean(false),
+        Reply::Bulk(Some(s)) => LuaValue::String(lua.create_string(&s)?),
+        Reply::Array(items) => {
+            let table = lua.create_table()?;
+            for (i, item) in items.into_iter().enumerate() {
+                table.set(i + 1, to_lua(lua, item)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
+
+// 与 Redis 相同：数字截断为整数，表按数组转换到第一个 nil 为止，false 和 nil 转换为空值
+fn from_lua(value: LuaValue) -> Reply {
+    match value {
+        LuaValue::Integer(i) => Reply::Int(i),
+        LuaValue::Number(n) => Reply::Int(n as i64),
+        LuaValue::Boolean(true) => Reply::Int(1),
+        LuaValue::String(s) => Reply::Bulk(Some(s.to_string_lossy().into_owned())),
+        LuaValue::Table(table) => {
+            if let Ok(LuaValue::String(e)) = table.get::<_, LuaValue>("err") {
+                return Reply::Error(e.to_string_lossy().into_owned());
+            }
+            if let Ok(LuaValue::String(ok)) = table.get::<_, LuaValue>("ok") {
+                return Reply::Status(ok.to_string_lossy().into_owned());
+            }
+            Reply::Array(
+                table
+                    .sequence_values::<LuaValue>()
+                    .map_while(Result::ok)
+                    .map(from_lua)
+                    .collect(),
+            )
+        }
+        _ => Reply::Bulk(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scripts_may_only_touch_declared_keys() {
+        let redis = TestRedis::start().await;
+        let mut conn = redis.connect().await;
+        let declared: i32 = redis::Script::new("return redis.call('HSET', KEYS[1], 'f', ARGV[1])")
+            .key("declared")
+            .arg("v")
+            .invoke_async(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(declared, 1);
+
+        let undeclared: redis::RedisResult<i32> =
+            redis::Script::new("return redis.call('HSET', ARGV[1] .. 'x', 'f', 'v')")
+                .key("declared")
+                .arg("un")
+                .invoke_async(&mut conn)
+                .await;
+        assert!(
+            undeclared
+                .unwrap_err()
+                .to_string()
+                .contains("undeclared key unx")
+        );
+        assert_eq!(redis.keys(), vec!["declared".to_string()]);
+    }
+}
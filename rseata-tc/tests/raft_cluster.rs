@@ -0,0 +1,224 @@
+//! 以独立进程启动三节点 raft 集群的 TC，杀掉 leader 进程后由剩余节点继续服务
+
+use rseata_core::types::GlobalStatus;
+use rseata_proto::rseata_proto::proto::transaction_manager_service_client::TransactionManagerServiceClient;
+use rseata_proto::rseata_proto::proto::{
+    GlobalBeginRequest, GlobalCommitRequest, GlobalStatusRequest,
+};
+use std::io::{BufRead, BufReader};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tonic::transport::Channel;
+
+const NODES: u64 = 3;
+const WAIT: Duration = Duration::from_secs(30);
+
+struct TcProcess {
+    child: Child,
+    grpc_addr: String,
+}
+
+/// 集群内各进程及其输出的日志，drop 时杀掉仍在运行的进程并删除数据目录
+struct TcCluster {
+    nodes: Vec<Option<TcProcess>>,
+    logs: Arc<Mutex<Vec<(u64, String)>>>,
+    data_dir: PathBuf,
+}
+
+impl TcCluster {
+    fn start() -> Self {
+        let data_dir = std::env::temp_dir().join(format!("rseata-raft-{}", uuid::Uuid::new_v4()));
+        let ports: Vec<u16> = (0..NODES * 3).map(|_| free_port()).collect();
+        let addr = |i: u64, kind: u64| format!("127.0.0.1:{}", ports[(i * 3 + kind) as usize]);
+        let members = (0..NODES)
+            .map(|i| format!("{}={}|{}", i + 1, addr(i, 0), addr(i, 1)))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let nodes = (0..NODES)
+            .map(|i| {
+                let node_id = i + 1;
+                let mut child = Command::new(env!("CARGO_BIN_EXE_rseata-tc"))
+                    .current_dir(std::env::temp_dir())
+                    .env("NO_COLOR", "1")
+                    .env("RSEATA_TC_STORE_MODE", "raft")
+                    .env("RSEATA_TC_RAFT_NODE_ID", node_id.to_string())
+                    .env("RSEATA_TC_RAFT_NODES", &members)
+                    .env(
+                        "RSEATA_TC_RAFT_DATA_DIR",
+                        data_dir.join(node_id.to_string()),
+                    )
+                    .env("RSEATA_TC_RAFT_ELECTION_TIMEOUT_MILLIS", "300")
+                    .env("RSEATA_TC_RAFT_HEARTBEAT_INTERVAL_MILLIS", "50")
+                    .env("GRPC_BIND", addr(i, 1))
+                    .env("RSEATA_WEB_SERVER_IP", "127.0.0.1")
+                    .env(
+                        "RSEATA_WEB_SERVER_PROT",
+                        ports[(i * 3 + 2) as usize].to_string(),
+                    )
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .unwrap();
+                let stdout = BufReader::new(child.stdout.take().unwrap());
+                let logs = logs.clone();
+                std::thread::spawn(move || {
+                    for line in stdout.lines().map_while(Result::ok) {
+                        logs.lock().unwrap().push((node_id, line));
+                    }
+                });
+                Some(TcProcess {
+                    child,
+                    grpc_addr: addr(i, 1),
+                })
+            })
+            .collect();
+        Self {
+            nodes,
+            logs,
+            data_dir,
+        }
+    }
+
+    // 日志中任期最高的 leader，只统计仍在运行的节点
+    fn leader(&self) -> Option<(u64, u64)> {
+        self.logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(node_id, _)| self.nodes[*node_id as usize - 1].is_some())
+            .filter_map(|(node_id, line)| {
+                let term = line.split("becomes leader of term ").nth(1)?;
+                Some((term.trim().parse().ok()?, *node_id))
+            })
+            .max()
+            .map(|(term, node_id)| (node_id, term))
+    }
+
+    async fn wait_leader(&self, after_term: u64) -> (u64, u64) {
+        let deadline = Instant::now() + WAIT;
+        loop {
+            if let Some((node_id, term)) = self.leader()
+                && term > after_term
+            {
+                return (node_id, term);
+            }
+            assert!(Instant::now() < deadline, "no leader elected");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    fn kill(&mut self, node_id: u64) {
+        let mut node = self.nodes[node_id as usize - 1].take().unwrap();
+        node.child.kill().unwrap();
+        node.child.wait().unwrap();
+    }
+
+    async fn client(&self, node_id: u64) -> TransactionManagerServiceClient<Channel> {
+        let addr = &self.nodes[node_id as usize - 1].as_ref().unwrap().grpc_addr;
+        let deadline = Instant::now() + WAIT;
+        loop {
+            match TransactionManagerServiceClient::connect(format!("http://{addr}")).await {
+                Ok(client) => return client,
+                Err(e) => assert!(Instant::now() < deadline, "connect {addr} failed: {e}"),
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+impl Drop for TcCluster {
+    fn drop(&mut self) {
+        for node in self.nodes.iter_mut().flatten() {
+            node.child.kill().ok();
+            node.child.wait().ok();
+        }
+        std::fs::remove_dir_all(&self.data_dir).ok();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+// 新 leader 刚当选时请求可能仍被拒绝，重试到成功为止
+async fn begin(client: &mut TransactionManagerServiceClient<Channel>) -> String {
+    let deadline = Instant::now() + WAIT;
+    loop {
+        let response = client
+            .global_begin(GlobalBeginRequest {
+                application_id: "app".to_string(),
+                transaction_service_group: "group".to_string(),
+                transaction_name: "tx".to_string(),
+                timeout_millis: 60_000,
+                extra_data: None,
+            })
+            .await;
+        match response {
+            Ok(response) if !response.get_ref().xid.is_empty() => {
+                return response.into_inner().xid;
+            }
+            other => assert!(Instant::now() < deadline, "begin failed: {other:?}"),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+async fn status(client: &mut TransactionManagerServiceClient<Channel>, xid: &str) -> GlobalStatus {
+    let deadline = Instant::now() + WAIT;
+    loop {
+        let response = client
+            .get_global_status(GlobalStatusRequest {
+                xid: xid.to_string(),
+                extra_data: String::new(),
+            })
+            .await;
+        match response {
+            Ok(response) => {
+                return GlobalStatus::from_code(response.get_ref().global_status).unwrap();
+            }
+            Err(e) => assert!(Instant::now() < deadline, "get status failed: {e}"),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[tokio::test]
+async fn killed_leader_process_is_replaced_without_losing_sessions() {
+    let mut cluster = TcCluster::start();
+    let (leader, term) = cluster.wait_leader(0).await;
+    let follower = (1..=NODES).find(|id| *id != leader).unwrap();
+
+    // 经跟随者转发到 leader 开启全局事务
+    let mut client = cluster.client(follower).await;
+    let xid = begin(&mut client).await;
+    assert_eq!(status(&mut client, &xid).await, GlobalStatus::Begin);
+
+    cluster.kill(leader);
+    let (new_leader, _) = cluster.wait_leader(term).await;
+    assert_ne!(new_leader, leader);
+
+    // 剩余两个节点仍构成多数派，已复制的会话由新 leader 接管
+    assert_eq!(status(&mut client, &xid).await, GlobalStatus::Begin);
+    let committed = client
+        .global_commit(GlobalCommitRequest {
+            xid: xid.clone(),
+            extra_data: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        GlobalStatus::from_code(committed.get_ref().global_status),
+        Ok(GlobalStatus::Committed)
+    );
+    let mut client = cluster.client(new_leader).await;
+    assert!(!begin(&mut client).await.is_empty());
+}
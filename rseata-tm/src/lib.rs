@@ -1,3 +1,4 @@
+pub mod saga;
 pub mod transaction_manager;
 
 pub use futures::FutureExt;
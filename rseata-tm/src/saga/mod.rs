@@ -0,0 +1,493 @@
+pub mod saga_log;
+pub mod saga_tc;
+
+use crate::saga::saga_log::{
+    FileSagaLog, SagaLog, SagaRecord, SagaRecordStatus, StepRecord, StepStatus,
+};
+use crate::saga::saga_tc::{GrpcSagaTc, SagaTc};
+use rseata_core::branch::BranchStatus;
+use rseata_core::types::GlobalStatus;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub type SagaFuture = Pin<Box<dyn Future<Output = anyhow::Result<Value>> + Send>>;
+type SagaFn = Arc<dyn Fn(Value) -> SagaFuture + Send + Sync>;
+
+struct SagaStep {
+    action: SagaFn,
+    compensation: SagaFn,
+}
+
+/// 代码方式定义的线性 Saga
+///
+/// 每个步骤注册为一个 SAGA 分支，步骤的返回值持久化后作为补偿函数的入参；
+/// 进程崩溃后用同名 Saga 调用 `recover` 继续未完成的实例
+///
+/// ```ignore
+/// Saga::new("create_order")
+///     .step(|input| Box::pin(create_order(input)), |output| Box::pin(cancel_order(output)))
+///     .step(|input| Box::pin(deduct(input)), |output| Box::pin(refund(output)))
+///     .run(json!({ "user_id": 1 }))
+///     .await?;
+/// ```
+pub struct Saga {
+    name: String,
+    timeout_millis: u64,
+    steps: Vec<SagaStep>,
+    log: Arc<dyn SagaLog>,
+    tc: Arc<dyn SagaTc>,
+}
+
+impl Saga {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            timeout_millis: 60_000,
+            steps: vec![],
+            log: Arc::new(FileSagaLog::new_with_env()),
+            tc: Arc::new(GrpcSagaTc),
+        }
+    }
+
+    pub fn timeout_millis(mut self, timeout_millis: u64) -> Self {
+        self.timeout_millis = timeout_millis;
+        self
+    }
+
+    pub fn log(mut self, log: Arc<dyn SagaLog>) -> Self {
+        self.log = log;
+        self
+    }
+
+    pub fn tc(mut self, tc: Arc<dyn SagaTc>) -> Self {
+        self.tc = tc;
+        self
+    }
+
+    /// 添加步骤：`action` 接收 Saga 的输入，`compensation` 接收该步骤的返回值
+    pub fn step<A, C>(mut self, action: A, compensation: C) -> Self
+    where
+        A: Fn(Value) -> SagaFuture + Send + Sync + 'static,
+        C: Fn(Value) -> SagaFuture + Send + Sync + 'static,
+    {
+        self.steps.push(SagaStep {
+            action: Arc::new(action),
+            compensation: Arc::new(compensation),
+        });
+        self
+    }
+
+    /// 执行 Saga，全部成功时返回各步骤的返回值；失败并补偿后返回原始错误
+    pub async fn run(&self, input: Value) -> anyhow::Result<Vec<Value>> {
+        // Saga 总是开启自己的全局事务，由本进程驱动补偿并通过 GlobalReport 上报结果
+        let xid = self
+            .tc
+            .begin(self.name.clone(), self.timeout_millis)
+            .await?;
+
+        let mut record = SagaRecord {
+            id: Uuid::new_v4().to_string(),
+            name: self.name.clone(),
+            xid,
+            input,
+            status: SagaRecordStatus::Running,
+            steps: vec![],
+            error: None,
+        };
+        self.log.save(&record).await?;
+        self.drive(&mut record).await
+    }
+
+    /// 恢复本进程崩溃前未完成的同名 Saga，返回恢复的实例数
+    pub async fn recover(&self) -> anyhow::Result<usize> {
+        let records = self.log.find_unfinished(&self.name).await?;
+        let count = records.len();
+        for mut record in records {
+            tracing::info!("recover saga {} {}", record.name, record.id);
+            if let Err(e) = self.drive(&mut record).await {
+                tracing::warn!(
+                    "saga {} {} recovered with error: {}",
+                    record.name,
+                    record.id,
+                    e
+                );
+            }
+        }
+        Ok(count)
+    }
+
+    async fn drive(&self, record: &mut SagaRecord) -> anyhow::Result<Vec<Value>> {
+        if record.status == SagaRecordStatus::Running
+            && let Err(e) = self.run_forward(record).await
+        {
+            record.status = SagaRecordStatus::Compensating;
+            record.error = Some(e.to_string());
+            self.log.save(record).await?;
+        }
+
+        if record.status == SagaRecordStatus::Compensating {
+            let compensated = self.compensate(record).await;
+            let global_status = if compensated.is_ok() {
+                record.status = SagaRecordStatus::Compensated;
+                GlobalStatus::Rollbacked
+            } else {
+                GlobalStatus::RollbackRetrying
+            };
+            self.finish(record, global_status).await?;
+            compensated?;
+            return Err(anyhow::anyhow!(
+                "saga {} compensated: {}",
+                record.name,
+                record.error.clone().unwrap_or_default()
+            ));
+        }
+
+        record.status = SagaRecordStatus::Committed;
+        self.finish(record, GlobalStatus::Committed).await?;
+        Ok(record
+            .steps
+            .iter()
+            .map(|s| s.output.clone().unwrap_or(Value::Null))
+            .collect())
+    }
+
+    async fn run_forward(&self, record: &mut SagaRecord) -> anyhow::Result<()> {
+        if record.steps.len() > self.steps.len() {
+            return Err(anyhow::anyhow!(
+                "saga {} has {} steps but log has {}",
+                self.name,
+                self.steps.len(),
+                record.steps.len()
+            ));
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            if record.steps.get(index).map(|s| s.status) == Some(StepStatus::Done) {
+                continue;
+            }
+            if record.steps.len() <= index {
+                let branch_id = self.branch_register(record).await?;
+                record.steps.push(StepRecord {
+                    branch_id,
+                    status: StepStatus::Registered,
+                    output: None,
+                });
+                self.log.save(record).await?;
+            }
+
+            let result = (step.action)(record.input.clone()).await;
+            let branch_id = record.steps[index].branch_id;
+            match result {
+                Ok(output) => {
+                    record.steps[index].status = StepStatus::Done;
+                    record.steps[index].output = Some(output);
+                    self.log.save(record).await?;
+                    self.branch_report(record, branch_id, BranchStatus::PhaseOneDone)
+                        .await?;
+                }
+                Err(e) => {
+                    record.steps[index].status = StepStatus::Failed;
+                    self.log.save(record).await?;
+                    self.branch_report(record, branch_id, BranchStatus::PhaseOneFailed)
+                        .await?;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 逆序补偿已完成的步骤
+    async fn compensate(&self, record: &mut SagaRecord) -> anyhow::Result<()> {
+        for index in (0..record.steps.len()).rev() {
+            if record.steps[index].status != StepStatus::Done {
+                continue;
+            }
+            let output = record.steps[index].output.clone().unwrap_or(Value::Null);
+            (self.steps[index].compensation)(output).await?;
+            record.steps[index].status = StepStatus::Compensated;
+            self.log.save(record).await?;
+            let branch_id = record.steps[index].branch_id;
+            self.branch_report(record, branch_id, BranchStatus::PhaseTwoRollbacked)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(&self, record: &SagaRecord, global_status: GlobalStatus) -> anyhow::Result<()> {
+        // 没有注册任何分支时不是 Saga 事务，由 TC 直接结束
+        if record.steps.is_empty() {
+            if global_status == GlobalStatus::Committed {
+                self.tc.commit(record.xid.clone()).await?;
+            } else {
+                self.tc.rollback(record.xid.clone()).await?;
+            }
+        } else {
+            self.tc.global_report(&record.xid, global_status).await?;
+        }
+        if record.is_finished() {
+            self.log.remove(&record.id).await?;
+        } else {
+            self.log.save(record).await?;
+        }
+        Ok(())
+    }
+
+    async fn branch_register(&self, record: &SagaRecord) -> anyhow::Result<u64> {
+        self.tc
+            .branch_register(&self.name, &record.xid, record.id.clone())
+            .await
+    }
+
+    async fn branch_report(
+        &self,
+        record: &SagaRecord,
+        branch_id: u64,
+        status: BranchStatus,
+    ) -> anyhow::Result<()> {
+        self.tc.branch_report(&record.xid, branch_id, status).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rseata_core::types::Xid;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    /// 记录 Saga 发往 TC 的调用，分支 ID 从 1 开始递增
+    #[derive(Default)]
+    struct RecordingTc {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl RecordingTc {
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl SagaTc for RecordingTc {
+        async fn begin(&self, name: String, _timeout_millis: u64) -> anyhow::Result<Xid> {
+            self.record(format!("begin {name}"));
+            Ok(Xid::from("xid"))
+        }
+
+        async fn commit(&self, _xid: Xid) -> anyhow::Result<()> {
+            self.record("commit".to_string());
+            Ok(())
+        }
+
+        async fn rollback(&self, _xid: Xid) -> anyhow::Result<()> {
+            self.record("rollback".to_string());
+            Ok(())
+        }
+
+        async fn global_report(&self, _xid: &Xid, status: GlobalStatus) -> anyhow::Result<()> {
+            self.record(format!("report {status:?}"));
+            Ok(())
+        }
+
+        async fn branch_register(
+            &self,
+            _saga_name: &str,
+            _xid: &Xid,
+            _application_data: String,
+        ) -> anyhow::Result<u64> {
+            let mut calls = self.calls.lock().unwrap();
+            let branch_id = calls.iter().filter(|c| c.starts_with("register")).count() + 1;
+            calls.push(format!("register {branch_id}"));
+            Ok(branch_id as u64)
+        }
+
+        async fn branch_report(
+            &self,
+            _xid: &Xid,
+            branch_id: u64,
+            status: BranchStatus,
+        ) -> anyhow::Result<()> {
+            self.record(format!("branch {branch_id} {status:?}"));
+            Ok(())
+        }
+    }
+
+    struct LogDir(PathBuf);
+
+    impl LogDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("rseata-saga-log-{}", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for LogDir {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    // 步骤记录调用并返回步骤名，名为 failing 的动作失败
+    fn order_saga(
+        dir: &LogDir,
+        tc: Arc<RecordingTc>,
+        calls: &Calls,
+        failing: &'static str,
+    ) -> Saga {
+        let mut saga = Saga::new("create_order")
+            .log(Arc::new(FileSagaLog::new(&dir.0)))
+            .tc(tc);
+        for name in ["order", "stock", "pay"] {
+            let (action_calls, compensation_calls) = (calls.clone(), calls.clone());
+            saga = saga.step(
+                move |_| {
+                    action_calls.lock().unwrap().push(name.to_string());
+                    Box::pin(async move {
+                        if name == failing {
+                            anyhow::bail!("{name} failed");
+                        }
+                        Ok(json!(name))
+                    })
+                },
+                move |output| {
+                    compensation_calls
+                        .lock()
+                        .unwrap()
+                        .push(format!("cancel {}", output.as_str().unwrap()));
+                    Box::pin(async { Ok(Value::Null) })
+                },
+            );
+        }
+        saga
+    }
+
+    fn step(branch_id: u64, status: StepStatus, output: Option<&str>) -> StepRecord {
+        StepRecord {
+            branch_id,
+            status,
+            output: output.map(|o| json!(o)),
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_step_compensates_earlier_steps_in_reverse() {
+        let dir = LogDir::new();
+        let tc = Arc::new(RecordingTc::default());
+        let calls = Calls::default();
+        let saga = order_saga(&dir, tc.clone(), &calls, "pay");
+
+        let error = saga.run(json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("pay failed"));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["order", "stock", "pay", "cancel stock", "cancel order"]
+        );
+        assert_eq!(
+            tc.calls(),
+            [
+                "begin create_order",
+                "register 1",
+                "branch 1 PhaseOneDone",
+                "register 2",
+                "branch 2 PhaseOneDone",
+                "register 3",
+                "branch 3 PhaseOneFailed",
+                "branch 2 PhaseTwoRollbacked",
+                "branch 1 PhaseTwoRollbacked",
+                "report Rollbacked",
+            ]
+        );
+        // 补偿完成后删除执行记录
+        assert_eq!(saga.recover().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn recover_replays_the_log_without_rerunning_done_steps() {
+        let dir = LogDir::new();
+        let log = FileSagaLog::new(&dir.0);
+        // 第二步已注册分支，进程在执行动作前崩溃
+        let running = SagaRecord {
+            id: Uuid::new_v4().to_string(),
+            name: "create_order".to_string(),
+            xid: Xid::from("xid"),
+            input: json!({}),
+            status: SagaRecordStatus::Running,
+            steps: vec![
+                step(11, StepStatus::Done, Some("order")),
+                step(12, StepStatus::Registered, None),
+            ],
+            error: None,
+        };
+        log.save(&running).await.unwrap();
+
+        let tc = Arc::new(RecordingTc::default());
+        let calls = Calls::default();
+        let saga = order_saga(&dir, tc.clone(), &calls, "");
+        assert_eq!(saga.recover().await.unwrap(), 1);
+
+        assert_eq!(*calls.lock().unwrap(), ["stock", "pay"]);
+        assert_eq!(
+            tc.calls(),
+            [
+                "branch 12 PhaseOneDone",
+                "register 1",
+                "branch 1 PhaseOneDone",
+                "report Committed",
+            ]
+        );
+        assert!(
+            log.find_unfinished("create_order")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn recover_continues_an_interrupted_compensation() {
+        let dir = LogDir::new();
+        let log = FileSagaLog::new(&dir.0);
+        let compensating = SagaRecord {
+            id: Uuid::new_v4().to_string(),
+            name: "create_order".to_string(),
+            xid: Xid::from("xid"),
+            input: json!({}),
+            status: SagaRecordStatus::Compensating,
+            steps: vec![
+                step(1, StepStatus::Done, Some("order")),
+                step(2, StepStatus::Compensated, Some("stock")),
+                step(3, StepStatus::Failed, None),
+            ],
+            error: Some("pay failed".to_string()),
+        };
+        log.save(&compensating).await.unwrap();
+
+        let tc = Arc::new(RecordingTc::default());
+        let calls = Calls::default();
+        let saga = order_saga(&dir, tc.clone(), &calls, "");
+        assert_eq!(saga.recover().await.unwrap(), 1);
+
+        // 只补偿尚未补偿的步骤，不重新执行任何动作
+        assert_eq!(*calls.lock().unwrap(), ["cancel order"]);
+        assert_eq!(
+            tc.calls(),
+            ["branch 1 PhaseTwoRollbacked", "report Rollbacked"]
+        );
+        assert!(
+            log.find_unfinished("create_order")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
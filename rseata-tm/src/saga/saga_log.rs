@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use rseata_core::types::Xid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SagaRecordStatus {
+    Running,
+    Compensating,
+    Committed,
+    Compensated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Registered,
+    Done,
+    Failed,
+    Compensated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub branch_id: u64,
+    pub status: StepStatus,
+    /// 步骤的返回值，补偿时传给补偿函数
+    pub output: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaRecord {
+    pub id: String,
+    pub name: String,
+    pub xid: Xid,
+    pub input: Value,
+    pub status: SagaRecordStatus,
+    pub steps: Vec<StepRecord>,
+    pub error: Option<String>,
+}
+
+impl SagaRecord {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            SagaRecordStatus::Committed | SagaRecordStatus::Compensated
+        )
+    }
+}
+
+/// Saga 执行记录的持久化，用于崩溃后恢复
+#[async_trait]
+pub trait SagaLog: Send + Sync + 'static {
+    async fn save(&self, record: &SagaRecord) -> anyhow::Result<()>;
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()>;
+
+    async fn find_unfinished(&self, name: &str) -> anyhow::Result<Vec<SagaRecord>>;
+}
+
+/// 每个 Saga 实例一个 JSON 文件，先写临时文件再 rename 保证原子性
+pub struct FileSagaLog {
+    dir: PathBuf,
+}
+
+impl FileSagaLog {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn new_with_env() -> Self {
+        Self::new(std::env::var("RSEATA_TM_SAGA_LOG_DIR").unwrap_or("saga_log".to_string()))
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+}
+
+#[async_trait]
+impl SagaLog for FileSagaLog {
+    async fn save(&self, record: &SagaRecord) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let tmp = self.dir.join(format!("{}.json.tmp", record.id));
+        tokio::fs::write(&tmp, serde_json::to_vec(record)?).await?;
+        tokio::fs::File::open(&tmp).await?.sync_all().await?;
+        tokio::fs::rename(&tmp, self.path(&record.id)).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.path(id)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn find_unfinished(&self, name: &str) -> anyhow::Result<Vec<SagaRecord>> {
+        let mut records = vec![];
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(records),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let record: SagaRecord = match serde_json::from_slice(&tokio::fs::read(&path).await?) {
+                Ok(record) => record,
+                Err(e) => {
+                    tracing::warn!("skip broken saga log {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            if record.name == name && !record.is_finished() {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(name: &str, status: SagaRecordStatus) -> SagaRecord {
+        SagaRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            xid: Xid::from("xid"),
+            input: json!({ "user_id": 1 }),
+            status,
+            steps: vec![StepRecord {
+                branch_id: 1,
+                status: StepStatus::Done,
+                output: Some(json!("order")),
+            }],
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn file_log_returns_unfinished_records_of_one_saga() {
+        let dir = std::env::temp_dir().join(format!("rseata-saga-log-{}", uuid::Uuid::new_v4()));
+        let log = FileSagaLog::new(&dir);
+        // 目录尚未创建时没有记录
+        assert!(
+            log.find_unfinished("create_order")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let running = record("create_order", SagaRecordStatus::Running);
+        let compensating = record("create_order", SagaRecordStatus::Compensating);
+        for record in [
+            &running,
+            &compensating,
+            &record("create_order", SagaRecordStatus::Committed),
+            &record("refund", SagaRecordStatus::Running),
+        ] {
+            log.save(record).await.unwrap();
+        }
+        // 写到一半的临时文件和损坏的记录被跳过
+        std::fs::write(dir.join("partial.json.tmp"), b"{").unwrap();
+        std::fs::write(dir.join("broken.json"), b"{").unwrap();
+
+        let mut ids: Vec<_> = log
+            .find_unfinished("create_order")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        ids.sort();
+        let mut expected = vec![running.id.clone(), compensating.id.clone()];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        // 覆盖写入后读到最新的步骤
+        let mut updated = running.clone();
+        updated.steps[0].status = StepStatus::Compensated;
+        log.save(&updated).await.unwrap();
+        log.remove(&compensating.id).await.unwrap();
+        log.remove(&compensating.id).await.unwrap();
+        let unfinished = log.find_unfinished("create_order").await.unwrap();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].steps[0].status, StepStatus::Compensated);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
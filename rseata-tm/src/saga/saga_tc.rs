@@ -0,0 +1,126 @@
+use crate::RSEATA_TM;
+use crate::transaction_manager::get_tc_grpc_server_addr;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use rseata_core::branch::{BranchStatus, BranchType, TM_DRIVEN_RESOURCE_PREFIX};
+use rseata_core::grpc_client::GrpcContext;
+use rseata_core::grpc_client::rm_grpc_client::LazyRMGrpcClient;
+use rseata_core::transaction::transaction_manager::TransactionManager;
+use rseata_core::types::{GlobalStatus, Xid};
+use rseata_proto::rseata_proto::proto::{BranchRegisterRequest, BranchReportRequest};
+
+lazy_static! {
+    static ref RM_GRPC_CLIENT: LazyRMGrpcClient = LazyRMGrpcClient::new(GrpcContext {
+        endpoint: get_tc_grpc_server_addr()
+    });
+}
+
+/// Saga 对 TC 的调用：开启和结束全局事务，注册并上报每个步骤对应的分支
+#[async_trait]
+pub trait SagaTc: Send + Sync + 'static {
+    async fn begin(&self, name: String, timeout_millis: u64) -> anyhow::Result<Xid>;
+
+    async fn commit(&self, xid: Xid) -> anyhow::Result<()>;
+
+    async fn rollback(&self, xid: Xid) -> anyhow::Result<()>;
+
+    async fn global_report(&self, xid: &Xid, global_status: GlobalStatus) -> anyhow::Result<()>;
+
+    /// 注册步骤分支，application_data 为 Saga 实例 ID
+    async fn branch_register(
+        &self,
+        saga_name: &str,
+        xid: &Xid,
+        application_data: String,
+    ) -> anyhow::Result<u64>;
+
+    async fn branch_report(
+        &self,
+        xid: &Xid,
+        branch_id: u64,
+        status: BranchStatus,
+    ) -> anyhow::Result<()>;
+}
+
+/// 通过 RSEATA_TM 和 RM gRPC 客户端访问 TC
+pub struct GrpcSagaTc;
+
+#[async_trait]
+impl SagaTc for GrpcSagaTc {
+    async fn begin(&self, name: String, timeout_millis: u64) -> anyhow::Result<Xid> {
+        RSEATA_TM
+            .begin(
+                RSEATA_TM.application_id.to_string(),
+                RSEATA_TM.transaction_service_group.to_string(),
+                name,
+                timeout_millis,
+            )
+            .await
+    }
+
+    async fn commit(&self, xid: Xid) -> anyhow::Result<()> {
+        RSEATA_TM.commit(xid).await?;
+        Ok(())
+    }
+
+    async fn rollback(&self, xid: Xid) -> anyhow::Result<()> {
+        RSEATA_TM.rollback(xid).await?;
+        Ok(())
+    }
+
+    async fn global_report(&self, xid: &Xid, global_status: GlobalStatus) -> anyhow::Result<()> {
+        RSEATA_TM
+            .global_report(
+                xid,
+                RSEATA_TM.application_id.to_string(),
+                RSEATA_TM.transaction_service_group.to_string(),
+                global_status,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn branch_register(
+        &self,
+        saga_name: &str,
+        xid: &Xid,
+        application_data: String,
+    ) -> anyhow::Result<u64> {
+        let response = RM_GRPC_CLIENT
+            .get()
+            .await?
+            .rm
+            .branch_register(BranchRegisterRequest {
+                branch_type: BranchType::SAGA.into(),
+                // 补偿由本进程执行，TC 不向这些分支下发指令
+                resource_id: format!("{TM_DRIVEN_RESOURCE_PREFIX}{saga_name}"),
+                client_id: 0,
+                xid: xid.to_string(),
+                application_data,
+                lock_keys: String::new(),
+            })
+            .await?;
+        Ok(response.into_inner().branch_id)
+    }
+
+    async fn branch_report(
+        &self,
+        xid: &Xid,
+        branch_id: u64,
+        status: BranchStatus,
+    ) -> anyhow::Result<()> {
+        RM_GRPC_CLIENT
+            .get()
+            .await?
+            .rm
+            .branch_report(BranchReportRequest {
+                branch_type: BranchType::SAGA.into(),
+                xid: xid.to_string(),
+                branch_id,
+                status: status.into(),
+                application_data: String::new(),
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -25,6 +25,7 @@ lazy_static! {
 pub struct RseataTM {
     pub application_id: Arc<String>,
     pub transaction_service_group: Arc<String>,
+    pub timeout_millis: u64,
 }
 impl RseataTM {
     pub fn new_with_env() -> Self {
@@ -32,9 +33,15 @@ impl RseataTM {
             env::var("RSEATA_TM_APPLICATION_ID").expect("env RSEATA_TM_APPLICATION_ID not set");
         let group = env::var("RSEATA_TM_TRANSACTION_SERVICE_GROUP")
             .unwrap_or("RSEATA_TM_TRANSACTION_SERVICE_GROUP".to_owned());
+        // 全局事务超时时间，超时后 TC 自动回滚
+        let timeout_millis = env::var("RSEATA_TM_GLOBAL_TRANSACTION_TIMEOUT_MILLIS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60_000);
         Self {
             application_id: Arc::new(app),
             transaction_service_group: Arc::new(group),
+            timeout_millis,
         }
     }
 }
@@ -100,6 +107,8 @@ impl TransactionManager for RseataTM {
     async fn global_report(
         &self,
         xid: &Xid,
+        application_id: String,
+        transaction_service_group: String,
         global_status: GlobalStatus,
     ) -> anyhow::Result<GlobalStatus> {
         let status = TM_GRPC_CLIENT
@@ -109,6 +118,8 @@ impl TransactionManager for RseataTM {
             .global_report(GlobalReportRequest {
                 xid: xid.to_string(),
                 global_status: global_status.code(),
+                application_id,
+                transaction_service_group,
             })
             .await?;
         Ok(GlobalStatus::from_code(status.into_inner().global_status)
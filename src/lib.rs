@@ -6,12 +6,21 @@ pub use rseata_tm::RSEATA_CLIENT_SESSION;
 pub use rseata_tm::RSEATA_TM;
 #[cfg(feature = "tm")]
 pub use rseata_tm::RseataTM;
+#[cfg(feature = "tm")]
+pub use rseata_tm::saga as saga_tm;
 
 #[cfg(feature = "rm")]
 pub use rseata_rm::RSEATA_RM;
+#[cfg(feature = "rm")]
+pub mod tcc;
+
+#[cfg(feature = "saga")]
+pub use rseata_saga as saga;
 
 #[cfg(feature = "micros")]
 pub use rseata_micro::global_transaction;
+#[cfg(feature = "micros")]
+pub use rseata_micro::tcc_action;
 
 pub mod core {
     pub use rseata_core::ClientSession;
@@ -0,0 +1,54 @@
+pub use rseata_rm::tcc::*;
+
+#[cfg(feature = "tm")]
+pub use with_tm::{tcc_try, tcc_try_with};
+
+/// RM 只在已有的全局事务中执行 TCC，未开启时先由 TM 开启
+#[cfg(feature = "tm")]
+mod with_tm {
+    use rseata_core::RSEATA_CLIENT_SESSION;
+    use rseata_core::tcc::{TccAction, TccBranch};
+    use rseata_core::transaction::transaction_manager::TransactionManager;
+    use rseata_tm::RSEATA_TM;
+    use std::future::Future;
+    use std::sync::Arc;
+
+    async fn begin_if_absent() -> anyhow::Result<()> {
+        let Ok(session) = RSEATA_CLIENT_SESSION.try_get() else {
+            return Ok(());
+        };
+        if !session.is_global_tx_started() {
+            let xid = RSEATA_TM
+                .begin(
+                    RSEATA_TM.application_id.to_string(),
+                    RSEATA_TM.transaction_service_group.to_string(),
+                    session.transaction_name.clone(),
+                    RSEATA_TM.timeout_millis,
+                )
+                .await?;
+            session.begin_global_transaction(xid)?;
+        }
+        Ok(())
+    }
+
+    /// 在全局事务中执行 TCC 一阶段：注册分支、执行 try 并上报结果
+    pub async fn tcc_try<A: TccAction>(action: A, context: A::Context) -> anyhow::Result<()> {
+        begin_if_absent().await?;
+        rseata_rm::tcc::tcc_try(action, context).await
+    }
+
+    /// 与 `tcc_try` 相同，但一阶段执行 `f`，用于 try 需要返回值的场景（如 `#[tcc_action]`）
+    pub async fn tcc_try_with<A, F, Fut, T>(
+        action: Arc<A>,
+        context: &A::Context,
+        f: F,
+    ) -> anyhow::Result<T>
+    where
+        A: TccAction,
+        F: FnOnce(TccBranch) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        begin_if_absent().await?;
+        rseata_rm::tcc::tcc_try_with(action, context, f).await
+    }
+}